@@ -13,20 +13,32 @@
 
 use criterion::{criterion_group, criterion_main, Criterion, SamplingMode};
 use pprof::criterion::{PProfProfiler, Output};
-use ever_block::{StateInit, Deserializable, GlobalCapabilities};
+use ever_block::{StateInit, Deserializable, GlobalCapabilities, HashmapE, HashmapType, Serializable};
 use ever_assembler::compile_code_to_cell;
 use ever_block::SliceData;
 use ever_vm::{
-    executor::{Engine, gas::gas_state::Gas},
-    stack::{savelist::SaveList, Stack, StackItem, continuation::ContinuationData, integer::IntegerData}
+    executor::{Engine, VmCapabilities, gas::gas_state::Gas},
+    stack::{
+        savelist::SaveList, Stack, StackItem, continuation::ContinuationData,
+        integer::{
+            IntegerData,
+            serialization::{Encoding, SignedIntegerBigEndianEncoding, UnsignedIntegerBigEndianEncoding}
+        },
+        serialization::{Deserializer, Serializer}
+    }
 };
 use std::time::Duration;
 
 static DEFAULT_CAPABILITIES: u64 = 0x572e;
 
+// files are resolved against the crate root, so benchmarks can be run from any directory
+fn bench_file(filename: &str) -> std::path::PathBuf {
+    std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join(filename)
+}
+
 fn read_boc(filename: &str) -> Vec<u8> {
     let mut bytes = Vec::new();
-    let mut file = std::fs::File::open(filename).unwrap();
+    let mut file = std::fs::File::open(bench_file(filename)).unwrap();
     std::io::Read::read_to_end(&mut file, &mut bytes).unwrap();
     bytes
 }
@@ -37,7 +49,7 @@ fn load_boc(filename: &str) -> ever_block::Cell {
 }
 
 fn load_stateinit(filename: &str) -> StateInit {
-    StateInit::construct_from_file(filename).unwrap()
+    StateInit::construct_from_file(bench_file(filename).to_str().unwrap()).unwrap()
 }
 
 fn bench_elector_algo_1000_vtors(c: &mut Criterion) {
@@ -466,19 +478,271 @@ fn bench_chksignu(c: &mut Criterion) {
     }));
 }
 
+// more than thousand globals are set in nested calls, most of them are in nested tuples of c7
+fn bench_many_globals(c: &mut Criterion) {
+    let code = compile_code_to_cell("
+        PUSHINT 0
+        PUSHINT 1000
+        PUSHCONT {
+            INC
+            DUP
+            DUP
+            SETGLOBVAR
+            PUSHCONT {
+                DUP
+                DUP
+                ADDCONST 100
+                SETGLOBVAR
+            }
+            CALLX
+        }
+        REPEAT
+    ").unwrap();
+
+    c.bench_function("many-globals", |b| b.iter(|| {
+        let mut engine = Engine::with_capabilities(DEFAULT_CAPABILITIES)
+            .with_vm_capabilities(VmCapabilities::CapLongGlobals as u64)
+            .setup_with_libraries(
+                SliceData::load_cell_ref(&code).unwrap(),
+                None,
+                None,
+                None,
+                vec!());
+        engine.execute().unwrap();
+        assert_eq!(engine.stack().depth(), 1);
+    }));
+}
+
+// tight arithmetic loop without memory allocations
+fn bench_arithmetic_loop(c: &mut Criterion) {
+    let code = compile_code_to_cell("
+        PUSHINT 1
+        PUSHINT 10000
+        PUSHCONT {
+            PUSHINT 3
+            MUL
+            PUSHINT 1000003
+            MOD
+            INC
+        }
+        REPEAT
+    ").unwrap();
+
+    c.bench_function("arithmetic-loop", |b| b.iter(|| {
+        let mut engine = Engine::with_capabilities(DEFAULT_CAPABILITIES).setup_with_libraries(
+            SliceData::load_cell_ref(&code).unwrap(),
+            None,
+            None,
+            None,
+            vec!());
+        engine.execute().unwrap();
+        assert_eq!(engine.stack().depth(), 1);
+    }));
+}
+
+// WHILE and UNTIL loops with small bodies, their iterations are dominated by switching of continuations
+fn bench_while_until_loops(c: &mut Criterion) {
+    let code = compile_code_to_cell("
+        PUSHINT 0
+        PUSHCONT {
+            DUP
+            PUSHINT 10000
+            LESS
+        }
+        PUSHCONT {
+            INC
+        }
+        WHILE
+        PUSHCONT {
+            DEC
+            DUP
+            ISZERO
+        }
+        UNTIL
+    ").unwrap();
+
+    c.bench_function("while-until-loops", |b| b.iter(|| {
+        let mut engine = Engine::with_capabilities(DEFAULT_CAPABILITIES).setup_with_libraries(
+            SliceData::load_cell_ref(&code).unwrap(),
+            None,
+            None,
+            None,
+            vec!());
+        engine.execute().unwrap();
+        assert_eq!(engine.stack().get(0), &StackItem::int(0));
+    }));
+}
+
+const DICT_ENTRIES: u32 = 1000;
+
+fn bench_dictionary_set(c: &mut Criterion) {
+    let code = compile_code_to_cell("
+        NEWDICT
+        PUSHINT 0
+        PUSHINT 1000
+        PUSHCONT {
+            DUP
+            NEWC
+            STU 32
+            OVER
+            PUSH s3
+            PUSHINT 32
+            DICTUSETB
+            ROT
+            DROP
+            SWAP
+            INC
+        }
+        REPEAT
+    ").unwrap();
+
+    c.bench_function("dictionary-set", |b| b.iter(|| {
+        let mut engine = Engine::with_capabilities(DEFAULT_CAPABILITIES).setup_with_libraries(
+            SliceData::load_cell_ref(&code).unwrap(),
+            None,
+            None,
+            None,
+            vec!());
+        engine.execute().unwrap();
+        assert_eq!(engine.stack().get(0), &StackItem::int(DICT_ENTRIES));
+    }));
+}
+
+fn bench_dictionary_get(c: &mut Criterion) {
+    let mut dict = HashmapE::with_bit_len(32);
+    for i in 0..DICT_ENTRIES {
+        let key = SliceData::load_builder(i.write_to_new_cell().unwrap()).unwrap();
+        dict.set_builder(key, &i.write_to_new_cell().unwrap()).unwrap();
+    }
+    let code = compile_code_to_cell("
+        PUSHINT 1000
+        PUSHCONT {
+            DUP
+            PUSH s2
+            PUSHINT 32
+            DICTUGET
+            THROWIFNOT 100
+            DROP
+            INC
+        }
+        REPEAT
+    ").unwrap();
+
+    let mut stack = Stack::new();
+    stack.push(StackItem::cell(dict.data().unwrap().clone()));
+    stack.push(StackItem::int(0));
+
+    c.bench_function("dictionary-get", |b| b.iter(|| {
+        let mut engine = Engine::with_capabilities(DEFAULT_CAPABILITIES).setup_with_libraries(
+            SliceData::load_cell_ref(&code).unwrap(),
+            None,
+            Some(stack.clone()),
+            None,
+            vec!());
+        engine.execute().unwrap();
+        assert_eq!(engine.stack().get(0), &StackItem::int(DICT_ENTRIES));
+    }));
+}
+
+// every iteration creates a cell and loads it back
+fn bench_cell_churn(c: &mut Criterion) {
+    let code = compile_code_to_cell("
+        PUSHINT 10000
+        PUSHCONT {
+            NEWC
+            PUSHINT 1
+            STUR 8
+            ENDC
+            CTOS
+            DROP
+        }
+        REPEAT
+    ").unwrap();
+
+    c.bench_function("cell-churn", |b| b.iter(|| {
+        let mut engine = Engine::with_capabilities(DEFAULT_CAPABILITIES).setup_with_libraries(
+            SliceData::load_cell_ref(&code).unwrap(),
+            None,
+            None,
+            None,
+            vec!());
+        engine.execute().unwrap();
+        assert_eq!(engine.stack().depth(), 0);
+    }));
+}
+
+fn bench_integer_serialization(c: &mut Criterion) {
+    let value = IntegerData::from_str_radix(
+        "F0E1D2C3B4A5968778695A4B3C2D1E0F0123456789ABCDEFFEDCBA9876543210", 16
+    ).unwrap();
+    let unsigned = UnsignedIntegerBigEndianEncoding::new(256);
+    let signed = SignedIntegerBigEndianEncoding::new(257);
+    let data = unsigned.try_serialize(&value).unwrap();
+    c.bench_function("stu-256", |b| b.iter(|| {
+        unsigned.try_serialize(&value).unwrap()
+    }));
+    c.bench_function("ldu-256", |b| b.iter(|| {
+        unsigned.deserialize(data.data())
+    }));
+    c.bench_function("sti-257", |b| b.iter(|| {
+        signed.try_serialize(&value).unwrap()
+    }));
+    let small = IntegerData::from_i64(-1234567890);
+    let encoding = SignedIntegerBigEndianEncoding::new(64);
+    c.bench_function("sti-64", |b| b.iter(|| {
+        encoding.try_serialize(&small).unwrap()
+    }));
+}
+
+fn config() -> Criterion {
+    Criterion::default().with_profiler(PProfProfiler::new(100, Output::Flamegraph(None)))
+}
+
 criterion_group!(
-    name = benches;
-    config = Criterion::default().with_profiler(PProfProfiler::new(100, Output::Flamegraph(None)));
+    name = arithmetic;
+    config = config();
     targets =
         bench_num_bigint,
         // bench_rug_bigint,
-        bench_load_boc,
-        bench_elector_algo_1000_vtors,
         bench_tiny_loop_200000_iters,
-        bench_mergesort_tuple,
+        bench_arithmetic_loop,
+        bench_integer_serialization,
+);
+criterion_group!(
+    name = dictionaries;
+    config = config();
+    targets =
+        bench_dictionary_set,
+        bench_dictionary_get,
+);
+criterion_group!(
+    name = cells;
+    config = config();
+    targets =
+        bench_load_boc,
         bench_massive_cell_upload,
         bench_massive_cell_finalize,
+        bench_cell_churn,
+);
+criterion_group!(
+    name = continuations;
+    config = config();
+    targets =
+        bench_mergesort_tuple,
+        bench_many_globals,
+        bench_while_until_loops,
+);
+criterion_group!(
+    name = signatures;
+    config = config();
+    targets =
         bench_ed25519_verify,
         bench_chksignu,
 );
-criterion_main!(benches);
+criterion_group!(
+    name = contracts;
+    config = config();
+    targets =
+        bench_elector_algo_1000_vtors,
+);
+criterion_main!(arithmetic, dictionaries, cells, continuations, signatures, contracts);
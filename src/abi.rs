@@ -0,0 +1,176 @@
+/*
+* Copyright (C) 2019-2024 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+use crate::{
+    executor::harness::{run_get_method_with_params, HarnessParams},
+    stack::{StackItem, integer::IntegerData},
+};
+use ever_block::{fail, Cell, ExceptionCode, Result, SliceData};
+use num::{BigInt, ToPrimitive};
+
+/// Typed parameter or result of get method
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Int(BigInt),
+    Uint(BigInt),
+    Bool(bool),
+    Cell(Cell),
+    Slice(SliceData),
+    Tuple(Vec<Value>),
+    Null,
+}
+
+/// Expected type of get method result, stack keeps no types of integers
+#[derive(Clone, Debug, PartialEq)]
+pub enum ValueType {
+    Int,
+    Uint,
+    Bool,
+    Cell,
+    Slice,
+    Tuple(Vec<ValueType>),
+    Null,
+}
+
+impl Value {
+    fn to_stack_item(&self) -> Result<StackItem> {
+        let item = match self {
+            Value::Int(value) => StackItem::int(IntegerData::from(value.clone())?),
+            Value::Uint(value) => {
+                if value.sign() == num::bigint::Sign::Minus {
+                    return err!(ExceptionCode::RangeCheckError, "negative value {} of uint", value)
+                }
+                StackItem::int(IntegerData::from(value.clone())?)
+            }
+            Value::Bool(value) => boolean!(*value),
+            Value::Cell(cell) => StackItem::Cell(cell.clone()),
+            Value::Slice(slice) => StackItem::Slice(slice.clone()),
+            Value::Tuple(values) => StackItem::tuple(
+                values.iter().map(Value::to_stack_item).collect::<Result<_>>()?
+            ),
+            Value::Null => StackItem::None,
+        };
+        Ok(item)
+    }
+
+    /// Integers are returned as Int since stack does not keep their types,
+    /// builders are finalized to cells
+    fn from_stack_item(item: &StackItem) -> Result<Self> {
+        let value = match item {
+            StackItem::None => Value::Null,
+            StackItem::Integer(value) => Value::Int(value.take_value_of(|x| Some(x.clone()))?),
+            StackItem::Cell(cell) => Value::Cell(cell.clone()),
+            StackItem::Slice(slice) => Value::Slice(slice.clone()),
+            StackItem::Builder(builder) => Value::Cell(builder.as_ref().clone().into_cell()?),
+            StackItem::Tuple(items) => Value::Tuple(
+                items.iter().map(Value::from_stack_item).collect::<Result<_>>()?
+            ),
+            StackItem::Continuation(_) => fail!("continuation cannot be returned as value"),
+        };
+        Ok(value)
+    }
+
+    /// Converts stack item to value of expected type, mismatch is a type check error
+    fn from_stack_item_as(item: &StackItem, value_type: &ValueType) -> Result<Self> {
+        let value = match (value_type, item) {
+            (ValueType::Int, StackItem::Integer(_)) => Self::from_stack_item(item)?,
+            (ValueType::Uint, StackItem::Integer(value)) => {
+                let value = value.take_value_of(|x| Some(x.clone()))?;
+                if value.sign() == num::bigint::Sign::Minus {
+                    return err!(ExceptionCode::RangeCheckError, "negative value {} of uint", value)
+                }
+                Value::Uint(value)
+            }
+            (ValueType::Bool, StackItem::Integer(value)) => match value.take_value_of(|x| x.to_i8())? {
+                0 => Value::Bool(false),
+                -1 => Value::Bool(true),
+                x => return err!(ExceptionCode::RangeCheckError, "{} is not a boolean", x)
+            }
+            (ValueType::Cell, StackItem::Cell(_) | StackItem::Builder(_))
+            | (ValueType::Slice, StackItem::Slice(_))
+            | (ValueType::Null, StackItem::None) => Self::from_stack_item(item)?,
+            (ValueType::Tuple(types), StackItem::Tuple(items)) if types.len() == items.len() => Value::Tuple(
+                items.iter().zip(types).map(|(item, value_type)| Self::from_stack_item_as(item, value_type))
+                    .collect::<Result<_>>()?
+            ),
+            _ => return err!(ExceptionCode::TypeCheckError, "{} is not of type {:?}", item, value_type)
+        };
+        Ok(value)
+    }
+}
+
+// CRC-16/XMODEM
+fn crc16(data: &[u8]) -> u16 {
+    let mut crc = 0u16;
+    for byte in data {
+        crc ^= (*byte as u16) << 8;
+        for _ in 0..8 {
+            crc = match crc & 0x8000 {
+                0 => crc << 1,
+                _ => (crc << 1) ^ 0x1021
+            };
+        }
+    }
+    crc
+}
+
+/// Id of get method as it is computed by compilers from method name
+pub fn get_method_id(name: &str) -> u32 {
+    crc16(name.as_bytes()) as u32 | 0x10000
+}
+
+/// Runs get method by name in the default environment, integer results are returned as Int
+pub fn run_get_method_typed(code: Cell, data: Cell, name: &str, params: &[Value]) -> Result<Vec<Value>> {
+    run_get_method_typed_with_params(code, data, name, params, None, &HarnessParams::default())
+}
+
+/// Runs get method by name in the default environment, results are converted to outputs types
+pub fn run_get_method_typed_as(
+    code: Cell,
+    data: Cell,
+    name: &str,
+    params: &[Value],
+    outputs: &[ValueType]
+) -> Result<Vec<Value>> {
+    run_get_method_typed_with_params(code, data, name, params, Some(outputs), &HarnessParams::default())
+}
+
+/// Runs get method by name, values left on the stack are returned from bottom to top.
+/// If outputs are given, their number must match the stack depth.
+/// Non zero exit code is returned as error
+pub fn run_get_method_typed_with_params(
+    code: Cell,
+    data: Cell,
+    name: &str,
+    params: &[Value],
+    outputs: Option<&[ValueType]>,
+    harness_params: &HarnessParams
+) -> Result<Vec<Value>> {
+    let params = params.iter().map(Value::to_stack_item).collect::<Result<Vec<_>>>()?;
+    let result = run_get_method_with_params(code, data, get_method_id(name), params, harness_params)?;
+    if result.exit_code != 0 && result.exit_code != 1 {
+        fail!("get method {} failed with exit code {}", name, result.exit_code)
+    }
+    let items = &result.stack.storage;
+    match outputs {
+        None => items.iter().map(Value::from_stack_item).collect(),
+        Some(outputs) if outputs.len() == items.len() => items.iter().zip(outputs)
+            .map(|(item, value_type)| Value::from_stack_item_as(item, value_type))
+            .collect(),
+        Some(outputs) => fail!("get method {} returned {} values instead of {}", name, items.len(), outputs.len())
+    }
+}
+
+#[cfg(test)]
+#[path = "tests/test_abi.rs"]
+mod tests;
@@ -0,0 +1,95 @@
+/*
+* Copyright (C) 2019-2024 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+use ever_block::{
+    BuilderData, ExceptionCode, IBitstring, Result, SliceData, UInt256, MAX_DATA_BITS
+};
+
+/*
+Address layouts produced and accepted here (anycast is never set):
+
+addr_std$10 anycast:(Maybe Anycast) workchain_id:int8 address:bits256 = MsgAddressInt;
+addr_var$11 anycast:(Maybe Anycast) addr_len:(## 9)
+    workchain_id:int32 address:(bits addr_len) = MsgAddressInt;
+*/
+
+const ADDR_STD_TAG: usize = 0b10;
+const ADDR_VAR_TAG: usize = 0b11;
+const ADDR_VAR_MAX_LEN: usize = (1 << 9) - 1;
+
+/// Build addr_std slice as it is expected in c7 myaddr and in message headers
+pub fn std_addr(workchain_id: i8, address: &UInt256) -> Result<SliceData> {
+    let mut builder = BuilderData::new();
+    builder.append_bits(ADDR_STD_TAG, 2)?;
+    builder.append_bit_zero()?;
+    builder.append_i8(workchain_id)?;
+    builder.append_raw(address.as_slice(), 256)?;
+    SliceData::load_builder(builder)
+}
+
+/// Build addr_var slice from workchain and address bits
+pub fn var_addr(workchain_id: i32, address: &SliceData) -> Result<SliceData> {
+    let len = address.remaining_bits();
+    if len > ADDR_VAR_MAX_LEN || len + 2 + 1 + 9 + 32 > MAX_DATA_BITS {
+        return err!(ExceptionCode::RangeCheckError, "address is too long: {} bits", len)
+    }
+    let mut builder = BuilderData::new();
+    builder.append_bits(ADDR_VAR_TAG, 2)?;
+    builder.append_bit_zero()?;
+    builder.append_bits(len, 9)?;
+    builder.append_i32(workchain_id)?;
+    builder.append_bytestring(address)?;
+    SliceData::load_builder(builder)
+}
+
+/// Parse slice built by std_addr returning workchain and address
+pub fn parse_std_addr(slice: &SliceData) -> Result<(i8, UInt256)> {
+    let mut slice = slice.clone();
+    if slice.get_next_int(2)? as usize != ADDR_STD_TAG {
+        return err!(ExceptionCode::TypeCheckError, "slice does not contain addr_std")
+    }
+    if slice.get_next_bit()? {
+        return err!(ExceptionCode::TypeCheckError, "anycast addresses are not supported")
+    }
+    let workchain_id = slice.get_next_byte()? as i8;
+    let address = slice.get_next_hash()?;
+    check_remainder(&slice)?;
+    Ok((workchain_id, address))
+}
+
+/// Parse slice built by var_addr returning workchain and address bits
+pub fn parse_var_addr(slice: &SliceData) -> Result<(i32, SliceData)> {
+    let mut slice = slice.clone();
+    if slice.get_next_int(2)? as usize != ADDR_VAR_TAG {
+        return err!(ExceptionCode::TypeCheckError, "slice does not contain addr_var")
+    }
+    if slice.get_next_bit()? {
+        return err!(ExceptionCode::TypeCheckError, "anycast addresses are not supported")
+    }
+    let len = slice.get_next_int(9)? as usize;
+    let workchain_id = slice.get_next_i32()?;
+    let address = slice.get_next_slice(len)?;
+    check_remainder(&slice)?;
+    Ok((workchain_id, address))
+}
+
+fn check_remainder(slice: &SliceData) -> Result<()> {
+    if slice.remaining_bits() != 0 || slice.remaining_references() != 0 {
+        return err!(ExceptionCode::CellOverflow, "extra data after address: {}", slice)
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+#[path = "tests/test_addr.rs"]
+mod tests;
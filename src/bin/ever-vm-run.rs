@@ -0,0 +1,142 @@
+/*
+* Copyright (C) 2019-2024 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+use ever_block::{read_single_root_boc, Cell, Result, SliceData, fail};
+use ever_vm::{
+    executor::{Engine, gas::gas_state::Gas},
+    error::tvm_exception_full,
+    smart_contract_info::SmartContractInfo,
+    stack::{Stack, StackItem, json::parse_stack, savelist::SaveList},
+};
+
+const USAGE: &str = "\
+Usage: ever-vm-run <code.boc> [options]
+Options:
+    --data <data.boc>       contract data placed in c4
+    --stack <json>          initial stack as JSON array: numbers or numeric strings are integers,
+                            null is null, arrays are tuples, {\"cell\": \"<hex boc>\"} and
+                            {\"slice\": \"<hex boc>\"} are cell and slice
+    --gas <limit>           gas limit, 1000000 by default
+    --capabilities <caps>   global capabilities as decimal or 0x-prefixed hex number
+    --vm-capabilities <caps> VM capabilities without network flag, the same format
+    --trace                 print trace of executed instructions";
+
+const DEFAULT_GAS_LIMIT: i64 = 1_000_000;
+
+#[derive(Default)]
+struct Args {
+    code: String,
+    data: Option<String>,
+    stack: Option<String>,
+    gas_limit: Option<i64>,
+    capabilities: u64,
+    vm_capabilities: u64,
+    trace: bool,
+}
+
+fn parse_number(value: &str) -> Result<u64> {
+    match value.strip_prefix("0x") {
+        Some(hex) => Ok(u64::from_str_radix(hex, 16)?),
+        None => Ok(value.parse()?)
+    }
+}
+
+fn parse_args() -> Result<Args> {
+    let mut args = Args::default();
+    let mut iter = std::env::args().skip(1);
+    while let Some(arg) = iter.next() {
+        let mut value = || iter.next().ok_or_else(|| ever_block::error!("{} requires value", arg));
+        match arg.as_str() {
+            "--data" => args.data = Some(value()?),
+            "--stack" => args.stack = Some(value()?),
+            "--gas" => args.gas_limit = Some(value()?.parse()?),
+            "--capabilities" => args.capabilities = parse_number(&value()?)?,
+            "--vm-capabilities" => args.vm_capabilities = parse_number(&value()?)?,
+            "--trace" => args.trace = true,
+            "-h" | "--help" => fail!("{}", USAGE),
+            _ if arg.starts_with('-') || !args.code.is_empty() => fail!("unexpected argument {}\n{}", arg, USAGE),
+            _ => args.code = arg.clone()
+        }
+    }
+    if args.code.is_empty() {
+        fail!("{}", USAGE)
+    }
+    Ok(args)
+}
+
+fn load_boc(filename: &str) -> Result<Cell> {
+    read_single_root_boc(std::fs::read(filename)?)
+}
+
+fn run(args: Args) -> Result<()> {
+    let code = load_boc(&args.code)?;
+    let data = match &args.data {
+        Some(filename) => load_boc(filename)?,
+        None => Cell::default()
+    };
+    let stack = match &args.stack {
+        Some(json) => parse_stack(json)?,
+        None => Stack::new()
+    };
+    let smc_info = SmartContractInfo {
+        capabilities: args.capabilities,
+        vm_capabilities: args.vm_capabilities,
+        mycode: code.clone(),
+        ..Default::default()
+    };
+    let mut ctrls = SaveList::new();
+    ctrls.put(4, &mut StackItem::cell(data))?;
+    ctrls.put(7, &mut smc_info.into_temp_data_item())?;
+    let gas_limit = args.gas_limit.unwrap_or(DEFAULT_GAS_LIMIT);
+    let mut engine = Engine::with_capabilities(args.capabilities)
+        .with_vm_capabilities(args.vm_capabilities)
+        .setup_with_libraries(
+            SliceData::load_cell(code)?,
+            Some(ctrls),
+            Some(stack),
+            Some(Gas::new(gas_limit, 0, gas_limit, 10)),
+            vec![]
+        );
+    if args.trace {
+        engine.set_trace(Engine::TRACE_ALL);
+        engine.set_trace_callback(|_, info| {
+            if info.has_cmd() {
+                println!("{}: {} (gas: {})", info.step, info.cmd_str, info.gas_cmd);
+            }
+        });
+    }
+    let exit_code = match engine.execute() {
+        Ok(exit_code) => exit_code,
+        Err(err) => match tvm_exception_full(&err) {
+            Some(exception) => {
+                println!("exception: {}", exception);
+                exception.exception_or_custom_code()
+            }
+            None => return Err(err)
+        }
+    };
+    println!("stack: {}", engine.get_stack_result_fift());
+    println!("exit code: {}", exit_code);
+    println!("gas used: {}", engine.gas_used());
+    if engine.get_committed_state().is_committed() {
+        println!("committed:{}", engine.get_committed_state_fift());
+    }
+    Ok(())
+}
+
+fn main() {
+    if let Err(err) = parse_args().and_then(run) {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,125 @@
+/*
+* Copyright (C) 2019-2024 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+use ever_block::UInt256;
+use std::collections::{HashMap, HashSet};
+
+/// Key of memoized execution: account state, inbound message and blockchain config hashes
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct ExecutionKey {
+    pub state_hash: UInt256,
+    pub message_hash: UInt256,
+    pub config_hash: UInt256,
+}
+
+impl ExecutionKey {
+    pub fn new(state_hash: UInt256, message_hash: UInt256, config_hash: UInt256) -> Self {
+        Self { state_hash, message_hash, config_hash }
+    }
+}
+
+/// Cache of execution results for simulators replaying the same message over the same state.
+/// Results are dropped explicitly when state they were produced from is changed.
+#[derive(Debug)]
+pub struct ExecutionCache<R> {
+    results: HashMap<ExecutionKey, R>,
+    by_state: HashMap<UInt256, HashSet<ExecutionKey>>,
+    capacity: usize,
+    uncached: Option<R>, // the last result executed by cache of zero capacity
+}
+
+impl<R> Default for ExecutionCache<R> {
+    fn default() -> Self {
+        Self::with_capacity(usize::MAX)
+    }
+}
+
+impl<R> ExecutionCache<R> {
+    /// Creates cache holding no more than capacity results, the whole cache is reset on overflow
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            results: HashMap::new(),
+            by_state: HashMap::new(),
+            capacity,
+            uncached: None,
+        }
+    }
+
+    pub fn get(&self, key: &ExecutionKey) -> Option<&R> {
+        self.results.get(key)
+    }
+
+    pub fn insert(&mut self, key: ExecutionKey, result: R) -> Option<R> {
+        if self.capacity == 0 {
+            return None
+        }
+        if self.results.len() >= self.capacity && !self.results.contains_key(&key) {
+            self.clear();
+        }
+        self.by_state.entry(key.state_hash.clone()).or_default().insert(key.clone());
+        self.results.insert(key, result)
+    }
+
+    /// Returns cached result or executes and memoizes it, cache of zero capacity executes every time
+    pub fn get_or_try_insert_with<E>(
+        &mut self,
+        key: ExecutionKey,
+        execute: impl FnOnce() -> std::result::Result<R, E>
+    ) -> std::result::Result<&R, E> {
+        if !self.results.contains_key(&key) {
+            let result = execute()?;
+            if self.capacity == 0 {
+                return Ok(self.uncached.insert(result))
+            }
+            self.insert(key.clone(), result);
+        }
+        Ok(&self.results[&key])
+    }
+
+    /// Drops all results produced from the state, must be called when account state is changed
+    pub fn invalidate_state(&mut self, state_hash: &UInt256) -> usize {
+        match self.by_state.remove(state_hash) {
+            Some(keys) => keys.iter().filter(|key| self.results.remove(*key).is_some()).count(),
+            None => 0
+        }
+    }
+
+    /// Drops all results produced with the config
+    pub fn invalidate_config(&mut self, config_hash: &UInt256) -> usize {
+        let count = self.results.len();
+        self.results.retain(|key, _| &key.config_hash != config_hash);
+        let results = &self.results;
+        self.by_state.retain(|_, keys| {
+            keys.retain(|key| results.contains_key(key));
+            !keys.is_empty()
+        });
+        count - self.results.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.results.clear();
+        self.by_state.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.results.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.results.is_empty()
+    }
+}
+
+#[cfg(test)]
+#[path = "tests/test_cache.rs"]
+mod tests;
@@ -0,0 +1,95 @@
+/*
+* Copyright (C) 2019-2024 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+use ever_block::{UInt256, MAX_DATA_BITS};
+use std::collections::HashMap;
+
+const BITMAP_WORDS: usize = (MAX_DATA_BITS + 63) / 64;
+
+/// Bitmap of executed instruction offsets in one code cell
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct CellCoverage {
+    bitmap: [u64; BITMAP_WORDS],
+}
+
+impl CellCoverage {
+    pub fn set(&mut self, offset: usize) {
+        if offset < MAX_DATA_BITS {
+            self.bitmap[offset / 64] |= 1 << (offset % 64);
+        }
+    }
+
+    pub fn is_set(&self, offset: usize) -> bool {
+        offset < MAX_DATA_BITS && self.bitmap[offset / 64] & (1 << (offset % 64)) != 0
+    }
+
+    pub fn count(&self) -> usize {
+        self.bitmap.iter().map(|word| word.count_ones() as usize).sum()
+    }
+
+    pub fn offsets(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..MAX_DATA_BITS).filter(|offset| self.is_set(*offset))
+    }
+
+    pub fn merge(&mut self, other: &CellCoverage) {
+        for (word, other) in self.bitmap.iter_mut().zip(other.bitmap.iter()) {
+            *word |= other;
+        }
+    }
+
+    pub fn as_words(&self) -> &[u64] {
+        &self.bitmap
+    }
+}
+
+/// Executed instructions collected with coverage feature:
+/// offsets of instructions in code cells and execution counts of opcodes
+#[derive(Clone, Debug, Default)]
+pub struct Coverage {
+    pub cells: HashMap<UInt256, CellCoverage>,
+    pub opcodes: HashMap<&'static str, u64>,
+}
+
+impl Coverage {
+    pub fn record(&mut self, cell_hash: &UInt256, offset: usize, opcode: Option<&'static str>) {
+        match self.cells.get_mut(cell_hash) {
+            Some(cell) => cell.set(offset),
+            None => self.cells.entry(cell_hash.clone()).or_default().set(offset)
+        }
+        if let Some(opcode) = opcode {
+            *self.opcodes.entry(opcode).or_default() += 1;
+        }
+    }
+
+    pub fn is_covered(&self, cell_hash: &UInt256, offset: usize) -> bool {
+        self.cells.get(cell_hash).map_or(false, |cell| cell.is_set(offset))
+    }
+
+    /// Total number of distinct executed instructions
+    pub fn instructions_count(&self) -> usize {
+        self.cells.values().map(CellCoverage::count).sum()
+    }
+
+    pub fn merge(&mut self, other: &Coverage) {
+        for (hash, cell) in &other.cells {
+            self.cells.entry(hash.clone()).or_default().merge(cell);
+        }
+        for (opcode, count) in &other.opcodes {
+            *self.opcodes.entry(opcode).or_default() += count;
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+}
@@ -0,0 +1,67 @@
+/*
+* Copyright (C) 2019-2024 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+use ever_block::UInt256;
+use std::{collections::HashMap, fmt};
+
+/// Position in source code which instruction was compiled from
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct SourcePosition {
+    pub filename: String,
+    pub line: usize,
+}
+
+impl fmt::Display for SourcePosition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.filename, self.line)
+    }
+}
+
+/// Map of instructions to source positions emitted by compilers,
+/// instruction is identified by hash of code cell and bit offset in it
+#[derive(Clone, Debug, Default)]
+pub struct DebugInfo {
+    positions: HashMap<UInt256, HashMap<usize, SourcePosition>>,
+}
+
+impl DebugInfo {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, cell_hash: UInt256, offset: usize, position: SourcePosition) {
+        self.positions.entry(cell_hash).or_default().insert(offset, position);
+    }
+
+    pub fn find(&self, cell_hash: &UInt256, offset: usize) -> Option<&SourcePosition> {
+        self.positions.get(cell_hash)?.get(&offset)
+    }
+
+    pub fn len(&self) -> usize {
+        self.positions.values().map(HashMap::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+}
+
+impl FromIterator<(UInt256, usize, SourcePosition)> for DebugInfo {
+    fn from_iter<I: IntoIterator<Item = (UInt256, usize, SourcePosition)>>(iter: I) -> Self {
+        let mut debug_info = Self::new();
+        for (cell_hash, offset, position) in iter {
+            debug_info.insert(cell_hash, offset, position);
+        }
+        debug_info
+    }
+}
@@ -11,8 +11,9 @@
 * limitations under the License.
 */
 
-use ever_block::{fail, Error, Result, ExceptionCode};
-use crate::types::Exception;
+use ever_block::{fail, Error, Result, ExceptionCode, UInt256};
+use crate::{debug_info::SourcePosition, stack::StackItem, types::Exception};
+use std::fmt;
 
 #[derive(Debug, thiserror::Error)]
 pub enum TvmError {
@@ -28,6 +29,12 @@ pub enum TvmError {
     /// TVM Exception description
     #[error("VM Exception: {0} {1}")]
     TvmExceptionFull(Exception, String),
+    /// Execution is aborted by step or time limit, it can not be caught by contract
+    #[error("Execution limit exceeded: {0}")]
+    LimitExceeded(String),
+    /// Committed state exceeds commit limits, it can not be caught by contract
+    #[error("Commit rejected: {0}")]
+    CommitRejected(String),
 }
 
 pub fn tvm_exception(err: Error) -> Result<Exception> {
@@ -90,6 +97,189 @@ pub fn update_error_description(mut err: Error, f: impl FnOnce(&str) -> String)
     err
 }
 
+/// State of VM at the instruction which raised the last exception
+#[derive(Clone, Debug)]
+pub struct ErrorContext {
+    pub exception_code: i32,
+    /// Mnemonic of instruction, empty if instruction was not decoded
+    pub opcode: String,
+    /// Hash of code cell and offset of instruction in it
+    pub code_hash: UInt256,
+    pub bit_offset: usize,
+    pub gas_used: i64,
+    /// Several stack items at the moment of exception, the top one is the first
+    pub stack_top: Vec<StackItem>,
+    pub source: Option<SourcePosition>,
+}
+
+impl fmt::Display for ErrorContext {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f, "code {} at {} in cell {:x}:{}, gas used {}",
+            self.exception_code, self.opcode, self.code_hash, self.bit_offset, self.gas_used
+        )?;
+        match &self.source {
+            Some(source) => write!(f, ", source {}", source),
+            None => Ok(())
+        }
+    }
+}
+
+/// Exception caught by handler on the way to the error returned from execution
+#[derive(Clone, Debug)]
+pub struct BacktraceFrame {
+    pub context: ErrorContext,
+    /// Hash of code cell of the handler which has got control
+    pub handler_hash: UInt256,
+    /// Type of handler continuation as it is printed by Fift
+    pub handler_type: &'static str,
+}
+
+impl fmt::Display for BacktraceFrame {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} caught by {} in cell {:x}", self.context, self.handler_type, self.handler_hash)
+    }
+}
+
+/// Execution error with context of failed instruction
+#[derive(Debug)]
+pub struct VmError {
+    pub error: Error,
+    pub context: Option<ErrorContext>,
+    /// Exceptions caught before the final one, the oldest is the first
+    pub backtrace: Vec<BacktraceFrame>,
+}
+
+impl VmError {
+    pub fn vm_backtrace(&self) -> &[BacktraceFrame] {
+        &self.backtrace
+    }
+
+    pub fn status(&self) -> VmStatusCode {
+        VmStatusCode::from_error(&self.error)
+    }
+}
+
+impl fmt::Display for VmError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.context {
+            Some(context) => write!(f, "{} ({})", self.error, context),
+            None => write!(f, "{}", self.error)
+        }
+    }
+}
+
+impl std::error::Error for VmError {}
+
+/// Category of VM status, stable across crate versions
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum VmStatusCategory {
+    /// Execution finished normally
+    Success,
+    /// Standard TVM exception
+    Exception,
+    /// Exception thrown by contract code with custom code
+    Custom,
+    /// Engine was set up or fed with wrong data
+    Setup,
+    /// Internal failure not caused by executed code
+    Fatal,
+}
+
+/// Stable status of VM execution suitable for external APIs.
+/// Numeric values of statuses are part of public contract and must never be changed:
+/// exit codes and TVM exceptions keep their own values, custom codes are shifted by CUSTOM_BASE,
+/// setup and internal failures are mapped to negative values below -1000.
+/// exit_code() returns the code as TVM reports it, where custom codes and exceptions overlap
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum VmStatusCode {
+    Success(i32),
+    Exception(ExceptionCode),
+    Custom(i32),
+    InvalidArg,
+    InvalidData,
+    Fatal,
+    Unknown,
+    LimitExceeded,
+    CommitRejected,
+}
+
+impl VmStatusCode {
+    pub const INVALID_ARG: i32 = -1001;
+    pub const INVALID_DATA: i32 = -1002;
+    pub const FATAL: i32 = -1003;
+    pub const UNKNOWN: i32 = -1004;
+    pub const LIMIT_EXCEEDED: i32 = -1005;
+    pub const COMMIT_REJECTED: i32 = -1006;
+    /// Custom code n is reported by code() as CUSTOM_BASE + n
+    pub const CUSTOM_BASE: i32 = 0x1_0000;
+
+    pub fn from_error(err: &Error) -> Self {
+        match err.downcast_ref::<TvmError>() {
+            Some(TvmError::TvmExceptionFull(err, _)) => match err.exception_code() {
+                Some(code) => Self::Exception(code),
+                None => Self::Custom(err.exception_or_custom_code())
+            }
+            Some(TvmError::InvalidArg(_)) => Self::InvalidArg,
+            Some(TvmError::InvalidData(_)) => Self::InvalidData,
+            Some(TvmError::FatalError(_)) => Self::Fatal,
+            Some(TvmError::LimitExceeded(_)) => Self::LimitExceeded,
+            Some(TvmError::CommitRejected(_)) => Self::CommitRejected,
+            None => match err.downcast_ref::<ExceptionCode>() {
+                Some(code) => Self::Exception(*code),
+                None => Self::Unknown
+            }
+        }
+    }
+
+    /// Status of execution result returned by Engine::execute
+    pub fn from_result(result: &Result<i32>) -> Self {
+        match result {
+            Ok(exit_code) => Self::Success(*exit_code),
+            Err(err) => Self::from_error(err)
+        }
+    }
+
+    /// Unique code of the status
+    pub fn code(&self) -> i32 {
+        match self {
+            Self::Success(code) => *code,
+            Self::Custom(code) => Self::CUSTOM_BASE + *code,
+            Self::Exception(code) => *code as i32,
+            Self::InvalidArg => Self::INVALID_ARG,
+            Self::InvalidData => Self::INVALID_DATA,
+            Self::Fatal => Self::FATAL,
+            Self::Unknown => Self::UNKNOWN,
+            Self::LimitExceeded => Self::LIMIT_EXCEEDED,
+            Self::CommitRejected => Self::COMMIT_REJECTED,
+        }
+    }
+
+    /// Exit code of TVM: custom codes are not shifted, so they can be equal to exception codes
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::Custom(code) => *code,
+            status => status.code()
+        }
+    }
+
+    pub fn category(&self) -> VmStatusCategory {
+        match self {
+            Self::Success(_) => VmStatusCategory::Success,
+            Self::Exception(_) => VmStatusCategory::Exception,
+            Self::Custom(_) => VmStatusCategory::Custom,
+            Self::InvalidArg | Self::InvalidData | Self::LimitExceeded | Self::CommitRejected => VmStatusCategory::Setup,
+            Self::Fatal | Self::Unknown => VmStatusCategory::Fatal,
+        }
+    }
+}
+
+impl From<&Error> for VmStatusCode {
+    fn from(err: &Error) -> Self {
+        Self::from_error(err)
+    }
+}
+
 #[cfg(test)]
 #[path = "tests/test_error.rs"]
 mod tests;
@@ -454,7 +454,7 @@ fn process_stakers(stakers: Vec<ShardAccount>, gas_consumer: &mut dyn GasConsume
             Ok((addr, staker)) => {
                 contracts.entry(addr).or_default().push(staker);
             }
-            Err(err) => log::trace!(target: "tvm", "staker was not used due to: {}", err),
+            Err(err) => tvm_log!(trace, crate::logging::targets::ACCOUNTS, "staker was not used due to: {}", err),
         }
     }
     contracts
@@ -472,7 +472,7 @@ fn find_validators(engine: &mut Engine, cfg17: &ConfigParam17) -> Result<Vec<Val
     };
     let mc_seqno = engine.smci_param(13)?.as_integer()?.into(0..=u32::MAX)?;
 
-    log::trace!(target: "tvm", "found {} validators", validators.len());
+    tvm_log!(trace, crate::logging::targets::ACCOUNTS, "found {} validators", validators.len());
     let stakers = &mut process_stakers(stakers, engine);
     let cfg34_result = engine.read_config_param::<ConfigParam34>(34);
     let cur_validators = match cfg34_result.as_ref() {
@@ -485,7 +485,7 @@ fn find_validators(engine: &mut Engine, cfg17: &ConfigParam17) -> Result<Vec<Val
     for validator in &validators {
         match process_validator(validator, stakers, &min_stake, &max_stake, engine) {
             Err(err) => {
-                log::trace!(target: "tvm", "cannot use depool contract of account {:x}: {:?}",
+                tvm_log!(trace, crate::logging::targets::ACCOUNTS, "cannot use depool contract of account {:x}: {:?}",
                     validator.account_cell().repr_hash(), err);
             }
             Ok(mut descr) => {
@@ -0,0 +1,82 @@
+/*
+* Copyright (C) 2019-2024 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+use ever_block::{Cell, Deserializable, OutAction, OutActions, Result};
+
+/// Decodes list of output actions committed to c5, actions are returned in order of creation
+pub fn decode_actions(c5: &Cell) -> Result<Vec<OutAction>> {
+    Ok(OutActions::construct_from_cell(c5.clone())?.into_iter().collect())
+}
+
+/// Modes of reserve action, they are combined
+pub mod reserve_mode {
+    /// reserve all the remaining balance but the amount
+    pub const ALL_BUT: u8 = 1;
+    /// reserve as much as possible instead of failing if the balance is insufficient
+    pub const IGNORE_ERROR: u8 = 2;
+    /// amount is added to the original balance, i.e. balance before compute phase
+    pub const PLUS_ORIGINAL: u8 = 4;
+    /// amount is subtracted from the original balance instead, valid only with PLUS_ORIGINAL
+    pub const REVERSE: u8 = 8;
+    /// transaction is bounced if the action fails, allowed under VmCapabilities::CapReserveBounce
+    pub const BOUNCE_ON_FAIL: u8 = 16;
+    pub const MAX: u8 = 31;
+}
+
+/// Result code of action phase for invalid action
+pub const RESULT_INVALID_ACTION: i32 = 34;
+/// Result code of action phase for insufficient balance
+pub const RESULT_NOT_ENOUGH_FUNDS: i32 = 37;
+
+/// Nanograms of reserve action predicted by predict_reserve()
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ReserveOutcome {
+    pub reserved: u128,
+    /// balance remaining for further actions
+    pub remaining: u128,
+}
+
+/// Predicts reserve made by action of RAWRESERVE with given mode and amount the way action
+/// phase does it. original_balance is balance before compute phase and remaining_balance is
+/// balance remaining when action is processed. Error is result code of action phase
+pub fn predict_reserve(
+    mode: u8,
+    amount: u128,
+    original_balance: u128,
+    remaining_balance: u128,
+) -> std::result::Result<ReserveOutcome, i32> {
+    if mode & !reserve_mode::MAX != 0 {
+        return Err(RESULT_INVALID_ACTION)
+    }
+    let mut reserve = amount;
+    if mode & reserve_mode::PLUS_ORIGINAL != 0 {
+        reserve = match mode & reserve_mode::REVERSE {
+            0 => original_balance.checked_add(reserve).ok_or(RESULT_INVALID_ACTION)?,
+            _ => original_balance.checked_sub(reserve).ok_or(RESULT_INVALID_ACTION)?
+        };
+    } else if mode & reserve_mode::REVERSE != 0 {
+        return Err(RESULT_INVALID_ACTION)
+    }
+    if mode & reserve_mode::IGNORE_ERROR != 0 {
+        reserve = reserve.min(remaining_balance);
+    }
+    let rest = remaining_balance.checked_sub(reserve).ok_or(RESULT_NOT_ENOUGH_FUNDS)?;
+    Ok(match mode & reserve_mode::ALL_BUT {
+        0 => ReserveOutcome { reserved: reserve, remaining: rest },
+        _ => ReserveOutcome { reserved: rest, remaining: reserve }
+    })
+}
+
+#[cfg(test)]
+#[path = "../tests/test_actions.rs"]
+mod tests;
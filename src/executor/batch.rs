@@ -0,0 +1,111 @@
+/*
+* Copyright (C) 2019-2024 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+use crate::{
+    executor::{
+        engine::Engine,
+        harness::{run_code, HarnessParams, HarnessResult}
+    },
+    stack::{Stack, StackItem, integer::IntegerData}
+};
+use ever_block::{error, Cell, Result};
+use std::{
+    panic::{catch_unwind, AssertUnwindSafe}, sync::{Arc, Mutex}, thread
+};
+
+// every job gets its own engine on a worker thread, engine internals must stay movable
+const _: fn() = || {
+    fn assert_send<T: Send>() {}
+    assert_send::<Engine>();
+    assert_send::<Job>();
+    assert_send::<Outcome>();
+};
+
+/// Independent contract run. Cells of code, data and params are shared between jobs
+/// and threads without copying
+#[derive(Clone, Debug)]
+pub struct Job {
+    pub code: Cell,
+    pub data: Cell,
+    pub stack: Stack,
+    pub params: Arc<HarnessParams>,
+}
+
+impl Job {
+    pub fn new(code: Cell, data: Cell, stack: Stack, params: Arc<HarnessParams>) -> Self {
+        Self { code, data, stack, params }
+    }
+
+    /// Get method call: stack is params followed by method id
+    pub fn get_method(
+        code: Cell,
+        data: Cell,
+        method_id: u32,
+        params: Vec<StackItem>,
+        harness_params: Arc<HarnessParams>
+    ) -> Self {
+        let mut stack = Stack::with_storage(params);
+        stack.push(int!(method_id));
+        Self::new(code, data, stack, harness_params)
+    }
+
+    fn run(self) -> Outcome {
+        match catch_unwind(AssertUnwindSafe(|| run_code(self.code, self.data, self.stack, &self.params))) {
+            Ok(outcome) => outcome,
+            Err(_) => Err(error!("job execution panicked"))
+        }
+    }
+}
+
+pub type Outcome = Result<HarnessResult>;
+
+/// Executes jobs on all available cores, outcomes are returned in order of jobs
+pub fn run_many(jobs: Vec<Job>) -> Vec<Outcome> {
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    run_many_with_threads(jobs, threads)
+}
+
+/// Executes jobs on given number of worker threads, outcomes are returned in order of jobs
+pub fn run_many_with_threads(jobs: Vec<Job>, threads: usize) -> Vec<Outcome> {
+    let count = jobs.len();
+    let threads = threads.clamp(1, count.max(1));
+    if threads == 1 || !crate::platform::THREADS_AVAILABLE {
+        return jobs.into_iter().map(Job::run).collect()
+    }
+    let queue = Mutex::new(jobs.into_iter().enumerate());
+    let next = || queue.lock().unwrap().next();
+    let mut outcomes: Vec<Option<Outcome>> = Vec::with_capacity(count);
+    outcomes.resize_with(count, || None);
+    thread::scope(|scope| {
+        let workers = (0..threads).map(|_| scope.spawn(|| {
+            let mut done = Vec::new();
+            while let Some((index, job)) = next() {
+                done.push((index, job.run()));
+            }
+            done
+        })).collect::<Vec<_>>();
+        for worker in workers {
+            // jobs never panic outside of catch_unwind, so join always succeeds
+            for (index, outcome) in worker.join().unwrap_or_default() {
+                outcomes[index] = Some(outcome);
+            }
+        }
+    });
+    outcomes.into_iter()
+        .map(|outcome| outcome.unwrap_or_else(|| Err(error!("job was not executed"))))
+        .collect()
+}
+
+#[cfg(test)]
+#[path = "../tests/test_batch.rs"]
+mod tests;
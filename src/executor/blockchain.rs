@@ -13,7 +13,8 @@
 
 use crate::{
     executor::{
-        engine::{storage::fetch_stack, Engine},
+        actions::reserve_mode,
+        engine::{storage::fetch_stack, Engine, VmCapabilities},
         serialize_currency_collection,
         types::Instruction,
     },
@@ -28,8 +29,8 @@ use crate::{
 };
 use num::{bigint::Sign, BigInt};
 use ever_block::{
-    Deserializable, GlobalCapabilities, MsgAddressInt, ACTION_CHANGE_LIB, ACTION_COPYLEFT,
-    ACTION_RESERVE, ACTION_SEND_MSG, ACTION_SET_CODE,
+    Deserializable, GlobalCapabilities, MsgAddressInt, MsgForwardPrices, ACTION_CHANGE_LIB,
+    ACTION_COPYLEFT, ACTION_RESERVE, ACTION_SEND_MSG, ACTION_SET_CODE,
 };
 use ever_block::{
     types::ExceptionCode, BuilderData, Cell, GasConsumer, IBitstring, Result, SliceData,
@@ -62,12 +63,27 @@ fn add_action(engine: &mut Engine, action_id: u32, cell: Option<Cell>, suffix: B
     Ok(())
 }
 
+// mode of library change: 0 - remove, 1 - add private, 2 - add public,
+// since CapLibraryBounce it can be combined with bounce flag 16 as in the reference VM
+fn library_mode(engine: &Engine) -> Result<u8> {
+    let x = engine.cmd.var(0).as_integer()?;
+    if engine.has_vm_capability(VmCapabilities::CapLibraryBounce) {
+        let x = x.into(0..=18)? as u8;
+        if x & !16 > 2 {
+            return err!(ExceptionCode::RangeCheckError, "wrong library mode {}", x)
+        }
+        Ok(x)
+    } else {
+        Ok(x.into(0..=2)? as u8)
+    }
+}
+
 /// CHANGELIB (h x - )
 pub(super) fn execute_changelib(engine: &mut Engine) -> Status {
     engine.check_capability(GlobalCapabilities::CapSetLibCode)?;
     engine.load_instruction(Instruction::new("CHANGELIB"))?;
     fetch_stack(engine, 2)?;
-    let x = engine.cmd.var(0).as_integer()?.into(0..=2)? as u8;
+    let x = library_mode(engine)?;
     let hash = engine.cmd.var(1).as_integer()?.as_builder::<UnsignedIntegerBigEndianEncoding>(256)?;
     let mut suffix = BuilderData::with_raw(vec![x * 2], 8)?;
     suffix.append_builder(&hash)?;
@@ -85,6 +101,87 @@ pub(super) fn execute_sendrawmsg(engine: &mut Engine) -> Status {
     add_action(engine, ACTION_SEND_MSG, Some(cell), suffix)
 }
 
+// mode bit of SENDMSG to estimate fee without sending message
+const SENDMSG_ESTIMATE_ONLY: usize = 1024;
+
+/// Forwarding fee of message with given number of cells and bits, root cell is not counted:
+/// lump_price + ceil((bit_price * bits + cell_price * cells) / 2^16)
+pub fn compute_fwd_fee(prices: &MsgForwardPrices, cells: u64, bits: u64) -> u128 {
+    let price = prices.bit_price as u128 * bits as u128 + prices.cell_price as u128 * cells as u128;
+    prices.lump_price as u128 + ((price + 0xFFFF) >> 16)
+}
+
+// workchain of internal address, None for other ones
+fn address_workchain(slice: &mut SliceData) -> Result<Option<i32>> {
+    let address = parse_address(slice, false)?;
+    match address[0].as_integer()?.into(0..=3u8)? {
+        0b10 | 0b11 => Ok(Some(address[2].as_integer()?.into(i32::MIN..=i32::MAX)?)),
+        _ => Ok(None)
+    }
+}
+
+/// SENDMSG (c x - fee): computes forwarding fee of message c with prices of config param 24
+/// if source or destination is in masterchain, otherwise with prices of config param 25.
+/// Internal message with ihr_disabled unset is also charged IHR fee: fwd_fee * ihr_price_factor / 2^16.
+/// Message is put to output actions with mode x like SENDRAWMSG,
+/// if bit 1024 of x is set only the fee is computed.
+/// Cells of c are loaded and charged as by other instructions, inbound external message is rejected
+pub(super) fn execute_sendmsg(engine: &mut Engine) -> Status {
+    engine.load_instruction(Instruction::new("SENDMSG"))?;
+    fetch_stack(engine, 2)?;
+    let x = engine.cmd.var(0).as_integer()?.into(0..=2047)?;
+    if x & !(SENDMSG_ESTIMATE_ONLY | 0xFF) != 0 {
+        return err!(ExceptionCode::RangeCheckError, "wrong mode {}", x)
+    }
+    let cell = engine.cmd.var(1).as_cell()?.clone();
+    let mut header = engine.load_cell(cell.clone())?;
+    let (dest_workchain, ihr_disabled) = if header.get_next_bit()? {
+        // ext_out_msg_info$11 src:MsgAddressInt dest:MsgAddressExt
+        if !header.get_next_bit()? {
+            return err!(ExceptionCode::UnknownError, "inbound external message can not be sent")
+        }
+        (None, true)
+    } else {
+        // int_msg_info$0 ihr_disabled:Bool bounce:Bool bounced:Bool src:MsgAddressInt dest:MsgAddressInt
+        let ihr_disabled = header.get_next_bit()?;
+        header.move_by(2)?;
+        address_workchain(&mut header)?;
+        (address_workchain(&mut header)?, ihr_disabled)
+    };
+    let my_workchain = address_workchain(&mut engine.smci_param(8)?.as_slice()?.clone())?;
+    let masterchain = dest_workchain == Some(-1) || my_workchain == Some(-1);
+    let prices = engine.read_config_param::<MsgForwardPrices>(if masterchain { 24 } else { 25 })?;
+
+    // cells are loaded as is and charged as any other cell load, fee counts distinct ones
+    let mut visited = std::collections::HashSet::new();
+    let (mut cells, mut bits) = (0, 0);
+    let mut queue = Vec::new();
+    for i in 0..cell.references_count() {
+        queue.push(cell.reference(i)?);
+    }
+    while let Some(cell) = queue.pop() {
+        if visited.insert(cell.repr_hash()) {
+            engine.load_hashed_cell(cell.clone(), false)?;
+            cells += 1;
+            bits += cell.bit_length() as u64;
+            for i in 0..cell.references_count() {
+                queue.push(cell.reference(i)?);
+            }
+        }
+    }
+    let mut fee = compute_fwd_fee(&prices, cells, bits);
+    if !ihr_disabled {
+        fee += (fee * prices.ihr_price_factor as u128) >> 16;
+    }
+
+    if x & SENDMSG_ESTIMATE_ONLY == 0 {
+        let suffix = BuilderData::with_raw(vec![x as u8], 8)?;
+        add_action(engine, ACTION_SEND_MSG, Some(cell), suffix)?;
+    }
+    engine.cc.stack.push(int!(fee));
+    Ok(())
+}
+
 /// SETCODE (c - )
 pub(super) fn execute_setcode(engine: &mut Engine) -> Status {
     engine.load_instruction(Instruction::new("SETCODE"))?;
@@ -98,7 +195,7 @@ pub(super) fn execute_setlibcode(engine: &mut Engine) -> Status {
     engine.check_capability(GlobalCapabilities::CapSetLibCode)?;
     engine.load_instruction(Instruction::new("SETLIBCODE"))?;
     fetch_stack(engine, 2)?;
-    let x = engine.cmd.var(0).as_integer()?.into(0..=2)? as u8;
+    let x = library_mode(engine)?;
     let cell = engine.cmd.var(1).as_cell()?.clone();
     add_action(engine, ACTION_CHANGE_LIB, Some(cell), BuilderData::with_raw(vec![x * 2 + 1], 8)?)
 }
@@ -106,7 +203,9 @@ pub(super) fn execute_setlibcode(engine: &mut Engine) -> Status {
 /// COPYLEFT (s n - )
 pub(super) fn execute_copyleft(engine: &mut Engine) -> Status {
     engine.check_capability(GlobalCapabilities::CapCopyleft)?;
-    if engine.check_or_set_flags(Engine::FLAG_COPYLEFTED) {
+    // since CapTvmV20 instruction is paid and failed one does not prevent another attempt
+    let v20 = engine.check_capabilities(GlobalCapabilities::CapTvmV20 as u64);
+    if !v20 && engine.check_or_set_flags(Engine::FLAG_COPYLEFTED) {
         return Status::Err(ExceptionCode::IllegalInstruction.into());
     }
     engine.load_instruction(Instruction::new("COPYLEFT"))?;
@@ -122,28 +221,41 @@ pub(super) fn execute_copyleft(engine: &mut Engine) -> Status {
         }
         let mut suffix = BuilderData::new();
         suffix.append_raw(&num, 8)?.append_bytestring(slice)?;
+        if v20 && engine.check_or_set_flags(Engine::FLAG_COPYLEFTED) {
+            return Status::Err(ExceptionCode::IllegalInstruction.into());
+        }
         add_action(engine, ACTION_COPYLEFT, None, suffix)
     } else {
         Ok(())
     }
 }
 
-/// RAWRESERVE (x y - )
+// mode of reserve is checked by action phase, see actions::predict_reserve(),
+// under CapReserveBounce it can be combined with bounce flag 16 as in the reference VM
+fn reserve_mode(engine: &Engine) -> Result<u8> {
+    let max = match engine.has_vm_capability(VmCapabilities::CapReserveBounce) {
+        true => reserve_mode::MAX,
+        false => reserve_mode::MAX & !reserve_mode::BOUNCE_ON_FAIL
+    };
+    engine.cmd.var(0).as_integer()?.into(0..=max)
+}
+
+/// RAWRESERVE (x y - ): reserves x nanograms with mode y, see actions::reserve_mode
 pub(super) fn execute_rawreserve(engine: &mut Engine) -> Status {
     engine.load_instruction(Instruction::new("RAWRESERVE"))?;
     fetch_stack(engine, 2)?;
-    let y = engine.cmd.var(0).as_integer()?.into(0..=15)?;
+    let y = reserve_mode(engine)?;
     let mut suffix = BuilderData::with_raw(vec![y], 8)?;
     let x = engine.cmd.var(1).as_grams()?;
     suffix.append_builder(&serialize_currency_collection(x, None)?)?;
     add_action(engine, ACTION_RESERVE, None, suffix)
 }
 
-/// RAWRESERVEX (s y - )
+/// RAWRESERVEX (x D y - ): reserves x nanograms and extra currencies D with mode y
 pub(super) fn execute_rawreservex(engine: &mut Engine) -> Status {
     engine.load_instruction(Instruction::new("RAWRESERVEX"))?;
     fetch_stack(engine, 3)?;
-    let y = engine.cmd.var(0).as_integer()?.into(0..=15)?;
+    let y = reserve_mode(engine)?;
     let mut suffix = BuilderData::with_raw(vec![y], 8)?;
     let other = engine.cmd.var(1).as_dict()?;
     let x = engine.cmd.var(2).as_grams()?;
@@ -151,6 +263,39 @@ pub(super) fn execute_rawreservex(engine: &mut Engine) -> Status {
     add_action(engine, ACTION_RESERVE, None, suffix)
 }
 
+/// BUILDMSG (b dest x D bounce - b'): stores header of internal message sending x nanograms
+/// and D extra currencies to dest. Source address, fees and creation time are left zero
+/// to be filled by transaction executor
+pub(super) fn execute_buildmsg(engine: &mut Engine) -> Status {
+    engine.load_instruction(Instruction::new("BUILDMSG"))?;
+    fetch_stack(engine, 5)?;
+    let bounce = engine.cmd.var(0).as_bool()?;
+    let other = engine.cmd.var(1).as_dict()?.cloned();
+    let value = engine.cmd.var(2).as_grams()?;
+    let dest = engine.cmd.var(3).as_slice()?;
+    let mut remainder = dest.clone();
+    let tag = parse_address(&mut remainder, true)?[0].as_integer()?.into(0..=3u8)?;
+    if tag < 0b10 || remainder.remaining_bits() != 0 || remainder.remaining_references() != 0 {
+        return err!(ExceptionCode::RangeCheckError, "destination is not an internal address")
+    }
+    let mut header = BuilderData::new();
+    // int_msg_info$0 ihr_disabled:Bool bounce:Bool bounced:Bool src:addr_none$00
+    header.append_bits(0b010000 | (bounce as usize) << 3, 6)?;
+    header.append_bytestring(dest)?;
+    header.append_builder(&serialize_currency_collection(value, other)?)?;
+    // ihr_fee:Grams fwd_fee:Grams created_lt:uint64 created_at:uint32
+    header.append_bits(0, 8)?;
+    header.append_raw(&[0; 12], 96)?;
+    let b = engine.cmd.var(4).as_builder()?;
+    if !engine.builder_limits().check_space(b, header.bits_used(), header.references_used()) {
+        return err!(ExceptionCode::CellOverflow)
+    }
+    let mut b = engine.cmd.var_mut(4).as_builder_mut()?;
+    b.append_builder(&header)?;
+    engine.cc.stack.push_builder(b);
+    Ok(())
+}
+
 pub(super) fn execute_ldmsgaddr<T: OperationBehavior>(engine: &mut Engine) -> Status {
     engine.load_instruction(
         Instruction::new(if T::quiet() {"LDMSGADDRQ"} else {"LDMSGADDR"})
@@ -158,7 +303,8 @@ pub(super) fn execute_ldmsgaddr<T: OperationBehavior>(engine: &mut Engine) -> St
     fetch_stack(engine, 1)?;
     let mut slice = engine.cmd.var(0).as_slice()?.clone();
     let mut remainder = slice.clone();
-    if parse_address(&mut remainder).is_ok() {
+    let strict = engine.has_vm_capability(VmCapabilities::CapStrictAnycast);
+    if parse_address(&mut remainder, strict).is_ok() {
         slice.shrink_by_remainder(&remainder);
         engine.cc.stack.push(StackItem::Slice(slice));
         engine.cc.stack.push(StackItem::Slice(remainder));
@@ -182,7 +328,8 @@ where F: FnOnce(Vec<StackItem>, &mut dyn GasConsumer) -> Result<Vec<StackItem>>,
     fetch_stack(engine, 1)?;
     let mut slice = engine.cmd.var(0).as_slice()?.clone();
     let mut result = false;
-    if let Ok(addr) = parse_address(&mut slice) {
+    let strict = engine.has_vm_capability(VmCapabilities::CapStrictAnycast);
+    if let Ok(addr) = parse_address(&mut slice, strict) {
         if let Ok(mut stack) = op(addr, engine) {
             stack.drain(..).for_each(|var| {engine.cc.stack.push(var);});
             result = true;
@@ -256,17 +403,22 @@ pub(super) fn execute_rewrite_var_addr<T: OperationBehavior>(engine: &mut Engine
     })
 }
 
-fn read_rewrite_pfx(cell: &mut SliceData) -> Result<Option<SliceData>> {
+// anycast_info$_ depth:(#<= 30) { depth >= 1 } rewrite_pfx:(bits depth)
+// constraints on depth are checked in strict mode only as they were ignored before CapStrictAnycast
+fn read_rewrite_pfx(cell: &mut SliceData, strict: bool) -> Result<Option<SliceData>> {
     match cell.get_next_bit()? {
         true => {
             let len = cell.get_next_int(5)?;
+            if strict && !(1..=30).contains(&len) {
+                return err!(ExceptionCode::CellUnderflow, "wrong anycast depth {}", len)
+            }
             Ok(Some(cell.get_next_slice(len as usize)?))
         }
         false => Ok(None)
     }
 }
 
-fn parse_address(cell: &mut SliceData) -> Result<Vec<StackItem>> {
+fn parse_address(cell: &mut SliceData, strict: bool) -> Result<Vec<StackItem>> {
     let addr_type = cell.get_next_int(2)? as u8;
     let mut tuple = vec!(int!(addr_type));
     match addr_type & 0b11 {
@@ -276,7 +428,7 @@ fn parse_address(cell: &mut SliceData) -> Result<Vec<StackItem>> {
             tuple.push(StackItem::Slice(cell.get_next_slice(len as usize)?));
         }
         0b10 => {
-            tuple.push(match read_rewrite_pfx(cell)? {
+            tuple.push(match read_rewrite_pfx(cell, strict)? {
                 Some(slice) => StackItem::Slice(slice),
                 None => StackItem::None
             });
@@ -284,7 +436,7 @@ fn parse_address(cell: &mut SliceData) -> Result<Vec<StackItem>> {
             tuple.push(StackItem::Slice(cell.get_next_slice(256)?));
         }
         0b11 => {
-            tuple.push(match read_rewrite_pfx(cell)? {
+            tuple.push(match read_rewrite_pfx(cell, strict)? {
                 Some(slice) => StackItem::Slice(slice),
                 None => StackItem::None
             });
@@ -15,7 +15,7 @@ use std::{str::FromStr, sync::Arc, usize};
 
 use crate::{
     executor::{
-        engine::{storage::fetch_stack, Engine}, gas::gas_state::Gas, types::Instruction
+        engine::{storage::fetch_stack, Engine, VmCapabilities}, gas::gas_state::Gas, types::Instruction
     },
     stack::{
         integer::{
@@ -69,7 +69,10 @@ fn slice_to_msg(slice: &SliceData) -> Result<Vec<u8>> {
 pub(super) fn execute_bls_verify(engine: &mut Engine) -> Status {
     engine.check_capability(GlobalCapabilities::CapTvmV20)?;
     engine.load_instruction(Instruction::new("BLS_VERIFY"))?;
-    engine.try_use_gas(Gas::bls_verify_gas_price())?;
+    // price was charged twice before the fix
+    if !engine.has_vm_capability(VmCapabilities::CapBlsGasFix) {
+        engine.try_use_gas(Gas::bls_verify_gas_price())?;
+    }
 
     fetch_stack(engine, 3)?;
 
@@ -15,7 +15,10 @@ use crate::{
     executor::{engine::{Engine, storage::fetch_stack}, types::{InstructionOptions, Instruction}},
     stack::{StackItem, integer::IntegerData}, types::Status
 };
-use ever_block::GlobalCapabilities;
+use ever_block::{
+    Cell, Deserializable, ExceptionCode, GasConsumer, GasLimitsPrices, GlobalCapabilities, Result,
+    SizeLimitsConfig, SliceData
+};
 
 fn execute_config_param(engine: &mut Engine, name: &'static str, opt: bool) -> Status {
     engine.load_instruction(Instruction::new(name))?;
@@ -60,6 +63,79 @@ pub(super) fn execute_config_ref_param(engine: &mut Engine) -> Status {
     execute_config_param(engine, "CONFIGPARAM", false)
 }
 
+/// Gas prices of workchain as tuple
+/// [ gas_price flat_gas_limit flat_gas_price gas_limit special_gas_limit gas_credit
+///   block_gas_limit freeze_due_limit delete_due_limit ]
+pub fn gas_prices_item(prices: &GasLimitsPrices) -> StackItem {
+    StackItem::tuple(vec![
+        int!(prices.gas_price),
+        int!(prices.flat_gas_limit),
+        int!(prices.flat_gas_price),
+        int!(prices.gas_limit),
+        int!(prices.special_gas_limit),
+        int!(prices.gas_credit),
+        int!(prices.block_gas_limit),
+        int!(prices.freeze_due_limit),
+        int!(prices.delete_due_limit),
+    ])
+}
+
+/// Size limits as tuple
+/// [ max_msg_bits max_msg_cells max_library_cells max_vm_data_depth max_ext_msg_size max_ext_msg_depth ]
+pub fn size_limits_item(limits: &SizeLimitsConfig) -> StackItem {
+    StackItem::tuple(vec![
+        int!(limits.max_msg_bits),
+        int!(limits.max_msg_cells),
+        int!(limits.max_library_cells),
+        int!(limits.max_vm_data_depth),
+        int!(limits.max_ext_msg_size),
+        int!(limits.max_ext_msg_depth),
+    ])
+}
+
+// loads all cells of config param, so decoding of the param is charged as cell loads
+fn load_config_param(engine: &mut Engine, cell: Cell) -> Result<SliceData> {
+    let slice = engine.load_cell(cell)?;
+    for i in 0..slice.remaining_references() {
+        load_config_param(engine, slice.reference(i)?)?;
+    }
+    Ok(slice)
+}
+
+// decodes config param if it is present, otherwise pushes null
+fn decoded_config_param<T: Deserializable>(
+    engine: &mut Engine,
+    index: i32,
+    item: impl FnOnce(&T) -> StackItem
+) -> Status {
+    let value = match engine.get_config_param(index)? {
+        Some(cell) => {
+            let mut slice = load_config_param(engine, cell)?;
+            let param = T::construct_from(&mut slice).map_err(|err| exception!(
+                ExceptionCode::CellUnderflow, "cannot decode config param {}: {}", index, err
+            ))?;
+            item(&param)
+        }
+        None => StackItem::None
+    };
+    engine.cc.stack.push(value);
+    Ok(())
+}
+
+/// (f - t or null): gas prices of masterchain if f is true, otherwise of basechain
+pub(super) fn execute_config_gas_prices(engine: &mut Engine) -> Status {
+    engine.load_instruction(Instruction::new("CONFIGGASPRICES"))?;
+    fetch_stack(engine, 1)?;
+    let index = if engine.cmd.var(0).as_bool()? { 20 } else { 21 };
+    decoded_config_param(engine, index, gas_prices_item)
+}
+
+/// ( - t or null)
+pub(super) fn execute_config_size_limits(engine: &mut Engine) -> Status {
+    engine.load_instruction(Instruction::new("CONFIGSIZELIMITS"))?;
+    decoded_config_param(engine, 43, size_limits_item)
+}
+
 fn extract_config(engine: &mut Engine, name: &'static str) -> Status {
     engine.load_instruction(
         Instruction::new(name).set_opts(InstructionOptions::Length(0..16))
@@ -127,3 +203,47 @@ pub(super) fn execute_storage_fees_collected(engine: &mut Engine) -> Status {
     engine.check_capability(GlobalCapabilities::CapStorageFeeToTvm)?;
     extract_config(engine, "STORAGEFEE")
 }
+
+// - t
+pub(super) fn execute_prev_blocks_info(engine: &mut Engine) -> Status {
+    extract_config(engine, "PREVBLOCKSINFOTUPLE")
+}
+
+fn prev_blocks_info_item(engine: &mut Engine, name: &'static str, index: usize) -> Status {
+    engine.load_instruction(Instruction::new(name))?;
+    let value = engine.smci_param(14)?.tuple_item(index, false)?;
+    engine.cc.stack.push(value);
+    Ok(())
+}
+
+// - t
+pub(super) fn execute_prev_mc_blocks(engine: &mut Engine) -> Status {
+    prev_blocks_info_item(engine, "PREVMCBLOCKS", 0)
+}
+
+// - t
+pub(super) fn execute_prev_key_block(engine: &mut Engine) -> Status {
+    prev_blocks_info_item(engine, "PREVKEYBLOCK", 1)
+}
+
+fn smci_param_item(engine: &mut Engine, name: &'static str, index: usize) -> Status {
+    engine.load_instruction(Instruction::new(name))?;
+    let value = engine.smci_param(index)?.clone();
+    engine.cc.stack.push(value);
+    Ok(())
+}
+
+// - integer
+pub(super) fn execute_global_id(engine: &mut Engine) -> Status {
+    smci_param_item(engine, "GLOBALID", 15)
+}
+
+// - t
+pub(super) fn execute_incoming_value(engine: &mut Engine) -> Status {
+    smci_param_item(engine, "INCOMINGVALUE", 16)
+}
+
+// - integer | none
+pub(super) fn execute_due_payment(engine: &mut Engine) -> Status {
+    smci_param_item(engine, "DUEPAYMENT", 17)
+}
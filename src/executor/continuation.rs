@@ -265,14 +265,20 @@ fn jmpx(engine: &mut Engine, need_convert: bool) -> Status {
 // cc.stack -= move_out, continuation.stack += move_in,
 // cc = continuation, c[*] = cc.savelist[*]
 pub(super) fn switch(engine: &mut Engine, continuation: u16) -> Status {
+    #[cfg(feature = "tracing")]
+    let _span = tracing::trace_span!(
+        target: crate::logging::targets::CONT, "switch", from = continuation, step = engine.steps()
+    ).entered();
     pop_all(engine, continuation)?;
     swap(engine, continuation, CC)?;
-    let drop_c0 = (continuation == ctrl!(0)) && engine.cc.savelist.get(0).is_none();
     let drop_c1 = (continuation == ctrl!(1)) && engine.cc.savelist.get(1).is_none();
-    apply_savelist(engine)?;
-    if drop_c0 {
-        engine.ctrls.remove(0);
+    // previous frame is replaced either by savelist of new cc or by nothing
+    if continuation == ctrl!(0) {
+        discard_frame(engine, 0);
+    } else if continuation == ctrl!(1) {
+        discard_frame(engine, 1);
     }
+    apply_savelist(engine)?;
     if drop_c1 {
         let cont = ContinuationData::with_type(ContinuationType::Quit(1));
         engine.ctrls.put(1, &mut StackItem::continuation(cont))?;
@@ -285,14 +291,17 @@ pub(super) fn switch_to_c0(engine: &mut Engine) -> Status {
     let c0 = engine.ctrls.get_mut(0)
         .ok_or(ExceptionCode::FatalError)?.as_continuation_mut()?;
     mem::swap(&mut engine.cc, c0);
-    let drop_c0 = engine.cc.savelist.get(0).is_none();
+    discard_frame(engine, 0);
     engine.ctrls.apply(&mut engine.cc.savelist);
-    if drop_c0 {
-        engine.ctrls.remove(0);
-    }
     Ok(())
 }
 
+// removes previous cc left in control register after switch, the frame is not referenced anymore
+// in chains of returns and jumps, so it is released before savelist of new cc is applied
+fn discard_frame(engine: &mut Engine, index: usize) {
+    engine.ctrls.remove(index);
+}
+
 // Continuation related instructions ******************************************
 // (c - ), execute C infinitely
 pub(super) fn execute_again(engine: &mut Engine) -> Status {
@@ -1389,3 +1398,44 @@ pub(super) fn execute_whileend_break(engine: &mut Engine) -> Status {
     swap(engine, savelist!(var!(0), 1), var!(2))?;  // condition.savelist[1] = ec_while
     switch(engine, var!(0))
 }
+
+// Introspection **************************************************************
+
+fn inspect_cont(engine: &mut Engine, name: &'static str, op: fn(&ContinuationData) -> StackItem) -> Status {
+    engine.load_instruction(Instruction::new(name))?;
+    fetch_stack(engine, 1)?;
+    let item = op(engine.cmd.var(0).as_continuation()?);
+    engine.cc.stack.push(item);
+    Ok(())
+}
+
+// (c - s)
+pub(super) fn execute_contcode(engine: &mut Engine) -> Status {
+    inspect_cont(engine, "CONTCODE", |cont| StackItem::Slice(cont.code().clone()))
+}
+
+// (c - n), n = -1 if number of arguments is not set
+pub(super) fn execute_contnargs(engine: &mut Engine) -> Status {
+    inspect_cont(engine, "CONTNARGS", |cont| int!(cont.nargs))
+}
+
+// (c - t), tuple of values saved in continuation stack
+pub(super) fn execute_contstack(engine: &mut Engine) -> Status {
+    inspect_cont(engine, "CONTSTACK", |cont| StackItem::tuple(cont.stack.storage.clone()))?;
+    let len = engine.cc.stack.get(0).as_tuple()?.len();
+    engine.use_gas(engine.gas_prices().tuple(len));
+    Ok(())
+}
+
+// (c i - x or null), value of control register saved in continuation
+pub(super) fn execute_contgetctr(engine: &mut Engine) -> Status {
+    engine.load_instruction(Instruction::new("CONTGETCTR"))?;
+    fetch_stack(engine, 2)?;
+    let creg = engine.cmd.var(0).as_integer()?.into(0..=255)?;
+    if !SaveList::REGS.contains(&(creg as usize)) {
+        return err!(ExceptionCode::RangeCheckError)
+    }
+    let item = engine.cmd.var(1).as_continuation()?.savelist.get(creg as usize).cloned();
+    engine.cc.stack.push(item.unwrap_or_default());
+    Ok(())
+}
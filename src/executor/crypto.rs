@@ -13,23 +13,37 @@
 
 use crate::{
     executor::{
-        engine::{Engine, storage::fetch_stack}, gas::gas_state::Gas, types::Instruction
+        engine::{Engine, SignatureDomain, storage::fetch_stack}, gas::gas_state::Gas, types::Instruction
     },
     stack::{
         StackItem,
         integer::{
-            IntegerData,
+            IntegerData, behavior::OperationBehavior,
             serialization::UnsignedIntegerBigEndianEncoding
         },
     },
     types::Status
 };
-use std::borrow::Cow;
+use std::{borrow::Cow, str::FromStr};
+use num::bigint::Sign;
+use curve25519_dalek::{
+    edwards::{CompressedEdwardsY, EdwardsPoint}, ristretto::{CompressedRistretto, RistrettoPoint}, Scalar
+};
+use secp256k1::{ecdsa::{RecoverableSignature, RecoveryId}, Message, SECP256K1};
+use p256::ecdsa::{signature::Verifier, Signature as P256Signature, VerifyingKey as P256VerifyingKey};
 use ever_block::{
-    BuilderData, Ed25519PublicKey, ExceptionCode, GasConsumer, GlobalCapabilities, UInt256,
+    sha512_digest, BuilderData, Ed25519PublicKey, ExceptionCode, GasConsumer, GlobalCapabilities, Result,
+    SliceData, UInt256,
     ED25519_PUBLIC_KEY_LENGTH, ED25519_SIGNATURE_LENGTH
 };
 
+lazy_static::lazy_static! {
+    // order of ristretto255 group
+    static ref RIST255_L: IntegerData = IntegerData::from_str(
+        "7237005577332262213973186563042994240857116359379907606001950938285454250989"
+    ).expect("Wrong ristretto255 order value string");
+}
+
 fn hash_to_uint(bits: impl AsRef<[u8]>) -> IntegerData {
     IntegerData::from_unsigned_bytes_be(bits)
 }
@@ -89,21 +103,26 @@ impl AsRef<[u8]> for DataForSignature {
     }
 }
 
-fn preprocess_signed_data<'a>(_engine: &Engine, data: &'a [u8]) -> Cow<'a, [u8]> {
-    #[cfg(feature = "signature_with_id")]
-    if _engine.check_capabilities(GlobalCapabilities::CapSignatureWithId as u64) {
-        let mut extended_data = Vec::with_capacity(4 + data.len());
-        extended_data.extend_from_slice(&_engine.signature_id().to_be_bytes());
-        extended_data.extend_from_slice(data);
-        return Cow::Owned(extended_data)
-    }
-    Cow::Borrowed(data)
+fn preprocess_signed_data<'a>(engine: &Engine, data: &'a [u8]) -> Cow<'a, [u8]> {
+    let prefix = match engine.signature_domain() {
+        #[cfg(feature = "signature_with_id")]
+        SignatureDomain::GlobalId(signature_id)
+            if engine.check_capabilities(GlobalCapabilities::CapSignatureWithId as u64) => {
+            signature_id.to_be_bytes().to_vec()
+        }
+        SignatureDomain::Custom(prefix) => prefix.clone(),
+        _ => return Cow::Borrowed(data)
+    };
+    let mut extended_data = Vec::with_capacity(prefix.len() + data.len());
+    extended_data.extend_from_slice(&prefix);
+    extended_data.extend_from_slice(data);
+    Cow::Owned(extended_data)
 }
 
 fn check_signature(engine: &mut Engine, name: &'static str, hash: bool) -> Status {
     if engine.check_capabilities(GlobalCapabilities::CapTvmV19 as u64) {
         engine.checked_signatures_count = engine.checked_signatures_count.saturating_add(1);
-        engine.try_use_gas(Gas::check_signature_price(engine.checked_signatures_count))?;
+        engine.try_use_gas(engine.gas_prices().check_signature(engine.checked_signatures_count))?;
     }
     engine.load_instruction(Instruction::new(name))?;
     fetch_stack(engine, 3)?;
@@ -127,23 +146,31 @@ fn check_signature(engine: &mut Engine, name: &'static str, hash: bool) -> Statu
         }
         DataForSignature::Slice(engine.cmd.var(2).as_slice()?.get_bytestring(0))
     };
+    let signature = engine.cmd.var(1).as_slice()?.get_bytestring(0);
+    let result = verify_signature(engine, &data, &signature, &pub_key, hash)?;
+    engine.cc.stack.push(boolean!(result));
+    Ok(())
+}
+
+// verifies Ed25519 signature of CHKSIGNU or CHKSIGNS after arguments are checked
+fn verify_signature(
+    engine: &Engine,
+    data: &DataForSignature,
+    signature: &[u8],
+    pub_key: &BuilderData,
+    hash: bool
+) -> Result<bool> {
     let pub_key = match Ed25519PublicKey::from_bytes(pub_key.data().try_into()?) {
         Ok(pub_key) => pub_key,
         Err(err) => if engine.check_capabilities(GlobalCapabilities::CapsTvmBugfixes2022 as u64) {
-                engine.cc.stack.push(boolean!(false));
-                return Ok(())
+                return Ok(false)
             } else {
                 return err!(ExceptionCode::FatalError, "cannot load public key {}", err)
             }
     };
-    let signature = engine.cmd.var(1).as_slice()?.get_bytestring(0);
     if signature.len() < ED25519_SIGNATURE_LENGTH {
-        if engine.check_capabilities(GlobalCapabilities::CapsTvmBugfixes2022 as u64) {
-            engine.cc.stack.push(boolean!(false));
-            return Ok(())
-        } else if hash {
-            engine.cc.stack.push(boolean!(false));
-            return Ok(())
+        if engine.check_capabilities(GlobalCapabilities::CapsTvmBugfixes2022 as u64) || hash {
+            return Ok(false)
         } else {
             return err!(ExceptionCode::FatalError, "cannot load signature")
         }
@@ -154,8 +181,7 @@ fn check_signature(engine: &mut Engine, name: &'static str, hash: bool) -> Statu
         engine.modifiers.chksig_always_succeed || pub_key.verify(&data, &signature[..ED25519_SIGNATURE_LENGTH].try_into()?);
     #[cfg(not(feature = "signature_no_check"))]
     let result = pub_key.verify(&data, &signature[..ED25519_SIGNATURE_LENGTH].try_into()?);
-    engine.cc.stack.push(boolean!(result));
-    Ok(())
+    Ok(result)
 }
 
 // CHKSIGNS (d s k – ?)
@@ -173,3 +199,359 @@ pub(super) fn execute_chksigns(engine: &mut Engine) -> Status {
 pub(super) fn execute_chksignu(engine: &mut Engine) -> Status {
     check_signature(engine, "CHKSIGNU", true)
 }
+
+/// CHKSIGNS_BATCH (t - ?)
+/// checks Ed25519-signatures in tuple t of triples (h s k) one by one,
+/// each triple has the same meaning as the arguments of CHKSIGNU and is counted and charged
+/// like CHKSIGNU with CapTvmV19, without it the whole batch costs one basic instruction.
+/// Returns -1 only if all signatures are valid, checks stop at the first invalid one.
+/// Empty tuple is valid.
+pub(super) fn execute_chksigns_batch(engine: &mut Engine) -> Status {
+    engine.load_instruction(Instruction::new("CHKSIGNS_BATCH"))?;
+    fetch_stack(engine, 1)?;
+    let triples = engine.cmd.var(0).clone();
+    let mut result = true;
+    for item in triples.as_tuple()? {
+        let triple = item.as_tuple()?;
+        if triple.len() != 3 {
+            return err!(ExceptionCode::TypeCheckError, "signature triple expected, got {} items", triple.len())
+        }
+        let pub_key = triple[2].as_integer()?
+            .as_builder::<UnsignedIntegerBigEndianEncoding>(ED25519_PUBLIC_KEY_LENGTH * 8)?;
+        let signature = triple[1].as_slice()?;
+        let hash = triple[0].as_integer()?;
+        if signature.remaining_bits() < ED25519_SIGNATURE_LENGTH * 8 {
+            return err!(ExceptionCode::CellUnderflow)
+        }
+        let data = DataForSignature::Hash(hash.as_builder::<UnsignedIntegerBigEndianEncoding>(256)?);
+        if engine.check_capabilities(GlobalCapabilities::CapTvmV19 as u64) {
+            engine.checked_signatures_count = engine.checked_signatures_count.saturating_add(1);
+            engine.try_use_gas(engine.gas_prices().check_signature(engine.checked_signatures_count))?;
+        }
+        if !verify_signature(engine, &data, &signature.get_bytestring(0), &pub_key, true)? {
+            result = false;
+            break
+        }
+    }
+    engine.cc.stack.push(boolean!(result));
+    Ok(())
+}
+
+fn recover_secp256k1_key(hash: &[u8], v: u8, signature: &[u8]) -> Option<[u8; 65]> {
+    let recovery_id = RecoveryId::from_i32(v as i32).ok()?;
+    let signature = RecoverableSignature::from_compact(signature, recovery_id).ok()?;
+    let message = Message::from_slice(hash).ok()?;
+    let key = SECP256K1.recover_ecdsa(&message, &signature).ok()?;
+    Some(key.serialize_uncompressed())
+}
+
+/// ECRECOVER (hash v r s - 0 or h x1 x2 -1)
+/// recovers secp256k1 public key from the signature (v, r, s) of 256-bit hash.
+/// On success pushes the uncompressed key as 8-bit prefix h and two 256-bit coordinates.
+pub(super) fn execute_ecrecover(engine: &mut Engine) -> Status {
+    engine.load_instruction(Instruction::new("ECRECOVER"))?;
+    fetch_stack(engine, 4)?;
+    engine.try_use_gas(Gas::ecrecover_price())?;
+    let s = engine.cmd.var(0).as_integer()?
+        .as_builder::<UnsignedIntegerBigEndianEncoding>(256)?;
+    let r = engine.cmd.var(1).as_integer()?
+        .as_builder::<UnsignedIntegerBigEndianEncoding>(256)?;
+    let v = engine.cmd.var(2).as_integer()?.into(0..=255u8)?;
+    let hash = engine.cmd.var(3).as_integer()?
+        .as_builder::<UnsignedIntegerBigEndianEncoding>(256)?;
+    let mut signature = r.data().to_vec();
+    signature.extend_from_slice(s.data());
+    match recover_secp256k1_key(hash.data(), v, &signature) {
+        Some(key) => {
+            engine.cc.stack.push(int!(key[0]));
+            engine.cc.stack.push(StackItem::integer(hash_to_uint(&key[1..33])));
+            engine.cc.stack.push(StackItem::integer(hash_to_uint(&key[33..65])));
+            engine.cc.stack.push(boolean!(true));
+        }
+        None => engine.cc.stack.push(boolean!(false))
+    }
+    Ok(())
+}
+
+const P256_PUBLIC_KEY_LENGTH: usize = 33;
+const P256_SIGNATURE_LENGTH: usize = 64;
+
+// signatures with s above half of the group order are valid as in the reference VM
+fn verify_p256_signature(data: &[u8], signature: &[u8], key: &[u8]) -> bool {
+    let Ok(key) = P256VerifyingKey::from_sec1_bytes(key) else {
+        return false
+    };
+    let Ok(signature) = P256Signature::from_slice(signature) else {
+        return false
+    };
+    key.verify(data, &signature).is_ok()
+}
+
+fn check_p256_signature(engine: &mut Engine, name: &'static str, hash: bool) -> Status {
+    engine.load_instruction(Instruction::new(name))?;
+    fetch_stack(engine, 3)?;
+    engine.try_use_gas(Gas::p256_chksign_price())?;
+    let key = engine.cmd.var(0).as_slice()?;
+    if key.remaining_bits() < P256_PUBLIC_KEY_LENGTH * 8 {
+        return err!(ExceptionCode::CellUnderflow)
+    }
+    let key = key.get_bytestring(0);
+    let signature = engine.cmd.var(1).as_slice()?;
+    if signature.remaining_bits() < P256_SIGNATURE_LENGTH * 8 {
+        return err!(ExceptionCode::CellUnderflow)
+    }
+    let signature = signature.get_bytestring(0);
+    let data = if hash {
+        engine.cmd.var(2).as_integer()?
+            .as_builder::<UnsignedIntegerBigEndianEncoding>(256)?.data().to_vec()
+    } else {
+        let data = engine.cmd.var(2).as_slice()?;
+        if data.remaining_bits() % 8 != 0 {
+            return err!(ExceptionCode::CellUnderflow)
+        }
+        data.get_bytestring(0)
+    };
+    let result = verify_p256_signature(
+        &data, &signature[..P256_SIGNATURE_LENGTH], &key[..P256_PUBLIC_KEY_LENGTH]
+    );
+    #[cfg(feature = "signature_no_check")]
+    let result = engine.modifiers.chksig_always_succeed || result;
+    engine.cc.stack.push(boolean!(result));
+    Ok(())
+}
+
+/// P256_CHKSIGNU (h s k - ?)
+/// checks secp256r1 signature s (64-byte slice of r and s) of 256-bit hash h
+/// using public key k (33-byte slice of compressed SEC1 point).
+/// The hash is signed as 32-byte message, i.e. sha256 of it is actually signed.
+/// Both s and n - s forms of signature are accepted.
+pub(super) fn execute_p256_chksignu(engine: &mut Engine) -> Status {
+    check_p256_signature(engine, "P256_CHKSIGNU", true)
+}
+
+/// P256_CHKSIGNS (d s k - ?)
+/// checks secp256r1 signature s of the data portion of slice d similarly to P256_CHKSIGNU.
+/// If the bit length of d is not divisible by eight, throws a cell underflow exception.
+pub(super) fn execute_p256_chksigns(engine: &mut Engine) -> Status {
+    check_p256_signature(engine, "P256_CHKSIGNS", false)
+}
+
+// Ristretto255 points are passed as 256-bit unsigned integers holding big-endian
+// representation of compressed point, arithmetic is constant time in curve25519-dalek
+
+fn rist255_point(x: &IntegerData) -> Result<RistrettoPoint> {
+    let bytes = x.as_builder::<UnsignedIntegerBigEndianEncoding>(256)
+        .map_err(|_| exception!(ExceptionCode::RangeCheckError, "ristretto point must be 256-bit unsigned"))?;
+    match CompressedRistretto::from_slice(bytes.data()).ok().and_then(|point| point.decompress()) {
+        Some(point) => Ok(point),
+        None => err!(ExceptionCode::RangeCheckError, "invalid ristretto point")
+    }
+}
+
+// any integer is reduced modulo group order, negative ones too
+fn rist255_scalar(n: &IntegerData) -> Result<Scalar> {
+    let n = n.take_value_of(|x| Some(x.clone()))?;
+    let mut bytes = n.magnitude().to_bytes_le();
+    bytes.resize(64, 0);
+    let scalar = Scalar::from_bytes_mod_order_wide(bytes.as_slice().try_into()?);
+    match n.sign() {
+        Sign::Minus => Ok(-scalar),
+        _ => Ok(scalar)
+    }
+}
+
+fn push_rist255_point(engine: &mut Engine, point: RistrettoPoint) {
+    engine.cc.stack.push(StackItem::integer(hash_to_uint(point.compress().as_bytes())));
+}
+
+// quiet instructions push -1 after the result or only 0 if arguments are invalid
+fn push_rist255_result<T: OperationBehavior>(engine: &mut Engine, result: Result<Option<RistrettoPoint>>) -> Status {
+    match result {
+        Ok(point) => {
+            if let Some(point) = point {
+                push_rist255_point(engine, point);
+            }
+            if T::quiet() {
+                engine.cc.stack.push(boolean!(true));
+            }
+            Ok(())
+        }
+        Err(_) if T::quiet() => {
+            engine.cc.stack.push(boolean!(false));
+            Ok(())
+        }
+        Err(err) => Err(err)
+    }
+}
+
+fn rist255_name<T: OperationBehavior>(name: &'static str, quiet_name: &'static str) -> &'static str {
+    if T::quiet() {quiet_name} else {name}
+}
+
+/// RIST255_FROMHASH (h1 h2 - x)
+/// maps 512-bit hash h1 * 2^256 + h2 to a ristretto point
+pub(super) fn execute_rist255_fromhash(engine: &mut Engine) -> Status {
+    engine.load_instruction(Instruction::new("RIST255_FROMHASH"))?;
+    fetch_stack(engine, 2)?;
+    engine.try_use_gas(Gas::rist255_fromhash_gas_price())?;
+    let mut hash = [0; 64];
+    for (i, part) in hash.chunks_mut(32).enumerate() {
+        let bytes = engine.cmd.var(1 - i).as_integer()?
+            .as_builder::<UnsignedIntegerBigEndianEncoding>(256)?;
+        part.copy_from_slice(bytes.data());
+    }
+    push_rist255_point(engine, RistrettoPoint::from_uniform_bytes(&hash));
+    Ok(())
+}
+
+/// RIST255_VALIDATE (x - )
+/// throws range check exception if x is not a valid ristretto point.
+/// RIST255_QVALIDATE (x - 0 or -1) checks the point quietly
+pub(super) fn execute_rist255_validate<T: OperationBehavior>(engine: &mut Engine) -> Status {
+    engine.load_instruction(Instruction::new(rist255_name::<T>("RIST255_VALIDATE", "RIST255_QVALIDATE")))?;
+    fetch_stack(engine, 1)?;
+    engine.try_use_gas(Gas::rist255_validate_gas_price())?;
+    let x = engine.cmd.var(0).as_integer()?;
+    let result = rist255_point(x).map(|_| None);
+    push_rist255_result::<T>(engine, result)
+}
+
+fn rist255_add_sub<T: OperationBehavior>(engine: &mut Engine, name: &'static str, sub: bool) -> Status {
+    engine.load_instruction(Instruction::new(name))?;
+    fetch_stack(engine, 2)?;
+    engine.try_use_gas(Gas::rist255_add_sub_gas_price())?;
+    let y = engine.cmd.var(0).as_integer()?;
+    let x = engine.cmd.var(1).as_integer()?;
+    let result = rist255_point(x).and_then(|x| {
+        let y = rist255_point(y)?;
+        Ok(Some(if sub { x - y } else { x + y }))
+    });
+    push_rist255_result::<T>(engine, result)
+}
+
+/// RIST255_ADD (x y - x+y)
+/// adds two ristretto points, RIST255_QADD (x y - x+y -1 or 0) does it quietly
+pub(super) fn execute_rist255_add<T: OperationBehavior>(engine: &mut Engine) -> Status {
+    rist255_add_sub::<T>(engine, rist255_name::<T>("RIST255_ADD", "RIST255_QADD"), false)
+}
+
+/// RIST255_SUB (x y - x-y)
+/// subtracts ristretto point y from x, RIST255_QSUB (x y - x-y -1 or 0) does it quietly
+pub(super) fn execute_rist255_sub<T: OperationBehavior>(engine: &mut Engine) -> Status {
+    rist255_add_sub::<T>(engine, rist255_name::<T>("RIST255_SUB", "RIST255_QSUB"), true)
+}
+
+/// RIST255_MUL (x n - x*n)
+/// multiplies ristretto point x by scalar n. Any n is valid, including negative.
+/// RIST255_QMUL (x n - x*n -1 or 0) does it quietly
+pub(super) fn execute_rist255_mul<T: OperationBehavior>(engine: &mut Engine) -> Status {
+    engine.load_instruction(Instruction::new(rist255_name::<T>("RIST255_MUL", "RIST255_QMUL")))?;
+    fetch_stack(engine, 2)?;
+    engine.try_use_gas(Gas::rist255_mul_gas_price())?;
+    let n = engine.cmd.var(0).as_integer()?;
+    let x = engine.cmd.var(1).as_integer()?;
+    let result = rist255_scalar(n).and_then(|n| Ok(Some(rist255_point(x)? * n)));
+    push_rist255_result::<T>(engine, result)
+}
+
+/// RIST255_MULBASE (n - g*n)
+/// multiplies generator of ristretto group by scalar n. Any n is valid, including negative.
+/// RIST255_QMULBASE (n - g*n -1 or 0) does it quietly
+pub(super) fn execute_rist255_mulbase<T: OperationBehavior>(engine: &mut Engine) -> Status {
+    engine.load_instruction(Instruction::new(rist255_name::<T>("RIST255_MULBASE", "RIST255_QMULBASE")))?;
+    fetch_stack(engine, 1)?;
+    engine.try_use_gas(Gas::rist255_mulbase_gas_price())?;
+    let n = engine.cmd.var(0).as_integer()?;
+    let result = rist255_scalar(n).map(|n| Some(RistrettoPoint::mul_base(&n)));
+    push_rist255_result::<T>(engine, result)
+}
+
+/// RIST255_PUSHL ( - l)
+/// pushes order of ristretto group
+pub(super) fn execute_rist255_pushl(engine: &mut Engine) -> Status {
+    engine.load_instruction(Instruction::new("RIST255_PUSHL"))?;
+    engine.cc.stack.push(StackItem::integer(RIST255_L.clone()));
+    Ok(())
+}
+
+// ECVRF-EDWARDS25519-SHA512-TAI suite of RFC 9381
+const VRF_SUITE: u8 = 0x03;
+const VRF_PROOF_LENGTH: usize = 80;
+const VRF_OUTPUT_LENGTH: usize = 64;
+
+// RFC 8032 decoding: non canonical encodings are rejected
+fn vrf_string_to_point(bytes: &[u8]) -> Option<EdwardsPoint> {
+    let point = CompressedEdwardsY::from_slice(bytes).ok()?.decompress()?;
+    (point.compress().as_bytes() == bytes).then_some(point)
+}
+
+fn vrf_hash(domain: u8, data: &[&[u8]]) -> Vec<u8> {
+    let mut buffer = vec![VRF_SUITE, domain];
+    data.iter().for_each(|data| buffer.extend_from_slice(data));
+    buffer.push(0);
+    sha512_digest(buffer).to_vec()
+}
+
+// encode_to_curve with try and increment method
+fn vrf_encode_to_curve(key: &[u8], alpha: &[u8]) -> Option<EdwardsPoint> {
+    (0..=255u8).find_map(|ctr| {
+        let hash = vrf_hash(0x01, &[key, alpha, &[ctr]]);
+        vrf_string_to_point(&hash[..32])
+    }).map(|point| point.mul_by_cofactor())
+}
+
+/// Verifies proof of public key over alpha and returns VRF output on success
+fn vrf_verify(key: &[u8], proof: &[u8], alpha: &[u8]) -> Option<Vec<u8>> {
+    let y = vrf_string_to_point(key)?;
+    if y.is_small_order() {
+        return None
+    }
+    let gamma = vrf_string_to_point(&proof[..32])?;
+    let mut c = [0; 32];
+    c[..16].copy_from_slice(&proof[32..48]);
+    let c = Scalar::from_bytes_mod_order(c);
+    let s = Option::<Scalar>::from(Scalar::from_canonical_bytes(proof[48..80].try_into().ok()?))?;
+    let h = vrf_encode_to_curve(key, alpha)?;
+    let u = EdwardsPoint::vartime_double_scalar_mul_basepoint(&-c, &y, &s);
+    let v = h * s - gamma * c;
+    let challenge = vrf_hash(0x02, &[
+        key, h.compress().as_bytes(), &proof[..32], u.compress().as_bytes(), v.compress().as_bytes()
+    ]);
+    if challenge[..16] != proof[32..48] {
+        return None
+    }
+    Some(vrf_hash(0x03, &[gamma.mul_by_cofactor().compress().as_bytes()]))
+}
+
+/// VRFVERIFY (d p k - h -1 or 0)
+/// verifies ECVRF-EDWARDS25519-SHA512-TAI proof p (80-byte slice) of the data portion
+/// of slice d using public key k (256-bit unsigned integer) as defined in RFC 9381.
+/// On success pushes 512-bit slice h with VRF output and -1, otherwise pushes 0.
+/// If the bit length of d is not divisible by eight, throws a cell underflow exception.
+/// It is counted and charged as CHKSIGNU.
+pub(super) fn execute_vrfverify(engine: &mut Engine) -> Status {
+    engine.checked_signatures_count = engine.checked_signatures_count.saturating_add(1);
+    engine.try_use_gas(engine.gas_prices().check_signature(engine.checked_signatures_count))?;
+    engine.load_instruction(Instruction::new("VRFVERIFY"))?;
+    fetch_stack(engine, 3)?;
+    let key = engine.cmd.var(0).as_integer()?
+        .as_builder::<UnsignedIntegerBigEndianEncoding>(256)?;
+    let proof = engine.cmd.var(1).as_slice()?;
+    if proof.remaining_bits() < VRF_PROOF_LENGTH * 8 {
+        return err!(ExceptionCode::CellUnderflow)
+    }
+    let proof = proof.get_bytestring(0);
+    let data = engine.cmd.var(2).as_slice()?;
+    if data.remaining_bits() % 8 != 0 {
+        return err!(ExceptionCode::CellUnderflow)
+    }
+    let data = data.get_bytestring(0);
+    match vrf_verify(key.data(), &proof[..VRF_PROOF_LENGTH], &data) {
+        Some(output) => {
+            engine.cc.stack.push(StackItem::Slice(SliceData::from_raw(output, VRF_OUTPUT_LENGTH * 8)));
+            engine.cc.stack.push(boolean!(true));
+        }
+        None => engine.cc.stack.push(boolean!(false))
+    }
+    Ok(())
+}
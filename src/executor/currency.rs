@@ -13,43 +13,48 @@
 
 use crate::{
     executor::{engine::{Engine, storage::fetch_stack}, types::Instruction},
-    stack::{StackItem, integer::IntegerData},
+    stack::{
+        StackItem, integer::{IntegerData, serialization::VarIntegerEncoding}, serialization::Serializer
+    },
     types::Status
 };
-use ever_block::{BuilderData, ExceptionCode, GlobalCapabilities, IBitstring};
+use ever_block::{
+    BuilderData, Cell, ExceptionCode, GasConsumer, GlobalCapabilities, HashmapE, HashmapType,
+    IBitstring, Result, SliceData,
+};
+use num::{BigInt, bigint::Sign, Zero};
+
+/// Bit length of currency id in ExtraCurrencyCollection
+pub const EXTRA_CURRENCY_KEY_BITS: usize = 32;
+// values are VarUInteger 32
+const EXTRA_CURRENCY_MAX_BITS: u64 = 248;
 
 // slice - uint slice'
-fn load_var(engine: &mut Engine, name: &'static str, max_bytes: u8, sign: bool) -> Status {
+fn load_var(engine: &mut Engine, name: &'static str, encoding: VarIntegerEncoding) -> Status {
     engine.load_instruction(Instruction::new(name))?;
     fetch_stack(engine, 1)?;
     let mut slice = engine.cmd.var(0).as_slice()?.clone();
-    let len = 8 - (max_bytes - 1).leading_zeros() as usize;
-    let bytes = slice.get_next_int(len)? as usize;
-    let vec = slice.get_next_bytes(bytes)?;
-    let value = match sign {
-        true => num::BigInt::from_signed_bytes_be(&vec),
-        false => num::BigInt::from_bytes_be(num::bigint::Sign::Plus, &vec)
-    };
-    engine.cc.stack.push(int!(value));
+    let value = encoding.read(&mut slice)?;
+    engine.cc.stack.push(StackItem::integer(value));
     engine.cc.stack.push(StackItem::Slice(slice));
     Ok(())
 }
 
 pub(super) fn execute_ldgrams(engine: &mut Engine) -> Status {
-    load_var(engine, "LDGRAMS", 16, false)
+    load_var(engine, "LDGRAMS", VarIntegerEncoding::unsigned(16))
 }
 pub(super) fn execute_ldvarint16(engine: &mut Engine) -> Status {
-    load_var(engine, "LDVARINT16", 16, true)
+    load_var(engine, "LDVARINT16", VarIntegerEncoding::signed(16))
 }
 pub(super) fn execute_ldvaruint32(engine: &mut Engine) -> Status {
-    load_var(engine, "LDVARUINT32", 32, false)
+    load_var(engine, "LDVARUINT32", VarIntegerEncoding::unsigned(32))
 }
 pub(super) fn execute_ldvarint32(engine: &mut Engine) -> Status {
-    load_var(engine, "LDVARINT32", 32, true)
+    load_var(engine, "LDVARINT32", VarIntegerEncoding::signed(32))
 }
 
 // builder uint - builder'
-fn store_var(engine: &mut Engine, name: &'static str, max_bits: usize, sign: bool) -> Status {
+fn store_var(engine: &mut Engine, name: &'static str, encoding: VarIntegerEncoding) -> Status {
     engine.load_instruction(Instruction::new(name))?;
     fetch_stack(engine, 2)?;
     let x = engine.cmd.var(0).as_integer()?;
@@ -57,31 +62,8 @@ fn store_var(engine: &mut Engine, name: &'static str, max_bits: usize, sign: boo
         return err!(ExceptionCode::RangeCheckError);
     }
     let b = engine.cmd.var(1).as_builder()?;
-    let (bits, vec) = match sign {
-        false => {
-            x.check_neg()?;
-            (x.ubitsize()?, x.take_value_of(|x| Some(x.to_bytes_be().1))?)
-        }
-        true => (x.bitsize()?, x.take_value_of(|x| Some(x.to_signed_bytes_be()))?)
-    };
-    if bits > max_bits {
-        return err!(ExceptionCode::RangeCheckError, "{} has {} bits, but max is {}", x, bits, max_bits)
-    }
-    let len = 16 - (max_bits as u16 / 8).leading_zeros();
-    match max_bits {
-        120 => debug_assert_eq!(len, 4),
-        248 => debug_assert_eq!(len, 5),
-        _ => debug_assert_eq!(len, 0)
-    }
-    let mut x = BuilderData::new();
-    let bytes = if bits != 0 {
-        vec.len()
-    } else {
-        0
-    };
-    x.append_bits(bytes, len as usize)?;
-    x.append_raw(&vec, bytes * 8)?;
-    if b.can_append(&x) {
+    let x = encoding.try_serialize(x)?;
+    if engine.builder_limits().check_space(b, x.bits_used(), x.references_used()) {
         let mut b = engine.cmd.var_mut(1).as_builder_mut()?;
         b.append_builder(&x).expect("free space was checked before");
         engine.cc.stack.push_builder(b);
@@ -92,17 +74,174 @@ fn store_var(engine: &mut Engine, name: &'static str, max_bits: usize, sign: boo
 }
 
 pub(super) fn execute_stgrams(engine: &mut Engine) -> Status {
-    store_var(engine, "STGRAMS", 120, false)
+    store_var(engine, "STGRAMS", VarIntegerEncoding::unsigned(16))
 }
 
 pub(super) fn execute_stvarint16(engine: &mut Engine) -> Status {
-    store_var(engine, "STVARINT16", 120, true)
+    store_var(engine, "STVARINT16", VarIntegerEncoding::signed(16))
 }
 
 pub(super) fn execute_stvaruint32(engine: &mut Engine) -> Status {
-    store_var(engine, "STVARUINT32", 248, false)
+    store_var(engine, "STVARUINT32", VarIntegerEncoding::unsigned(32))
 }
 
 pub(super) fn execute_stvarint32(engine: &mut Engine) -> Status {
-    store_var(engine, "STVARINT32", 248, true)
+    store_var(engine, "STVARINT32", VarIntegerEncoding::signed(32))
+}
+
+// Extra currencies **********************************************************
+
+fn extra_currency_key(id: u32) -> Result<SliceData> {
+    SliceData::load_builder(BuilderData::with_raw(id.to_be_bytes().to_vec(), EXTRA_CURRENCY_KEY_BITS)?)
+}
+
+/// Reads amount of extra currency stored as VarUInteger 32
+pub fn read_extra_amount(slice: &mut SliceData) -> Result<BigInt> {
+    let bytes = slice.get_next_int(5)? as usize;
+    Ok(BigInt::from_bytes_be(Sign::Plus, &slice.get_next_bytes(bytes)?))
+}
+
+/// Serializes amount of extra currency as VarUInteger 32, amounts above 2^248-1 overflow
+pub fn write_extra_amount(amount: &BigInt) -> Result<BuilderData> {
+    if amount.sign() == Sign::Minus || amount.bits() > EXTRA_CURRENCY_MAX_BITS {
+        return err!(ExceptionCode::IntegerOverflow, "extra currency amount {} is out of range", amount)
+    }
+    let bytes = match amount.is_zero() {
+        true => vec![],
+        false => amount.to_bytes_be().1
+    };
+    let mut builder = BuilderData::new();
+    builder.append_bits(bytes.len(), 5)?;
+    builder.append_raw(&bytes, bytes.len() * 8)?;
+    Ok(builder)
+}
+
+/// Amount of extra currency with given id, zero if it is absent
+pub fn get_extra_currency(dict: &HashmapE, id: u32, gas_consumer: &mut dyn GasConsumer) -> Result<BigInt> {
+    match dict.get_with_gas(extra_currency_key(id)?, gas_consumer)? {
+        Some(mut value) => read_extra_amount(&mut value),
+        None => Ok(BigInt::zero())
+    }
+}
+
+// every cell of the dictionary is charged as it is reached
+fn charge_cells(cell: Cell, gas_consumer: &mut dyn GasConsumer) -> Status {
+    let slice = gas_consumer.load_cell(cell)?;
+    for i in 0..slice.remaining_references() {
+        charge_cells(slice.reference(i)?, gas_consumer)?;
+    }
+    Ok(())
+}
+
+// every entry of other collection is visited with gas before update
+fn other_entries(other: &HashmapE, gas_consumer: &mut dyn GasConsumer) -> Result<Vec<(SliceData, BigInt)>> {
+    if let Some(root) = other.data() {
+        charge_cells(root.clone(), gas_consumer)?;
+    }
+    let mut entries = Vec::new();
+    other.iterate_slices(|key, mut value| {
+        entries.push((key, read_extra_amount(&mut value)?));
+        Ok(true)
+    })?;
+    Ok(entries)
 }
+
+fn update_extra_currency(
+    dict: &mut HashmapE,
+    key: SliceData,
+    amount: &BigInt,
+    gas_consumer: &mut dyn GasConsumer
+) -> Result<()> {
+    if amount.is_zero() {
+        dict.remove_with_gas(key, gas_consumer)?;
+    } else {
+        dict.set_builder_with_gas(key, &write_extra_amount(amount)?, gas_consumer)?;
+    }
+    Ok(())
+}
+
+/// Adds other collection to dict, sum above 2^248-1 throws integer overflow
+pub fn add_extra_currencies(dict: &mut HashmapE, other: &HashmapE, gas_consumer: &mut dyn GasConsumer) -> Result<()> {
+    for (key, amount) in other_entries(other, gas_consumer)? {
+        let value = match dict.get_with_gas(key.clone(), gas_consumer)? {
+            Some(mut value) => read_extra_amount(&mut value)? + amount,
+            None => amount
+        };
+        update_extra_currency(dict, key, &value, gas_consumer)?;
+    }
+    Ok(())
+}
+
+/// Subtracts other collection from dict. Returns false and leaves dict untouched
+/// if some currency is insufficient, currencies spent completely are removed
+pub fn sub_extra_currencies(dict: &mut HashmapE, other: &HashmapE, gas_consumer: &mut dyn GasConsumer) -> Result<bool> {
+    let mut result = dict.clone();
+    for (key, amount) in other_entries(other, gas_consumer)? {
+        let value = match result.get_with_gas(key.clone(), gas_consumer)? {
+            Some(mut value) => read_extra_amount(&mut value)?,
+            None => BigInt::zero()
+        };
+        if value < amount {
+            return Ok(false)
+        }
+        update_extra_currency(&mut result, key, &(value - amount), gas_consumer)?;
+    }
+    *dict = result;
+    Ok(true)
+}
+
+fn extra_currencies(engine: &Engine, index: usize) -> Result<HashmapE> {
+    Ok(HashmapE::with_hashmap(EXTRA_CURRENCY_KEY_BITS, engine.cmd.var(index).as_dict()?.cloned()))
+}
+
+/// LDEXTRACURRENCY (s - D s')
+pub(super) fn execute_ldextracurrency(engine: &mut Engine) -> Status {
+    engine.load_instruction(Instruction::new("LDEXTRACURRENCY"))?;
+    fetch_stack(engine, 1)?;
+    let mut slice = engine.cmd.var(0).as_slice()?.clone();
+    let dict = match slice.get_next_bit()? {
+        true => StackItem::Cell(slice.checked_drain_reference()?),
+        false => StackItem::None
+    };
+    engine.cc.stack.push(dict);
+    engine.cc.stack.push(StackItem::Slice(slice));
+    Ok(())
+}
+
+/// EXTRACURRENCYGET (D id - x)
+pub(super) fn execute_extracurrencyget(engine: &mut Engine) -> Status {
+    engine.load_instruction(Instruction::new("EXTRACURRENCYGET"))?;
+    fetch_stack(engine, 2)?;
+    let id = engine.cmd.var(0).as_integer()?.into(0..=u32::MAX)?;
+    let dict = extra_currencies(engine, 1)?;
+    let amount = get_extra_currency(&dict, id, engine)?;
+    engine.cc.stack.push(int!(amount));
+    Ok(())
+}
+
+/// EXTRACURRENCYADD (D1 D2 - D)
+pub(super) fn execute_extracurrencyadd(engine: &mut Engine) -> Status {
+    engine.load_instruction(Instruction::new("EXTRACURRENCYADD"))?;
+    fetch_stack(engine, 2)?;
+    let other = extra_currencies(engine, 0)?;
+    let mut dict = extra_currencies(engine, 1)?;
+    add_extra_currencies(&mut dict, &other, engine)?;
+    engine.cc.stack.push(StackItem::dict(&dict));
+    Ok(())
+}
+
+/// EXTRACURRENCYSUB (D1 D2 - D -1 or D1 0)
+pub(super) fn execute_extracurrencysub(engine: &mut Engine) -> Status {
+    engine.load_instruction(Instruction::new("EXTRACURRENCYSUB"))?;
+    fetch_stack(engine, 2)?;
+    let other = extra_currencies(engine, 0)?;
+    let mut dict = extra_currencies(engine, 1)?;
+    let done = sub_extra_currencies(&mut dict, &other, engine)?;
+    engine.cc.stack.push(StackItem::dict(&dict));
+    engine.cc.stack.push(boolean!(done));
+    Ok(())
+}
+
+#[cfg(test)]
+#[path = "../tests/test_currency.rs"]
+mod tests;
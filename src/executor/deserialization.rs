@@ -12,13 +12,14 @@
 */
 
 use crate::{
+    error::tvm_exception_code,
     executor::{
-        Mask, engine::{Engine, data::convert, storage::fetch_stack},
+        Mask, engine::{CellLoadMode, Engine, VmCapabilities, data::convert, storage::fetch_stack},
         microcode::{SLICE, CELL, VAR}, types::{InstructionOptions, Instruction}
     },
     stack::{
         StackItem,
-        continuation::ContinuationData,
+        continuation::{ContinuationData, ContinuationFormat},
         integer::{
             IntegerData,
             serialization::{
@@ -139,22 +140,32 @@ pub fn execute_pldslice(engine: &mut Engine) -> Status {
 }
 
 pub fn execute_ldslicexq(engine: &mut Engine) -> Status {
-    ld_slice(engine, "LDSLICEXQ", 1023, STACK | QUIET | STAY)
+    let limit = engine.data_bits_limit();
+    ld_slice(engine, "LDSLICEXQ", limit, STACK | QUIET | STAY)
 }
 
 /// LDSLICEX(sl - s`` s`), loads the first 0 =< l =< 1023 bits from Slice s
 /// into a separate Slice s``, returning the remainder of s as s`.
 pub fn execute_ldslicex(engine: &mut Engine) -> Status {
-    ld_slice(engine, "LDSLICEX", 1023, STACK | STAY)
+    let limit = engine.data_bits_limit();
+    ld_slice(engine, "LDSLICEX", limit, STACK | STAY)
 }
 
 pub fn execute_pldslicexq(engine: &mut Engine) -> Status {
-    ld_slice(engine, "PLDSLICEXQ", 1023, STACK | QUIET)
+    let limit = engine.data_bits_limit();
+    ld_slice(engine, "PLDSLICEXQ", limit, STACK | QUIET)
+}
+
+/// SDCUTFIRST (s l - s'), leaves the first l bits of s, it works like PLDSLICEX.
+pub fn execute_sdcutfirst(engine: &mut Engine) -> Status {
+    let limit = engine.data_bits_limit();
+    ld_slice(engine, "SDCUTFIRST", limit, STACK)
 }
 
 /// PLDSLICEX(sl - s``)
 pub fn execute_pldslicex(engine: &mut Engine) -> Status {
-    ld_slice(engine, "PLDSLICEX", 1023, STACK)
+    let limit = engine.data_bits_limit();
+    ld_slice(engine, "PLDSLICEX", limit, STACK)
 }
 
 // (cell - slice)
@@ -176,7 +187,7 @@ pub fn execute_xctos(engine: &mut Engine) -> Status {
     fetch_stack(engine, 1)?;
     let cell = engine.cmd.var(0).as_cell()?.clone();
     let special = cell.cell_type() != CellType::Ordinary;
-    let slice = engine.load_hashed_cell(cell, false)?;
+    let slice = engine.load_cell_with_mode(cell, CellLoadMode::Explicit)?;
     engine.cc.stack.push(StackItem::Slice(slice));
     engine.cc.stack.push(boolean!(special));
     Ok(())
@@ -188,9 +199,13 @@ pub fn execute_xload(engine: &mut Engine) -> Status {
         Instruction::new("XLOAD")
     )?;
     fetch_stack(engine, 1)?;
-    // now it does nothing as Durov's code
     let cell = engine.cmd.var(0).as_cell()?.clone();
-    let slice = engine.load_cell(cell)?;
+    let slice = if engine.has_vm_capability(VmCapabilities::CapXLoad) {
+        engine.load_cell_with_mode(cell, CellLoadMode::Exotic)?
+    } else {
+        // now it does nothing as Durov's code
+        engine.load_cell(cell)?
+    };
     let cell = slice.into_cell();
     engine.cc.stack.push(StackItem::Cell(cell));
     Ok(())
@@ -202,9 +217,18 @@ pub fn execute_xloadq(engine: &mut Engine) -> Status {
         Instruction::new("XLOADQ")
     )?;
     fetch_stack(engine, 1)?;
-    // now it does nothing as Durov's code
     let cell = engine.cmd.var(0).as_cell()?.clone();
-    if let Ok(slice) = engine.load_cell(cell.clone()) {
+    let result = if engine.has_vm_capability(VmCapabilities::CapXLoad) {
+        // only cells which can not be opened are reported, out of gas is not
+        match engine.load_cell_with_mode(cell.clone(), CellLoadMode::Exotic) {
+            Err(err) if tvm_exception_code(&err) != Some(ExceptionCode::CellUnderflow) => return Err(err),
+            result => result
+        }
+    } else {
+        // now it does nothing as Durov's code
+        engine.load_cell(cell.clone())
+    };
+    if let Ok(slice) = result {
         let cell = slice.into_cell();
         engine.cc.stack.push(StackItem::Cell(cell));
         engine.cc.stack.push(boolean!(true));
@@ -432,7 +456,7 @@ fn sdcut(engine: &mut Engine, bits: u8, refs: u8) -> Status {
     };
     let l1 = if (bits & SIZE) == SIZE {
         i += 1;
-        engine.cmd.var(i - 1).as_integer()?.into(0..=1023)?
+        engine.cmd.var(i - 1).as_integer()?.into(0..=engine.data_bits_limit())?
     } else {
         0
     };
@@ -442,7 +466,7 @@ fn sdcut(engine: &mut Engine, bits: u8, refs: u8) -> Status {
     } else {
         0
     };
-    let l0 = engine.cmd.var(i).as_integer()?.into(0..=1023)?;
+    let l0 = engine.cmd.var(i).as_integer()?.into(0..=engine.data_bits_limit())?;
     let mut slice = engine.cmd.var(i + 1).as_slice()?.clone();
     let data_len = slice.remaining_bits();
     let refs_count = slice.remaining_references();
@@ -615,7 +639,7 @@ fn schkbitrefs(engine: &mut Engine, name: &'static str, quiet: bool) -> Status {
     )?;
     fetch_stack(engine, 3)?;
     let r = engine.cmd.var(0).as_integer()?.into(0..=4)?;
-    let l = engine.cmd.var(1).as_integer()?.into(0..=1023)?;
+    let l = engine.cmd.var(1).as_integer()?.into(0..=engine.data_bits_limit())?;
     let s = engine.cmd.var(2).as_slice()?;
     let data_len = s.remaining_bits();
     let refs_count = s.remaining_references();
@@ -629,11 +653,13 @@ fn schkbitrefs(engine: &mut Engine, name: &'static str, quiet: bool) -> Status {
 }
 
 pub fn execute_schkbitsq(engine: &mut Engine) -> Status {
-    schkbits(engine, "SCHKBITSQ", 1023, true)
+    let limit = engine.data_bits_limit();
+    schkbits(engine, "SCHKBITSQ", limit, true)
 }
 
 pub fn execute_schkbits(engine: &mut Engine) -> Status {
-    schkbits(engine, "SCHKBITS", 1023, false)
+    let limit = engine.data_bits_limit();
+    schkbits(engine, "SCHKBITS", limit, false)
 }
 
 pub fn execute_schkrefsq(engine: &mut Engine) -> Status {
@@ -699,84 +725,30 @@ pub fn execute_sbitrefs(engine: &mut Engine) -> Status {
     sbitrefs(engine, "SBITREFS", Target::BitRefs)
 }
 
-// (slice - x slice)
-pub fn execute_ldile4(engine: &mut Engine) -> Status {
-    ld_int::<SignedIntegerLittleEndianEncoding>(engine, "LDILE4", 32, PARAM | STAY)
-}
-
-// (slice - x slice)
-pub fn execute_ldule4(engine: &mut Engine) -> Status {
-    ld_int::<UnsignedIntegerLittleEndianEncoding>(engine, "LDULE4", 32, PARAM | STAY)
-}
-
-// (slice - x slice)
-pub fn execute_ldile8(engine: &mut Engine) -> Status {
-    ld_int::<SignedIntegerLittleEndianEncoding>(engine, "LDILE8", 64, PARAM | STAY)
-}
-
-// (slice - x slice)
-pub fn execute_ldule8(engine: &mut Engine) -> Status {
-    ld_int::<UnsignedIntegerLittleEndianEncoding>(engine, "LDULE8", 64, PARAM | STAY)
-}
-
-// (slice - x slice)
-pub fn execute_pldile4(engine: &mut Engine) -> Status {
-    ld_int::<SignedIntegerLittleEndianEncoding>(engine, "PLDILE4", 32, PARAM)
-}
-
-// (slice - x slice)
-pub fn execute_pldule4(engine: &mut Engine) -> Status {
-    ld_int::<UnsignedIntegerLittleEndianEncoding>(engine, "PLDULE4", 32, PARAM)
-}
-
-// (slice - x slice)
-pub fn execute_pldile8(engine: &mut Engine) -> Status {
-    ld_int::<SignedIntegerLittleEndianEncoding>(engine, "PLDILE8", 64, PARAM)
-}
-
-// (slice - x slice)
-pub fn execute_pldule8(engine: &mut Engine) -> Status {
-    ld_int::<UnsignedIntegerLittleEndianEncoding>(engine, "PLDULE8", 64, PARAM)
-}
-
-// (slice - x slice)
-pub fn execute_ldile4q(engine: &mut Engine) -> Status {
-    ld_int::<SignedIntegerLittleEndianEncoding>(engine, "LDILE4Q", 32, PARAM | QUIET | STAY)
-}
-
-// (slice - x slice)
-pub fn execute_ldule4q(engine: &mut Engine) -> Status {
-    ld_int::<UnsignedIntegerLittleEndianEncoding>(engine, "LDULE4Q", 32, PARAM | QUIET | STAY)
-}
-
-// (slice - x slice)
-pub fn execute_ldile8q(engine: &mut Engine) -> Status {
-    ld_int::<SignedIntegerLittleEndianEncoding>(engine, "LDILE8Q", 64, PARAM | QUIET | STAY)
-}
-
-// (slice - x slice)
-pub fn execute_ldule8q(engine: &mut Engine) -> Status {
-    ld_int::<UnsignedIntegerLittleEndianEncoding>(engine, "LDULE8Q", 64, PARAM | QUIET | STAY)
-}
-
-// (slice - x slice)
-pub fn execute_pldile4q(engine: &mut Engine) -> Status {
-    ld_int::<SignedIntegerLittleEndianEncoding>(engine, "PLDILE4Q", 32, PARAM | QUIET)
-}
-
-// (slice - x slice)
-pub fn execute_pldule4q(engine: &mut Engine) -> Status {
-    ld_int::<UnsignedIntegerLittleEndianEncoding>(engine, "PLDULE4Q", 32, PARAM | QUIET)
-}
-
-// (slice - x slice)
-pub fn execute_pldile8q(engine: &mut Engine) -> Status {
-    ld_int::<SignedIntegerLittleEndianEncoding>(engine, "PLDILE8Q", 64, PARAM | QUIET)
-}
-
-// (slice - x slice)
-pub fn execute_pldule8q(engine: &mut Engine) -> Status {
-    ld_int::<UnsignedIntegerLittleEndianEncoding>(engine, "PLDULE8Q", 64, PARAM | QUIET)
+const LDLE_NAMES: [&str; 32] = [
+    "LDILE4", "LDULE4", "LDILE8", "LDULE8", "PLDILE4", "PLDULE4", "PLDILE8", "PLDULE8",
+    "LDILE4Q", "LDULE4Q", "LDILE8Q", "LDULE8Q", "PLDILE4Q", "PLDULE4Q", "PLDILE8Q", "PLDULE8Q",
+    "LDILE16", "LDULE16", "LDILE32", "LDULE32", "PLDILE16", "PLDULE16", "PLDILE32", "PLDULE32",
+    "LDILE16Q", "LDULE16Q", "LDILE32Q", "LDULE32Q", "PLDILE16Q", "PLDULE16Q", "PLDILE32Q", "PLDULE32Q",
+];
+
+/// LDILE4..PLDULE32Q (slice - x slice), loads a little-endian integer of 4, 8, 16 or 32 bytes.
+/// Bits of opcode: 0 - unsigned, 1 - double size, 2 - preload, 3 - quiet, 7 - 16 or 32 bytes.
+pub fn execute_ldle(engine: &mut Engine) -> Status {
+    let cmd = engine.last_cmd() as usize;
+    let name = LDLE_NAMES[(cmd & 0x0F) | (cmd & 0x80) >> 3];
+    let bits = 32 << ((cmd >> 1 & 1) | (cmd >> 6 & 2));
+    let mut how = PARAM;
+    if cmd & 4 == 0 {
+        how |= STAY
+    }
+    if cmd & 8 != 0 {
+        how |= QUIET
+    }
+    match cmd & 1 {
+        0 => ld_int::<SignedIntegerLittleEndianEncoding>(engine, name, bits, how),
+        _ => ld_int::<UnsignedIntegerLittleEndianEncoding>(engine, name, bits, how)
+    }
 }
 
 fn trim_leading_bits(slice: &mut SliceData, bit: u8) -> usize {
@@ -829,7 +801,7 @@ fn split(engine: &mut Engine, name: &'static str, quiet: bool) -> Status {
     )?;
     fetch_stack(engine, 3)?;
     let r = engine.cmd.var(0).as_integer()?.into(0..=4)?;
-    let l = engine.cmd.var(1).as_integer()?.into(0..=1023)?;
+    let l = engine.cmd.var(1).as_integer()?.into(0..=engine.data_bits_limit())?;
     let mut slice = engine.cmd.var(2).as_slice()?.clone();
     let data_len = slice.remaining_bits();
     let refs_count = slice.remaining_references();
@@ -976,13 +948,20 @@ pub fn execute_ldcont(engine: &mut Engine) -> Status {
     engine.load_instruction(Instruction::new("LDCONT"))?;
     fetch_stack(engine, 1)?;
     let mut slice = engine.cmd.var(0).as_slice()?.clone();
-    let cont = if engine.check_capabilities(ever_block::GlobalCapabilities::CapStcontNewFormat as u64) {
-        ContinuationData::deserialize(&mut slice, engine)?
+    let format = if engine.check_capabilities(ever_block::GlobalCapabilities::CapStcontNewFormat as u64) {
+        ContinuationFormat::New
+    } else {
+        ContinuationFormat::Old
+    };
+    let prices = engine.gas_prices().clone();
+    let (cont, gas) = if engine.has_vm_capability(VmCapabilities::CapAnyContinuationFormat) {
+        // continuations stored before format switch and framed ones are accepted too
+        let (cont, _, gas) = ContinuationData::deserialize_any(&mut slice, format, engine, &prices)?;
+        (cont, gas)
     } else {
-        let (cont, gas) = ContinuationData::deserialize_old(&mut slice)?;
-        engine.use_gas(gas);
-        cont
+        ContinuationData::deserialize_with_format(&mut slice, format, engine, &prices)?
     };
+    engine.use_gas(gas);
     engine.cc.stack.push_cont(cont);
     engine.cc.stack.push(StackItem::Slice(slice));
     Ok(())
@@ -31,9 +31,14 @@ use crate::{
 };
 use ever_block::{
     fail, BuilderData, ExceptionCode, GasConsumer,
-    HashmapE, HashmapSubtree, PfxHashmapE, Result, SliceData,
+    HashmapE, HashmapSubtree, Result, SliceData,
 };
 
+mod aug;
+pub mod pfx;
+pub(super) use aug::*;
+pub(super) use pfx::*;
+
 fn try_unref_leaf(slice: SliceData) -> Result<StackItem> {
     match slice.remaining_bits() == 0 && slice.remaining_references() != 0 {
         true => Ok(StackItem::Cell(slice.reference(0)?)),
@@ -206,104 +211,6 @@ fn find(
     Ok(())
 }
 
-// (value key slice nbits - slice -1|0)
-fn pfxdictset(engine: &mut Engine, name: &'static str, how: u8) -> Status {
-    engine.load_instruction(
-        Instruction::new(name)
-    )?;
-    let params = if how.bit(DEL) {
-        3
-    } else {
-        4
-    };
-    fetch_stack(engine, params)?;
-    let nbits = engine.cmd.var(0).as_integer()?.into(0..=1023)?;
-    let mut dict = PfxHashmapE::with_hashmap(nbits, engine.cmd.var(1).as_dict()?.cloned());
-    let key = engine.cmd.var(2).as_slice()?.clone();
-    let key_valid = if how.bit(DEL) { // remove
-        dict.remove_with_gas(key, engine)?.is_some()
-    } else {
-        let value = engine.cmd.var(3).as_slice()?.clone();
-        if how.bit(INV) { // add
-            if !dict.is_prefix(key.clone())? && dict.get(key.clone())?.is_none() {
-                dict.set_with_gas(key, &value, engine)?;
-                true
-            } else {
-                dict.get_with_gas(key, engine)?;
-                false
-            }
-        } else if how.bit(GET) { // replace
-            dict.replace_with_gas(key, &value, engine)?.is_some()
-        } else { // set
-            if !dict.is_prefix(key.clone())? {
-                dict.set_with_gas(key, &value, engine)?;
-                true
-            } else {
-                dict.get_prefix_leaf_with_gas(key, engine)?;
-                false
-            }
-        }
-    };
-    engine.cc.stack.push(StackItem::dict(&dict));
-    engine.cc.stack.push(boolean!(key_valid));
-    Ok(())
-}
-
-// (prefixed slice nbits - {prefix value suffix -1} | {prefixed | 0}
-fn pfxdictget(engine: &mut Engine, name: &'static str, how: u8) -> Status {
-    let get_cont = how.bit(CALLX) || how.bit(SWITCH);
-    let mut inst = Instruction::new(name);
-    if how.bit(CMD) {
-        inst = inst.set_opts(InstructionOptions::Dictionary(13, 10))
-    }
-    engine.load_instruction(inst)?;
-    fetch_stack(engine, if how.bit(CMD) {1} else {3})?;
-    let (nbits, dict, mut key);
-    if how.bit(CMD) {
-        nbits = engine.cmd.length();
-        dict  = PfxHashmapE::with_hashmap(nbits, engine.cmd.slice().reference_opt(0));
-        key   = engine.cmd.var(0).as_slice()?.clone();
-    } else {
-        nbits = engine.cmd.var(0).as_integer()?.into(0..=1023)?;
-        dict = PfxHashmapE::with_hashmap(nbits, engine.cmd.var(1).as_dict()?.cloned());
-        key   = engine.cmd.var(2).as_slice()?.clone();
-    }
-    if let (prefix, Some(value), suffix) = dict.get_prefix_leaf_with_gas(key.clone(), engine)? {
-        engine.cc.stack.push(StackItem::Slice(key.shrink_data(prefix.remaining_bits()..)));
-        if get_cont {
-            engine.cmd.vars.push(StackItem::continuation(
-                ContinuationData::with_code(value)
-            ));
-        } else {
-            engine.cc.stack.push(StackItem::Slice(value));
-        }
-        engine.cc.stack.push(StackItem::Slice(suffix));
-        if how.bit(RET) {
-            engine.cc.stack.push(boolean!(true));
-        }
-        if get_cont {
-            let n = engine.cmd.var_count();
-            if how.bit(SWITCH) {
-                switch(engine, var!(n - 1))
-            } else if how.bit(CALLX) {
-                callx(engine, n - 1, false)
-            } else {
-                fail!("pfxdictget: {:X}", how)
-            }
-        } else {
-            Ok(())
-        }
-    } else if how.bit(RET) || get_cont {
-        engine.cc.stack.push(engine.cmd.pop_var()?);
-        if how.bit(RET) {
-            engine.cc.stack.push(boolean!(false));
-        }
-        Ok(())
-    } else {
-        err!(ExceptionCode::CellUnderflow)
-    }
-}
-
 fn keyreader_from_slice(key: &StackItem, nbits: usize) -> Result<SliceData> {
     let mut key = key.as_slice()?.clone();
     if key.remaining_bits() < nbits {
@@ -1117,51 +1024,6 @@ pub(super) fn execute_dictugetexecz(engine: &mut Engine) -> Status {
     dictcont(engine, "DICTUGETEXECZ", keyreader_from_uint, CALLX | STAY)
 }
 
-// (value key slice nbits - slice -1|0)
-pub(super) fn execute_pfxdictset(engine: &mut Engine) -> Status {
-    pfxdictset(engine, "PFXDICTSET", 0)
-}
-
-// (value key slice nbits - slice -1|0)
-pub(super) fn execute_pfxdictreplace(engine: &mut Engine) -> Status {
-    pfxdictset(engine, "PFXDICTREPLACE", GET)
-}
-
-// (value key slice nbits - slice -1|0)
-pub(super) fn execute_pfxdictadd(engine: &mut Engine) -> Status {
-    pfxdictset(engine, "PFXDICTADD", INV | GET)
-}
-
-// (key slice nbits - slice -1|0)
-pub(super) fn execute_pfxdictdel(engine: &mut Engine) -> Status {
-    pfxdictset(engine, "PFXDICTDEL", DEL)
-}
-
-// (prefixed slice nbits - {prefix value suffix -1} | {prefixed | 0}
-pub(super) fn execute_pfxdictgetq(engine: &mut Engine) -> Status {
-    pfxdictget(engine, "PFXDICTGETQ", RET)
-}
-
-// (prefixed slice nbits - prefix value suffix -1}
-pub(super) fn execute_pfxdictget(engine: &mut Engine) -> Status {
-    pfxdictget(engine, "PFXDICTGET", 0)
-}
-
-// (s' s n - (s'' s''') | (s')))
-pub(super) fn execute_pfxdictgetjmp(engine: &mut Engine) -> Status {
-    pfxdictget(engine, "PFXDICTGETJMP", SWITCH)
-}
-
-// (s' s n - (s'' s'''))
-pub(super) fn execute_pfxdictgetexec(engine: &mut Engine) -> Status {
-    pfxdictget(engine, "PFXDICTGETEXEC", CALLX)
-}
-
-// (s' - (s'' s''') | (s')))
-pub(super) fn execute_pfxdictswitch(engine: &mut Engine) -> Status {
-    pfxdictget(engine, "PFXDICTSWITCH", CMD | SWITCH)
-}
-
 const QUIET: u8 = 0x01; // quiet variant
 const DICT:  u8 = 0x02; // dictionary
 const SLC:   u8 = 0x04; // slice
@@ -1301,3 +1163,7 @@ pub(super) fn execute_dictisetgetoptref(engine: &mut Engine) -> Status {
 pub(super) fn execute_dictusetgetoptref(engine: &mut Engine) -> Status {
     dict(engine, "DICTUSETGETOPTREF", keyreader_from_uint, SET | GET, valwriter_add_or_remove_refopt)
 }
+
+#[cfg(test)]
+#[path = "../tests/test_dictionary.rs"]
+mod tests;
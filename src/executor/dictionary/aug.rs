@@ -0,0 +1,129 @@
+/*
+* Copyright (C) 2019-2024 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+use super::{KeyReader, keyreader_from_int, keyreader_from_slice, keyreader_from_uint};
+use crate::{
+    executor::{engine::{Engine, storage::fetch_stack}, types::Instruction},
+    stack::{StackItem, aug::{AugDict, UintExtra}},
+    types::Status
+};
+use ever_block::Result;
+
+// (... D n m) - extra bits on top, key length and dictionary below them
+fn read_aug_dict(engine: &Engine) -> Result<AugDict<UintExtra>> {
+    let extra_bits = engine.cmd.var(0).as_integer()?.into(1..=256)?;
+    let bit_len = engine.cmd.var(1).as_integer()?.into(0..=1023)?;
+    Ok(AugDict::with_root(bit_len, UintExtra(extra_bits), engine.cmd.var(2).as_dict()?.cloned()))
+}
+
+fn aug_dict_item(dict: &AugDict<UintExtra>) -> StackItem {
+    match dict.root() {
+        Some(root) => StackItem::Cell(root.clone()),
+        None => StackItem::None
+    }
+}
+
+// (k D n m - x y -1 | 0)
+fn augdictget(engine: &mut Engine, name: &'static str, keyreader: KeyReader) -> Status {
+    engine.load_instruction(Instruction::new(name))?;
+    fetch_stack(engine, 4)?;
+    let dict = read_aug_dict(engine)?;
+    let key = keyreader(engine.cmd.var(3), dict.bit_len())?;
+    match dict.get(&key, engine)? {
+        Some((value, extra)) => {
+            engine.cc.stack.push(StackItem::Slice(value));
+            engine.cc.stack.push(StackItem::int(extra));
+            engine.cc.stack.push(boolean!(true));
+        }
+        None => {
+            engine.cc.stack.push(boolean!(false));
+        }
+    }
+    Ok(())
+}
+
+// (x y k D n m - D')
+fn augdictset(engine: &mut Engine, name: &'static str, keyreader: KeyReader) -> Status {
+    engine.load_instruction(Instruction::new(name))?;
+    fetch_stack(engine, 6)?;
+    let mut dict = read_aug_dict(engine)?;
+    let key = keyreader(engine.cmd.var(3), dict.bit_len())?;
+    let extra = engine.cmd.var(4).as_integer()?.clone();
+    let value = engine.cmd.var(5).as_slice()?.clone();
+    dict.set(&key, &value, &extra, engine)?;
+    engine.cc.stack.push(aug_dict_item(&dict));
+    Ok(())
+}
+
+// (k D n m - D' -1 | D 0)
+fn augdictdel(engine: &mut Engine, name: &'static str, keyreader: KeyReader) -> Status {
+    engine.load_instruction(Instruction::new(name))?;
+    fetch_stack(engine, 4)?;
+    let mut dict = read_aug_dict(engine)?;
+    let key = keyreader(engine.cmd.var(3), dict.bit_len())?;
+    let removed = dict.remove(&key, engine)?.is_some();
+    engine.cc.stack.push(aug_dict_item(&dict));
+    engine.cc.stack.push(boolean!(removed));
+    Ok(())
+}
+
+pub(in crate::executor) fn execute_augdictget(engine: &mut Engine) -> Status {
+    augdictget(engine, "AUGDICTGET", keyreader_from_slice)
+}
+
+pub(in crate::executor) fn execute_augdictiget(engine: &mut Engine) -> Status {
+    augdictget(engine, "AUGDICTIGET", keyreader_from_int)
+}
+
+pub(in crate::executor) fn execute_augdictuget(engine: &mut Engine) -> Status {
+    augdictget(engine, "AUGDICTUGET", keyreader_from_uint)
+}
+
+pub(in crate::executor) fn execute_augdictset(engine: &mut Engine) -> Status {
+    augdictset(engine, "AUGDICTSET", keyreader_from_slice)
+}
+
+pub(in crate::executor) fn execute_augdictiset(engine: &mut Engine) -> Status {
+    augdictset(engine, "AUGDICTISET", keyreader_from_int)
+}
+
+pub(in crate::executor) fn execute_augdictuset(engine: &mut Engine) -> Status {
+    augdictset(engine, "AUGDICTUSET", keyreader_from_uint)
+}
+
+pub(in crate::executor) fn execute_augdictdel(engine: &mut Engine) -> Status {
+    augdictdel(engine, "AUGDICTDEL", keyreader_from_slice)
+}
+
+pub(in crate::executor) fn execute_augdictidel(engine: &mut Engine) -> Status {
+    augdictdel(engine, "AUGDICTIDEL", keyreader_from_int)
+}
+
+pub(in crate::executor) fn execute_augdictudel(engine: &mut Engine) -> Status {
+    augdictdel(engine, "AUGDICTUDEL", keyreader_from_uint)
+}
+
+// (D n m - y)
+// returns extra aggregated over all leaves of augmented dictionary, zero for empty one
+pub(in crate::executor) fn execute_augdictextra(engine: &mut Engine) -> Status {
+    engine.load_instruction(Instruction::new("AUGDICTEXTRA"))?;
+    fetch_stack(engine, 3)?;
+    let dict = read_aug_dict(engine)?;
+    let extra = dict.extra(engine)?;
+    engine.cc.stack.push(StackItem::int(extra));
+    Ok(())
+}
+
+#[cfg(test)]
+#[path = "../../tests/test_aug.rs"]
+mod tests;
@@ -0,0 +1,212 @@
+/*
+* Copyright (C) 2019-2024 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+//! Prefix code dictionaries: primitives working with them and helpers serializing
+//! dictionaries and PFXDICTSWITCH dispatchers as they are used by contract code
+
+use super::{CALLX, CMD, DEL, GET, INV, RET, SWITCH};
+use crate::{
+    executor::{
+        Mask, continuation::{callx, switch}, engine::{Engine, storage::fetch_stack},
+        microcode::VAR, types::{Instruction, InstructionOptions}
+    },
+    stack::{StackItem, continuation::ContinuationData},
+    types::Status
+};
+use ever_block::{
+    error, fail, BuilderData, ExceptionCode, HashmapType, IBitstring, PfxHashmapE,
+    Result, SliceData,
+};
+
+/// Opcode prefix of PFXDICTSWITCH followed by dictionary bit, reference and key length
+const PFXDICTSWITCH_PREFIX: usize = 0x1E95;
+const PFXDICTSWITCH_PREFIX_BITS: usize = 13;
+const PFXDICTSWITCH_LENGTH_BITS: usize = 10;
+
+/// Builds prefix code dictionary with keys up to nbits bits long,
+/// keys must form a prefix code: none of them can be a prefix of another one
+pub fn pfx_dict_from_entries(
+    nbits: usize,
+    entries: impl IntoIterator<Item = (SliceData, SliceData)>
+) -> Result<PfxHashmapE> {
+    let mut dict = PfxHashmapE::with_bit_len(nbits);
+    for (key, value) in entries {
+        if dict.is_prefix(key.clone())? {
+            fail!("key {} is a prefix of another key of prefix dictionary", key)
+        }
+        if dict.set(key.clone(), &value)?.is_some() {
+            fail!("key {} is duplicated in prefix dictionary", key)
+        }
+    }
+    Ok(dict)
+}
+
+/// Serializes PFXDICTSWITCH instruction dispatching to continuations stored in dict
+pub fn pfxdictswitch_code(dict: &PfxHashmapE) -> Result<BuilderData> {
+    let root = dict.data().cloned().ok_or_else(
+        || error!("PFXDICTSWITCH requires non-empty prefix dictionary")
+    )?;
+    let mut code = BuilderData::new();
+    code.append_bits(PFXDICTSWITCH_PREFIX, PFXDICTSWITCH_PREFIX_BITS)?;
+    code.append_bit_one()?;
+    code.checked_append_reference(root)?;
+    code.append_bits(dict.bit_len(), PFXDICTSWITCH_LENGTH_BITS)?;
+    Ok(code)
+}
+
+// (value key slice nbits - slice -1|0)
+fn pfxdictset(engine: &mut Engine, name: &'static str, how: u8) -> Status {
+    engine.load_instruction(
+        Instruction::new(name)
+    )?;
+    let params = if how.bit(DEL) {
+        3
+    } else {
+        4
+    };
+    fetch_stack(engine, params)?;
+    let nbits = engine.cmd.var(0).as_integer()?.into(0..=1023)?;
+    let mut dict = PfxHashmapE::with_hashmap(nbits, engine.cmd.var(1).as_dict()?.cloned());
+    let key = engine.cmd.var(2).as_slice()?.clone();
+    let key_valid = if how.bit(DEL) { // remove
+        dict.remove_with_gas(key, engine)?.is_some()
+    } else {
+        let value = engine.cmd.var(3).as_slice()?.clone();
+        if how.bit(INV) { // add
+            if !dict.is_prefix(key.clone())? && dict.get(key.clone())?.is_none() {
+                dict.set_with_gas(key, &value, engine)?;
+                true
+            } else {
+                dict.get_with_gas(key, engine)?;
+                false
+            }
+        } else if how.bit(GET) { // replace
+            dict.replace_with_gas(key, &value, engine)?.is_some()
+        } else { // set
+            if !dict.is_prefix(key.clone())? {
+                dict.set_with_gas(key, &value, engine)?;
+                true
+            } else {
+                dict.get_prefix_leaf_with_gas(key, engine)?;
+                false
+            }
+        }
+    };
+    engine.cc.stack.push(StackItem::dict(&dict));
+    engine.cc.stack.push(boolean!(key_valid));
+    Ok(())
+}
+
+// (prefixed slice nbits - {prefix value suffix -1} | {prefixed | 0}
+fn pfxdictget(engine: &mut Engine, name: &'static str, how: u8) -> Status {
+    let get_cont = how.bit(CALLX) || how.bit(SWITCH);
+    let mut inst = Instruction::new(name);
+    if how.bit(CMD) {
+        inst = inst.set_opts(InstructionOptions::Dictionary(PFXDICTSWITCH_PREFIX_BITS, PFXDICTSWITCH_LENGTH_BITS))
+    }
+    engine.load_instruction(inst)?;
+    fetch_stack(engine, if how.bit(CMD) {1} else {3})?;
+    let (nbits, dict, mut key);
+    if how.bit(CMD) {
+        nbits = engine.cmd.length();
+        dict  = PfxHashmapE::with_hashmap(nbits, engine.cmd.slice().reference_opt(0));
+        key   = engine.cmd.var(0).as_slice()?.clone();
+    } else {
+        nbits = engine.cmd.var(0).as_integer()?.into(0..=1023)?;
+        dict = PfxHashmapE::with_hashmap(nbits, engine.cmd.var(1).as_dict()?.cloned());
+        key   = engine.cmd.var(2).as_slice()?.clone();
+    }
+    if let (prefix, Some(value), suffix) = dict.get_prefix_leaf_with_gas(key.clone(), engine)? {
+        engine.cc.stack.push(StackItem::Slice(key.shrink_data(prefix.remaining_bits()..)));
+        if get_cont {
+            engine.cmd.vars.push(StackItem::continuation(
+                ContinuationData::with_code(value)
+            ));
+        } else {
+            engine.cc.stack.push(StackItem::Slice(value));
+        }
+        engine.cc.stack.push(StackItem::Slice(suffix));
+        if how.bit(RET) {
+            engine.cc.stack.push(boolean!(true));
+        }
+        if get_cont {
+            let n = engine.cmd.var_count();
+            if how.bit(SWITCH) {
+                switch(engine, var!(n - 1))
+            } else if how.bit(CALLX) {
+                callx(engine, n - 1, false)
+            } else {
+                fail!("pfxdictget: {:X}", how)
+            }
+        } else {
+            Ok(())
+        }
+    } else if how.bit(RET) || get_cont {
+        engine.cc.stack.push(engine.cmd.pop_var()?);
+        if how.bit(RET) {
+            engine.cc.stack.push(boolean!(false));
+        }
+        Ok(())
+    } else {
+        err!(ExceptionCode::CellUnderflow)
+    }
+}
+
+// (value key slice nbits - slice -1|0)
+pub(in crate::executor) fn execute_pfxdictset(engine: &mut Engine) -> Status {
+    pfxdictset(engine, "PFXDICTSET", 0)
+}
+
+// (value key slice nbits - slice -1|0)
+pub(in crate::executor) fn execute_pfxdictreplace(engine: &mut Engine) -> Status {
+    pfxdictset(engine, "PFXDICTREPLACE", GET)
+}
+
+// (value key slice nbits - slice -1|0)
+pub(in crate::executor) fn execute_pfxdictadd(engine: &mut Engine) -> Status {
+    pfxdictset(engine, "PFXDICTADD", INV | GET)
+}
+
+// (key slice nbits - slice -1|0)
+pub(in crate::executor) fn execute_pfxdictdel(engine: &mut Engine) -> Status {
+    pfxdictset(engine, "PFXDICTDEL", DEL)
+}
+
+// (prefixed slice nbits - {prefix value suffix -1} | {prefixed | 0}
+pub(in crate::executor) fn execute_pfxdictgetq(engine: &mut Engine) -> Status {
+    pfxdictget(engine, "PFXDICTGETQ", RET)
+}
+
+// (prefixed slice nbits - prefix value suffix -1}
+pub(in crate::executor) fn execute_pfxdictget(engine: &mut Engine) -> Status {
+    pfxdictget(engine, "PFXDICTGET", 0)
+}
+
+// (s' s n - (s'' s''') | (s')))
+pub(in crate::executor) fn execute_pfxdictgetjmp(engine: &mut Engine) -> Status {
+    pfxdictget(engine, "PFXDICTGETJMP", SWITCH)
+}
+
+// (s' s n - (s'' s'''))
+pub(in crate::executor) fn execute_pfxdictgetexec(engine: &mut Engine) -> Status {
+    pfxdictget(engine, "PFXDICTGETEXEC", CALLX)
+}
+
+// (s' - (s'' s''') | (s')))
+pub(in crate::executor) fn execute_pfxdictswitch(engine: &mut Engine) -> Status {
+    pfxdictget(engine, "PFXDICTSWITCH", CMD | SWITCH)
+}
+
+#[cfg(test)]
+#[path = "../../tests/test_pfx.rs"]
+mod tests;
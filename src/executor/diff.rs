@@ -21,7 +21,8 @@ use crate::{
     }
 };
 use std::io::{Cursor, Read};
-use std::time::{Duration, Instant};
+use std::time::Duration;
+use crate::platform::Instant;
 use ever_block::GlobalCapabilities;
 use ever_block::{error, ExceptionCode, Result, SliceData};
 use crate::error::tvm_exception_code;
@@ -103,9 +104,10 @@ fn diff_similar_lib(engine: &mut Engine, fst: &str, snd: &str) -> Result<String>
 
     let mut config = similar::TextDiffConfig::default();
     let current_time = Instant::now();
-    config
-        .algorithm(similar::Algorithm::Myers)
-        .deadline(current_time + DIFF_TIMEOUT);
+    config.algorithm(similar::Algorithm::Myers);
+    // there is no clock on wasm32, so diff is limited only by gas there
+    #[cfg(not(target_arch = "wasm32"))]
+    config.deadline(current_time + DIFF_TIMEOUT);
     let diff = config.diff_lines(fst, snd);
     if current_time.elapsed() >= DIFF_TIMEOUT - Duration::from_millis(1) {
         return err!(ExceptionCode::OutOfGas);
@@ -84,6 +84,27 @@ fn dump_var_impl(item: &StackItem, how: u8, in_tuple: bool) -> String {
         }
     }
 }
+/// Dump of argument of exception not handled by contract: cells and slices are dumped in hex
+/// followed by their data as text if it is printable, the result is cut to limit chars
+pub(crate) fn dump_exception_argument(item: &StackItem, limit: usize) -> String {
+    let (mut dump, data) = match item {
+        StackItem::None => ("(null)".to_string(), None),
+        StackItem::Integer(_) => (dump_var(item, 0), None),
+        StackItem::Builder(x) => (dump_var(item, HEX), Some(x.data().to_vec())),
+        StackItem::Cell(x) => (dump_var(item, HEX), Some(x.data().to_vec())),
+        StackItem::Slice(x) => (dump_var(item, HEX), Some(x.get_bytestring(0))),
+        _ => (dump_var(item, HEX), None),
+    };
+    if let Some(Ok(text)) = data.as_deref().map(str::from_utf8) {
+        if !text.is_empty() && !text.chars().any(char::is_control) {
+            dump += &format!(" \"{}\"", text);
+        }
+    }
+    if dump.chars().count() > limit {
+        dump = dump.chars().take(limit).collect::<String>() + "...";
+    }
+    dump
+}
 /// dumps stack vars using internal fn dump_var
 fn dump_stack(engine: &mut Engine, depth: usize, print_depth: bool) -> Status {
     for i in 0..depth {
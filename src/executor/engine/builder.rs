@@ -0,0 +1,194 @@
+/*
+* Copyright (C) 2019-2024 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+use crate::{
+    executor::{
+        engine::core::{BuilderLimits, Engine, EngineTraceInfo, Limits, TraceCallback},
+        gas::gas_state::{Gas, GasPrices}
+    },
+    profiler::SessionProfiler,
+    stack::{Stack, StackItem, savelist::SaveList}
+};
+use ever_block::{error, fail, Cell, HashmapE, HashmapType, Result, SliceData, MAX_REFERENCES_COUNT};
+use std::sync::Arc;
+
+/// Collects everything needed to start execution and returns ready to run Engine.
+/// Unlike Engine::setup_with_libraries() registers are passed by their meaning,
+/// so they can not be mixed up, and the combination is validated before build
+#[derive(Default)]
+pub struct EngineBuilder {
+    capabilities: u64,
+    vm_capabilities: u64,
+    code: Option<SliceData>,
+    data: Option<Cell>,
+    stack: Option<Stack>,
+    c7: Option<StackItem>,
+    gas: Option<Gas>,
+    gas_prices: Option<GasPrices>,
+    unlimited_gas: bool,
+    trace: u8,
+    trace_callback: Option<Arc<TraceCallback>>,
+    limits: Option<Limits>,
+    builder_limits: Option<BuilderLimits>,
+    libraries: Vec<HashmapE>,
+    profiler: Option<Arc<SessionProfiler>>,
+}
+
+impl EngineBuilder {
+    pub fn new(capabilities: u64) -> Self {
+        Self { capabilities, ..Default::default() }
+    }
+
+    /// Mask of VmCapabilities, none are set by default
+    pub fn vm_capabilities(mut self, vm_capabilities: u64) -> Self {
+        self.vm_capabilities = vm_capabilities;
+        self
+    }
+
+    /// Code to execute, it is also put to c3 - required
+    pub fn code(mut self, code: SliceData) -> Self {
+        self.code = Some(code);
+        self
+    }
+
+    /// Persistent data put to c4 - required
+    pub fn data(mut self, data: Cell) -> Self {
+        self.data = Some(data);
+        self
+    }
+
+    pub fn stack(mut self, stack: Stack) -> Self {
+        self.stack = Some(stack);
+        self
+    }
+
+    /// Tuple put to c7, e.g. SmartContractInfo::into_temp_data_item(), default one is built by code
+    pub fn c7(mut self, c7: StackItem) -> Self {
+        self.c7 = Some(c7);
+        self
+    }
+
+    /// Gas limits, test ones are used if they are not set
+    pub fn gas(mut self, gas: Gas) -> Self {
+        self.gas = Some(gas);
+        self
+    }
+
+    pub fn gas_prices(mut self, prices: GasPrices) -> Self {
+        self.gas_prices = Some(prices);
+        self
+    }
+
+    /// Counts gas without raising out of gas, see Engine::with_unlimited_gas()
+    pub fn unlimited_gas(mut self) -> Self {
+        self.unlimited_gas = true;
+        self
+    }
+
+    /// Trace mask as Engine::TRACE_* constants
+    pub fn trace(mut self, trace: u8) -> Self {
+        self.trace = trace;
+        self
+    }
+
+    pub fn trace_callback(mut self, callback: impl Fn(&Engine, &EngineTraceInfo) + Send + Sync + 'static) -> Self {
+        self.trace_callback = Some(Arc::new(callback));
+        self
+    }
+
+    pub fn limits(mut self, limits: Limits) -> Self {
+        self.limits = Some(limits);
+        self
+    }
+
+    /// Limits of builders and new cells, e.g. BuilderLimits::default().with_size_limits()
+    /// of network configuration
+    pub fn builder_limits(mut self, limits: BuilderLimits) -> Self {
+        self.builder_limits = Some(limits);
+        self
+    }
+
+    /// Dictionaries of libraries with 256-bit hashes as keys
+    pub fn libraries(mut self, libraries: Vec<HashmapE>) -> Self {
+        self.libraries = libraries;
+        self
+    }
+
+    /// Session profiler collecting statistics of calls, see Engine::set_profiler()
+    pub fn profiler(mut self, profiler: Arc<SessionProfiler>) -> Self {
+        self.profiler = Some(profiler);
+        self
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.code.is_none() {
+            fail!("code is not set")
+        }
+        if self.data.is_none() {
+            fail!("persistent data for c4 is not set")
+        }
+        if let Some(c7) = &self.c7 {
+            if !SaveList::can_put(7, c7) {
+                fail!("c7 must be a tuple, got {}", c7)
+            }
+        }
+        if self.trace_callback.is_some() && self.trace == Engine::TRACE_NONE {
+            fail!("trace callback is set but trace mask is empty")
+        }
+        if let Some(limits) = &self.builder_limits {
+            if limits.max_references > MAX_REFERENCES_COUNT {
+                fail!("references limit {} is greater than cells can keep", limits.max_references)
+            }
+        }
+        for library in &self.libraries {
+            if library.bit_len() != 256 {
+                fail!("library dictionary has {}-bit keys instead of 256-bit hashes", library.bit_len())
+            }
+        }
+        Ok(())
+    }
+
+    /// Validates parameters and builds engine
+    pub fn build(self) -> Result<Engine> {
+        self.validate()?;
+        let code = self.code.ok_or_else(|| error!("code is not set"))?;
+        let data = self.data.ok_or_else(|| error!("persistent data for c4 is not set"))?;
+        let mut ctrls = SaveList::new();
+        ctrls.put(4, &mut StackItem::Cell(data))?;
+        if let Some(mut c7) = self.c7 {
+            ctrls.put(7, &mut c7)?;
+        }
+        let mut engine = Engine::with_capabilities(self.capabilities)
+            .with_vm_capabilities(self.vm_capabilities);
+        if let Some(prices) = self.gas_prices {
+            engine = engine.with_gas_prices(prices);
+        }
+        if self.unlimited_gas {
+            engine = engine.with_unlimited_gas();
+        }
+        engine.set_trace(self.trace);
+        if let Some(callback) = self.trace_callback {
+            engine.set_arc_trace_callback(callback);
+        }
+        if let Some(limits) = self.limits {
+            engine.set_limits(limits);
+        }
+        if let Some(limits) = self.builder_limits {
+            engine.set_builder_limits(limits);
+        }
+        if let Some(profiler) = self.profiler {
+            engine.set_profiler(profiler);
+        }
+        Ok(engine.setup_with_libraries(code, Some(ctrls), self.stack, self.gas, self.libraries))
+    }
+}
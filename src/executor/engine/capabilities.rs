@@ -0,0 +1,221 @@
+/*
+* Copyright (C) 2019-2024 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+use crate::executor::engine::Engine;
+use ever_block::GlobalCapabilities;
+use std::fmt;
+
+/// Capabilities of VM features which have no flag in GlobalCapabilities of the network.
+/// They are kept in separate mask, see Engine::set_vm_capabilities(), and are disabled by default
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[repr(u64)]
+pub enum VmCapabilities {
+    CapLongGlobals = 0x0000_0001,
+    CapRunVm = 0x0000_0002,
+    /// GASCONSUMED, PREVBLOCKSINFOTUPLE, GLOBALID and their items of c7
+    CapBlockchainInfo = 0x0000_0004,
+    /// BLS_VERIFY is charged its price once
+    CapBlsGasFix = 0x0000_0008,
+    /// ECRECOVER
+    CapEcrecover = 0x0000_0010,
+    /// CHKSIGNS_BATCH
+    CapBatchSignatures = 0x0000_0020,
+    /// LDCONT accepts framed continuations and ones stored in the other format
+    CapAnyContinuationFormat = 0x0000_0040,
+    /// New cells are charged by their depth and size of proof they carry
+    CapCellDepthGas = 0x0000_0080,
+    /// INCOMINGVALUE, DUEPAYMENT and their items of c7
+    CapTransactionInfo = 0x0000_0100,
+    /// CONFIGGASPRICES and CONFIGSIZELIMITS
+    CapConfigParams = 0x0000_0200,
+    /// SETRAND and ADDRAND reject values which are not 256-bit unsigned
+    CapStrictRandSeed = 0x0000_0400,
+    /// Quiet SETINDEX is charged for unchanged tuple as in the reference VM
+    CapExactTupleGas = 0x0000_0800,
+    /// SETLIBCODE and CHANGELIB accept bounce flag 16 in mode
+    CapLibraryBounce = 0x0000_1000,
+    /// LDEXTRACURRENCY, EXTRACURRENCYGET, EXTRACURRENCYADD and EXTRACURRENCYSUB
+    CapExtraCurrency = 0x0000_2000,
+    /// BUILDMSG
+    CapBuildMsg = 0x0000_4000,
+    /// Depth of anycast is checked by address parsing instructions
+    CapStrictAnycast = 0x0000_8000,
+    /// CONTCODE, CONTNARGS, CONTSTACK and CONTGETCTR
+    CapContIntrospection = 0x0001_0000,
+    /// TRYKEEPARGS
+    CapTryKeepArgs = 0x0002_0000,
+    /// HASHEXT, HASHEXTR, HASHEXTA and HASHEXTAR
+    CapHashExt = 0x0004_0000,
+    /// P256_CHKSIGNU and P256_CHKSIGNS
+    CapP256Signatures = 0x0008_0000,
+    /// RIST255 instructions and their quiet variants
+    CapCrypto = 0x0010_0000,
+    /// VRFVERIFY
+    CapVrfVerify = 0x0020_0000,
+    /// CREATEPROOF and CREATEUPDATE
+    CapMerkleProofs = 0x0040_0000,
+    /// Exotic cells opened explicitly by XCTOS, XLOAD and XLOADQ are charged by their own price
+    CapExoticLoadGas = 0x0080_0000,
+    /// XLOAD and XLOADQ resolve library and Merkle cells instead of loading them as is
+    CapXLoad = 0x0100_0000,
+    /// Along with CapBigCells big cells are loaded as ordinary ones
+    /// and slice and builder primitives accept their lengths
+    CapBigCellLimits = 0x0200_0000,
+    /// AUGDICT* instructions of augmented dictionaries
+    CapAugDicts = 0x0400_0000,
+    /// LINDEXVAR and LTLEN
+    CapLongTuples = 0x0800_0000,
+    /// Little-endian integers of 16 and 32 bytes: STILE16..STULE32 and LDILE16..PLDULE32Q
+    CapWideLittleEndian = 0x1000_0000,
+    /// FIXMUL96, FIXDIV96, FIXMUL128, FIXDIV128, ISQRT, ILOG2 and their quiet variants
+    CapFixedPoint = 0x2000_0000,
+    /// SENDMSG
+    CapSendMsg = 0x4000_0000,
+    /// RAWRESERVE and RAWRESERVEX accept bounce flag 16 in mode
+    CapReserveBounce = 0x8000_0000,
+    /// Pruned branches of trees registered with CellLoader are fetched from it on load
+    CapCellLoader = 0x1_0000_0000,
+    /// CLEVELMASK, CHASHI and CDEPTHI
+    CapCellLevels = 0x2_0000_0000,
+}
+
+/// Capability changing behavior of the VM
+#[derive(Clone, Copy, Debug)]
+pub struct CapabilityInfo {
+    pub capability: GlobalCapabilities,
+    pub description: &'static str,
+    /// Cargo feature the VM must be built with to take the capability into account
+    pub feature: Option<&'static str>,
+}
+
+impl CapabilityInfo {
+    const fn new(capability: GlobalCapabilities, description: &'static str) -> Self {
+        Self { capability, description, feature: None }
+    }
+
+    /// Checks if this build of the VM takes the capability into account
+    pub fn is_built(&self) -> bool {
+        match self.feature {
+            Some("gosh") => cfg!(feature = "gosh"),
+            Some("signature_with_id") => cfg!(feature = "signature_with_id"),
+            Some(_) => false,
+            None => true
+        }
+    }
+}
+
+/// All capabilities known to the VM, other capabilities of the network are ignored
+pub const KNOWN_CAPABILITIES: &[CapabilityInfo] = &[
+    CapabilityInfo::new(GlobalCapabilities::CapTvmV19, "TVM v19 instructions and c7 parameters"),
+    CapabilityInfo::new(GlobalCapabilities::CapsTvmBugfixes2022, "fixes of exception handling, gas and cell operations of 2022"),
+    CapabilityInfo::new(GlobalCapabilities::CapFixTupleIndexBug, "fixed index checks of tuple instructions"),
+    CapabilityInfo::new(GlobalCapabilities::CapStcontNewFormat, "new format of serialized continuations"),
+    CapabilityInfo::new(GlobalCapabilities::CapMycode, "MYCODE and code in c7"),
+    CapabilityInfo::new(GlobalCapabilities::CapInitCodeHash, "INITCODEHASH and init code hash in c7"),
+    CapabilityInfo::new(GlobalCapabilities::CapStorageFeeToTvm, "STORAGEFEES and storage fee in c7"),
+    CapabilityInfo::new(GlobalCapabilities::CapDelections, "SEQNO and block seqno in c7"),
+    CapabilityInfo::new(GlobalCapabilities::CapSetLibCode, "SETLIBCODE action"),
+    CapabilityInfo::new(GlobalCapabilities::CapCopyleft, "COPYLEFT action"),
+    CapabilityInfo::new(GlobalCapabilities::CapTvmV20, "TVM v20 instructions and gas of cells"),
+    CapabilityInfo::new(GlobalCapabilities::CapBigCells, "big cells loaded as ordinary ones"),
+    CapabilityInfo::new(GlobalCapabilities::CapResolveMerkleCell, "merkle cells resolved to their roots on load"),
+    CapabilityInfo::new(GlobalCapabilities::CapIndexAccounts, "instructions of indexed accounts"),
+    CapabilityInfo::new(GlobalCapabilities::CapFastStorageStat, "fast storage statistics of CDATASIZE and SDATASIZE"),
+    CapabilityInfo::new(GlobalCapabilities::CapFastStorageStatBugfix, "fixed fast storage statistics"),
+    #[cfg(feature = "signature_with_id")]
+    CapabilityInfo {
+        capability: GlobalCapabilities::CapSignatureWithId,
+        description: "signatures checked with network id domain",
+        feature: Some("signature_with_id"),
+    },
+    #[cfg(feature = "gosh")]
+    CapabilityInfo {
+        capability: GlobalCapabilities::CapDiff,
+        description: "DIFF, DIFF_PATCH, ZIP and UNZIP instructions",
+        feature: Some("gosh"),
+    },
+];
+
+/// Set of capabilities known to the VM
+#[derive(Clone, Copy, Default, Eq, Hash, PartialEq)]
+pub struct CapabilitySet(u64);
+
+impl CapabilitySet {
+    /// Capabilities taken into account by this build of the VM
+    pub fn supported() -> Self {
+        KNOWN_CAPABILITIES.iter()
+            .filter(|info| info.is_built())
+            .fold(Self::default(), |set, info| set.with(info.capability))
+    }
+
+    /// Known capabilities of the mask, unknown bits are dropped
+    pub fn from_bits(capabilities: u64) -> Self {
+        Self(capabilities & KNOWN_CAPABILITIES.iter().fold(0, |mask, info| mask | info.capability as u64))
+    }
+
+    pub fn bits(&self) -> u64 {
+        self.0
+    }
+
+    pub fn contains(&self, capability: GlobalCapabilities) -> bool {
+        self.0 & capability as u64 != 0
+    }
+
+    pub fn with(self, capability: GlobalCapabilities) -> Self {
+        Self(self.0 | capability as u64)
+    }
+
+    pub fn without(self, capability: GlobalCapabilities) -> Self {
+        Self(self.0 & !(capability as u64))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &'static CapabilityInfo> + '_ {
+        KNOWN_CAPABILITIES.iter().filter(|info| self.contains(info.capability))
+    }
+}
+
+impl fmt::Debug for CapabilitySet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter().map(|info| info.capability)).finish()
+    }
+}
+
+impl Engine {
+    /// Known capabilities of the engine, see capabilities() for the raw mask
+    pub fn capability_set(&self) -> CapabilitySet {
+        CapabilitySet::from_bits(self.capabilities())
+    }
+
+    /// Checks if the capability is set and this build of the VM takes it into account
+    pub fn supports(&self, capability: GlobalCapabilities) -> bool {
+        self.check_capabilities(capability as u64) && CapabilitySet::supported().contains(capability)
+    }
+
+    /// Sets or clears the capability, instructions available on the next step change accordingly
+    pub fn set_capability(&mut self, capability: GlobalCapabilities, enabled: bool) {
+        let capabilities = match enabled {
+            true => self.capabilities() | capability as u64,
+            false => self.capabilities() & !(capability as u64)
+        };
+        self.set_capabilities(capabilities)
+    }
+
+    /// Sets or clears the VM capability, instructions available on the next step change accordingly
+    pub fn set_vm_capability(&mut self, capability: VmCapabilities, enabled: bool) {
+        let vm_capabilities = match enabled {
+            true => self.vm_capabilities() | capability as u64,
+            false => self.vm_capabilities() & !(capability as u64)
+        };
+        self.set_vm_capabilities(vm_capabilities)
+    }
+}
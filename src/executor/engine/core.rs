@@ -12,10 +12,16 @@
 */
 
 use crate::{
-    error::{tvm_exception_full, TvmError, update_error_description},
+    logging::targets,
+    error::{
+        tvm_exception_code, tvm_exception_full, tvm_exception_or_custom_code, BacktraceFrame, ErrorContext,
+        TvmError, update_error_description, VmError
+    },
     executor::{
-        continuation::{switch, switch_to_c0}, engine::handlers::Handlers,
-        gas::gas_state::Gas, math::DivMode, microcode::{VAR, CTRL},
+        continuation::{switch, switch_to_c0}, dump::dump_exception_argument,
+        engine::{capabilities::VmCapabilities, handlers::Handlers},
+        gas::gas_state::{Gas, GasPrices}, hash::{HashFunction, BUILTIN_HASH_FUNCTIONS, HASH_ID_FROM_STACK},
+        host::execute_hostcall, math::DivMode, microcode::{VAR, CTRL},
         types::{
             InstructionExt, Instruction, InstructionOptions, InstructionParameter, RegisterPair,
             RegisterTrio, LengthAndIndex, WhereToGetParams,
@@ -25,18 +31,21 @@ use crate::{
         Stack, StackItem, continuation::{ContinuationData, ContinuationType},
         integer::IntegerData, savelist::SaveList
     },
+    debug_info::{DebugInfo, SourcePosition},
+    profiler::{CallKey, SessionProfiler},
     smart_contract_info::SmartContractInfo,
     types::{Exception, ResultMut, ResultOpt, ResultRef, Status}
 };
-use std::{sync::{Arc, Mutex}, ops::Range};
-use std::collections::{HashMap, HashSet};
+use std::{sync::{atomic::{AtomicU64, Ordering}, Arc, Mutex, RwLock}, ops::Range};
+use crate::platform::monotonic_millis;
+use std::collections::{HashMap, HashSet, VecDeque};
 use ever_block::{
-    error, BuilderData, Cell, CellType, Error, ExceptionCode, GasConsumer, HashmapE, IBitstring,
-    Result, SliceData, UInt256
+    error, fail, BuilderData, Cell, CellType, Error, ExceptionCode, GasConsumer, HashmapE, IBitstring,
+    Result, SizeLimitsConfig, SliceData, UInt256, MAX_DATA_BITS, MAX_LEVEL, MAX_REFERENCES_COUNT
 };
-use ever_block::{ShardAccount, Deserializable, GlobalCapabilities};
+use ever_block::{ShardAccount, Deserializable, GlobalCapabilities, Serializable, StateInit};
 
-pub(super) type ExecuteHandler = fn(&mut Engine) -> Status;
+pub type ExecuteHandler = fn(&mut Engine) -> Status;
 
 pub trait IndexProvider: Send + Sync {
     fn get_accounts_by_init_code_hash(&self, hash: &UInt256) -> Result<Vec<ShardAccount>>;
@@ -44,6 +53,28 @@ pub trait IndexProvider: Send + Sync {
     fn get_accounts_by_data_hash(&self, hash: &UInt256) -> Result<Vec<ShardAccount>>;
 }
 
+/// Source of library cells absent in libraries passed to Engine on setup
+pub trait LibraryProvider: Send + Sync {
+    fn get_library(&self, hash: &UInt256) -> Result<Option<Cell>>;
+}
+
+/// Source of cells kept outside of memory, e.g. in database. Execution can start with the tree
+/// where such cells are replaced by pruned branches, they are fetched by hash when contract
+/// loads them and the loading is charged as usual. Only pruned branches of trees registered
+/// by Engine::set_cell_loader() are fetched, see VmCapabilities::CapCellLoader
+pub trait CellLoader: Send + Sync {
+    fn load_cell(&self, hash: &UInt256) -> Result<Option<Cell>>;
+    /// Hint that cells are likely to be loaded soon, it is called when their parent is loaded
+    fn prefetch(&self, _hashes: &[UInt256]) {}
+}
+
+/// Host functions available to code executed outside consensus via HOSTCALL
+pub trait HostInterface: Send + Sync {
+    /// Executes host function with given id taking arguments from the stack and pushing results.
+    /// Returns gas to be charged for the call
+    fn call(&self, id: u8, stack: &mut Stack, gas_remaining: i64) -> Result<i64>;
+}
+
 pub(super) struct SliceProto {
     data_window: Range<usize>,
     references_window: Range<usize>,
@@ -74,6 +105,18 @@ impl From<&SliceData> for SliceProto {
 }
 
 pub type TraceCallback = dyn Fn(&Engine, &EngineTraceInfo) + Send + Sync;
+/// Called on every successful commit with hashes of committed c4 and c5
+pub type CommitCallback = dyn Fn(&UInt256, &UInt256) + Send + Sync;
+/// External entropy source for off-chain runs, it is mixed into random seed before every random number
+pub type EntropyCallback = dyn Fn() -> [u8; 32] + Send + Sync;
+/// Monotonic clock in milliseconds checked by time limit, e.g. performance.now() on wasm32
+pub type ClockCallback = dyn Fn() -> u64 + Send + Sync;
+
+// every child VM is executed recursively, so nesting is limited
+const MAX_CHILD_VM_DEPTH: usize = 16;
+const ERROR_CONTEXT_STACK_ITEMS: usize = 5;
+const MAX_BACKTRACE_FRAMES: usize = 32;
+const RESUME_CHECKPOINT_STEPS: usize = 32;
 
 pub struct Engine {
     pub(in crate::executor) cc: ContinuationData,
@@ -81,13 +124,24 @@ pub struct Engine {
     pub(in crate::executor) ctrls: SaveList,
     pub(in crate::executor) libraries: Vec<HashmapE>, // 256 bit dictionaries
     pub(in crate::executor) index_provider: Option<Arc<dyn IndexProvider>>,
+    library_provider: Option<Arc<dyn LibraryProvider>>,
+    cell_loader: Option<Arc<dyn CellLoader>>,
+    external_cells: HashSet<UInt256>, // cells of trees pruned by cell loader reached so far
+    host_interface: Option<Arc<dyn HostInterface>>,
     pub(in crate::executor) modifiers: BehaviorModifiers,
     pub(in crate::executor) checked_signatures_count: usize,
     // SliceData::load_cell() is faster than trying to cache SliceData for each
     // visited cell with HashMap<UInt256, SliceData>
     visited_cells: HashSet<UInt256>,
     visited_exotic_cells: HashMap<UInt256, SliceData>,
+    visited_journal: Option<VisitedJournal>,
+    history: Option<History>,
+    suspended: bool,
+    replaying: bool, // instructions are executed again after rollback, side effects are not repeated
     cstate: CommittedState,
+    commit_history: Vec<CommittedState>, // all commits made during execution
+    commit_callback: Option<Arc<CommitCallback>>,
+    entropy_source: Option<Arc<EntropyCallback>>,
     time: u64,
     gas: Gas,
     code_page: isize,
@@ -101,9 +155,35 @@ pub struct Engine {
     log_string: Option<&'static str>,
     flags: u64,
     capabilities: u64,
+    vm_capabilities: u64, // mask of VmCapabilities, features absent in GlobalCapabilities
     block_version: u32,
-    #[cfg(feature = "signature_with_id")]
-    signature_id: i32,
+    builder_limits: BuilderLimits,
+    limits: Limits,
+    commit_limits: CommitLimits,
+    committed_data_stats: Option<(UInt256, u64, u64)>, // hash, cells and bits of c4 checked by the last commit
+    started: Option<u64>, // time of the first instruction, set only if time is limited
+    clock: Option<Arc<ClockCallback>>, // platform clock is used if it is not set
+    stack_limit: usize,
+    error_context: Option<ErrorContext>,
+    exception_dump_limit: Option<usize>, // argument of unhandled exception is dumped to error
+    backtrace: Vec<BacktraceFrame>, // exceptions caught by handlers, only last ones are kept
+    backtrace_enabled: bool, // set by execute_with_context, otherwise only unhandled exceptions get context
+    debug_info: Option<Arc<DebugInfo>>,
+    cmd_cell_hash: Option<UInt256>, // hash of current code cell, it is tracked with debug info only
+    #[cfg(feature = "coverage")]
+    coverage: crate::coverage::Coverage,
+    #[cfg(feature = "metrics")]
+    metrics: Option<crate::metrics::ExecutionMetrics>,
+    #[cfg(feature = "debug")]
+    watchpoints: crate::watchpoints::Watchpoints,
+    vm_depth: usize, // nesting level of child VM started by RUNVM
+    handlers: Option<Arc<Handlers>>, // code page 0 extended with custom instructions
+    dispatch: Option<Arc<Handlers>>, // handlers resolved for enabled capabilities on the first instruction
+    gas_prices: GasPrices,
+    decode_only: Option<bool>, // instruction is not executed, flag is set when it is decoded
+    signature_domain: SignatureDomain,
+    hash_functions: HashMap<u8, HashFunction>, // registered by embedder in addition to builtin ones
+    profiler: Option<Arc<SessionProfiler>>,
 }
 
 #[cfg(feature = "signature_no_check")]
@@ -116,6 +196,131 @@ pub struct BehaviorModifiers {
 #[derive(Debug, Clone, Default)]
 pub struct BehaviorModifiers;
 
+/// Limits of execution independent of gas, exceeding any of them aborts execution
+/// with TvmError::LimitExceeded
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Limits {
+    pub max_steps: Option<u32>,
+    pub max_millis: Option<u64>,
+}
+
+/// Limits of data committed to c4 and c5 by COMMIT and at the end of execution, so states
+/// rejected later by block producer are not committed. Committing state which exceeds
+/// any of them aborts execution with TvmError::CommitRejected. Nothing is checked by default
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct CommitLimits {
+    /// Maximal number of distinct cells of c4
+    pub max_data_cells: Option<u64>,
+    /// Maximal number of bits in distinct cells of c4
+    pub max_data_bits: Option<u64>,
+    pub max_data_depth: Option<u16>,
+    /// Maximal number of output actions in c5
+    pub max_actions: Option<usize>,
+}
+
+/// Domain of signatures checked by CHKSIGNU, CHKSIGNS and batch checks: its prefix
+/// is prepended to signed data, so signatures can not be replayed in another network
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum SignatureDomain {
+    /// Data is checked as is
+    None,
+    /// Big endian network id, it is prepended only if CapSignatureWithId is set
+    /// and the VM is built with feature signature_with_id
+    GlobalId(i32),
+    /// Arbitrary prefix defined by network, it is prepended regardless of capabilities
+    Custom(Vec<u8>),
+}
+
+impl Default for SignatureDomain {
+    // feature signature_with_id keeps behavior of engines set up without id
+    fn default() -> Self {
+        match cfg!(feature = "signature_with_id") {
+            true => SignatureDomain::GlobalId(0),
+            false => SignatureDomain::None
+        }
+    }
+}
+
+/// Invocation of contract set up by Engine::from_state_init()
+#[derive(Clone, Debug)]
+pub enum StateInitCall {
+    /// Stack is given by caller, e.g. parameters of get method followed by its id
+    Stack(Stack),
+    /// Tick or tock transaction of special contract
+    TickTock { tock: bool },
+}
+
+/// Treatment of exotic cells by cell loading
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CellLoadMode {
+    /// Library cells are resolved, Merkle cells are resolved only with CapResolveMerkleCell (CTOS)
+    Resolve,
+    /// Cell is opened as is, e.g. by CDATASIZE
+    Raw,
+    /// Cell is opened as is by explicit request of contract (XCTOS)
+    Explicit,
+    /// Library and Merkle cells are resolved, pruned branches can be loaded only from CellLoader (XLOAD)
+    Exotic,
+}
+
+/// Limits of builder used by checking and quiet storing primitives and of cells built by the VM.
+/// Defaults are the ones of the standard cell library, networks with other cell library
+/// assumptions set them from their configuration
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct BuilderLimits {
+    pub max_data_bits: usize,
+    pub max_references: usize,
+    /// Maximal depth of new cells
+    pub max_cell_depth: u16,
+}
+
+/// Depth limit of cells in the standard cell library
+pub const MAX_CELL_DEPTH: u16 = 1024;
+
+/// Data limit of big cells allowed with CapBigCells and CapBigCellLimits
+pub const MAX_BIG_CELL_DATA_BITS: usize = 0xFF_FFFF * 8;
+
+impl Default for BuilderLimits {
+    fn default() -> Self {
+        Self {
+            max_data_bits: MAX_DATA_BITS,
+            max_references: MAX_REFERENCES_COUNT,
+            max_cell_depth: MAX_CELL_DEPTH,
+        }
+    }
+}
+
+impl BuilderLimits {
+    /// Default limits of network: big cells raise data limit
+    pub fn for_capabilities(capabilities: u64, vm_capabilities: u64) -> Self {
+        let mut limits = Self::default();
+        if capabilities & GlobalCapabilities::CapBigCells as u64 != 0
+            && vm_capabilities & VmCapabilities::CapBigCellLimits as u64 != 0 {
+            limits.max_data_bits = MAX_BIG_CELL_DATA_BITS;
+        }
+        limits
+    }
+    /// Limits with depth of new cells taken from size limits of network configuration
+    pub fn with_size_limits(mut self, limits: &SizeLimitsConfig) -> Self {
+        self.max_cell_depth = limits.max_vm_data_depth;
+        self
+    }
+    /// Checks if builder with the given content can still store data and references
+    pub fn check_space(&self, builder: &BuilderData, bits: usize, refs: usize) -> bool {
+        builder.bits_used() + bits <= self.max_data_bits
+            && builder.references_used() + refs <= self.max_references
+            && builder.check_enough_space(bits)
+            && builder.check_enough_refs(refs)
+    }
+    pub fn bits_free(&self, builder: &BuilderData) -> usize {
+        self.max_data_bits.min(builder.bits_used() + builder.bits_free()).saturating_sub(builder.bits_used())
+    }
+    pub fn references_free(&self, builder: &BuilderData) -> usize {
+        self.max_references.min(builder.references_used() + builder.references_free())
+            .saturating_sub(builder.references_used())
+    }
+}
+
 #[derive(Eq, Debug, PartialEq)]
 pub enum EngineTraceInfoType {
     Start,
@@ -134,6 +339,8 @@ pub struct EngineTraceInfo<'a> {
     pub stack: &'a Stack,
     pub gas_used: i64,
     pub gas_cmd: i64,
+    pub source: Option<&'a SourcePosition>, // resolved by debug info if it is set
+    pub error: Option<&'a Error>, // raised by the command or by implicit step
 }
 
 impl<'a> EngineTraceInfo<'a> {
@@ -142,7 +349,61 @@ impl<'a> EngineTraceInfo<'a> {
     }
 }
 
-#[derive(Debug)]
+// cells visited since the last checkpoint, they are forgotten on rollback
+// to keep gas consumption the same as without rollback
+#[derive(Default)]
+struct VisitedJournal {
+    cells: Vec<UInt256>,
+    exotic_cells: Vec<UInt256>,
+}
+
+impl VisitedJournal {
+    fn undo(self, engine: &mut Engine) {
+        for hash in self.cells {
+            engine.visited_cells.remove(&hash);
+        }
+        for hash in self.exotic_cells {
+            engine.visited_exotic_cells.remove(&hash);
+        }
+    }
+}
+
+// engine state before the instruction, used to resume execution after out of gas
+#[derive(Clone)]
+struct Checkpoint {
+    cc: ContinuationData,
+    ctrls: SaveList,
+    cstate: CommittedState,
+    commits: usize,
+    gas: Gas,
+    step: u32,
+    flags: u64,
+    checked_signatures_count: usize,
+}
+
+/// State of engine saved by Engine::checkpoint() to be restored by Engine::rollback().
+/// Stack items and registers are shared with the engine until one of them is modified
+#[derive(Clone)]
+pub struct EngineCheckpoint {
+    state: Checkpoint,
+    visited_cells: HashSet<UInt256>,
+    visited_exotic_cells: HashMap<UInt256, SliceData>,
+}
+
+// state before instruction with cells visited by it
+struct HistoryStep {
+    checkpoint: Checkpoint,
+    journal: VisitedJournal, // empty for the last step, its cells are in History::journal
+}
+
+// the last executed instructions recorded in trace mode for step_back()
+struct History {
+    window: usize,
+    steps: VecDeque<HistoryStep>,
+    journal: VisitedJournal, // cells visited by the current instruction
+}
+
+#[derive(Clone, Debug)]
 pub struct CommittedState {
     c4: StackItem,
     c5: StackItem,
@@ -180,12 +441,43 @@ impl CommittedState {
     }
 }
 
+/// Everything observable after execution of a contract collected in one place,
+/// so callers do not have to query engine state in a particular order
+#[derive(Clone, Debug)]
+pub struct ExecutionOutcome {
+    /// Exit code, exceptions are converted to their codes and out of gas is reported as -14
+    pub exit_code: i32,
+    pub gas_used: i64,
+    pub steps: u32,
+    /// c4 as it was set before execution
+    pub c4_before: Option<Cell>,
+    /// Committed c4 and c5, None if nothing was committed
+    pub c4_after: Option<Cell>,
+    pub c5_actions: Option<Cell>,
+    /// Result stack, the only item is the exception parameter if execution has failed
+    pub stack: Stack,
+}
+
+impl ExecutionOutcome {
+    pub fn is_committed(&self) -> bool {
+        self.c4_after.is_some()
+    }
+}
+
 impl GasConsumer for Engine {
     fn finalize_cell(&mut self, builder: BuilderData) -> Result<Cell> {
-        self.use_gas(Gas::finalize_price());
-        builder
-            .finalize(1024)
-            .map_err(|err| exception!(ExceptionCode::CellOverflow, "finalize cell error: {}", err))
+        self.use_gas(self.gas_prices.cell_create);
+        #[cfg(feature = "metrics")]
+        if let Some(metrics) = self.metrics.as_mut() {
+            metrics.cells_created += 1;
+        }
+        let cell = builder
+            .finalize(self.builder_limits.max_cell_depth)
+            .map_err(|err| exception!(ExceptionCode::CellOverflow, "finalize cell error: {}", err))?;
+        if self.has_vm_capability(VmCapabilities::CapCellDepthGas) {
+            self.use_gas(self.gas_prices.new_cell(&cell));
+        }
+        Ok(cell)
     }
     fn load_cell(&mut self, cell: Cell) -> Result<SliceData> {
         self.load_hashed_cell(cell, true)
@@ -198,6 +490,40 @@ impl GasConsumer for Engine {
 
 lazy_static::lazy_static! {
     static ref HANDLERS_CP0: Handlers = Handlers::new_code_page_0();
+    // capabilities gating any handler, other ones do not change dispatch table
+    static ref GATING_CAPABILITIES: (u64, u64) = HANDLERS_CP0.gating_capabilities();
+    // all handlers are enabled, instructions are decoded regardless of capabilities
+    static ref DECODING_TABLE: Arc<Handlers> = Arc::new(HANDLERS_CP0.resolve(u64::MAX, u64::MAX));
+}
+
+// tables of capability sets used recently are kept, the least recently used one is evicted
+const DISPATCH_TABLES_LIMIT: usize = 16;
+static DISPATCH_TABLES_TICK: AtomicU64 = AtomicU64::new(0);
+// table and tick of its last use by gating capabilities
+type DispatchTables = HashMap<(u64, u64), (Arc<Handlers>, AtomicU64)>;
+lazy_static::lazy_static! {
+    static ref DISPATCH_TABLES: RwLock<DispatchTables> = RwLock::new(HashMap::new());
+}
+
+// dispatch tables are built once per capability set and shared by all engines
+fn dispatch_table(capabilities: u64, vm_capabilities: u64) -> Arc<Handlers> {
+    let key = (capabilities & GATING_CAPABILITIES.0, vm_capabilities & GATING_CAPABILITIES.1);
+    let tick = DISPATCH_TABLES_TICK.fetch_add(1, Ordering::Relaxed);
+    if let Some((handlers, used)) = DISPATCH_TABLES.read().unwrap().get(&key) {
+        used.store(tick, Ordering::Relaxed);
+        return handlers.clone()
+    }
+    let handlers = Arc::new(HANDLERS_CP0.resolve(key.0, key.1));
+    let mut tables = DISPATCH_TABLES.write().unwrap();
+    if !tables.contains_key(&key) && tables.len() >= DISPATCH_TABLES_LIMIT {
+        let lru = tables.iter()
+            .min_by_key(|(_, (_, used))| used.load(Ordering::Relaxed))
+            .map(|(key, _)| *key);
+        if let Some(lru) = lru {
+            tables.remove(&lru);
+        }
+    }
+    tables.entry(key).or_insert_with(|| (handlers, AtomicU64::new(tick))).0.clone()
 }
 
 impl Engine {
@@ -210,6 +536,9 @@ impl Engine {
     pub const TRACE_ALL:   u8 = 0xFF;
     pub const TRACE_ALL_BUT_CTRLS: u8 = 0x07;
 
+    /// Stack depth is not limited by default as in reference implementation, deep stack is paid by gas
+    pub const DEFAULT_STACK_LIMIT: usize = usize::MAX;
+
     pub (crate) const FLAG_COPYLEFTED: u64 = 0x01;
 
     // External API ***********************************************************
@@ -222,11 +551,7 @@ impl Engine {
         } else {
             Engine::TRACE_NONE
         };
-        let log_enabled = log::log_enabled!(target: "tvm", log::Level::Debug)
-            || log::log_enabled!(target: "tvm", log::Level::Trace)
-            || log::log_enabled!(target: "tvm", log::Level::Info)
-            || log::log_enabled!(target: "tvm", log::Level::Error)
-            || log::log_enabled!(target: "tvm", log::Level::Warn);
+        let log_enabled = crate::logging::trace_enabled();
         let trace_callback: Option<Arc<TraceCallback>> = if !log_enabled {
             None
         } else if cfg!(feature="fift_check") {
@@ -242,6 +567,10 @@ impl Engine {
             ctrls: SaveList::new(),
             libraries: Vec::new(),
             index_provider: None,
+            library_provider: None,
+            cell_loader: None,
+            external_cells: HashSet::new(),
+            host_interface: None,
             #[cfg(not(feature = "signature_no_check"))]
             modifiers: BehaviorModifiers,
             #[cfg(feature = "signature_no_check")]
@@ -249,7 +578,14 @@ impl Engine {
             checked_signatures_count: 0,
             visited_cells: HashSet::new(),
             visited_exotic_cells: HashMap::new(),
+            visited_journal: None,
+            history: None,
+            suspended: false,
+            replaying: false,
             cstate: CommittedState::new_empty(),
+            commit_history: Vec::new(),
+            commit_callback: None,
+            entropy_source: None,
             time: 0,
             gas: Gas::empty(),
             code_page: 0,
@@ -263,10 +599,83 @@ impl Engine {
             log_string: None,
             flags: 0,
             capabilities,
+            vm_capabilities: 0,
             block_version: 0,
-            #[cfg(feature = "signature_with_id")]
-            signature_id: 0,
+            builder_limits: BuilderLimits::for_capabilities(capabilities, 0),
+            limits: Limits::default(),
+            commit_limits: CommitLimits::default(),
+            committed_data_stats: None,
+            started: None,
+            clock: None,
+            stack_limit: Engine::DEFAULT_STACK_LIMIT,
+            error_context: None,
+            exception_dump_limit: None,
+            backtrace: Vec::new(),
+            backtrace_enabled: false,
+            debug_info: None,
+            cmd_cell_hash: None,
+            #[cfg(feature = "coverage")]
+            coverage: Default::default(),
+            #[cfg(feature = "metrics")]
+            metrics: None,
+            #[cfg(feature = "debug")]
+            watchpoints: Default::default(),
+            vm_depth: 0,
+            handlers: None,
+            dispatch: None,
+            gas_prices: GasPrices::DEFAULT,
+            decode_only: None,
+            signature_domain: SignatureDomain::default(),
+            hash_functions: HashMap::new(),
+            profiler: None,
+        }
+    }
+
+    /// Creates engine for child VM inheriting capabilities and trace settings
+    pub(in crate::executor) fn child_engine(&self) -> Result<Engine> {
+        if self.vm_depth >= MAX_CHILD_VM_DEPTH {
+            return err!(ExceptionCode::RangeCheckError, "too deep child VM nesting: {}", self.vm_depth)
         }
+        let mut engine = Engine::with_capabilities(self.capabilities);
+        engine.vm_capabilities = self.vm_capabilities;
+        engine.index_provider = self.index_provider.clone();
+        engine.library_provider = self.library_provider.clone();
+        engine.cell_loader = self.cell_loader.clone();
+        engine.external_cells = self.external_cells.clone();
+        engine.host_interface = self.host_interface.clone();
+        engine.modifiers = self.modifiers.clone();
+        engine.trace = self.trace;
+        engine.trace_callback = self.trace_callback.clone();
+        engine.block_version = self.block_version;
+        engine.builder_limits = self.builder_limits;
+        engine.limits = self.limits;
+        engine.commit_limits = self.commit_limits;
+        engine.started = self.started;
+        engine.clock = self.clock.clone();
+        engine.stack_limit = self.stack_limit;
+        engine.vm_depth = self.vm_depth + 1;
+        engine.handlers = self.handlers.clone();
+        engine.dispatch = self.dispatch.clone();
+        engine.gas_prices = self.gas_prices.clone();
+        engine.entropy_source = self.entropy_source.clone();
+        engine.signature_domain = self.signature_domain.clone();
+        engine.hash_functions = self.hash_functions.clone();
+        engine.profiler = self.profiler.clone();
+        #[cfg(feature = "metrics")]
+        if self.metrics.is_some() {
+            engine.metrics = Some(Default::default());
+        }
+        Ok(engine)
+    }
+
+    /// Shares loaded cells of parent with child VM, it is called before and after execution
+    pub(in crate::executor) fn swap_visited_cells(&mut self, other: &mut Engine) {
+        std::mem::swap(&mut self.visited_cells, &mut other.visited_cells);
+        std::mem::swap(&mut self.visited_exotic_cells, &mut other.visited_exotic_cells);
+    }
+
+    pub(in crate::executor) fn libraries(&self) -> &Vec<HashmapE> {
+        &self.libraries
     }
 
     pub fn set_block_version(&mut self, block_version: u32) {
@@ -275,7 +684,15 @@ impl Engine {
 
     #[cfg(feature = "signature_with_id")]
     pub fn set_signature_id(&mut self, signature_id: i32) {
-        self.signature_id = signature_id;
+        self.signature_domain = SignatureDomain::GlobalId(signature_id);
+    }
+
+    pub fn set_signature_domain(&mut self, signature_domain: SignatureDomain) {
+        self.signature_domain = signature_domain
+    }
+
+    pub fn signature_domain(&self) -> &SignatureDomain {
+        &self.signature_domain
     }
 
     pub fn assert_ctrl(&self, ctrl: usize, item: &StackItem) -> &Engine {
@@ -291,6 +708,40 @@ impl Engine {
         self
     }
 
+    pub fn capabilities(&self) -> u64 {
+        self.capabilities
+    }
+
+    /// Replaces capabilities, dispatch table and builder limits are set for the new ones
+    /// as by with_capabilities, so builder limits set before are to be set again
+    pub fn set_capabilities(&mut self, capabilities: u64) {
+        self.capabilities = capabilities;
+        self.builder_limits = BuilderLimits::for_capabilities(capabilities, self.vm_capabilities);
+        self.dispatch = None;
+    }
+
+    /// Mask of VmCapabilities enabling VM features which are not assigned to GlobalCapabilities
+    pub fn vm_capabilities(&self) -> u64 {
+        self.vm_capabilities
+    }
+
+    /// Replaces VM capabilities, dispatch table and builder limits are set for the new ones
+    /// as by set_capabilities, so builder limits set before are to be set again
+    pub fn set_vm_capabilities(&mut self, vm_capabilities: u64) {
+        self.vm_capabilities = vm_capabilities;
+        self.builder_limits = BuilderLimits::for_capabilities(self.capabilities, vm_capabilities);
+        self.dispatch = None;
+    }
+
+    pub fn with_vm_capabilities(mut self, vm_capabilities: u64) -> Self {
+        self.set_vm_capabilities(vm_capabilities);
+        self
+    }
+
+    pub fn has_vm_capability(&self, capability: VmCapabilities) -> bool {
+        self.vm_capabilities & capability as u64 != 0
+    }
+
     pub fn check_capabilities(&self, capabilities: u64) -> bool {
         (self.capabilities & capabilities) == capabilities
     }
@@ -307,9 +758,176 @@ impl Engine {
         self.block_version
     }
 
+    /// Sets limits checked by BCHK*, BREM* and quiet store primitives,
+    /// data can not be stored over the limits of the cell implementation anyway
+    pub fn set_builder_limits(&mut self, limits: BuilderLimits) {
+        self.builder_limits = limits
+    }
+
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.limits = limits
+    }
+
+    /// Replaces clock checked by time limit. There is no clock on wasm32,
+    /// so time limit is never exceeded there without it
+    pub fn set_clock(&mut self, clock: impl Fn() -> u64 + Send + Sync + 'static) {
+        self.clock = Some(Arc::new(clock));
+    }
+
+    pub fn limits(&self) -> &Limits {
+        &self.limits
+    }
+
+    pub fn set_commit_limits(&mut self, limits: CommitLimits) {
+        self.commit_limits = limits
+    }
+
+    pub fn commit_limits(&self) -> &CommitLimits {
+        &self.commit_limits
+    }
+
+    fn check_commit_limits(&mut self, c4: &StackItem, c5: &StackItem) -> Status {
+        let limits = self.commit_limits;
+        if let Ok(c4) = c4.as_cell() {
+            if let Some(max_depth) = limits.max_data_depth {
+                let depth = c4.depth(MAX_LEVEL);
+                if depth > max_depth {
+                    return Err(TvmError::CommitRejected(format!("data depth {} exceeds {}", depth, max_depth)).into())
+                }
+            }
+            if limits.max_data_cells.is_some() || limits.max_data_bits.is_some() {
+                let (cells, bits) = match self.committed_data_stats {
+                    Some((hash, cells, bits)) if hash == c4.repr_hash() => (cells, bits),
+                    _ => {
+                        let mut visited = HashSet::new();
+                        let mut queue = vec![c4.clone()];
+                        let (mut cells, mut bits) = (0, 0);
+                        while let Some(cell) = queue.pop() {
+                            if visited.insert(cell.repr_hash()) {
+                                cells += 1;
+                                bits += cell.bit_length() as u64;
+                                for i in 0..cell.references_count() {
+                                    queue.push(cell.reference(i)?);
+                                }
+                            }
+                        }
+                        self.committed_data_stats = Some((c4.repr_hash(), cells, bits));
+                        (cells, bits)
+                    }
+                };
+                if let Some(max_cells) = limits.max_data_cells.filter(|max_cells| cells > *max_cells) {
+                    return Err(TvmError::CommitRejected(format!("data has {} cells, limit is {}", cells, max_cells)).into())
+                }
+                if let Some(max_bits) = limits.max_data_bits.filter(|max_bits| bits > *max_bits) {
+                    return Err(TvmError::CommitRejected(format!("data has {} bits, limit is {}", bits, max_bits)).into())
+                }
+            }
+        }
+        if let (Some(max_actions), Ok(c5)) = (limits.max_actions, c5.as_cell()) {
+            let mut actions = 0;
+            let mut cell = c5.clone();
+            while cell.references_count() != 0 || cell.bit_length() != 0 {
+                actions += 1;
+                if actions > max_actions {
+                    return Err(TvmError::CommitRejected(format!("more than {} actions", max_actions)).into())
+                }
+                cell = match cell.reference(0) {
+                    Ok(prev) => prev,
+                    Err(_) => return Err(TvmError::CommitRejected(format!("action {} has no link to previous one", actions)).into())
+                };
+            }
+        }
+        Ok(())
+    }
+
+    fn check_limits(&mut self) -> Status {
+        if let Some(max_steps) = self.limits.max_steps {
+            if self.step >= max_steps {
+                return Err(TvmError::LimitExceeded(format!("{} steps executed", self.step)).into())
+            }
+        }
+        if let Some(max_millis) = self.limits.max_millis {
+            let now = match &self.clock {
+                Some(clock) => clock(),
+                None => monotonic_millis()
+            };
+            let elapsed = now.saturating_sub(*self.started.get_or_insert(now));
+            if elapsed > max_millis {
+                return Err(TvmError::LimitExceeded(format!("{} ms elapsed", elapsed)).into())
+            }
+        }
+        Ok(())
+    }
+
+    pub fn builder_limits(&self) -> &BuilderLimits {
+        &self.builder_limits
+    }
+
+    /// Upper bound of bit lengths accepted by range checks of slice primitives:
+    /// with CapBigCellLimits limits set for builders can extend it beyond standard cell size
+    pub fn data_bits_limit(&self) -> usize {
+        match self.has_vm_capability(VmCapabilities::CapBigCellLimits) {
+            true => self.builder_limits.max_data_bits.max(MAX_DATA_BITS),
+            false => MAX_DATA_BITS
+        }
+    }
+
+    /// Dry run mode for gas estimation: gas is counted as usual but out of gas is never raised,
+    /// so gas_used() after execution is the exact requirement. It is kept after setup
+    pub fn with_unlimited_gas(mut self) -> Self {
+        self.gas.set_unlimited(true);
+        self
+    }
+
+    /// Replaces default gas prices of basic operations, e.g. by ones chosen from network config
+    pub fn with_gas_prices(mut self, prices: GasPrices) -> Self {
+        self.gas_prices = prices;
+        self
+    }
+
+    pub fn gas_prices(&self) -> &GasPrices {
+        &self.gas_prices
+    }
+
+    /// Registers hash function available to HASHEXT family by id, ids of builtin functions
+    /// and 255 can not be used
+    pub fn register_hash_function(&mut self, id: u8, function: HashFunction) -> Status {
+        if (id as usize) < BUILTIN_HASH_FUNCTIONS.len() || id == HASH_ID_FROM_STACK {
+            fail!("hash function id {} is reserved", id)
+        }
+        self.hash_functions.insert(id, function);
+        Ok(())
+    }
+
+    pub(in crate::executor) fn hash_function(&self, id: u8) -> Option<HashFunction> {
+        BUILTIN_HASH_FUNCTIONS.get(id as usize).or_else(|| self.hash_functions.get(&id)).copied()
+    }
+
+    /// Sets maximum stack depth, exceeding it after any instruction raises stack overflow
+    pub fn set_stack_limit(&mut self, stack_limit: usize) {
+        self.stack_limit = stack_limit
+    }
+
+    pub fn stack_limit(&self) -> usize {
+        self.stack_limit
+    }
+
+    fn check_stack_limit(&self) -> Status {
+        if self.cc.stack.depth() > self.stack_limit {
+            return err!(
+                ExceptionCode::StackOverflow,
+                "stack depth {} exceeds limit {}", self.cc.stack.depth(), self.stack_limit
+            )
+        }
+        Ok(())
+    }
+
     #[cfg(feature = "signature_with_id")]
     pub fn signature_id(&self) -> i32 {
-        self.signature_id
+        match self.signature_domain {
+            SignatureDomain::GlobalId(signature_id) => signature_id,
+            _ => 0
+        }
     }
 
     pub fn check_or_set_flags(&mut self, flags: u64) -> bool {
@@ -329,6 +947,65 @@ impl Engine {
         &self.cc.stack
     }
 
+    pub fn stack_mut(&mut self) -> &mut Stack {
+        &mut self.cc.stack
+    }
+
+    /// Hashes of ordinary cells loaded during execution, with proof::create_proof
+    /// they give proof of the part of account state used by contract
+    pub fn visited_cells(&self) -> &HashSet<UInt256> {
+        &self.visited_cells
+    }
+
+    /// Registers application specific instruction with opcode given by its bytes.
+    /// Only opcodes unknown to the VM can be used. The handler is called as built-in one:
+    /// it must start with load_custom_instruction and report failures with TVM exceptions
+    pub fn register_handler(&mut self, opcode_prefix: &[u8], handler: ExecuteHandler) -> Status {
+        let mut handlers = self.handlers.take()
+            .unwrap_or_else(|| Arc::new(HANDLERS_CP0.clone()));
+        let result = Arc::make_mut(&mut handlers).register_custom(opcode_prefix, handler);
+        self.dispatch = None;
+        self.handlers = Some(handlers);
+        result
+    }
+
+    /// Lists opcodes of code page 0 with their handlers
+    pub(in crate::executor) fn code_page_0_opcodes() -> Vec<(Vec<u8>, ExecuteHandler)> {
+        HANDLERS_CP0.opcodes()
+    }
+
+    /// Masks of global and VM capabilities gating the opcode of code page 0, None if it is unknown
+    pub(in crate::executor) fn opcode_capabilities(opcode: &[u8]) -> Option<(u64, u64)> {
+        HANDLERS_CP0.opcode_capabilities(opcode)
+    }
+
+    /// Creates engine decoding instructions of code page 0 regardless of capabilities,
+    /// its dispatch table is not taken from the cache of capability sets
+    pub(in crate::executor) fn decoder() -> Engine {
+        let mut engine = Engine::with_capabilities(u64::MAX);
+        engine.vm_capabilities = u64::MAX;
+        engine.dispatch = Some(DECODING_TABLE.clone());
+        engine.setup_with_libraries(SliceData::default(), None, None, None, vec![])
+    }
+
+    /// Decodes current instruction without executing it, code is advanced past the instruction.
+    /// Returns opcode length in bits if the instruction was decoded
+    pub(in crate::executor) fn decode_instruction(&mut self) -> Result<Option<usize>> {
+        self.cmd_code = SliceProto::from(self.cc.code());
+        let start = self.cc.code().pos();
+        let handler = self.fetch_handler()?;
+        let opcode_bits = self.cc.code().pos() - start;
+        self.decode_only = Some(false);
+        let _ = handler(self);
+        let decoded = self.decode_only.take() == Some(true);
+        Ok(decoded.then_some(opcode_bits))
+    }
+
+    /// Loads current custom instruction charging basic gas for it
+    pub fn load_custom_instruction(&mut self, name: &'static str) -> Status {
+        self.load_instruction(Instruction::new(name))
+    }
+
     pub fn try_use_gas(&mut self, gas: i64) -> Result<()> {
         self.gas.try_use_gas(gas)?;
         Ok(())
@@ -358,8 +1035,32 @@ impl Engine {
         format!(" {} {}", self.cstate.c4.dump_as_fift(), self.cstate.c5.dump_as_fift())
     }
 
+    /// Commits c4 and c5 if they fit into commit limits
+    pub fn checked_commit(&mut self) -> Status {
+        if self.commit_limits != CommitLimits::default() {
+            let (c4, c5) = (self.get_root(), self.get_actions());
+            self.check_commit_limits(&c4, &c5)?;
+        }
+        self.commit();
+        Ok(())
+    }
+
     pub fn commit(&mut self) {
         self.cstate = CommittedState::with_params(self.get_root(), self.get_actions());
+        if !self.cstate.committed {
+            return
+        }
+        if let Some(callback) = self.commit_callback.as_ref().filter(|_| !self.replaying) {
+            if let (Ok(c4), Ok(c5)) = (self.cstate.c4.as_cell(), self.cstate.c5.as_cell()) {
+                callback(&c4.repr_hash(), &c5.repr_hash())
+            }
+        }
+        self.commit_history.push(self.cstate.clone());
+    }
+
+    /// All states committed during execution in order, the last one is get_committed_state()
+    pub fn commit_history(&self) -> &[CommittedState] {
+        &self.commit_history
     }
 
     pub fn steps(&self) -> u32 {
@@ -371,6 +1072,19 @@ impl Engine {
     }
 
     fn trace_info(&self, info_type: EngineTraceInfoType, gas: i64, log_string: Option<String>) {
+        self.trace_info_with_error(info_type, gas, log_string, None)
+    }
+
+    fn trace_info_with_error(
+        &self,
+        info_type: EngineTraceInfoType,
+        gas: i64,
+        log_string: Option<String>,
+        error: Option<&Error>
+    ) {
+        if self.replaying {
+            return
+        }
         if let Some(trace_callback) = self.trace_callback.as_ref() {
             // bigint param has been withdrawn during execution, so take it from the stack
             let cmd_str = if self.cmd.biginteger_raw().is_some() {
@@ -391,6 +1105,8 @@ impl Engine {
                 stack: &self.cc.stack,
                 gas_used: self.gas_used(),
                 gas_cmd: self.gas_used() - gas,
+                source: self.cmd_source_position(),
+                error,
             };
             trace_callback(self, &info);
         }
@@ -398,8 +1114,7 @@ impl Engine {
 
     fn default_trace_callback(&self, info: &EngineTraceInfo) {
         if self.trace_bit(Engine::TRACE_CODE) && info.has_cmd() {
-            log::trace!(
-                target: "tvm",
+            tvm_log!(trace, targets::CODE,
                 "{}: {}\n{}\n",
                 info.step,
                 info.cmd_str,
@@ -407,63 +1122,62 @@ impl Engine {
             );
         }
         if self.trace_bit(Engine::TRACE_GAS) {
-            log::trace!(
-                target: "tvm",
+            tvm_log!(trace, targets::GAS,
                 "Gas: {} ({})\n",
                 info.gas_used,
                 info.gas_cmd
             );
         }
         if self.trace_bit(Engine::TRACE_STACK) {
-            log::trace!(target: "tvm", "{}", self.dump_stack("Stack trace", false));
+            tvm_log!(trace, targets::STACK, "{}", self.dump_stack("Stack trace", false));
         }
         if self.trace_bit(Engine::TRACE_CTRLS) {
-            log::trace!(target: "tvm", "{}", self.dump_ctrls(true));
+            tvm_log!(trace, targets::CTRLS, "{}", self.dump_ctrls(true));
         }
         if info.info_type == EngineTraceInfoType::Dump {
-            log::info!(target: "tvm", "{}", info.cmd_str);
+            tvm_log!(info, targets::DEBUG, "{}", info.cmd_str);
         }
     }
 
     #[allow(dead_code)]
     fn fift_trace_callback(&self, info: &EngineTraceInfo) {
         if info.info_type == EngineTraceInfoType::Dump {
-            log::info!(target: "tvm", "{}", info.cmd_str);
+            tvm_log!(info, targets::DEBUG, "{}", info.cmd_str);
         } else if info.info_type == EngineTraceInfoType::Start {
             if self.trace_bit(Engine::TRACE_CTRLS) {
-                log::trace!(target: "tvm", "{}", self.dump_ctrls(true));
+                tvm_log!(trace, targets::CTRLS, "{}", self.dump_ctrls(true));
             }
             if self.trace_bit(Engine::TRACE_STACK) {
-                log::info!(target: "tvm", " [ {} ] \n", self.get_stack_result_fift());
+                tvm_log!(info, targets::STACK, " [ {} ] \n", self.get_stack_result_fift());
             }
             if self.trace_bit(Engine::TRACE_GAS) {
-                log::info!(target: "tvm", "gas - {}\n", info.gas_used);
+                tvm_log!(info, targets::GAS, "gas - {}\n", info.gas_used);
             }
         } else if info.info_type == EngineTraceInfoType::Exception {
             if self.trace_bit(Engine::TRACE_CODE) {
-                log::info!(target: "tvm", "BAD_CODE: {}\n", info.cmd_str);
+                tvm_log!(info, targets::EXCEPTION, "BAD_CODE: {}\n", info.cmd_str);
             }
             if self.trace_bit(Engine::TRACE_STACK) {
-                log::info!(target: "tvm", " [ {} ] \n", self.get_stack_result_fift());
+                tvm_log!(info, targets::STACK, " [ {} ] \n", self.get_stack_result_fift());
             }
             if self.trace_bit(Engine::TRACE_CTRLS) {
-                log::trace!(target: "tvm", "{}", self.dump_ctrls(true));
+                tvm_log!(trace, targets::CTRLS, "{}", self.dump_ctrls(true));
             }
             if self.trace_bit(Engine::TRACE_GAS) {
-                log::info!(target: "tvm", "gas - {}\n", info.gas_used);
+                tvm_log!(info, targets::GAS, "gas - {}\n", info.gas_used);
             }
         } else if info.has_cmd() {
             if self.trace_bit(Engine::TRACE_CODE) {
-                log::info!(target: "tvm", "execute {}\n", info.cmd_str);
+                tvm_log!(info, targets::CODE, "execute {}\n", info.cmd_str);
             }
             if self.trace_bit(Engine::TRACE_STACK) {
-                log::info!(target: "tvm", " [ {} ] \n", self.get_stack_result_fift());
+                tvm_log!(info, targets::STACK, " [ {} ] \n", self.get_stack_result_fift());
             }
             if self.trace_bit(Engine::TRACE_CTRLS) {
-                log::trace!(target: "tvm", "{}", self.dump_ctrls(true));
+                tvm_log!(trace, targets::CTRLS, "{}", self.dump_ctrls(true));
             }
             if self.trace_bit(Engine::TRACE_GAS) {
-                log::info!(target: "tvm", "gas - {}\n", info.gas_used);
+                tvm_log!(info, targets::GAS, "gas - {}\n", info.gas_used);
             }
         }
     }
@@ -521,89 +1235,533 @@ impl Engine {
     #[allow(dead_code)]
     pub fn simple_trace_callback(enine: &Engine, info: &EngineTraceInfo) {
         if info.info_type == EngineTraceInfoType::Dump {
-            log::info!(target: "tvm", "{}", info.cmd_str);
+            tvm_log!(info, targets::DEBUG, "{}", info.cmd_str);
         } else if info.info_type == EngineTraceInfoType::Start {
             if enine.trace_bit(Engine::TRACE_CTRLS) {
-                log::trace!(target: "tvm", "{}", enine.dump_ctrls(true));
+                tvm_log!(trace, targets::CTRLS, "{}", enine.dump_ctrls(true));
             }
             if enine.trace_bit(Engine::TRACE_STACK) {
-                log::info!(target: "tvm", " [ {} ] \n", Self::dump_stack_result(info.stack));
+                tvm_log!(info, targets::STACK, " [ {} ] \n", Self::dump_stack_result(info.stack));
             }
             if enine.trace_bit(Engine::TRACE_GAS) {
-                log::info!(target: "tvm", "gas - {}\n", info.gas_used);
+                tvm_log!(info, targets::GAS, "gas - {}\n", info.gas_used);
             }
         } else if info.info_type == EngineTraceInfoType::Exception {
             if enine.trace_bit(Engine::TRACE_CODE) {
-                log::info!(target: "tvm", "{} ({}) BAD_CODE: {}\n", info.step, info.gas_cmd, info.cmd_str);
+                tvm_log!(info, targets::EXCEPTION, "{} ({}) BAD_CODE: {}\n", info.step, info.gas_cmd, info.cmd_str);
             }
             if enine.trace_bit(Engine::TRACE_STACK) {
-                log::info!(target: "tvm", " [ {} ] \n", Self::dump_stack_result(info.stack));
+                tvm_log!(info, targets::STACK, " [ {} ] \n", Self::dump_stack_result(info.stack));
             }
             if enine.trace_bit(Engine::TRACE_CTRLS) {
-                log::trace!(target: "tvm", "{}", enine.dump_ctrls(true));
+                tvm_log!(trace, targets::CTRLS, "{}", enine.dump_ctrls(true));
             }
             if enine.trace_bit(Engine::TRACE_GAS) {
-                log::info!(target: "tvm", "gas - {}\n", info.gas_used);
+                tvm_log!(info, targets::GAS, "gas - {}\n", info.gas_used);
             }
         } else if info.has_cmd() {
             if enine.trace_bit(Engine::TRACE_CODE) {
-                log::info!(target: "tvm", "{}\n", info.cmd_str);
+                tvm_log!(info, targets::CODE, "{}\n", info.cmd_str);
             }
             if enine.trace_bit(Engine::TRACE_STACK) {
-                log::info!(target: "tvm", " [ {} ] \n", Self::dump_stack_result(info.stack));
+                tvm_log!(info, targets::STACK, " [ {} ] \n", Self::dump_stack_result(info.stack));
             }
             if enine.trace_bit(Engine::TRACE_CTRLS) {
-                log::trace!(target: "tvm", "{}", enine.dump_ctrls(true));
+                tvm_log!(trace, targets::CTRLS, "{}", enine.dump_ctrls(true));
             }
             if enine.trace_bit(Engine::TRACE_GAS) {
-                log::info!(target: "tvm", "gas - {}\n", info.gas_used);
+                tvm_log!(info, targets::GAS, "gas - {}\n", info.gas_used);
             }
         }
     }
 
     pub fn execute(&mut self) -> Result<i32> {
+        let call = self.profiler.is_some().then(|| self.profiled_call());
+        #[cfg(feature = "metrics")]
+        if let Some(metrics) = self.metrics.as_mut() {
+            *metrics = Default::default();
+        }
+        let result = self.execute_code();
+        if let (Some(profiler), Some((code_hash, method_id))) = (&self.profiler, call) {
+            profiler.record(code_hash, method_id, self.gas_used(), result.is_err());
+        }
+        #[cfg(feature = "metrics")]
+        let gas_used = self.gas_used().max(0) as u64;
+        #[cfg(feature = "metrics")]
+        if let Some(metrics) = self.metrics.as_mut() {
+            metrics.gas_used = gas_used;
+            metrics.report();
+        }
+        result
+    }
+
+    fn execute_code(&mut self) -> Result<i32> {
         self.trace_info(EngineTraceInfoType::Start, 0, None);
         let result = loop {
-            if let Some(result) = self.seek_next_cmd()? {
+            if let Some(result) = self.execute_cmd()? {
                 break result
             }
-            let gas = self.gas_used();
-            self.cmd_code = SliceProto::from(self.cc.code());
-            let execution_result = match HANDLERS_CP0.get_handler(self) {
-                Err(err) => {
-                    self.basic_use_gas(8);
-                    Some(err)
+        };
+        self.finish_execution(result)
+    }
+
+    // code hash and method id of the call to be profiled, they are taken before execution.
+    // Method id is the top of initial stack as it is pushed by get method calls, message body is not parsed,
+    // so calls by messages are keyed by their selector: 0 for internal and -1 for external ones
+    fn profiled_call(&self) -> CallKey {
+        let code_hash = self.cc.code().cell_opt().map(|cell| cell.repr_hash()).unwrap_or_default();
+        let method_id = match self.cc.stack.depth() {
+            0 => None,
+            _ => self.cc.stack.get(0).as_integer().and_then(|x| x.into(i64::MIN..=i64::MAX)).ok()
+        };
+        (code_hash, method_id)
+    }
+
+    /// Plugs profiler collecting statistics of calls, it is shared with child VMs
+    pub fn set_profiler(&mut self, profiler: Arc<SessionProfiler>) {
+        self.profiler = Some(profiler);
+    }
+
+    /// Executes code like execute() collecting results into ExecutionOutcome,
+    /// only errors not being VM exceptions are returned as Err
+    pub fn execute_with_outcome(&mut self) -> Result<ExecutionOutcome> {
+        let c4_before = self.ctrls.get(4).and_then(|c4| c4.as_cell().ok()).cloned();
+        let (exit_code, stack) = match self.execute() {
+            Ok(exit_code) => (exit_code, self.withdraw_stack()),
+            Err(err) => match tvm_exception_full(&err) {
+                Some(exception) => {
+                    let exit_code = exception.exit_code();
+                    let mut stack = Stack::new();
+                    stack.push(exception.value);
+                    (exit_code, stack)
                 }
-                Ok(handler) => {
-                    match handler(self) {
-                        Err(e) => {
-                            Some(update_error_description(e, |e|
-                                format!("CMD: {}{} err: {}", self.cmd.proto.name_prefix.unwrap_or_default(), self.cmd.proto.name, e)
-                            ))
-                        }
-                        Ok(_) => self.gas.check_gas_remaining().err(),
-                    }
+                None => return Err(err)
+            }
+        };
+        let (c4_after, c5_actions) = match self.cstate.is_committed() {
+            true => (
+                Some(self.cstate.get_root().as_cell()?.clone()),
+                Some(self.cstate.get_actions().as_cell()?.clone())
+            ),
+            false => (None, None)
+        };
+        Ok(ExecutionOutcome {
+            exit_code,
+            gas_used: self.gas_used(),
+            steps: self.step,
+            c4_before,
+            c4_after,
+            c5_actions,
+            stack,
+        })
+    }
+
+    /// Executes code like execute() returning error with context of failed instruction
+    pub fn execute_with_context(&mut self) -> std::result::Result<i32, VmError> {
+        self.error_context = None;
+        self.backtrace.clear();
+        self.backtrace_enabled = true;
+        let result = self.execute();
+        self.backtrace_enabled = false;
+        result.map_err(|error| VmError {
+            error,
+            context: self.error_context.clone(),
+            backtrace: self.backtrace.clone(),
+        })
+    }
+
+    /// Collects counters of every execute() and reports them to the recorder of metrics crate,
+    /// child VMs report their own counters
+    #[cfg(feature = "metrics")]
+    pub fn enable_metrics(&mut self) {
+        self.metrics = Some(Default::default())
+    }
+
+    /// Counters of the last execution, None if metrics are not enabled
+    #[cfg(feature = "metrics")]
+    pub fn metrics(&self) -> Option<&crate::metrics::ExecutionMetrics> {
+        self.metrics.as_ref()
+    }
+
+    /// Returns instructions executed since the previous call
+    #[cfg(feature = "coverage")]
+    pub fn take_coverage(&mut self) -> crate::coverage::Coverage {
+        std::mem::take(&mut self.coverage)
+    }
+
+    /// Registers condition pausing execute_resumable() after instruction, returns its id
+    #[cfg(feature = "debug")]
+    pub fn add_watchpoint(&mut self, watchpoint: crate::watchpoints::Watchpoint) -> usize {
+        self.watchpoints.add(watchpoint)
+    }
+
+    #[cfg(feature = "debug")]
+    pub fn remove_watchpoint(&mut self, id: usize) -> Option<crate::watchpoints::Watchpoint> {
+        self.watchpoints.remove(id)
+    }
+
+    /// Id of watchpoint which has paused execution, None if it is paused by out of gas
+    #[cfg(feature = "debug")]
+    pub fn triggered_watchpoint(&self) -> Option<usize> {
+        self.watchpoints.triggered()
+    }
+
+    /// Sets map of instructions to source positions used in traces and error context,
+    /// description of error is kept as is
+    pub fn set_debug_info(&mut self, debug_info: Arc<DebugInfo>) {
+        self.debug_info = Some(debug_info)
+    }
+
+    /// Source position of current instruction
+    pub fn cmd_source_position(&self) -> Option<&SourcePosition> {
+        self.debug_info.as_ref()?.find(self.cmd_cell_hash.as_ref()?, self.cmd_code.pos())
+    }
+
+    /// Argument of exception not handled by contract, e.g. payload of THROWARG, is dumped
+    /// to description of returned error cut to limit chars. None disables dumping
+    pub fn set_exception_dump(&mut self, limit: Option<usize>) {
+        self.exception_dump_limit = limit
+    }
+
+    /// Context of the last exception raised during execution
+    pub fn error_context(&self) -> Option<&ErrorContext> {
+        self.error_context.as_ref()
+    }
+
+    /// Exceptions caught by handlers during execution, the oldest is the first
+    pub fn vm_backtrace(&self) -> &[BacktraceFrame] {
+        &self.backtrace
+    }
+
+    // called when exception is passed to handler, normal termination is not recorded
+    fn record_caught_exception(&mut self) {
+        if self.replaying || matches!(self.cc.type_of, ContinuationType::Quit(_)) {
+            return
+        }
+        if let Some(context) = self.error_context.clone() {
+            if self.backtrace.len() == MAX_BACKTRACE_FRAMES {
+                self.backtrace.remove(0);
+            }
+            self.backtrace.push(BacktraceFrame {
+                context,
+                handler_hash: self.cc.code().cell_opt().map(|cell| cell.repr_hash()).unwrap_or_default(),
+                handler_type: self.cc.type_name(),
+            });
+        }
+    }
+
+    // exception terminates execution if c2 is not set or it is ExcQuit
+    fn has_exception_handler(&self) -> bool {
+        self.ctrls.get(2)
+            .and_then(|c2| c2.as_continuation().ok())
+            .map_or(false, |c2| c2.type_of != ContinuationType::ExcQuit)
+    }
+
+    fn make_error_context(&self, err: &Error, decoded: bool) -> ErrorContext {
+        let opcode = match (decoded, self.cmd.proto.name_prefix) {
+            (false, _) => String::new(),
+            (true, Some(prefix)) => format!("{}{}", prefix, self.cmd.proto.name),
+            (true, None) => self.cmd.proto.name.to_string()
+        };
+        ErrorContext {
+            exception_code: tvm_exception_or_custom_code(err),
+            opcode,
+            code_hash: self.cc.code().cell_opt().map(|cell| cell.repr_hash()).unwrap_or_default(),
+            bit_offset: self.cmd_code.pos(),
+            gas_used: self.gas_used(),
+            stack_top: self.cc.stack.iter().rev().take(ERROR_CONTEXT_STACK_ITEMS).cloned().collect(),
+            source: self.cmd_source_position().cloned(),
+        }
+    }
+
+    /// Executes code like execute(), but out of gas does not terminate execution:
+    /// the engine is rolled back to the start of the instruction which ran out of gas
+    /// and suspended, so the caller can call add_gas() and continue with this method.
+    /// With debug feature the engine is also suspended after instruction hitting a watchpoint.
+    /// Returns Ok(None) if the engine is suspended and Ok(Some(exit_code)) on termination.
+    pub fn execute_resumable(&mut self) -> Result<Option<i32>> {
+        if !self.suspended {
+            self.trace_info(EngineTraceInfoType::Start, 0, None);
+        }
+        self.suspended = false;
+        // state is saved once per RESUME_CHECKPOINT_STEPS instructions, instructions executed after
+        // the checkpoint are replayed on out of gas. Host and entropy source can answer differently
+        // on replay, so with them state is saved before each instruction
+        let interval = match self.host_interface.is_some() || self.entropy_source.is_some() {
+            true => 1,
+            false => RESUME_CHECKPOINT_STEPS
+        };
+        let mut checkpoint = self.make_checkpoint();
+        let mut executed = 0;
+        let result = loop {
+            if executed == interval {
+                checkpoint = self.make_checkpoint();
+                executed = 0;
+            }
+            #[cfg(feature = "debug")]
+            let watched = !self.watchpoints.is_empty();
+            #[cfg(feature = "debug")]
+            if watched {
+                self.watchpoints.before(&self.cc.stack, &self.ctrls);
+            }
+            match self.execute_cmd() {
+                #[cfg(feature = "debug")]
+                Ok(None) if watched && self.watchpoints.after(&self.cc.stack, &self.ctrls).is_some() => {
+                    self.visited_journal = None;
+                    self.suspended = true;
+                    return Ok(None)
                 }
-            };
-            self.trace_info(EngineTraceInfoType::Normal, gas, None);
-            self.cmd.clear();
-            if let Some(err) = execution_result {
-                if self.check_capabilities(GlobalCapabilities::CapsTvmBugfixes2022 as u64) {
-                    self.raise_exception_bugfix0(err)?;
-                } else {
-                    self.raise_exception(err)?;
+                Ok(None) => executed += 1,
+                Ok(Some(result)) => break result,
+                Err(err) => {
+                    if tvm_exception_code(&err) == Some(ExceptionCode::OutOfGas) {
+                        self.restore_checkpoint(checkpoint, executed + 1);
+                        self.replay(executed)?;
+                        self.suspended = true;
+                        return Ok(None)
+                    }
+                    self.visited_journal = None;
+                    return Err(err)
                 }
             }
         };
+        self.visited_journal = None;
+        self.finish_execution(result).map(Some)
+    }
+
+    // executes again instructions done after the checkpoint, they finished successfully before
+    fn replay(&mut self, count: usize) -> Status {
+        self.visited_journal = None;
+        self.replaying = true;
+        let mut result = Ok(());
+        for _ in 0..count {
+            if !matches!(self.execute_cmd(), Ok(None)) {
+                result = err!(ExceptionCode::FatalError, "execution diverged on replay after out of gas");
+                break
+            }
+        }
+        self.replaying = false;
+        result
+    }
+
+    /// Returns true if execute_resumable() has stopped because of out of gas
+    pub fn is_suspended(&self) -> bool {
+        self.suspended
+    }
+
+    /// Tops up gas limit of the engine, used to resume suspended execution
+    pub fn add_gas(&mut self, gas: i64) {
+        self.gas.add_gas(gas)
+    }
+
+    fn make_checkpoint(&mut self) -> Checkpoint {
+        self.visited_journal = Some(VisitedJournal::default());
+        self.capture_state()
+    }
+
+    fn capture_state(&self) -> Checkpoint {
+        Checkpoint {
+            cc: self.cc.clone(),
+            ctrls: self.ctrls.clone(),
+            cstate: self.cstate.clone(),
+            commits: self.commit_history.len(),
+            gas: self.gas.clone(),
+            step: self.step,
+            flags: self.flags,
+            checked_signatures_count: self.checked_signatures_count,
+        }
+    }
+
+    // steps is the number of instructions executed after the checkpoint
+    fn restore_checkpoint(&mut self, checkpoint: Checkpoint, steps: usize) {
+        if let Some(journal) = self.visited_journal.take() {
+            journal.undo(self);
+        }
+        // the instructions will be executed again, so they are recorded again
+        for _ in 0..steps {
+            self.forget_last_step();
+        }
+        self.restore_state(checkpoint)
+    }
+
+    fn restore_state(&mut self, checkpoint: Checkpoint) {
+        self.cc = checkpoint.cc;
+        self.ctrls = checkpoint.ctrls;
+        self.cstate = checkpoint.cstate;
+        self.commit_history.truncate(checkpoint.commits);
+        self.gas = checkpoint.gas;
+        self.step = checkpoint.step;
+        self.flags = checkpoint.flags;
+        self.checked_signatures_count = checkpoint.checked_signatures_count;
+        self.cmd.clear();
+        self.debug_buffer.clear();
+    }
+
+    /// Saves stack, registers, committed state, gas and visited cells,
+    /// so execution can be reverted to this point by rollback()
+    pub fn checkpoint(&self) -> EngineCheckpoint {
+        EngineCheckpoint {
+            state: self.capture_state(),
+            visited_cells: self.visited_cells.clone(),
+            visited_exotic_cells: self.visited_exotic_cells.clone(),
+        }
+    }
+
+    /// Reverts engine to the checkpoint, e.g. after speculative execution,
+    /// recorded history of instructions executed after it is dropped
+    pub fn rollback(&mut self, checkpoint: EngineCheckpoint) {
+        self.restore_state(checkpoint.state);
+        self.visited_cells = checkpoint.visited_cells;
+        self.visited_exotic_cells = checkpoint.visited_exotic_cells;
+        self.visited_journal = None;
+        if let Some(history) = self.history.as_mut() {
+            history.steps.clear();
+            history.journal = VisitedJournal::default();
+        }
+        self.error_context = None;
+    }
+
+    /// Keeps state before each of the last window instructions while trace is enabled,
+    /// so execution can be reversed by step_back(). Zero window disables recording
+    pub fn set_history_window(&mut self, window: usize) {
+        self.history = match window {
+            0 => None,
+            window => Some(History { window, steps: VecDeque::new(), journal: VisitedJournal::default() })
+        };
+    }
+
+    /// Number of instructions which can be reversed
+    pub fn history_len(&self) -> usize {
+        self.history.as_ref().map_or(0, |history| history.steps.len())
+    }
+
+    /// Reverts engine to the state before the last executed instruction including
+    /// stack, registers, gas and visited cells. Returns false if there is nothing to revert.
+    /// Execution can be continued with execute_resumable()
+    pub fn step_back(&mut self) -> bool {
+        match self.forget_last_step() {
+            Some(checkpoint) => {
+                self.restore_state(checkpoint);
+                self.suspended = true;
+                true
+            }
+            None => false
+        }
+    }
+
+    fn recording_history(&mut self) -> Option<&mut History> {
+        match self.trace_callback {
+            Some(_) => self.history.as_mut(),
+            None => None
+        }
+    }
+
+    fn record_step(&mut self) {
+        if self.recording_history().is_none() {
+            return
+        }
+        let checkpoint = self.capture_state();
+        if let Some(history) = self.recording_history() {
+            let journal = std::mem::take(&mut history.journal);
+            if let Some(last) = history.steps.back_mut() {
+                last.journal = journal;
+            }
+            history.steps.push_back(HistoryStep { checkpoint, journal: VisitedJournal::default() });
+            if history.steps.len() > history.window {
+                history.steps.pop_front();
+            }
+        }
+    }
+
+    fn forget_last_step(&mut self) -> Option<Checkpoint> {
+        let history = self.history.as_mut()?;
+        let step = history.steps.pop_back()?;
+        let journal = std::mem::replace(
+            &mut history.journal,
+            history.steps.back_mut().map(|last| std::mem::take(&mut last.journal)).unwrap_or_default()
+        );
+        journal.undo(self);
+        Some(step.checkpoint)
+    }
+
+    fn finish_execution(&mut self, result: i32) -> Result<i32> {
         self.trace_info(EngineTraceInfoType::Finish, self.gas_used(), Some("NORMAL TERMINATION".to_string()));
-        self.commit();
+        self.checked_commit()?;
         Ok(result)
     }
 
+    // executes one instruction with all implicit steps before it
+    // return Ok(Some(exit_code)) - if execution is finished
+    fn execute_cmd(&mut self) -> Result<Option<i32>> {
+        self.check_limits()?;
+        self.record_step();
+        #[cfg(feature = "tracing")]
+        let span = tracing::trace_span!(
+            target: targets::EXEC, "instruction", step = self.step, opcode = tracing::field::Empty
+        ).entered();
+        if let Some(result) = self.seek_next_cmd()? {
+            return Ok(Some(result))
+        }
+        let gas = self.gas_used();
+        let step = self.step;
+        self.cmd_code = SliceProto::from(self.cc.code());
+        if self.debug_info.is_some() || cfg!(feature = "coverage") {
+            self.cmd_cell_hash = self.cc.code().cell_opt().map(|cell| cell.repr_hash());
+        }
+        let handler = self.fetch_handler();
+        let execution_result = match handler {
+            Err(err) => {
+                self.basic_use_gas(8);
+                Some(err)
+            }
+            Ok(handler) => {
+                match handler(self) {
+                    Err(e) => {
+                        Some(update_error_description(e, |e|
+                            format!("CMD: {}{} err: {}", self.cmd.proto.name_prefix.unwrap_or_default(), self.cmd.proto.name, e)
+                        ))
+                    }
+                    Ok(_) => self.gas.check_gas_remaining().err()
+                        .or_else(|| self.check_stack_limit().err()),
+                }
+            }
+        };
+        #[cfg(feature = "coverage")]
+        if let Some(hash) = self.cmd_cell_hash.as_ref().filter(|_| !self.replaying) {
+            let opcode = (self.step != step).then_some(self.cmd.proto.name);
+            self.coverage.record(hash, self.cmd_code.pos(), opcode);
+        }
+        #[cfg(feature = "metrics")]
+        if let Some(metrics) = self.metrics.as_mut().filter(|_| !self.replaying) {
+            metrics.record_instruction((self.step != step).then_some(self.cmd.proto.name), execution_result.as_ref());
+        }
+        if let Some(err) = &execution_result {
+            // context is built only for exceptions which can be reported to the caller
+            self.error_context = match self.backtrace_enabled || !self.has_exception_handler() {
+                true => Some(self.make_error_context(err, self.step != step)),
+                false => None
+            };
+        }
+        #[cfg(feature = "tracing")]
+        span.record("opcode", self.cmd.proto.name);
+        self.trace_info_with_error(EngineTraceInfoType::Normal, gas, None, execution_result.as_ref());
+        self.cmd.clear();
+        if let Some(err) = execution_result {
+            if self.check_capabilities(GlobalCapabilities::CapsTvmBugfixes2022 as u64) {
+                self.raise_exception_bugfix0(err)?;
+            } else {
+                self.raise_exception(err)?;
+            }
+            self.record_caught_exception();
+        }
+        Ok(None)
+    }
+
+
     fn step_next_ref(&mut self, reference: Cell) -> Result<Option<i32>> {
         self.step += 1;
         self.log_string = Some("IMPLICIT JMPREF");
-        self.try_use_gas(Gas::implicit_jmp_price())?;
+        self.try_use_gas(self.gas_prices.implicit_jmpref)?;
         let code = self.load_hashed_cell(reference, true)?;
         *self.cc.code_mut() = code;
         Ok(None)
@@ -611,13 +1769,62 @@ impl Engine {
     fn step_ordinary(&mut self) -> Result<Option<i32>> {
         self.step += 1;
         self.log_string = Some("implicit RET");
-        self.try_use_gas(Gas::implicit_ret_price())?;
+        self.try_use_gas(self.gas_prices.implicit_ret)?;
         if self.ctrls.get(0).is_none() {
             return Ok(Some(0))
         }
-        switch_to_c0(self)?;
+        if !self.restart_loop_body()? {
+            switch_to_c0(self)?;
+        }
         Ok(None)
     }
+    // Body of a loop returning to the loop continuation is restarted in place, so the loop
+    // continuation is not moved to cc and created again for the next iteration. The result is
+    // the same as of switching to c0 and executing the loop step, traces keep the general path.
+    // WHILE and UNTIL take this path only if their condition is an integer continuing the loop
+    fn restart_loop_body(&mut self) -> Result<bool> {
+        if self.is_trace_enabled() || self.cc.nargs >= 0 || !self.cc.savelist.is_empty() {
+            return Ok(false)
+        }
+        let condition = match self.cc.stack.depth() {
+            0 => None,
+            _ => self.cc.stack.get(0).as_integer().ok().map(|x| !x.is_zero())
+        };
+        // body, condition of WHILE and whether condition is to be dropped from the stack
+        let (body, cond, checked) = match self.ctrls.get(0).map(StackItem::as_continuation) {
+            Some(Ok(cont)) if cont.nargs < 0 && cont.stack.is_empty() && cont.code().is_empty() => {
+                let saves_c0 = cont.savelist.get(0).is_some() && cont.savelist.iter().all(|(index, _)| index == 0);
+                match &cont.type_of {
+                    ContinuationType::RepeatLoopBody(body, counter) if *counter > 1 && saves_c0 => (body.clone(), None, false),
+                    ContinuationType::AgainLoopBody(body) if cont.savelist.is_empty() => (body.clone(), None, false),
+                    ContinuationType::UntilLoopCondition(body) if condition == Some(false) && saves_c0 => {
+                        (body.clone(), None, true)
+                    }
+                    ContinuationType::WhileLoopCondition(body, cond) if condition == Some(true) && saves_c0 => {
+                        (body.clone(), Some(cond.clone()), true)
+                    }
+                    _ => return Ok(false)
+                }
+            }
+            _ => return Ok(false)
+        };
+        if let ContinuationType::RepeatLoopBody(_, counter) = &mut self.ctrl_mut(0)?.as_continuation_mut()?.type_of {
+            *counter -= 1;
+        }
+        if checked {
+            self.cc.stack.drop(0)?;
+        }
+        if let Some(cond) = cond {
+            // condition runs before the next iteration and returns to the same loop continuation
+            let mut cond = ContinuationData::with_code(cond);
+            if let Some(mut while_) = self.ctrls.remove(0) {
+                cond.savelist.put_opt(0, &mut while_);
+            }
+            self.ctrls.put_opt(0, &mut StackItem::continuation(cond));
+        }
+        *self.cc.code_mut() = body;
+        Ok(true)
+    }
     fn step_pushint(&mut self, code: i32) -> Result<Option<i32>> {
         self.step += 1;
         self.log_string = Some("implicit PUSHINT");
@@ -628,7 +1835,7 @@ impl Engine {
     fn step_try_catch(&mut self) -> Result<Option<i32>> {
         self.step += 1;
         self.log_string = Some("IMPLICIT RET FROM TRY-CATCH");
-        self.try_use_gas(Gas::implicit_ret_price())?;
+        self.try_use_gas(self.gas_prices.implicit_ret)?;
         self.ctrls.remove(2);
         switch(self, ctrl!(0))?;
         Ok(None)
@@ -731,15 +1938,22 @@ impl Engine {
             number == ExceptionCode::AlternativeTermination as usize {
             return Ok(Some(number as i32))
         }
-        let value = match self.cc.stack.drop(0) {
-            Ok(item) => item.as_integer().cloned().unwrap_or_default(),
-            Err(_) => IntegerData::zero()
-        };
+        let argument = self.cc.stack.drop(0).unwrap_or_default();
+        let value = argument.as_integer().cloned().unwrap_or_default();
         let exception = match ExceptionCode::from_usize(number) {
             Some(code) => Exception::from_code_and_value(code, value, file!(), line!()),
             None => Exception::from_number_and_value(number, StackItem::int(value), file!(), line!())
         };
-        Err(error!(TvmError::TvmExceptionFull(exception, String::new())))
+        Err(self.unhandled_exception(exception, &argument))
+    }
+
+    // error of exception not handled by contract, its argument is dumped if it is enabled
+    fn unhandled_exception(&self, exception: Exception, argument: &StackItem) -> Error {
+        let dump = match self.exception_dump_limit {
+            Some(limit) => format!("argument: {}", dump_exception_argument(argument, limit)),
+            None => String::new()
+        };
+        error!(TvmError::TvmExceptionFull(exception, dump))
     }
 
     // return Ok(Some(exit_code)) - if you want to stop execution
@@ -759,13 +1973,20 @@ impl Engine {
                     ContinuationType::RepeatLoopBody(code, _counter) => self.step_repeat_loop(code),
                     ContinuationType::UntilLoopCondition(body) => self.step_until_loop(body),
                     ContinuationType::AgainLoopBody(slice) => self.step_again_loop(slice),
-                    ContinuationType::ExcQuit => Ok(self.make_external_error()?),
+                    ContinuationType::ExcQuit => {
+                        let result = self.make_external_error();
+                        if let (true, Err(err)) = (self.is_trace_enabled(), &result) {
+                            let log_string = Some(format!("UNHANDLED EXCEPTION: {}", err));
+                            self.trace_info_with_error(EngineTraceInfoType::Exception, gas, log_string, Some(err));
+                        }
+                        Ok(result?)
+                    }
                     ContinuationType::CatchRevert(depth) => self.step_catch_revert(depth),
                 }
             };
             if self.is_trace_enabled() {
                 if let Some(log_string) = self.log_string {
-                    self.trace_info(EngineTraceInfoType::Implicit, gas, Some(log_string.to_string()));
+                    self.trace_info_with_error(EngineTraceInfoType::Implicit, gas, Some(log_string.to_string()), result.as_ref().err());
                 }
             }
             match self.gas.check_gas_remaining().and(result) {
@@ -801,38 +2022,119 @@ impl Engine {
                 return Ok(lib);
             }
         }
+        if let Some(provider) = self.library_provider.clone() {
+            self.try_use_gas(self.gas_prices.library_lookup)?;
+            let hash = hash.clone().get_next_hash()?;
+            if let Some(lib) = provider.get_library(&hash)? {
+                if lib.repr_hash() != hash {
+                    return err!(ExceptionCode::DictionaryError, "Library provider returned cell with wrong hash {:x}", hash)
+                }
+                return Ok(lib);
+            }
+        }
         err!(ExceptionCode::CellUnderflow, "Libraries do not contain code with hash {:x}", hash)
     }
 
+    // pruned branch keeps hash of the original cell
+    fn load_external_cell(&self, pruned: &Cell) -> Result<Cell> {
+        let hash = pruned.hash(0);
+        let loader = self.cell_loader.as_ref().ok_or_else(|| error!("no cell loader"))?;
+        match loader.load_cell(&hash)? {
+            Some(cell) if cell.repr_hash() == hash => Ok(cell),
+            Some(_) => err!(ExceptionCode::CellUnderflow, "Cell loader returned cell with wrong hash {:x}", hash),
+            None => err!(ExceptionCode::CellUnderflow, "Cell loader has no cell with hash {:x}", hash)
+        }
+    }
+
+    // children of cell of tree pruned by cell loader belong to the tree too,
+    // pruned branches among them are hinted to the loader when they are reached first time
+    fn track_external_cell(&mut self, cell: &Cell) -> Status {
+        let loader = match &self.cell_loader {
+            Some(loader) if self.has_vm_capability(VmCapabilities::CapCellLoader) => loader.clone(),
+            _ => return Ok(())
+        };
+        if !self.external_cells.contains(&cell.repr_hash()) {
+            return Ok(())
+        }
+        let mut hashes = Vec::new();
+        for i in 0..cell.references_count() {
+            let child = cell.reference(i)?;
+            if self.external_cells.insert(child.repr_hash()) && child.cell_type() == CellType::PrunedBranch {
+                hashes.push(child.hash(0));
+            }
+        }
+        if !hashes.is_empty() {
+            loader.prefetch(&hashes);
+        }
+        Ok(())
+    }
+
     /// Loads cell to slice checking in precashed map
-    pub fn load_hashed_cell(&mut self, mut cell: Cell, resolve_special: bool) -> Result<SliceData> {
+    pub fn load_hashed_cell(&mut self, cell: Cell, resolve_special: bool) -> Result<SliceData> {
+        let mode = if resolve_special { CellLoadMode::Resolve } else { CellLoadMode::Raw };
+        self.load_cell_with_mode(cell, mode)
+    }
+
+    /// Loads cell to slice treating exotic cells according to mode
+    pub fn load_cell_with_mode(&mut self, mut cell: Cell, mode: CellLoadMode) -> Result<SliceData> {
+        #[cfg(feature = "metrics")]
+        if let Some(metrics) = self.metrics.as_mut() {
+            metrics.cells_loaded += 1;
+        }
+        let exotic_prices = self.has_vm_capability(VmCapabilities::CapExoticLoadGas)
+            && matches!(mode, CellLoadMode::Explicit | CellLoadMode::Exotic);
         let mut previous_hashes = Vec::new();
+        let mut through_merkle = false;
         let slice = loop {
             let hash = cell.repr_hash();
-            if !resolve_special || cell.cell_type() == CellType::Ordinary {
+            // big cells hold plain data, they are loaded as ordinary ones
+            let big = cell.cell_type() == CellType::Big
+                && self.check_capabilities(GlobalCapabilities::CapBigCells as u64)
+                && self.has_vm_capability(VmCapabilities::CapBigCellLimits);
+            let exotic = cell.cell_type() != CellType::Ordinary && !big;
+            let load_price = |prices: &GasPrices, first: bool| match exotic && exotic_prices {
+                true => prices.load_exotic_cell(first),
+                false => prices.load_cell(first)
+            };
+            if matches!(mode, CellLoadMode::Raw | CellLoadMode::Explicit) || !exotic {
+                if !exotic && !through_merkle {
+                    self.track_external_cell(&cell)?;
+                }
                 if self.visited_cells.contains(&hash) {
-                    self.try_use_gas(Gas::load_cell_price(false))?;
+                    self.try_use_gas(load_price(&self.gas_prices, false))?;
                     break SliceData::load_cell(cell)?;
                 } else {
-                    self.try_use_gas(Gas::load_cell_price(true))?;
+                    self.try_use_gas(load_price(&self.gas_prices, true))?;
+                    if big {
+                        self.try_use_gas(self.gas_prices.big_cell_data(cell.bit_length()))?;
+                    }
+                    if let Some(journal) = self.visited_journal.as_mut() {
+                        journal.cells.push(hash.clone());
+                    }
+                    if let Some(history) = self.recording_history() {
+                        history.journal.cells.push(hash.clone());
+                    }
                     self.visited_cells.insert(hash);
                     break SliceData::load_cell(cell)?;
                 }
             }
             if let Some(slice) = self.visited_exotic_cells.get(&hash).cloned() {
-                self.try_use_gas(Gas::load_cell_price(false))?;
+                self.try_use_gas(self.gas_prices.load_cell(false))?;
                 break slice;
             }
             previous_hashes.push(hash);
+            // explicit loading opens Merkle cells regardless of capability
+            let resolve_merkle = mode == CellLoadMode::Exotic
+                || self.check_capabilities(GlobalCapabilities::CapResolveMerkleCell as u64);
             match cell.cell_type() {
                 CellType::LibraryReference => {
-                    self.try_use_gas(Gas::load_cell_price(true))?;
+                    self.try_use_gas(load_price(&self.gas_prices, true))?;
                     cell = self.load_library_cell(cell)?;
                     continue;
                 }
                 CellType::MerkleProof => {
-                    if self.check_capabilities(GlobalCapabilities::CapResolveMerkleCell as u64) {
-                        self.try_use_gas(Gas::load_cell_price(true))?;
+                    if resolve_merkle {
+                        self.try_use_gas(load_price(&self.gas_prices, true))?;
                         let mut slice = SliceData::load_cell(cell.clone())?;
                         slice.move_by(8)?;
                         let hash = slice.get_next_hash()?;
@@ -843,12 +2145,13 @@ impl Engine {
                                 "hash of merkle proof cell is not corresponded to child cell"
                             )
                         }
+                        through_merkle = true;
                         continue
                     }
                 }
                 CellType::MerkleUpdate => {
-                    if self.check_capabilities(GlobalCapabilities::CapResolveMerkleCell as u64) {
-                        self.try_use_gas(Gas::load_cell_price(true))?;
+                    if resolve_merkle {
+                        self.try_use_gas(load_price(&self.gas_prices, true))?;
                         let mut slice = SliceData::load_cell(cell.clone())?;
                         slice.move_by(8)?;
                         let hash = slice.get_next_hash()?;
@@ -867,14 +2170,30 @@ impl Engine {
                                 "hash of merkle update cell is not corresponded to child cell"
                             )
                         }
+                        through_merkle = true;
                         continue
                     }
                 }
+                // cells of Merkle proofs and updates are never fetched, they are not part of own trees
+                CellType::PrunedBranch if !through_merkle
+                    && self.has_vm_capability(VmCapabilities::CapCellLoader)
+                    && self.external_cells.contains(&cell.repr_hash()) => {
+                    // only the fetched cell is charged, it is loaded as ordinary one
+                    cell = self.load_external_cell(&cell)?;
+                    self.external_cells.insert(cell.repr_hash());
+                    continue
+                }
                 _ => ()
             }
             return err!(ExceptionCode::CellUnderflow, "Wrong resolving cell type {}", cell.cell_type())
         };
         for hash in previous_hashes {
+            if let Some(journal) = self.visited_journal.as_mut() {
+                journal.exotic_cells.push(hash.clone());
+            }
+            if let Some(history) = self.recording_history() {
+                history.journal.exotic_cells.push(hash.clone());
+            }
             self.visited_exotic_cells.insert(hash, slice.clone());
         }
         Ok(slice)
@@ -908,6 +2227,11 @@ impl Engine {
             .ok_or_else(|| exception!(ExceptionCode::RangeCheckError, "get ctrl {} failed", index))
     }
 
+    /// Sets control register checking the type of item allowed for it, returns previous value
+    pub fn set_ctrl(&mut self, index: usize, mut item: StackItem) -> ResultOpt<StackItem> {
+        self.ctrls.put(index, &mut item)
+    }
+
     pub fn ctrls(&self) -> &SaveList {
         &self.ctrls
     }
@@ -964,6 +2288,20 @@ impl Engine {
         self.trace_callback = Some(callback);
     }
 
+    /// Plugs external entropy source: random numbers become unpredictable, so it must not be
+    /// used where result of execution is to be reproduced by validators
+    pub fn set_entropy_source(&mut self, source: impl Fn() -> [u8; 32] + Send + Sync + 'static) {
+        self.entropy_source = Some(Arc::new(source));
+    }
+
+    pub(in crate::executor) fn external_entropy(&self) -> Option<[u8; 32]> {
+        self.entropy_source.as_ref().map(|source| source())
+    }
+
+    pub fn set_commit_callback(&mut self, callback: impl Fn(&UInt256, &UInt256) + Send + Sync + 'static) {
+        self.commit_callback = Some(Arc::new(callback));
+    }
+
     pub fn trace_bit(&self, trace_mask: u8) -> bool {
         (self.trace & trace_mask) == trace_mask
     }
@@ -972,6 +2310,33 @@ impl Engine {
         self.index_provider = Some(index_provider)
     }
 
+    pub fn set_library_provider(&mut self, library_provider: Arc<dyn LibraryProvider>) {
+        self.library_provider = Some(library_provider)
+    }
+
+    /// Pruned branches of trees with given root hashes, e.g. of c4, are fetched from the loader
+    pub fn set_cell_loader(&mut self, loader: Arc<dyn CellLoader>, roots: impl IntoIterator<Item = UInt256>) {
+        self.cell_loader = Some(loader);
+        self.external_cells.extend(roots);
+    }
+
+    /// Enables HOSTCALL instructions, they are unknown opcodes without host interface.
+    /// Fails if HOSTCALL opcode is taken by custom handler
+    pub fn set_host_interface(&mut self, host_interface: Box<dyn HostInterface>) -> Status {
+        if self.host_interface.is_none() {
+            self.register_handler(&[0xFE, 0xD0], execute_hostcall)?;
+        }
+        self.host_interface = Some(Arc::from(host_interface));
+        Ok(())
+    }
+
+    pub(in crate::executor) fn call_host(&mut self, id: u8) -> Result<i64> {
+        match self.host_interface.as_ref() {
+            Some(host) => host.call(id, &mut self.cc.stack, self.gas.get_gas_available()),
+            None => err!(ExceptionCode::InvalidOpcode, "host interface is not set")
+        }
+    }
+
     pub fn behavior_modifiers(&self) -> &BehaviorModifiers {
         &self.modifiers
     }
@@ -997,7 +2362,9 @@ impl Engine {
         if let Some(stack) = stack {
             self.cc.stack = stack;
         }
+        let unlimited = self.gas.is_unlimited();
         self.gas = gas.unwrap_or_else(Gas::test);
+        self.gas.set_unlimited(unlimited);
         let cont = ContinuationType::Quit(ExceptionCode::NormalTermination as i32);
         self.ctrls.put(0, &mut StackItem::continuation(ContinuationData::with_type(cont))).unwrap();
         let cont = ContinuationType::Quit(ExceptionCode::AlternativeTermination as i32);
@@ -1016,6 +2383,54 @@ impl Engine {
         self
     }
 
+    /// Sets up engine to run contract from serialized StateInit: code is put to c3 and c7,
+    /// data to c4 (empty cell if it is absent) and libraries of StateInit are available to code.
+    /// Tick-tock transactions get stack (balance address is_tock -2) built from smc_info
+    pub fn from_state_init(
+        capabilities: u64,
+        state_init: Cell,
+        mut smc_info: SmartContractInfo,
+        call: StateInitCall,
+        gas: Option<Gas>
+    ) -> Result<Engine> {
+        let state_init = StateInit::construct_from_cell(state_init)?;
+        let code = match state_init.code.clone() {
+            Some(code) => code,
+            None => return err!(ExceptionCode::TypeCheckError, "state init has no code")
+        };
+        let data = state_init.data.clone().unwrap_or_default();
+        let stack = match call {
+            StateInitCall::Stack(stack) => stack,
+            StateInitCall::TickTock { tock } => {
+                let enabled = match &state_init.special {
+                    Some(special) => if tock { special.tock } else { special.tick },
+                    None => false
+                };
+                if !enabled {
+                    return err!(ExceptionCode::TypeCheckError, "{} is not enabled for contract", if tock { "tock" } else { "tick" })
+                }
+                let (_, address) = crate::addr::parse_std_addr(&smc_info.myself)?;
+                let mut stack = Stack::new();
+                stack
+                    .push(int!(smc_info.balance.grams.as_u128()))
+                    .push(StackItem::integer(IntegerData::from_unsigned_bytes_be(address.as_slice())))
+                    .push(boolean!(tock))
+                    .push(int!(-2));
+                stack
+            }
+        };
+        let mut libraries = HashmapE::with_bit_len(256);
+        libraries.read_from_cell(state_init.library.serialize()?)?;
+        smc_info.capabilities = capabilities;
+        smc_info.set_mycode(code.clone());
+        let mut ctrls = SaveList::new();
+        ctrls.put(4, &mut StackItem::cell(data))?;
+        ctrls.put(7, &mut smc_info.into_temp_data_item())?;
+        Ok(Engine::with_capabilities(capabilities).setup_with_libraries(
+            SliceData::load_cell(code)?, Some(ctrls), Some(stack), gas, vec![libraries]
+        ))
+    }
+
     // Internal API ***********************************************************
 
     #[allow(dead_code)]
@@ -1031,7 +2446,12 @@ impl Engine {
         self.cmd.params.clear();
         self.cmd.vars.clear();
         self.step += 1;
-        self.extract_instruction()
+        self.extract_instruction()?;
+        if let Some(decoded) = self.decode_only.as_mut() {
+            *decoded = true;
+            return err!(ExceptionCode::FatalError, "instruction is only decoded")
+        }
+        Ok(())
     }
 
     pub(in crate::executor) fn switch_debug(&mut self, on_off: bool) {
@@ -1047,10 +2467,10 @@ impl Engine {
     }
 
     pub(in crate::executor) fn flush(&mut self) {
-        if self.debug_on > 0 {
+        if self.debug_on > 0 && !self.replaying {
             let buffer = std::mem::take(&mut self.debug_buffer);
             if self.trace_callback.is_none() {
-                log::info!(target: "tvm", "{}", buffer);
+                tvm_log!(info, targets::DEBUG, "{}", buffer);
             } else {
                 self.trace_info(EngineTraceInfoType::Dump, 0, Some(buffer));
             }
@@ -1113,7 +2533,7 @@ impl Engine {
 
     fn basic_use_gas(&mut self, mut bits: usize) -> i64 {
         bits += self.cc.code().pos().saturating_sub(self.cmd_code.pos());
-        self.use_gas(Gas::basic_gas_price(bits, 0))
+        self.use_gas(self.gas_prices.basic(bits))
     }
 
     fn extract_instruction(&mut self) -> Status {
@@ -1194,6 +2614,11 @@ impl Engine {
                     let hi = (self.last_cmd() as i16) & 0x07;
                     let lo = self.next_cmd()? as i16;
                     (hi * 256 + lo) as isize
+                } else if *range == (0..4096) {
+                    self.basic_use_gas(8);
+                    let hi = (self.last_cmd() as i16) & 0x0F;
+                    let lo = self.next_cmd()? as i16;
+                    (hi * 256 + lo) as isize
                 } else if *range == (0..16384) {
                     self.basic_use_gas(8);
                     let hi = (self.last_cmd() as i16) & 0x3F;
@@ -1389,7 +2814,7 @@ impl Engine {
                 )
             }
             Some(InstructionOptions::Dictionary(offset, bits)) => {
-                self.use_gas(Gas::basic_gas_price(offset + 1 + bits, 0));
+                self.use_gas(self.gas_prices.basic(offset + 1 + bits));
                 let mut code = self.cmd_code()?;
                 code.shrink_data(offset..);
                 // TODO: need to check this failure case
@@ -1400,7 +2825,7 @@ impl Engine {
                 self.cmd.params.push(InstructionParameter::Length(length))
             }
             Some(InstructionOptions::Bytestring(offset, r, x, bytes)) => {
-                self.use_gas(Gas::basic_gas_price(offset + r + x, 0));
+                self.use_gas(self.gas_prices.basic(offset + r + x));
                 let slice = self.extract_slice(offset, r, x, 0, bytes)?;
                 if slice.remaining_bits() % 8 != 0 {
                     return err!(ExceptionCode::InvalidOpcode)
@@ -1408,7 +2833,7 @@ impl Engine {
                 self.cmd.params.push(InstructionParameter::Slice(slice))
             }
             Some(InstructionOptions::Bitstring(offset, r, x, refs)) => {
-                self.use_gas(Gas::basic_gas_price(offset + r + x, 0));
+                self.use_gas(self.gas_prices.basic(offset + r + x));
                 let mut slice = self.extract_slice(offset, r, x, refs, 0)?;
                 slice.trim_right();
                 self.cmd.params.push(InstructionParameter::Slice(slice));
@@ -1424,7 +2849,7 @@ impl Engine {
         let exception = match tvm_exception_full(&err) {
             Some(exception) => exception,
             None => {
-                log::trace!(target: "tvm", "BAD CODE: {}\n", self.cmd_code_string());
+                tvm_log!(trace, targets::EXCEPTION, "BAD CODE: {}\n", self.cmd_code_string());
                 return Err(err)
             }
         };
@@ -1432,10 +2857,10 @@ impl Engine {
             self.step += 1;
         }
         if exception.exception_code() == Some(ExceptionCode::OutOfGas) {
-            log::trace!(target: "tvm", "OUT OF GAS CODE: {}\n", self.cmd_code_string());
+            tvm_log!(trace, targets::EXCEPTION, "OUT OF GAS CODE: {}\n", self.cmd_code_string());
             return Err(err)
         }
-        if let Err(err) = self.gas.try_use_gas(Gas::exception_price()) {
+        if let Err(err) = self.gas.try_use_gas(self.gas_prices.exception) {
             self.step += 1;
             return Err(err);
         }
@@ -1454,7 +2879,7 @@ impl Engine {
             switch(self, var!(n))?;
         } else {
             let log_string = Some(format!("UNHANDLED EXCEPTION: {}", err));
-            self.trace_info(EngineTraceInfoType::Exception, self.gas_used(), log_string);
+            self.trace_info_with_error(EngineTraceInfoType::Exception, self.gas_used(), log_string, Some(&err));
             return Err(err)
         }
         Ok(())
@@ -1466,7 +2891,7 @@ impl Engine {
         let exception = match tvm_exception_full(&err) {
             Some(exception) => exception,
             None => {
-                log::trace!(target: "tvm", "BAD CODE: {}\n", self.cmd_code_string());
+                tvm_log!(trace, targets::EXCEPTION, "BAD CODE: {}\n", self.cmd_code_string());
                 return Err(err)
             }
         };
@@ -1474,10 +2899,10 @@ impl Engine {
             self.step += 1;
         }
         if exception.exception_code() == Some(ExceptionCode::OutOfGas) {
-            log::trace!(target: "tvm", "OUT OF GAS CODE: {}\n", self.cmd_code_string());
+            tvm_log!(trace, targets::EXCEPTION, "OUT OF GAS CODE: {}\n", self.cmd_code_string());
             return Err(err)
         }
-        if let Err(err) = self.gas.try_use_gas(Gas::exception_price()) {
+        if let Err(err) = self.gas.try_use_gas(self.gas_prices.exception) {
             self.step += 1;
             return Err(err);
         }
@@ -1497,7 +2922,8 @@ impl Engine {
                     self.cc.stack = Stack::new();
                     self.cc.stack.push(exception.value.clone());
                     self.cc.stack.push(int!(exception.exception_or_custom_code()));
-                    return Err(error!(TvmError::TvmExceptionFull(exception, String::new())))
+                    let argument = exception.value.clone();
+                    return Err(self.unhandled_exception(exception, &argument))
                 }
             }
         }
@@ -1520,6 +2946,16 @@ impl Engine {
         self.last_cmd
     }
 
+    // looks up handler of the next instruction in the dispatch table,
+    // the table is resolved once capabilities and custom handlers are set
+    fn fetch_handler(&mut self) -> Result<ExecuteHandler> {
+        let dispatch = self.dispatch.get_or_insert_with(|| match &self.handlers {
+            Some(handlers) => Arc::new(handlers.resolve(self.capabilities, self.vm_capabilities)),
+            None => dispatch_table(self.capabilities, self.vm_capabilities)
+        });
+        dispatch.get_handler(self.cc.code_mut(), &mut self.last_cmd)
+    }
+
     pub(in crate::executor) fn next_cmd(&mut self) -> Result<u8> {
         match self.cc.code_mut().get_next_byte() {
             Ok(cmd) => {
@@ -1572,6 +3008,20 @@ impl Engine {
     }
 
     pub(in crate::executor) fn set_rand(&mut self, rand: IntegerData) -> Status {
+        let (t1_len, len) = self.update_rand(rand)?;
+        self.use_gas(self.gas_prices.tuple(t1_len));
+        self.use_gas(self.gas_prices.tuple(len));
+        Ok(())
+    }
+
+    /// Sets random seed in c7 without charging gas, engine must be set up
+    pub fn set_rand_seed(&mut self, seed: [u8; 32]) -> Status {
+        self.update_rand(IntegerData::from_unsigned_bytes_be(seed))?;
+        Ok(())
+    }
+
+    // returns lengths of updated tuples
+    fn update_rand(&mut self, rand: IntegerData) -> Result<(usize, usize)> {
         let mut tuple = self.ctrl_mut(7)?.as_tuple_mut()?;
         let t1 = match tuple.first_mut() {
             Some(t1) => t1,
@@ -1582,11 +3032,11 @@ impl Engine {
             Some(v) => *v = StackItem::int(rand),
             None => return err!(ExceptionCode::RangeCheckError, "set tuple index is {} but length is {}", 6, t1_items.len())
         }
-        self.use_gas(Gas::tuple_gas_price(t1_items.len()));
+        let t1_len = t1_items.len();
         *t1 = StackItem::tuple(t1_items);
-        self.use_gas(Gas::tuple_gas_price(tuple.len()));
+        let len = tuple.len();
         *self.ctrl_mut(7)? = StackItem::tuple(tuple);
-        Ok(())
+        Ok((t1_len, len))
     }
 
     pub(crate) fn get_config_param(&mut self, index: i32) -> ResultOpt<Cell> {
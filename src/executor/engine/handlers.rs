@@ -15,18 +15,18 @@ use crate::{
     executor::{
         accounts::*, blockchain::*, bls::*, config::*, continuation::*, crypto::*, currency::*, 
         deserialization::*, dictionary::*, dump::*, 
-        engine::{core::ExecuteHandler, storage::fetch_stack, Engine}, 
-        exceptions::*, gas::*, globals::*, math::*, null::*, rand::*, serialization::*,
+        engine::{core::ExecuteHandler, storage::fetch_stack, Engine, VmCapabilities}, 
+        exceptions::*, gas::*, globals::*, hash::*, math::*, null::*, proof::*, rand::*, runvm::*, serialization::*,
         slice_comparison::*, stack::*, tuple::*,
         types::{Instruction, InstructionOptions}
     },
-    stack::integer::behavior::{Quiet, Signaling},
+    stack::integer::behavior::{OperationBehavior, Quiet, Signaling},
     types::Status
 };
 #[cfg(feature = "gosh")]
 use crate::executor::diff::*;
 use std::{fmt, ops::Range};
-use ever_block::{Result, ExceptionCode};
+use ever_block::{fail, ExceptionCode, GlobalCapabilities, Result, SliceData};
 
 // ( - )
 fn execute_nop(engine: &mut Engine) -> Status {
@@ -54,7 +54,7 @@ fn execute_setcpx(engine: &mut Engine) -> Status {
 
 fn execute_unknown(engine: &mut Engine) -> Status {
     let code = engine.last_cmd();
-    log::trace!(target: "tvm", "Invalid code: {} ({:#X})\n", code, code);
+    tvm_log!(trace, crate::logging::targets::DECODE, "Invalid code: {} ({:#X})\n", code, code);
     err!(ExceptionCode::InvalidOpcode)
 }
 
@@ -62,23 +62,86 @@ fn execute_unknown(engine: &mut Engine) -> Status {
 enum Handler {
     Direct(ExecuteHandler),
     Subset(usize),
+    // handler is available only with the capability, it is resolved when dispatch table is built
+    Gated(ExecuteHandler, u64),
+    // the same for capability of VmCapabilities
+    VmGated(ExecuteHandler, u64),
+}
+
+impl Handler {
+    fn is_vacant(&self) -> bool {
+        match self {
+            Handler::Direct(x) => *x as usize == execute_unknown as usize,
+            _ => false
+        }
+    }
 }
 
 #[derive(Clone)]
 pub struct Handlers {
-    directs: [Option<Handler>; 256],
+    directs: [Handler; 256],
     subsets: Vec<Handlers>,
 }
 
 
 impl Handlers {
-    const fn new() -> Handlers {
+    fn new() -> Handlers {
         Handlers {
-            directs: [None; 256],
+            directs: [Handler::Direct(execute_unknown); 256],
             subsets: Vec::new(),
         }
     }
 
+    /// Builds dispatch table for given capabilities: handlers of absent capabilities
+    /// are replaced with unknown opcode, so no capability checks remain in the table
+    pub(super) fn resolve(&self, capabilities: u64, vm_capabilities: u64) -> Handlers {
+        let mut resolved = self.clone();
+        for handler in resolved.directs.iter_mut() {
+            let (x, enabled) = match *handler {
+                Handler::Gated(x, capability) => (x, capabilities & capability != 0),
+                Handler::VmGated(x, capability) => (x, vm_capabilities & capability != 0),
+                _ => continue
+            };
+            *handler = match enabled {
+                true => Handler::Direct(x),
+                false => Handler::Direct(execute_unknown)
+            }
+        }
+        for subset in resolved.subsets.iter_mut() {
+            *subset = subset.resolve(capabilities, vm_capabilities);
+        }
+        resolved
+    }
+
+    /// Masks of global and VM capabilities gating handlers of the table
+    pub(super) fn gating_capabilities(&self) -> (u64, u64) {
+        let mut masks = (0, 0);
+        for handler in self.directs.iter() {
+            match handler {
+                Handler::Gated(_, capability) => masks.0 |= capability,
+                Handler::VmGated(_, capability) => masks.1 |= capability,
+                Handler::Subset(i) => {
+                    let subset = self.subsets[*i].gating_capabilities();
+                    masks = (masks.0 | subset.0, masks.1 | subset.1);
+                }
+                Handler::Direct(_) => ()
+            }
+        }
+        masks
+    }
+
+    /// Masks of global and VM capabilities gating the handler of the opcode,
+    /// None if the opcode is unknown
+    pub(super) fn opcode_capabilities(&self, opcode: &[u8]) -> Option<(u64, u64)> {
+        let (code, tail) = opcode.split_first()?;
+        match self.directs[*code as usize] {
+            Handler::Direct(handler) => (handler as usize != execute_unknown as usize).then_some((0, 0)),
+            Handler::Gated(_, capability) => Some((capability, 0)),
+            Handler::VmGated(_, capability) => Some((0, capability)),
+            Handler::Subset(i) => self.subsets[i].opcode_capabilities(tail),
+        }
+    }
+
     pub(super) fn new_code_page_0() -> Handlers {
         let mut handlers = Handlers::new();
         handlers
@@ -189,6 +252,8 @@ impl Handlers {
                 .set(0x8B, execute_tuple_last)
                 .set(0x8C, execute_tuple_push)
                 .set(0x8D, execute_tuple_pop)
+                .set_vm_gated(0x8E, execute_long_tuple_indexvar, VmCapabilities::CapLongTuples)
+                .set_vm_gated(0x8F, execute_long_tuple_len, VmCapabilities::CapLongTuples)
                 .set(0x90, execute_zeroswapif)
                 .set(0x91, execute_zeroswapifnot)
                 .set(0x92, execute_zerorotrif)
@@ -233,101 +298,90 @@ impl Handlers {
 
     fn add_code_page_0_arithmetic(&mut self) -> &mut Handlers {
         self
-            .set(0xA0, execute_add::<Signaling>)
-            .set(0xA1, execute_sub::<Signaling>)
-            .set(0xA2, execute_subr::<Signaling>)
-            .set(0xA3, execute_negate::<Signaling>)
-            .set(0xA4, execute_inc::<Signaling>)
-            .set(0xA5, execute_dec::<Signaling>)
-            .set(0xA6, execute_addconst::<Signaling>)
-            .set(0xA7, execute_mulconst::<Signaling>)
-            .set(0xA8, execute_mul::<Signaling>)
-            .set(0xA9, execute_divmod::<Signaling>)
-            .set(0xAA, execute_lshift::<Signaling>)
-            .set(0xAB, execute_rshift::<Signaling>)
-            .set(0xAC, execute_lshift::<Signaling>)
-            .set(0xAD, execute_rshift::<Signaling>)
-            .set(0xAE, execute_pow2::<Signaling>)
-            //0xB0
-            .set(0xB0, execute_and::<Signaling>)
-            .set(0xB1, execute_or::<Signaling>)
-            .set(0xB2, execute_xor::<Signaling>)
-            .set(0xB3, execute_not::<Signaling>)
-            .set(0xB4, execute_fits::<Signaling>)
-            .set(0xB5, execute_ufits::<Signaling>)
-            .add_subset(0xB6, Handlers::new()
-                .set(0x00, execute_fitsx::<Signaling>)
-                .set(0x01, execute_ufitsx::<Signaling>)
-                .set(0x02, execute_bitsize::<Signaling>)
-                .set(0x03, execute_ubitsize::<Signaling>)
-                .set(0x08, execute_min::<Signaling>)
-                .set(0x09, execute_max::<Signaling>)
-                .set(0x0A, execute_minmax::<Signaling>)
-                .set(0x0B, execute_abs::<Signaling>)
-            )
+            .add_integer_arithmetic::<Signaling>()
             .add_subset(0xB7, Handlers::new()
-                .set(0xA0, execute_add::<Quiet>)
-                .set(0xA1, execute_sub::<Quiet>)
-                .set(0xA2, execute_subr::<Quiet>)
-                .set(0xA3, execute_negate::<Quiet>)
-                .set(0xA4, execute_inc::<Quiet>)
-                .set(0xA5, execute_dec::<Quiet>)
-                .set(0xA6, execute_addconst::<Quiet>)
-                .set(0xA7, execute_mulconst::<Quiet>)
-                .set(0xA8, execute_mul::<Quiet>)
-                .set(0xA9, execute_divmod::<Quiet>)
-                .set(0xAA, execute_lshift::<Quiet>)
-                .set(0xAB, execute_rshift::<Quiet>)
-                .set(0xAC, execute_lshift::<Quiet>)
-                .set(0xAD, execute_rshift::<Quiet>)
-                .set(0xAE, execute_pow2::<Quiet>)
-                .set(0xB0, execute_and::<Quiet>)
-                .set(0xB1, execute_or::<Quiet>)
-                .set(0xB2, execute_xor::<Quiet>)
-                .set(0xB3, execute_not::<Quiet>)
-                .set(0xB4, execute_fits::<Quiet>)
-                .set(0xB5, execute_ufits::<Quiet>)
-                .add_subset(0xB6, Handlers::new()
-                    .set(0x00, execute_fitsx::<Quiet>)
-                    .set(0x01, execute_ufitsx::<Quiet>)
-                    .set(0x02, execute_bitsize::<Quiet>)
-                    .set(0x03, execute_ubitsize::<Quiet>)
-                    .set(0x08, execute_min::<Quiet>)
-                    .set(0x09, execute_max::<Quiet>)
-                    .set(0x0A, execute_minmax::<Quiet>)
-                    .set(0x0B, execute_abs::<Quiet>)
+                .add_integer_arithmetic::<Quiet>()
+                .add_integer_comparison::<Quiet>()
+                .add_subset(0xF9, Handlers::new()
+                    .add_rist255::<Quiet>()
                 )
-                .set(0xB8, execute_sgn::<Quiet>)
-                .set(0xB9, execute_less::<Quiet>)
-                .set(0xBA, execute_equal::<Quiet>)
-                .set(0xBB, execute_leq::<Quiet>)
-                .set(0xBC, execute_greater::<Quiet>)
-                .set(0xBD, execute_neq::<Quiet>)
-                .set(0xBE, execute_geq::<Quiet>)
-                .set(0xBF, execute_cmp::<Quiet>)
-                //0xC0
-                .set(0xC0, execute_eqint::<Quiet>)
-                .set(0xC1, execute_lessint::<Quiet>)
-                .set(0xC2, execute_gtint::<Quiet>)
-                .set(0xC3, execute_neqint::<Quiet>)
             )
     }
 
-    fn add_code_page_0_comparsion(&mut self) -> &mut Handlers {
+    // quiet variants of ristretto arithmetic are prefixed by B7
+    fn add_rist255<T: OperationBehavior>(&mut self) -> &mut Handlers {
+        self
+            .set_vm_gated(0x21, execute_rist255_validate::<T>, VmCapabilities::CapCrypto)
+            .set_vm_gated(0x22, execute_rist255_add::<T>, VmCapabilities::CapCrypto)
+            .set_vm_gated(0x23, execute_rist255_sub::<T>, VmCapabilities::CapCrypto)
+            .set_vm_gated(0x24, execute_rist255_mul::<T>, VmCapabilities::CapCrypto)
+            .set_vm_gated(0x25, execute_rist255_mulbase::<T>, VmCapabilities::CapCrypto)
+    }
+
+    // the same opcodes are used for quiet variants after prefix B7,
+    // so every signaling instruction gets its quiet counterpart
+    fn add_integer_arithmetic<T: OperationBehavior>(&mut self) -> &mut Handlers {
+        self
+            .set(0xA0, execute_add::<T>)
+            .set(0xA1, execute_sub::<T>)
+            .set(0xA2, execute_subr::<T>)
+            .set(0xA3, execute_negate::<T>)
+            .set(0xA4, execute_inc::<T>)
+            .set(0xA5, execute_dec::<T>)
+            .set(0xA6, execute_addconst::<T>)
+            .set(0xA7, execute_mulconst::<T>)
+            .set(0xA8, execute_mul::<T>)
+            .set(0xA9, execute_divmod::<T>)
+            .set(0xAA, execute_lshift::<T>)
+            .set(0xAB, execute_rshift::<T>)
+            .set(0xAC, execute_lshift::<T>)
+            .set(0xAD, execute_rshift::<T>)
+            .set(0xAE, execute_pow2::<T>)
+            //0xB0
+            .set(0xB0, execute_and::<T>)
+            .set(0xB1, execute_or::<T>)
+            .set(0xB2, execute_xor::<T>)
+            .set(0xB3, execute_not::<T>)
+            .set(0xB4, execute_fits::<T>)
+            .set(0xB5, execute_ufits::<T>)
+            .add_subset(0xB6, Handlers::new()
+                .set(0x00, execute_fitsx::<T>)
+                .set(0x01, execute_ufitsx::<T>)
+                .set(0x02, execute_bitsize::<T>)
+                .set(0x03, execute_ubitsize::<T>)
+                .set(0x08, execute_min::<T>)
+                .set(0x09, execute_max::<T>)
+                .set(0x0A, execute_minmax::<T>)
+                .set(0x0B, execute_abs::<T>)
+                .set_vm_gated(0x10, execute_fixmul96::<T>, VmCapabilities::CapFixedPoint)
+                .set_vm_gated(0x11, execute_fixdiv96::<T>, VmCapabilities::CapFixedPoint)
+                .set_vm_gated(0x12, execute_fixmul128::<T>, VmCapabilities::CapFixedPoint)
+                .set_vm_gated(0x13, execute_fixdiv128::<T>, VmCapabilities::CapFixedPoint)
+                .set_vm_gated(0x14, execute_isqrt::<T>, VmCapabilities::CapFixedPoint)
+                .set_vm_gated(0x15, execute_ilog2::<T>, VmCapabilities::CapFixedPoint)
+            )
+    }
+
+    fn add_integer_comparison<T: OperationBehavior>(&mut self) -> &mut Handlers {
         self
-            .set(0xB8, execute_sgn::<Signaling>)
-            .set(0xB9, execute_less::<Signaling>)
-            .set(0xBA, execute_equal::<Signaling>)
-            .set(0xBB, execute_leq::<Signaling>)
-            .set(0xBC, execute_greater::<Signaling>)
-            .set(0xBD, execute_neq::<Signaling>)
-            .set(0xBE, execute_geq::<Signaling>)
-            .set(0xBF, execute_cmp::<Signaling>)
+            .set(0xB8, execute_sgn::<T>)
+            .set(0xB9, execute_less::<T>)
+            .set(0xBA, execute_equal::<T>)
+            .set(0xBB, execute_leq::<T>)
+            .set(0xBC, execute_greater::<T>)
+            .set(0xBD, execute_neq::<T>)
+            .set(0xBE, execute_geq::<T>)
+            .set(0xBF, execute_cmp::<T>)
             //0xC0
-            .set(0xC0, execute_eqint::<Signaling>)
-            .set(0xC1, execute_lessint::<Signaling>)
-            .set(0xC2, execute_gtint::<Signaling>)
-            .set(0xC3, execute_neqint::<Signaling>)
+            .set(0xC0, execute_eqint::<T>)
+            .set(0xC1, execute_lessint::<T>)
+            .set(0xC2, execute_gtint::<T>)
+            .set(0xC3, execute_neqint::<T>)
+    }
+
+    fn add_code_page_0_comparsion(&mut self) -> &mut Handlers {
+        self
+            .add_integer_comparison::<Signaling>()
             .set(0xC4, execute_isnan)
             .set(0xC5, execute_chknan);
         let mut c7_handlers = Handlers::new();
@@ -352,18 +406,18 @@ impl Handlers {
             .set(0x13, execute_sdcnttrail1);
         #[cfg(feature = "gosh")] {
             c7_handlers
-                .set(0x14, execute_diff)
-                .set(0x15, execute_diff_patch_not_quiet)
+                .set_gated(0x14, execute_diff, GlobalCapabilities::CapDiff)
+                .set_gated(0x15, execute_diff_patch_not_quiet, GlobalCapabilities::CapDiff)
                 .set(0x16, execute_zip)
                 .set(0x17, execute_unzip)
-                .set(0x18, execute_diff_zip)
-                .set(0x19, execute_diff_patch_zip_not_quiet)
-                .set(0x20, execute_diff_patch_quiet)
-                .set(0x21, execute_diff_patch_zip_quiet)
-                .set(0x22, execute_diff_patch_binary_not_quiet)
-                .set(0x23, execute_diff_patch_binary_zip_not_quiet)
-                .set(0x24, execute_diff_patch_binary_quiet)
-                .set(0x25, execute_diff_patch_binary_zip_quiet);
+                .set_gated(0x18, execute_diff_zip, GlobalCapabilities::CapDiff)
+                .set_gated(0x19, execute_diff_patch_zip_not_quiet, GlobalCapabilities::CapDiff)
+                .set_gated(0x20, execute_diff_patch_quiet, GlobalCapabilities::CapDiff)
+                .set_gated(0x21, execute_diff_patch_zip_quiet, GlobalCapabilities::CapDiff)
+                .set_gated(0x22, execute_diff_patch_binary_not_quiet, GlobalCapabilities::CapDiff)
+                .set_gated(0x23, execute_diff_patch_binary_zip_not_quiet, GlobalCapabilities::CapDiff)
+                .set_gated(0x24, execute_diff_patch_binary_quiet, GlobalCapabilities::CapDiff)
+                .set_gated(0x25, execute_diff_patch_binary_zip_quiet, GlobalCapabilities::CapDiff);
         }
         self.add_subset(0xC7, &mut c7_handlers)
     }
@@ -413,10 +467,9 @@ impl Handlers {
                 .set(0x20, execute_strefconst)
                 .set(0x21, execute_stref2const)
                 .set(0x23, execute_endxc)
-                .set(0x28, execute_stile4)
-                .set(0x29, execute_stule4)
-                .set(0x2A, execute_stile8)
-                .set(0x2B, execute_stule8)
+                .set_vm_gated(0x24, execute_createproof, VmCapabilities::CapMerkleProofs)
+                .set_vm_gated(0x25, execute_createupdate, VmCapabilities::CapMerkleProofs)
+                .set_range(0x28..0x2C, execute_stle)
                 .set(0x30, execute_bdepth)
                 .set(0x31, execute_bbits)
                 .set(0x32, execute_brefs)
@@ -436,6 +489,7 @@ impl Handlers {
                 .set(0x41, execute_stones)
                 .set(0x42, execute_stsame)
                 .set(0x43, execute_stcont)
+                .set_range_vm_gated(0x44..0x48, execute_stle, VmCapabilities::CapWideLittleEndian)
                 .set_range(0x80..0xFF, execute_stsliceconst)
                 .set(0xFF, execute_stsliceconst)
             )
@@ -473,7 +527,7 @@ impl Handlers {
                 .set(0x1D, execute_pldslice)
                 .set(0x1E, execute_ldsliceq)
                 .set(0x1F, execute_pldsliceq)
-                .set(0x20, execute_pldslicex)
+                .set(0x20, execute_sdcutfirst)
                 .set(0x21, execute_sdskipfirst)
                 .set(0x22, execute_sdcutlast)
                 .set(0x23, execute_sdskiplast)
@@ -504,28 +558,17 @@ impl Handlers {
                 .set(0x4B, execute_sbitrefs)
                 .set(0x4C, execute_pldref)
                 .set_range(0x4D..0x50, execute_pldrefidx)
-                .set(0x50, execute_ldile4)
-                .set(0x51, execute_ldule4)
-                .set(0x52, execute_ldile8)
-                .set(0x53, execute_ldule8)
-                .set(0x54, execute_pldile4)
-                .set(0x55, execute_pldule4)
-                .set(0x56, execute_pldile8)
-                .set(0x57, execute_pldule8)
-                .set(0x58, execute_ldile4q)
-                .set(0x59, execute_ldule4q)
-                .set(0x5A, execute_ldile8q)
-                .set(0x5B, execute_ldule8q)
-                .set(0x5C, execute_pldile4q)
-                .set(0x5D, execute_pldule4q)
-                .set(0x5E, execute_pldile8q)
-                .set(0x5F, execute_pldule8q)
+                .set_range(0x50..0x60, execute_ldle)
                 .set(0x60, execute_ldzeroes)
                 .set(0x61, execute_ldones)
                 .set(0x62, execute_ldsame)
                 .set(0x64, execute_sdepth)
                 .set(0x65, execute_cdepth)
                 .set(0x66, execute_ldcont)
+                .set_vm_gated(0x67, execute_clevelmask, VmCapabilities::CapCellLevels)
+                .set_range_vm_gated(0x68..0x6C, execute_chashi, VmCapabilities::CapCellLevels)
+                .set_range_vm_gated(0x6C..0x70, execute_cdepthi, VmCapabilities::CapCellLevels)
+                .set_range_vm_gated(0x80..0x90, execute_ldle, VmCapabilities::CapWideLittleEndian)
             )
     }
 
@@ -552,6 +595,8 @@ impl Handlers {
                 .set(0x3D, execute_jmpref)
                 .set(0x3E, execute_jmprefdata)
                 .set(0x3F, execute_retdata)
+                .set_range_vm_gated(0x40..0x50, execute_runvm, VmCapabilities::CapRunVm)
+                .set_vm_gated(0x50, execute_runvmx, VmCapabilities::CapRunVm)
             )
             .set(0xDE, execute_if)
             .set(0xDC, execute_ifret)
@@ -600,6 +645,10 @@ impl Handlers {
                 .set(0x10, execute_returnva)
                 .set(0x11, execute_setcontva)
                 .set(0x12, execute_setnumvarargs)
+                .set_vm_gated(0x13, execute_contcode, VmCapabilities::CapContIntrospection)
+                .set_vm_gated(0x14, execute_contnargs, VmCapabilities::CapContIntrospection)
+                .set_vm_gated(0x15, execute_contstack, VmCapabilities::CapContIntrospection)
+                .set_vm_gated(0x16, execute_contgetctr, VmCapabilities::CapContIntrospection)
                 .set(0x1E, execute_bless)
                 .set(0x1F, execute_blessva)
                 .set_range(0x40..0x50, execute_pushctr)
@@ -656,6 +705,7 @@ impl Handlers {
                 .set(0xF3, execute_throwarganyif)
                 .set(0xF4, execute_throwanyifnot)
                 .set(0xF5, execute_throwarganyifnot)
+                .set_vm_gated(0xFD, execute_trykeepargs, VmCapabilities::CapTryKeepArgs)
                 .set(0xFE, execute_trykeep)
                 .set(0xFF, execute_try)
             )
@@ -673,6 +723,10 @@ impl Handlers {
                 .set(0x05, execute_ldvarint32)
                 .set(0x06, execute_stvaruint32)
                 .set(0x07, execute_stvarint32)
+                .set_vm_gated(0x08, execute_ldextracurrency, VmCapabilities::CapExtraCurrency)
+                .set_vm_gated(0x09, execute_extracurrencyget, VmCapabilities::CapExtraCurrency)
+                .set_vm_gated(0x0A, execute_extracurrencyadd, VmCapabilities::CapExtraCurrency)
+                .set_vm_gated(0x0B, execute_extracurrencysub, VmCapabilities::CapExtraCurrency)
                 .set(0x40, execute_ldmsgaddr::<Signaling>)
                 .set(0x41, execute_ldmsgaddr::<Quiet>)
                 .set(0x42, execute_parsemsgaddr::<Signaling>)
@@ -681,15 +735,17 @@ impl Handlers {
                 .set(0x45, execute_rewrite_std_addr::<Quiet>)
                 .set(0x46, execute_rewrite_var_addr::<Signaling>)
                 .set(0x47, execute_rewrite_var_addr::<Quiet>)
+                .set_vm_gated(0x48, execute_buildmsg, VmCapabilities::CapBuildMsg)
             )
             .add_subset(0xFB, Handlers::new()
                 .set(0x00, execute_sendrawmsg)
                 .set(0x02, execute_rawreserve)
                 .set(0x03, execute_rawreservex)
                 .set(0x04, execute_setcode)
-                .set(0x06, execute_setlibcode)
-                .set(0x07, execute_changelib)
-                .set(0x0A, execute_copyleft)
+                .set_vm_gated(0x08, execute_sendmsg, VmCapabilities::CapSendMsg)
+                .set_gated(0x06, execute_setlibcode, GlobalCapabilities::CapSetLibCode)
+                .set_gated(0x07, execute_changelib, GlobalCapabilities::CapSetLibCode)
+                .set_gated(0x0A, execute_copyleft, GlobalCapabilities::CapCopyleft)
             )
     }
 
@@ -840,6 +896,16 @@ impl Handlers {
                 .set(0xBD, execute_dictugetjmpz)
                 .set(0xBE, execute_dictigetexecz)
                 .set(0xBF, execute_dictugetexecz)
+                .set_vm_gated(0xC0, execute_augdictget, VmCapabilities::CapAugDicts)
+                .set_vm_gated(0xC1, execute_augdictiget, VmCapabilities::CapAugDicts)
+                .set_vm_gated(0xC2, execute_augdictuget, VmCapabilities::CapAugDicts)
+                .set_vm_gated(0xC4, execute_augdictset, VmCapabilities::CapAugDicts)
+                .set_vm_gated(0xC5, execute_augdictiset, VmCapabilities::CapAugDicts)
+                .set_vm_gated(0xC6, execute_augdictuset, VmCapabilities::CapAugDicts)
+                .set_vm_gated(0xC8, execute_augdictdel, VmCapabilities::CapAugDicts)
+                .set_vm_gated(0xC9, execute_augdictidel, VmCapabilities::CapAugDicts)
+                .set_vm_gated(0xCA, execute_augdictudel, VmCapabilities::CapAugDicts)
+                .set_vm_gated(0xCC, execute_augdictextra, VmCapabilities::CapAugDicts)
             )
     }
 
@@ -852,7 +918,8 @@ impl Handlers {
                 .set(0x02, execute_buygas)
                 .set(0x04, execute_gramtogas)
                 .set(0x05, execute_gastogram)
-                .set(0x06, execute_gas_remaining)
+                .set_gated(0x06, execute_gas_remaining, GlobalCapabilities::CapsTvmBugfixes2022)
+                .set_vm_gated(0x07, execute_gas_consumed, VmCapabilities::CapBlockchainInfo)
                 .set(0x0F, execute_commit)
                 .set(0x10, execute_randu256)
                 .set(0x11, execute_rand)
@@ -868,13 +935,23 @@ impl Handlers {
                 .set(0x27, execute_balance)
                 .set(0x28, execute_my_addr)
                 .set(0x29, execute_config_root)
-                .set(0x2A, execute_my_code)
-                .set(0x2B, execute_init_code_hash)
-                .set(0x2C, execute_storage_fees_collected)
-                .set(0x2D, execute_seq_no)
+                .set_gated(0x2A, execute_my_code, GlobalCapabilities::CapMycode)
+                .set_gated(0x2B, execute_init_code_hash, GlobalCapabilities::CapInitCodeHash)
+                .set_gated(0x2C, execute_storage_fees_collected, GlobalCapabilities::CapStorageFeeToTvm)
+                .set_gated(0x2D, execute_seq_no, GlobalCapabilities::CapDelections)
+                .set_vm_gated(0x2E, execute_prev_blocks_info, VmCapabilities::CapBlockchainInfo)
+                .set_vm_gated(0x2F, execute_due_payment, VmCapabilities::CapTransactionInfo)
                 .set(0x30, execute_config_dict)
+                .set_vm_gated(0x31, execute_incoming_value, VmCapabilities::CapTransactionInfo)
                 .set(0x32, execute_config_ref_param)
                 .set(0x33, execute_config_opt_param)
+                .add_subset(0x34, Handlers::new()
+                    .set_vm_gated(0x00, execute_prev_mc_blocks, VmCapabilities::CapBlockchainInfo)
+                    .set_vm_gated(0x01, execute_prev_key_block, VmCapabilities::CapBlockchainInfo)
+                )
+                .set_vm_gated(0x35, execute_global_id, VmCapabilities::CapBlockchainInfo)
+                .set_vm_gated(0x3E, execute_config_gas_prices, VmCapabilities::CapConfigParams)
+                .set_vm_gated(0x3F, execute_config_size_limits, VmCapabilities::CapConfigParams)
                 .set(0x40, execute_getglobvar)
                 .set_range(0x41..0x5F, execute_getglob)
                 .set(0x5F, execute_getglob)
@@ -891,8 +968,20 @@ impl Handlers {
             .set(0x00, execute_hashcu)
             .set(0x01, execute_hashsu)
             .set(0x02, execute_sha256u)
+            .set_vm_gated(0x04, execute_hashext, VmCapabilities::CapHashExt)
+            .set_vm_gated(0x05, execute_hashextr, VmCapabilities::CapHashExt)
+            .set_vm_gated(0x06, execute_hashexta, VmCapabilities::CapHashExt)
+            .set_vm_gated(0x07, execute_hashextar, VmCapabilities::CapHashExt)
             .set(0x10, execute_chksignu)
             .set(0x11, execute_chksigns)
+            .set_vm_gated(0x12, execute_ecrecover, VmCapabilities::CapEcrecover)
+            .set_vm_gated(0x14, execute_p256_chksignu, VmCapabilities::CapP256Signatures)
+            .set_vm_gated(0x15, execute_p256_chksigns, VmCapabilities::CapP256Signatures)
+            .set_vm_gated(0x16, execute_vrfverify, VmCapabilities::CapVrfVerify)
+            .set_vm_gated(0x18, execute_chksigns_batch, VmCapabilities::CapBatchSignatures)
+            .set_vm_gated(0x20, execute_rist255_fromhash, VmCapabilities::CapCrypto)
+            .add_rist255::<Signaling>()
+            .set_vm_gated(0x26, execute_rist255_pushl, VmCapabilities::CapCrypto)
             .set(0x40, execute_cdatasizeq)
             .set(0x41, execute_cdatasize)
             .set(0x42, execute_sdatasizeq)
@@ -902,30 +991,30 @@ impl Handlers {
             .set(0x46, execute_find_by_data_hash)
             .set(0x50, execute_try_elect)
             .add_subset(0x30, Handlers::new()
-                .set(0x00, execute_bls_verify)
-                .set(0x01, execute_bls_aggregate)
-                .set(0x02, execute_bls_fast_aggregate_verify)
-                .set(0x03, execute_bls_aggregate_verify)
-                .set(0x10, execute_bls_g1_add)
-                .set(0x11, execute_bls_g1_sub)
-                .set(0x12, execute_bls_g1_neg)
-                .set(0x13, execute_bls_g1_mul)
-                .set(0x14, execute_bls_g1_multiexp)
-                .set(0x15, execute_g1_zero)
-                .set(0x16, execute_bls_map_to_g1)
-                .set(0x17, execute_bls_g1_ingroup)
-                .set(0x18, execute_bls_g1_iszero)
-                .set(0x20, execute_bls_g2_add)
-                .set(0x21, execute_bls_g2_sub)
-                .set(0x22, execute_bls_g2_neg)
-                .set(0x23, execute_bls_g2_mul)
-                .set(0x24, execute_bls_g2_multiexp)
-                .set(0x25, execute_g2_zero)
-                .set(0x26, execute_bls_map_to_g2)
-                .set(0x27, execute_bls_g2_ingroup)
-                .set(0x28, execute_bls_g2_iszero)
-                .set(0x30, execute_bls_pairing)
-                .set(0x31, execute_bls_pushr)
+                .set_gated(0x00, execute_bls_verify, GlobalCapabilities::CapTvmV20)
+                .set_gated(0x01, execute_bls_aggregate, GlobalCapabilities::CapTvmV20)
+                .set_gated(0x02, execute_bls_fast_aggregate_verify, GlobalCapabilities::CapTvmV20)
+                .set_gated(0x03, execute_bls_aggregate_verify, GlobalCapabilities::CapTvmV20)
+                .set_gated(0x10, execute_bls_g1_add, GlobalCapabilities::CapTvmV20)
+                .set_gated(0x11, execute_bls_g1_sub, GlobalCapabilities::CapTvmV20)
+                .set_gated(0x12, execute_bls_g1_neg, GlobalCapabilities::CapTvmV20)
+                .set_gated(0x13, execute_bls_g1_mul, GlobalCapabilities::CapTvmV20)
+                .set_gated(0x14, execute_bls_g1_multiexp, GlobalCapabilities::CapTvmV20)
+                .set_gated(0x15, execute_g1_zero, GlobalCapabilities::CapTvmV20)
+                .set_gated(0x16, execute_bls_map_to_g1, GlobalCapabilities::CapTvmV20)
+                .set_gated(0x17, execute_bls_g1_ingroup, GlobalCapabilities::CapTvmV20)
+                .set_gated(0x18, execute_bls_g1_iszero, GlobalCapabilities::CapTvmV20)
+                .set_gated(0x20, execute_bls_g2_add, GlobalCapabilities::CapTvmV20)
+                .set_gated(0x21, execute_bls_g2_sub, GlobalCapabilities::CapTvmV20)
+                .set_gated(0x22, execute_bls_g2_neg, GlobalCapabilities::CapTvmV20)
+                .set_gated(0x23, execute_bls_g2_mul, GlobalCapabilities::CapTvmV20)
+                .set_gated(0x24, execute_bls_g2_multiexp, GlobalCapabilities::CapTvmV20)
+                .set_gated(0x25, execute_g2_zero, GlobalCapabilities::CapTvmV20)
+                .set_gated(0x26, execute_bls_map_to_g2, GlobalCapabilities::CapTvmV20)
+                .set_gated(0x27, execute_bls_g2_ingroup, GlobalCapabilities::CapTvmV20)
+                .set_gated(0x28, execute_bls_g2_iszero, GlobalCapabilities::CapTvmV20)
+                .set_gated(0x30, execute_bls_pairing, GlobalCapabilities::CapTvmV20)
+                .set_gated(0x31, execute_bls_pushr, GlobalCapabilities::CapTvmV20)
             )
         )
     }
@@ -949,57 +1038,120 @@ impl Handlers {
         )
     }
 
-    pub(super) fn get_handler(&self, engine: &mut Engine) -> Result<ExecuteHandler> {
-        let cmd = engine.next_cmd()?;
+    pub(super) fn get_handler(&self, code: &mut SliceData, last_cmd: &mut u8) -> Result<ExecuteHandler> {
+        let cmd = match code.get_next_byte() {
+            Ok(cmd) => cmd,
+            Err(_) => return err!(
+                ExceptionCode::InvalidOpcode,
+                "remaining bits expected >= 8, but actual value is: {}",
+                code.remaining_bits()
+            )
+        };
+        *last_cmd = cmd;
         // log::debug!(target: "tvm", "get_handler cmd: {:X}\n", cmd);
         match self.directs[cmd as usize] {
-            Some(Handler::Direct(handler)) => Ok(handler),
-            Some(Handler::Subset(i)) => self.subsets[i].get_handler(engine),
-            None => Ok(execute_unknown)
+            Handler::Direct(handler) | Handler::Gated(handler, _) | Handler::VmGated(handler, _) => Ok(handler),
+            Handler::Subset(i) => self.subsets[i].get_handler(code, last_cmd),
         }
     }
 
-    fn add_subset(&mut self, code: u8, subset: &mut Handlers) -> &mut Handlers {
-        match self.directs[code as usize] {
-            Some(Handler::Direct(x)) => {
-                if x as usize == execute_unknown as usize {
-                    self.directs[code as usize] = Some(Handler::Subset(self.subsets.len()));
-                    self.subsets.push(std::mem::replace(subset, Handlers::new()))
-                } else {
-                    panic!("Slot for subset {:02x} is already occupied", code)
+    /// Lists all known opcodes with their handlers in order of encoding
+    pub(super) fn opcodes(&self) -> Vec<(Vec<u8>, ExecuteHandler)> {
+        let mut opcodes = Vec::new();
+        self.collect_opcodes(&mut Vec::new(), &mut opcodes);
+        opcodes
+    }
+
+    fn collect_opcodes(&self, prefix: &mut Vec<u8>, opcodes: &mut Vec<(Vec<u8>, ExecuteHandler)>) {
+        for code in 0..self.directs.len() {
+            prefix.push(code as u8);
+            match self.directs[code] {
+                Handler::Direct(handler) | Handler::Gated(handler, _) | Handler::VmGated(handler, _) => {
+                    if handler as usize != execute_unknown as usize {
+                        opcodes.push((prefix.clone(), handler))
+                    }
                 }
+                Handler::Subset(i) => self.subsets[i].collect_opcodes(prefix, opcodes),
             }
-            None => {
-                self.directs[code as usize] = Some(Handler::Subset(self.subsets.len()));
+            prefix.pop();
+        }
+    }
+
+    pub(super) fn register_custom(&mut self, opcode_prefix: &[u8], handler: ExecuteHandler) -> Status {
+        let (code, tail) = match opcode_prefix.split_first() {
+            Some((code, tail)) => (*code as usize, tail),
+            None => fail!("opcode prefix must not be empty")
+        };
+        let vacant = self.directs[code].is_vacant();
+        if tail.is_empty() {
+            if !vacant {
+                fail!("opcode {:02X} is already occupied", code)
+            }
+            self.directs[code] = Handler::Direct(handler);
+            return Ok(())
+        }
+        if vacant {
+            self.directs[code] = Handler::Subset(self.subsets.len());
+            self.subsets.push(Handlers::new());
+        }
+        match self.directs[code] {
+            Handler::Subset(i) => self.subsets[i].register_custom(tail, handler),
+            _ => fail!("opcode {:02X} is already occupied", code)
+        }
+    }
+
+    fn add_subset(&mut self, code: u8, subset: &mut Handlers) -> &mut Handlers {
+        match self.directs[code as usize] {
+            Handler::Subset(_) => panic!("Subset {:02x} is already registered", code),
+            x if !x.is_vacant() => panic!("Slot for subset {:02x} is already occupied", code),
+            _ => {
+                self.directs[code as usize] = Handler::Subset(self.subsets.len());
                 self.subsets.push(std::mem::replace(subset, Handlers::new()))
             }
-            _ => panic!("Subset {:02x} is already registered", code),
         }
         self
     }
 
-    fn register_handler(&mut self, code: u8, handler: ExecuteHandler) {
+    fn register_handler(&mut self, code: u8, handler: Handler) {
         match self.directs[code as usize] {
-            None => self.directs[code as usize] = Some(Handler::Direct(handler)),
-            Some(Handler::Direct(x)) => {
-                if x as usize == execute_unknown as usize {
-                    self.directs[code as usize] = Some(Handler::Direct(handler))
-                } else {
-                    panic!("Code {:02x} is already registered", code)
-                }
-            }
-            _ => panic!("Slot for code {:02x} is already occupied", code)
+            Handler::Subset(_) => panic!("Slot for code {:02x} is already occupied", code),
+            x if !x.is_vacant() => panic!("Code {:02x} is already registered", code),
+            _ => self.directs[code as usize] = handler
         }
     }
 
     fn set(&mut self, code: u8, handler: ExecuteHandler) -> &mut Handlers {
-        self.register_handler(code, handler);
+        self.register_handler(code, Handler::Direct(handler));
         self
     }
 
     fn set_range(&mut self, codes: Range<u8>, handler: ExecuteHandler) -> &mut Handlers {
         for code in codes {
-            self.register_handler(code, handler);
+            self.register_handler(code, Handler::Direct(handler));
+        }
+        self
+    }
+
+    fn set_gated(&mut self, code: u8, handler: ExecuteHandler, capability: GlobalCapabilities) -> &mut Handlers {
+        self.register_handler(code, Handler::Gated(handler, capability as u64));
+        self
+    }
+
+    fn set_range_gated(&mut self, codes: Range<u8>, handler: ExecuteHandler, capability: GlobalCapabilities) -> &mut Handlers {
+        for code in codes {
+            self.register_handler(code, Handler::Gated(handler, capability as u64));
+        }
+        self
+    }
+
+    fn set_vm_gated(&mut self, code: u8, handler: ExecuteHandler, capability: VmCapabilities) -> &mut Handlers {
+        self.register_handler(code, Handler::VmGated(handler, capability as u64));
+        self
+    }
+
+    fn set_range_vm_gated(&mut self, codes: Range<u8>, handler: ExecuteHandler, capability: VmCapabilities) -> &mut Handlers {
+        for code in codes {
+            self.register_handler(code, Handler::VmGated(handler, capability as u64));
         }
         self
     }
@@ -1008,14 +1160,19 @@ impl Handlers {
 fn print_handlers(handlers: &Handlers, f: &mut fmt::Formatter, indent: String) -> fmt::Result {
     for h in 0..handlers.directs.len() {
         match handlers.directs[h] {
-            Some(Handler::Direct(func)) => {
+            Handler::Direct(func) => if func as usize != execute_unknown as usize {
                 writeln!(f, "{}{:02x}: 0x{:x}", indent, h, func as *const u8 as usize)?
             }
-            Some(Handler::Subset(i)) => {
+            Handler::Gated(func, capability) => {
+                writeln!(f, "{}{:02x}: 0x{:x} ({:#X})", indent, h, func as *const u8 as usize, capability)?
+            }
+            Handler::VmGated(func, capability) => {
+                writeln!(f, "{}{:02x}: 0x{:x} (vm {:#X})", indent, h, func as *const u8 as usize, capability)?
+            }
+            Handler::Subset(i) => {
                 writeln!(f, "{}{:02x}: subset", indent, h)?;
                 print_handlers(&handlers.subsets[i], f, format!("  {}", indent))?;
             }
-            None => {}
         }
     }
     fmt::Result::Ok(())
@@ -11,13 +11,19 @@
 * limitations under the License.
 */
 
+mod builder;
+mod capabilities;
 mod core;
 pub(in crate::executor) mod data;
 mod handlers;
+mod version;
 #[macro_use]
 pub(in crate::executor) mod storage;
 
+pub use self::builder::EngineBuilder;
+pub use self::capabilities::{CapabilityInfo, CapabilitySet, KNOWN_CAPABILITIES, VmCapabilities};
 pub use self::core::*;
+pub use self::version::TvmVersion;
 
 #[cfg(test)]
 #[path = "../../tests/test_microfunctions.rs"]
@@ -16,12 +16,12 @@ use crate::{
         engine::Engine,
         microcode::{VAR, STACK, CC, CC_SAVELIST, CTRL, CTRL_SAVELIST, VAR_SAVELIST}
     },
+    logging::targets,
     stack::{StackItem, continuation::ContinuationData, savelist::SaveList},
     types::{ResultMut, ResultRef, Status}
 };
 use std::{mem, ops::Range};
 use ever_block::{error, fail, Result, types::ExceptionCode};
-use crate::executor::gas::gas_state::Gas;
 
 // Utilities ******************************************************************
 
@@ -134,10 +134,10 @@ fn put_to_list(engine: &mut Engine, x: &mut Info, y: &mut StackItem) -> Result<O
 
 fn put_to_list_from_item(engine: &mut Engine, x: &mut Info, y: &Info) -> Result<Option<StackItem>> {
     if !SaveList::can_put(x.index, y.item(engine)?) {
-        if log::log_enabled!(log::Level::Error) {
+        if tvm_error_enabled!(targets::CTRLS) {
             let value = x.list(engine)?.get(x.index).cloned().unwrap_or_else(StackItem::default);
-            log::error!(
-                target: "tvm",
+            tvm_log!(
+                error, targets::CTRLS,
                 "Cannot set: {} to list with index: {} and value: {}",
                 y.item(engine)?.clone(), x.index, value
             );
@@ -158,11 +158,11 @@ fn put_to_list_from_list(engine: &mut Engine, x: &mut Info, y: &mut Info) -> Res
             }
         }
     }
-    if log::log_enabled!(log::Level::Error) {
+    if tvm_error_enabled!(targets::CTRLS) {
         let old = x.list(engine)?.get(x.index).cloned().unwrap_or_else(StackItem::default);
         let new = y.list(engine)?.get(y.index).cloned().unwrap_or_else(StackItem::default);
-        log::error!(
-            target: "tvm",
+        tvm_log!(
+            error, targets::CTRLS,
             "Cannot set: {} to list with index: {} and value: {}",
             new, x.index, old
         );
@@ -335,18 +335,18 @@ pub(in crate::executor) fn pop_range(engine: &mut Engine, drop: Range<usize>, ds
     let dst_depth = continuation_by_address(engine, dst)?.stack.depth();
     // pay for stack splitting
     if src_depth > save {
-        engine.try_use_gas(Gas::stack_price(save))?;
+        engine.try_use_gas(engine.gas_prices().stack(save))?;
     }
     // pay for stack concatenation
     if engine.check_capabilities(ever_block::GlobalCapabilities::CapTvmV19 as u64) {
         if dst_depth != 0 {
-            engine.try_use_gas(Gas::stack_price(save + dst_depth))?;
+            engine.try_use_gas(engine.gas_prices().stack(save + dst_depth))?;
         }
     } else {
         // According to the original implementation, the gas must still be consumed when save is zero.
         // The bug slipped in with PR #118.
         if dst_depth != 0 && save != 0 {
-            engine.try_use_gas(Gas::stack_price(save + dst_depth))?;
+            engine.try_use_gas(engine.gas_prices().stack(save + dst_depth))?;
         }
     }
     move_stack_from_cc(engine, dst, drop)
@@ -0,0 +1,98 @@
+/*
+* Copyright (C) 2019-2024 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+use crate::executor::engine::{Engine, EngineBuilder};
+use ever_block::GlobalCapabilities;
+
+/// Version of instruction set and VM behavior introduced by protocol upgrades.
+/// Every version is a bundle of capabilities including all capabilities of previous one.
+/// Extensions not bound to protocol upgrades (CapDiff, CapIndexAccounts, CapFastStorageStat)
+/// and network specific behavior (CapSignatureWithId, CapBigCells) are not part of any version
+/// and are to be added with capabilities
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum TvmVersion {
+    /// Original instruction set with c7 of 10 parameters
+    V1,
+    /// TVM v19 and bugfixes of 2022: MYCODE, INITCODEHASH, STORAGEFEES and SEQNO in c7,
+    /// SETLIBCODE, COPYLEFT, fixed tuple indexes and new format of serialized continuations
+    V2,
+    /// TVM v20: new instructions and gas of cells, resolving of merkle cells,
+    /// previous blocks and global id in c7
+    V3,
+    /// The newest version known to this VM
+    #[default]
+    Latest,
+}
+
+const V2_CAPABILITIES: u64 = GlobalCapabilities::CapTvmV19 as u64
+    | GlobalCapabilities::CapsTvmBugfixes2022 as u64
+    | GlobalCapabilities::CapFixTupleIndexBug as u64
+    | GlobalCapabilities::CapStcontNewFormat as u64
+    | GlobalCapabilities::CapMycode as u64
+    | GlobalCapabilities::CapInitCodeHash as u64
+    | GlobalCapabilities::CapStorageFeeToTvm as u64
+    | GlobalCapabilities::CapDelections as u64
+    | GlobalCapabilities::CapSetLibCode as u64
+    | GlobalCapabilities::CapCopyleft as u64;
+
+const V3_CAPABILITIES: u64 = V2_CAPABILITIES
+    | GlobalCapabilities::CapTvmV20 as u64
+    | GlobalCapabilities::CapResolveMerkleCell as u64;
+
+impl TvmVersion {
+    pub const ALL: [TvmVersion; 3] = [TvmVersion::V1, TvmVersion::V2, TvmVersion::V3];
+
+    /// Concrete version, Latest is resolved to the newest one
+    pub fn resolve(self) -> Self {
+        match self {
+            TvmVersion::Latest => TvmVersion::V3,
+            version => version
+        }
+    }
+
+    /// Capabilities switching on behavior of the version
+    pub fn capabilities(self) -> u64 {
+        match self.resolve() {
+            TvmVersion::V1 => 0,
+            TvmVersion::V2 => V2_CAPABILITIES,
+            _ => V3_CAPABILITIES
+        }
+    }
+
+    /// The highest version all capabilities of which are set
+    pub fn from_capabilities(capabilities: u64) -> Self {
+        Self::ALL.into_iter().rev()
+            .find(|version| capabilities & version.capabilities() == version.capabilities())
+            .unwrap_or(TvmVersion::V1)
+    }
+}
+
+impl Engine {
+    /// Engine with capabilities of the version
+    pub fn with_version(version: TvmVersion) -> Engine {
+        Engine::with_capabilities(version.capabilities())
+    }
+
+    /// Version the capabilities of the engine correspond to
+    pub fn tvm_version(&self) -> TvmVersion {
+        TvmVersion::from_capabilities(self.capabilities())
+    }
+}
+
+impl EngineBuilder {
+    /// Builder with capabilities of the version,
+    /// use EngineBuilder::new(version.capabilities() | extensions) to add extensions
+    pub fn with_version(version: TvmVersion) -> Self {
+        Self::new(version.capabilities())
+    }
+}
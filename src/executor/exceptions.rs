@@ -35,7 +35,11 @@ fn init_try_catch(engine: &mut Engine, keep: bool) -> Status {
     if engine.cc.stack.depth() < engine.cmd.pargs() {
         return err!(ExceptionCode::StackUnderflow)
     }
-    let depth: u32 = engine.cc.stack.depth().try_into()?;
+    // try body of TRYKEEPARGS gets only p arguments, so stack is reverted to them
+    let depth: u32 = match engine.cmd.pargs_raw() {
+        Some(pargs) if keep => pargs.try_into()?,
+        _ => engine.cc.stack.depth().try_into()?
+    };
     engine.cmd.var(1).as_continuation()?;
     let bugfix = engine.check_capabilities(GlobalCapabilities::CapsTvmBugfixes2022 as u64);
     engine.cmd.var_mut(0).as_continuation_mut().map(|catch_cont| {
@@ -269,3 +273,15 @@ pub(super) fn execute_trykeep(engine: &mut Engine) -> Status {
     )?;
     init_try_catch(engine, true)
 }
+
+// (c c' - )
+// as TRYARGS, but stack of c is reverted to p arguments before passing exception to c'
+pub(super) fn execute_trykeepargs(engine: &mut Engine) -> Status {
+    if !engine.check_capabilities(GlobalCapabilities::CapsTvmBugfixes2022 as u64) {
+        return Status::Err(ExceptionCode::InvalidOpcode.into());
+    }
+    engine.load_instruction(
+        Instruction::new("TRYKEEPARGS").set_opts(InstructionOptions::ArgumentAndReturnConstraints)
+    )?;
+    init_try_catch(engine, true)
+}
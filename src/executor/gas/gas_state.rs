@@ -11,7 +11,7 @@
 * limitations under the License.
 */
 
-use ever_block::{Result, ExceptionCode};
+use ever_block::{Cell, CellType, Result, ExceptionCode, MAX_DATA_BITS};
 
 // Gas state
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -22,19 +22,108 @@ pub struct Gas {
     gas_remaining: i64,
     gas_price: i64,
     gas_base: i64,
+    unlimited: bool, // dry run: gas is counted but out of gas is never raised
 }
 
-const CELL_LOAD_GAS_PRICE: i64 = 100;
-const CELL_RELOAD_GAS_PRICE: i64 = 25;
-const CELL_CREATE_GAS_PRICE: i64 = 500;
-const EXCEPTION_GAS_PRICE: i64 = 50;
-const TUPLE_ENTRY_GAS_PRICE: i64 = 1;
-const IMPLICIT_JMPREF_GAS_PRICE: i64 = 10;
-const IMPLICIT_RET_GAS_PRICE: i64 = 5;
-const FREE_STACK_DEPTH: usize = 32;
-const STACK_ENTRY_GAS_PRICE: i64 = 1;
-const CHECK_SIGNATURE_THRESHOLD: usize = 5;
-const CHECK_SIGNATURE_GAS_PRICE: i64 = 6500 - 26; // minus original price
+/// Prices of basic VM operations. Default table is the one of the reference implementation,
+/// networks with custom economics can inject their own table into the Engine
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct GasPrices {
+    pub instruction_base: i64,
+    pub cell_load: i64,
+    pub cell_reload: i64,
+    pub exotic_cell_load: i64, // charged for exotic cells opened explicitly by XCTOS and XLOAD
+    pub big_cell_byte: i64, // charged for every byte of big cell above standard cell size on first load
+    pub cell_create: i64,
+    pub library_lookup: i64,
+    pub exception: i64,
+    pub tuple_entry: i64,
+    pub implicit_jmpref: i64,
+    pub implicit_ret: i64,
+    pub free_stack_depth: usize,
+    pub stack_entry: i64,
+    pub check_signature_threshold: usize,
+    pub check_signature: i64, // charged for every signature check above the threshold
+    pub free_cell_depth: u16,
+    pub cell_depth: i64, // charged for every level of new cell above free depth
+    pub proof_depth: i64, // charged for every level of tree covered by new Merkle cell
+}
+
+impl GasPrices {
+    pub const DEFAULT: GasPrices = GasPrices {
+        instruction_base: 10,
+        cell_load: 100,
+        cell_reload: 25,
+        exotic_cell_load: 200,
+        big_cell_byte: 1,
+        cell_create: 500,
+        library_lookup: 100,
+        exception: 50,
+        tuple_entry: 1,
+        implicit_jmpref: 10,
+        implicit_ret: 5,
+        free_stack_depth: 32,
+        stack_entry: 1,
+        check_signature_threshold: 5,
+        check_signature: 6500 - 26, // minus original price
+        free_cell_depth: 64,
+        cell_depth: 10,
+        proof_depth: 10,
+    };
+
+    /// Instruction cost by its length in bits
+    pub const fn basic(&self, instruction_length: usize) -> i64 {
+        // old formula from spec: (10 + instruction_length + 5 * instruction_references_count) as i64
+        self.instruction_base + instruction_length as i64
+    }
+
+    pub const fn load_cell(&self, first: bool) -> i64 {
+        if first {self.cell_load} else {self.cell_reload}
+    }
+
+    pub const fn load_exotic_cell(&self, first: bool) -> i64 {
+        if first {self.exotic_cell_load} else {self.cell_reload}
+    }
+
+    pub const fn big_cell_data(&self, bits: usize) -> i64 {
+        self.big_cell_byte * (bits.saturating_sub(MAX_DATA_BITS) / 8) as i64
+    }
+
+    pub const fn stack(&self, stack_depth: usize) -> i64 {
+        self.stack_entry * stack_depth.saturating_sub(self.free_stack_depth) as i64
+    }
+
+    pub const fn tuple(&self, tuple_length: usize) -> i64 {
+        self.tuple_entry * tuple_length as i64
+    }
+
+    pub const fn check_signature(&self, count: usize) -> i64 {
+        if count > self.check_signature_threshold {
+            self.check_signature
+        } else {
+            0
+        }
+    }
+
+    /// Extra cost of new cell by its depth and size of proof it carries, deep cells are
+    /// cheap to build with loop but expensive to hash and traverse afterwards
+    pub fn new_cell(&self, cell: &Cell) -> i64 {
+        let depth = cell.depth(0);
+        let mut gas = self.cell_depth * depth.saturating_sub(self.free_cell_depth) as i64;
+        if matches!(cell.cell_type(), CellType::MerkleProof | CellType::MerkleUpdate) {
+            gas += self.proof_depth * depth as i64;
+        }
+        gas
+    }
+}
+
+impl Default for GasPrices {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+const DEFAULT: GasPrices = GasPrices::DEFAULT;
 #[cfg(feature = "gosh")]
 const DIFF_DURATION_FOR_LINE: i64 = 60;
 #[cfg(feature = "gosh")]
@@ -53,6 +142,12 @@ const ZIP_DURATION_FOR_BYTE: i64 = 4;
 const UNZIP_DURATION_FOR_BYTE: i64 = 1;
 // const MAX_DATA_DEPTH: usize = 512;
 
+const RUNVM_GAS_PRICE: i64 = 40;
+
+const ECRECOVER_GAS_PRICE: i64 = 1500;
+
+const P256_CHKSIGN_GAS_PRICE: i64 = 3500;
+
 const BLS_VERIFY_GAS_PRICE: i64 = 61000;
 const BLS_AGGREGATE_GAS_A: i64 = 4350;
 const BLS_AGGREGATE_GAS_B: i64 = 2650;
@@ -79,6 +174,12 @@ const BLS_G2_INGROUP_GAS_PRICE: i64 = 4250;
 const BLS_PAIRING_GAS_BASE: i64 = 20000;
 const BLS_PAIRING_GAS_ELEM: i64 = 11800;
 
+const RIST255_FROMHASH_GAS_PRICE: i64 = 600;
+const RIST255_VALIDATE_GAS_PRICE: i64 = 200;
+const RIST255_ADD_SUB_GAS_PRICE: i64 = 600;
+const RIST255_MUL_GAS_PRICE: i64 = 2000;
+const RIST255_MULBASE_GAS_PRICE: i64 = 750;
+
 impl Gas {
     /// Instance for constructors. Empty fields
     pub const fn empty() -> Gas {
@@ -89,6 +190,7 @@ impl Gas {
             gas_remaining: 0,
             gas_price: 0,
             gas_base: 0,
+            unlimited: false,
         }
     }
     /// Instance for debug and test. Cheat fields
@@ -100,6 +202,7 @@ impl Gas {
             gas_remaining: 1000000000,
             gas_credit: 0,
             gas_base: 1000000000,
+            unlimited: false,
         }
     }
     /// Instance for release
@@ -122,87 +225,80 @@ impl Gas {
             gas_remaining: remaining,
             gas_credit,
             gas_base: remaining,
+            unlimited: false,
         }
     }
-    /// Compute instruction cost
+    /// Compute instruction cost with default prices
     pub const fn basic_gas_price(instruction_length: usize, _instruction_references_count: usize) -> i64 {
-        // old formula from spec: (10 + instruction_length + 5 * instruction_references_count) as i64
-        (10 + instruction_length) as i64
+        DEFAULT.basic(instruction_length)
     }
     pub fn consume_basic(&mut self, instruction_length: usize, _instruction_references_count: usize) -> i64 {
-        // old formula from spec: (10 + instruction_length + 5 * instruction_references_count) as i64
-        self.use_gas((10 + instruction_length) as i64)
+        self.use_gas(DEFAULT.basic(instruction_length))
     }
 
-    /// Compute exception cost
+    /// Compute exception cost with default prices
     pub const fn exception_price() -> i64 {
-        EXCEPTION_GAS_PRICE
+        DEFAULT.exception
     }
     pub fn consume_exception(&mut self) -> i64 {
-        self.use_gas(EXCEPTION_GAS_PRICE)
+        self.use_gas(DEFAULT.exception)
     }
 
-    /// Compute exception cost
+    /// Compute cell creation cost with default prices
     pub const fn finalize_price() -> i64 {
-        CELL_CREATE_GAS_PRICE
+        DEFAULT.cell_create
     }
     pub fn consume_finalize(&mut self) -> i64 {
-        self.use_gas(CELL_CREATE_GAS_PRICE)
+        self.use_gas(DEFAULT.cell_create)
     }
 
-    /// Implicit JMP cost
+    /// Implicit JMP cost with default prices
     pub const fn implicit_jmp_price() -> i64 {
-        IMPLICIT_JMPREF_GAS_PRICE
+        DEFAULT.implicit_jmpref
     }
     pub fn consume_implicit_jmp(&mut self) -> i64 {
-        self.use_gas(IMPLICIT_JMPREF_GAS_PRICE)
+        self.use_gas(DEFAULT.implicit_jmpref)
     }
 
-    /// Implicit RET cost
+    /// Implicit RET cost with default prices
     pub const fn implicit_ret_price() -> i64 {
-        IMPLICIT_RET_GAS_PRICE
+        DEFAULT.implicit_ret
     }
     pub fn consume_implicit_ret(&mut self) -> i64 {
-        self.use_gas(IMPLICIT_RET_GAS_PRICE)
+        self.use_gas(DEFAULT.implicit_ret)
     }
 
-    /// Compute exception cost
+    /// Compute cell load cost with default prices
     pub const fn load_cell_price(first: bool) -> i64 {
-        if first {CELL_LOAD_GAS_PRICE} else {CELL_RELOAD_GAS_PRICE}
+        DEFAULT.load_cell(first)
     }
     pub fn consume_load_cell(&mut self, first: bool) -> i64 {
-        self.use_gas(if first {CELL_LOAD_GAS_PRICE} else {CELL_RELOAD_GAS_PRICE})
+        self.use_gas(DEFAULT.load_cell(first))
+    }
+
+    /// Cost of library lookup in external library provider with default prices
+    pub const fn library_lookup_price() -> i64 {
+        DEFAULT.library_lookup
     }
 
-    /// Stack cost
+    /// Stack cost with default prices
     pub const fn stack_price(stack_depth: usize) -> i64 {
-        let depth = if stack_depth > FREE_STACK_DEPTH {
-            stack_depth
-        } else {
-            FREE_STACK_DEPTH
-        };
-        STACK_ENTRY_GAS_PRICE * (depth - FREE_STACK_DEPTH) as i64
+        DEFAULT.stack(stack_depth)
     }
     pub fn consume_stack(&mut self, stack_depth: usize) -> i64 {
-        self.use_gas(
-            STACK_ENTRY_GAS_PRICE * (stack_depth.saturating_sub(FREE_STACK_DEPTH) as i64)
-        )
+        self.use_gas(DEFAULT.stack(stack_depth))
     }
 
-    /// Compute tuple usage cost
+    /// Compute tuple usage cost with default prices
     pub const fn tuple_gas_price(tuple_length: usize) -> i64 {
-        TUPLE_ENTRY_GAS_PRICE * tuple_length as i64
+        DEFAULT.tuple(tuple_length)
     }
     pub fn consume_tuple_gas(&mut self, tuple_length: usize) -> i64 {
-        self.use_gas(TUPLE_ENTRY_GAS_PRICE * tuple_length as i64)
+        self.use_gas(DEFAULT.tuple(tuple_length))
     }
 
     pub const fn check_signature_price(count: usize) -> i64 {
-        if count > CHECK_SIGNATURE_THRESHOLD {
-            CHECK_SIGNATURE_GAS_PRICE
-        } else {
-            0
-        }
+        DEFAULT.check_signature(count)
     }
 
     #[cfg(feature = "gosh")]
@@ -252,6 +348,21 @@ impl Gas {
         (UNZIP_DURATION_FOR_BYTE * bytes) / DURATION_TO_GAS_COEFFICIENT
     }
 
+    /// Cost of child VM start
+    pub const fn runvm_price() -> i64 {
+        RUNVM_GAS_PRICE
+    }
+
+    /// Cost of secp256k1 public key recovery
+    pub const fn ecrecover_price() -> i64 {
+        ECRECOVER_GAS_PRICE
+    }
+
+    /// Cost of secp256r1 signature check
+    pub const fn p256_chksign_price() -> i64 {
+        P256_CHKSIGN_GAS_PRICE
+    }
+
     pub fn bls_verify_gas_price() -> i64 {
         BLS_VERIFY_GAS_PRICE
     }
@@ -331,6 +442,26 @@ impl Gas {
         BLS_PAIRING_GAS_BASE + n * BLS_PAIRING_GAS_ELEM
     }
 
+    pub fn rist255_fromhash_gas_price() -> i64 {
+        RIST255_FROMHASH_GAS_PRICE
+    }
+
+    pub fn rist255_validate_gas_price() -> i64 {
+        RIST255_VALIDATE_GAS_PRICE
+    }
+
+    pub fn rist255_add_sub_gas_price() -> i64 {
+        RIST255_ADD_SUB_GAS_PRICE
+    }
+
+    pub fn rist255_mul_gas_price() -> i64 {
+        RIST255_MUL_GAS_PRICE
+    }
+
+    pub fn rist255_mulbase_gas_price() -> i64 {
+        RIST255_MULBASE_GAS_PRICE
+    }
+
     /// Set input gas to gas limit
     pub fn new_gas_limit(&mut self, gas_limit: i64) {
         self.gas_limit = gas_limit.min(self.gas_limit_max).max(0);
@@ -339,6 +470,15 @@ impl Gas {
         self.gas_base = self.gas_limit;
     }
 
+    /// Increase gas limit by the given amount, gas_limit_max is raised if needed
+    pub fn add_gas(&mut self, gas: i64) {
+        let gas = gas.max(0);
+        self.gas_limit = self.gas_limit.saturating_add(gas);
+        self.gas_limit_max = self.gas_limit_max.max(self.gas_limit);
+        self.gas_remaining = self.gas_remaining.saturating_add(gas);
+        self.gas_base = self.gas_base.saturating_add(gas);
+    }
+
     /// Update remaining gas limit
     pub fn use_gas(&mut self, gas: i64) -> i64 {
         self.gas_remaining -= gas;
@@ -351,9 +491,18 @@ impl Gas {
         self.check_gas_remaining()
     }
 
+    /// Never raise out of gas exception, gas used is counted as usual and may exceed the limit
+    pub fn set_unlimited(&mut self, unlimited: bool) {
+        self.unlimited = unlimited
+    }
+
+    pub const fn is_unlimited(&self) -> bool {
+        self.unlimited
+    }
+
     /// Raise out of gas exception
     pub fn check_gas_remaining(&self) -> Result<Option<i32>> {
-        if self.gas_remaining >= 0 {
+        if self.gas_remaining >= 0 || self.unlimited {
             Ok(None)
         } else {
             Err(exception!(ExceptionCode::OutOfGas, self.gas_base - self.gas_remaining, "check_gas_remaining"))
@@ -377,6 +526,16 @@ impl Gas {
         self.gas_remaining
     }
 
+    /// Gas which callee, e.g. child VM or host, may spend. It is not limited in dry run,
+    /// so callee is not cut short by the gas limit
+    pub const fn get_gas_available(&self) -> i64 {
+        if self.unlimited {
+            i64::MAX
+        } else {
+            self.gas_remaining
+        }
+    }
+
     pub const fn get_gas_credit(&self) -> i64 {
         self.gas_credit
     }
@@ -386,7 +545,7 @@ impl Gas {
     }
 
     pub const fn get_gas_used(&self) -> i64 {
-        if self.gas_remaining > 0 {
+        if self.gas_remaining > 0 || self.unlimited {
             self.gas_base - self.gas_remaining
         } else {
             self.gas_base
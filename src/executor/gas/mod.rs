@@ -28,7 +28,7 @@ fn gramtogas(engine: &Engine, nanograms: &IntegerData) -> Result<i64> {
     Ok(ret)
 }
 fn setgaslimit(engine: &mut Engine, gas_limit: i64) -> Status {
-    if gas_limit < engine.gas_used() {
+    if gas_limit < engine.gas_used() && !engine.get_gas().is_unlimited() {
         return err!(ExceptionCode::OutOfGas);
     }
     engine.new_gas_limit(gas_limit);
@@ -91,8 +91,7 @@ pub fn execute_gastogram(engine: &mut Engine) -> Status {
 // COMMIT - F80F
 pub fn execute_commit(engine: &mut Engine) -> Status {
     engine.load_instruction(Instruction::new("COMMIT"))?;
-    engine.commit();
-    Ok(())
+    engine.checked_commit()
 }
 
 pub fn execute_gas_remaining(engine: &mut Engine) -> Status {
@@ -101,3 +100,10 @@ pub fn execute_gas_remaining(engine: &mut Engine) -> Status {
     engine.cc.stack.push(StackItem::int(engine.gas_remaining()));
     Ok(())
 }
+
+// - integer
+pub fn execute_gas_consumed(engine: &mut Engine) -> Status {
+    engine.load_instruction(Instruction::new("GASCONSUMED"))?;
+    engine.cc.stack.push(StackItem::int(engine.gas_used()));
+    Ok(())
+}
@@ -13,16 +13,87 @@
 
 use crate::{
     executor::{
-        Mask, engine::{Engine, storage::fetch_stack}, gas::gas_state::Gas,
+        Mask, engine::{Engine, VmCapabilities, storage::fetch_stack},
         types::{Instruction, InstructionOptions}
     },
-    stack::StackItem, types::Status
+    stack::{MAX_TUPLE_SIZE, StackItem}, types::Status
 };
+use ever_block::{ExceptionCode, Result};
+use std::sync::Arc;
+
+/// Maximal index of global variable with CapLongGlobals
+pub const MAX_GLOBAL_INDEX: usize = 0xFFFF;
+
+// Globals with indexes from MAX_TUPLE_SIZE are kept in the last item of c7 as a tree of small
+// tuples, so setting such global copies only a few nodes on its path if they are shared.
+// Globals with lower indexes are kept in c7 exactly as without the capability
+const LONG_GLOBALS_ROOT: usize = MAX_TUPLE_SIZE - 1;
+const LONG_GLOBALS_FANOUT: usize = 16;
+const LONG_GLOBALS_DEPTH: usize = 4;
 
 const STACK: u8 = 0x02;
 const CMD:   u8 = 0x04;
 const SET:   u8 = 0x10;
 
+// indexes of items from c7 to the global
+fn long_global_path(k: usize) -> [usize; LONG_GLOBALS_DEPTH + 1] {
+    let mut path = [LONG_GLOBALS_ROOT; LONG_GLOBALS_DEPTH + 1];
+    let mut index = k - MAX_TUPLE_SIZE;
+    for item in path[1..].iter_mut().rev() {
+        *item = index % LONG_GLOBALS_FANOUT;
+        index /= LONG_GLOBALS_FANOUT;
+    }
+    path
+}
+
+// returns global or null if there is no such item, item which is not a tuple terminates the path
+fn long_global(c7: &StackItem, k: usize) -> Result<StackItem> {
+    c7.as_tuple()?;
+    let mut item = c7;
+    for index in long_global_path(k) {
+        item = match item {
+            StackItem::Tuple(node) => match node.get(index) {
+                Some(item) => item,
+                None => return Ok(StackItem::None)
+            }
+            _ => return Ok(StackItem::None)
+        }
+    }
+    Ok(item.clone())
+}
+
+// sets item of the tree, nodes on the path are copied only if they are shared,
+// returns number of items in changed nodes to charge gas
+fn set_long_global(node: &mut StackItem, path: &[usize], x: StackItem) -> Result<usize> {
+    let (index, path) = match path.split_first() {
+        Some((index, path)) => (*index, path),
+        None => {
+            *node = x;
+            return Ok(0)
+        }
+    };
+    let absent = match node {
+        StackItem::None => true,
+        StackItem::Tuple(items) => index >= items.len(),
+        _ => return err!(ExceptionCode::TypeCheckError, "node of globals is not a tuple")
+    };
+    if absent && x.is_null() {
+        return Ok(0)
+    }
+    if node.is_null() {
+        *node = StackItem::tuple(Vec::new());
+    }
+    let items = match node {
+        StackItem::Tuple(items) => Arc::make_mut(items),
+        _ => unreachable!("node is a tuple")
+    };
+    if index >= items.len() {
+        items.resize(index + 1, StackItem::None);
+    }
+    let len = set_long_global(&mut items[index], path, x)?;
+    Ok(items.len() + len)
+}
+
 fn execute_setget_globalvar(engine: &mut Engine, name: &'static str, how: u8) -> Status {
     let mut inst = Instruction::new(name);
     let mut params = 0;
@@ -37,12 +108,26 @@ fn execute_setget_globalvar(engine: &mut Engine, name: &'static str, how: u8) ->
     }
     engine.load_instruction(inst)?;
     fetch_stack(engine, params)?;
-    let k = if how.bit(STACK) {
-        engine.cmd.var(0).as_integer()?.into(0..=254)?
-    } else {
+    let long = engine.has_vm_capability(VmCapabilities::CapLongGlobals);
+    let k = if !how.bit(STACK) {
         engine.cmd.length()
+    } else if long {
+        engine.cmd.var(0).as_integer()?.into(0..=MAX_GLOBAL_INDEX)?
+    } else {
+        engine.cmd.var(0).as_integer()?.into(0..=254)?
     };
-    if how.bit(SET) {
+    if k >= MAX_TUPLE_SIZE {
+        if how.bit(SET) {
+            let x = engine.cmd.var_mut(params - 1).withdraw();
+            let c7 = engine.ctrl_mut(7)?;
+            c7.as_tuple()?;
+            let len = set_long_global(c7, &long_global_path(k), x)?;
+            engine.use_gas(engine.gas_prices().tuple(len));
+        } else {
+            let x = long_global(engine.ctrl(7)?, k)?;
+            engine.cc.stack.push(x);
+        }
+    } else if how.bit(SET) {
         let mut c7 = engine.ctrl_mut(7)?.as_tuple_mut()?;
         let x = engine.cmd.var_mut(params - 1).withdraw();
         let len = if k < c7.len() {
@@ -55,7 +140,7 @@ fn execute_setget_globalvar(engine: &mut Engine, name: &'static str, how: u8) ->
         } else {
             0
         };
-        engine.use_gas(Gas::tuple_gas_price(len));
+        engine.use_gas(engine.gas_prices().tuple(len));
         engine.ctrls.put(7, &mut StackItem::tuple(c7))?;
     } else {
         let x = engine.ctrl(7)?.tuple_item(k, true)?;
@@ -66,6 +151,7 @@ fn execute_setget_globalvar(engine: &mut Engine, name: &'static str, how: u8) ->
 
 // GETGLOBVAR (k–x), returns the k-th global variable for 0 ≤ k < 255.
 // Equivalent to PUSH c7; SWAP; INDEXVARQ
+// With CapLongGlobals k can be up to MAX_GLOBAL_INDEX, see LONG_GLOBALS_ROOT
 pub(super) fn execute_getglobvar(engine: &mut Engine) -> Status {
     execute_setget_globalvar(engine, "GETGLOBVAR", STACK)
 }
@@ -78,6 +164,7 @@ pub(super) fn execute_getglob(engine: &mut Engine) -> Status {
 
 // SETGLOBVAR (x k– ), assigns x to the k-th global variable for 0 ≤ k <255.
 // Equivalent to PUSH c7; ROTREV; SETINDEXVARQ; POP c7.
+// With CapLongGlobals k can be up to MAX_GLOBAL_INDEX, see LONG_GLOBALS_ROOT
 pub(super) fn execute_setglobvar(engine: &mut Engine) -> Status {
     execute_setget_globalvar(engine, "SETGLOBVAR", SET | STACK)
 }
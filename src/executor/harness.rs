@@ -0,0 +1,162 @@
+/*
+* Copyright (C) 2019-2024 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+use crate::{
+    executor::{engine::Engine, gas::gas_state::Gas},
+    smart_contract_info::SmartContractInfo,
+    stack::{Stack, StackItem, integer::IntegerData, savelist::SaveList},
+};
+use ever_block::{
+    Cell, ExceptionCode, HashmapE, Message, Result, Serializable, SliceData, StateInit
+};
+
+const GET_METHOD_GAS_LIMIT: i64 = 1_000_000;
+const EXTERNAL_MESSAGE_GAS_CREDIT: i64 = 10_000;
+const GAS_PRICE: i64 = 65536;
+
+/// Execution environment of contract run by harness
+#[derive(Clone, Debug)]
+pub struct HarnessParams {
+    pub capabilities: u64,
+    pub gas_limit: i64,
+    pub smc_info: SmartContractInfo,
+    pub libraries: Vec<HashmapE>,
+}
+
+impl Default for HarnessParams {
+    fn default() -> Self {
+        Self {
+            capabilities: 0,
+            gas_limit: GET_METHOD_GAS_LIMIT,
+            smc_info: SmartContractInfo::default(),
+            libraries: Vec::new(),
+        }
+    }
+}
+
+/// Result of contract execution, committed state is None if nothing was committed
+#[derive(Debug)]
+pub struct HarnessResult {
+    pub exit_code: i32,
+    pub accepted: bool,
+    pub stack: Stack,
+    pub data: Option<Cell>,
+    pub actions: Option<Cell>,
+    pub gas_used: i64,
+}
+
+fn run(
+    code: Cell,
+    data: Cell,
+    stack: Stack,
+    gas: Gas,
+    params: &HarnessParams
+) -> Result<HarnessResult> {
+    let mut smc_info = params.smc_info.clone();
+    smc_info.capabilities = params.capabilities;
+    smc_info.set_mycode(code.clone());
+    let mut ctrls = SaveList::new();
+    ctrls.put(4, &mut StackItem::cell(data))?;
+    ctrls.put(7, &mut smc_info.into_temp_data_item())?;
+    let mut engine = Engine::with_capabilities(params.capabilities).setup_with_libraries(
+        SliceData::load_cell(code)?,
+        Some(ctrls),
+        Some(stack),
+        Some(gas),
+        params.libraries.clone()
+    );
+    let outcome = engine.execute_with_outcome()?;
+    Ok(HarnessResult {
+        exit_code: outcome.exit_code,
+        accepted: engine.get_gas().get_gas_credit() == 0,
+        stack: outcome.stack,
+        data: outcome.c4_after,
+        actions: outcome.c5_actions,
+        gas_used: outcome.gas_used,
+    })
+}
+
+/// Runs code with given stack, c4 and c7 are set up from data and smc_info of params
+pub fn run_code(
+    code: Cell,
+    data: Cell,
+    stack: Stack,
+    harness_params: &HarnessParams
+) -> Result<HarnessResult> {
+    let gas = Gas::new(harness_params.gas_limit, 0, harness_params.gas_limit, GAS_PRICE);
+    run(code, data, stack, gas, harness_params)
+}
+
+/// Runs get method of contract with given parameters in the default environment
+pub fn run_get_method(
+    code: Cell,
+    data: Cell,
+    method_id: u32,
+    params: Vec<StackItem>
+) -> Result<HarnessResult> {
+    run_get_method_with_params(code, data, method_id, params, &HarnessParams::default())
+}
+
+/// Runs get method: stack is params followed by method id, c3 is code
+pub fn run_get_method_with_params(
+    code: Cell,
+    data: Cell,
+    method_id: u32,
+    params: Vec<StackItem>,
+    harness_params: &HarnessParams
+) -> Result<HarnessResult> {
+    let mut stack = Stack::with_storage(params);
+    stack.push(int!(method_id));
+    run_code(code, data, stack, harness_params)
+}
+
+/// Runs inbound external message in the default environment
+pub fn run_external_message(state_init: &StateInit, message: &Message) -> Result<HarnessResult> {
+    run_external_message_with_params(state_init, message, &HarnessParams::default())
+}
+
+/// Runs inbound external message as transaction compute phase does:
+/// stack is balance, message value (zero), message cell, message body and selector -1,
+/// gas credit is given until ACCEPT
+pub fn run_external_message_with_params(
+    state_init: &StateInit,
+    message: &Message,
+    harness_params: &HarnessParams
+) -> Result<HarnessResult> {
+    if !message.is_inbound_external() {
+        return err!(ExceptionCode::TypeCheckError, "message is not inbound external")
+    }
+    let code = match state_init.code.clone() {
+        Some(code) => code,
+        None => return err!(ExceptionCode::TypeCheckError, "state init has no code")
+    };
+    let data = state_init.data.clone().unwrap_or_default();
+    let mut params = harness_params.clone();
+    if let Some(dst) = message.dst() {
+        params.smc_info.myself = SliceData::load_builder(dst.write_to_new_cell()?)?;
+    }
+    let balance = params.smc_info.balance.grams.as_u128();
+    let mut stack = Stack::new();
+    stack
+        .push(int!(balance))
+        .push(int!(0))
+        .push(StackItem::cell(message.serialize()?))
+        .push(StackItem::Slice(message.body().unwrap_or_default()))
+        .push(int!(-1));
+    let gas = Gas::new(0, EXTERNAL_MESSAGE_GAS_CREDIT, params.gas_limit, GAS_PRICE);
+    run(code, data, stack, gas, &params)
+}
+
+#[cfg(test)]
+#[path = "../tests/test_harness.rs"]
+mod tests;
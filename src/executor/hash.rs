@@ -0,0 +1,170 @@
+/*
+* Copyright (C) 2019-2024 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+use crate::{
+    executor::{
+        engine::{Engine, storage::fetch_stack}, Mask,
+        types::{Instruction, InstructionOptions}
+    },
+    stack::{StackItem, integer::IntegerData},
+    types::Status
+};
+use blake2::Blake2b512;
+use ever_block::{sha512_digest, ExceptionCode, UInt256};
+use sha3::{Keccak256, Keccak512};
+
+/// Hash algorithm used by HASHEXT family of instructions
+#[derive(Clone, Copy, Debug)]
+pub struct HashFunction {
+    pub name: &'static str,
+    pub digest: fn(&[u8]) -> Vec<u8>,
+    /// Gas unit is charged for every bytes_per_gas hashed bytes
+    pub bytes_per_gas: usize,
+}
+
+/// Id of hash taken from the stack
+pub const HASH_ID_FROM_STACK: u8 = 255;
+
+fn sha256(data: &[u8]) -> Vec<u8> {
+    UInt256::calc_file_hash(data).as_slice().to_vec()
+}
+
+fn sha512(data: &[u8]) -> Vec<u8> {
+    sha512_digest(data).to_vec()
+}
+
+fn blake2b(data: &[u8]) -> Vec<u8> {
+    <Blake2b512 as blake2::Digest>::digest(data).to_vec()
+}
+
+fn keccak256(data: &[u8]) -> Vec<u8> {
+    <Keccak256 as sha3::Digest>::digest(data).to_vec()
+}
+
+fn keccak512(data: &[u8]) -> Vec<u8> {
+    <Keccak512 as sha3::Digest>::digest(data).to_vec()
+}
+
+/// Hash functions of the reference VM, ids of them can not be registered by embedders
+pub const BUILTIN_HASH_FUNCTIONS: [HashFunction; 5] = [
+    HashFunction { name: "SHA256", digest: sha256, bytes_per_gas: 33 },
+    HashFunction { name: "SHA512", digest: sha512, bytes_per_gas: 16 },
+    HashFunction { name: "BLAKE2B", digest: blake2b, bytes_per_gas: 19 },
+    HashFunction { name: "KECCAK256", digest: keccak256, bytes_per_gas: 11 },
+    HashFunction { name: "KECCAK512", digest: keccak512, bytes_per_gas: 6 },
+];
+
+// gas charged for every hashed stack entry
+const HASH_EXT_ENTRY_GAS: i64 = 1;
+
+const REVERSE: u8 = 0x01; // entries are hashed from the top of the stack
+const APPEND: u8 = 0x02;  // hash is stored to builder
+
+// appends first len bits of data to bit buffer
+fn append_bits(buffer: &mut Vec<u8>, bits: &mut usize, data: &[u8], len: usize) {
+    if *bits % 8 == 0 && len % 8 == 0 {
+        buffer.extend_from_slice(&data[..len / 8]);
+        *bits += len;
+        return
+    }
+    for i in 0..len {
+        if *bits % 8 == 0 {
+            buffer.push(0)
+        }
+        if (data[i / 8] >> (7 - i % 8)) & 1 != 0 {
+            *buffer.last_mut().expect("byte was pushed before") |= 0x80 >> (*bits % 8)
+        }
+        *bits += 1;
+    }
+}
+
+// (s_1 ... s_n n - h) or (b s_1 ... s_n n - b') with APPEND
+fn hash_ext(engine: &mut Engine, name: &'static str, how: u8) -> Status {
+    engine.load_instruction(Instruction::new(name).set_opts(InstructionOptions::Integer(0..256)))?;
+    let mut hash_id = engine.cmd.integer() as u8;
+    if hash_id == HASH_ID_FROM_STACK {
+        fetch_stack(engine, 1)?;
+        hash_id = engine.cmd.var(0).as_integer()?.into(0..=254)?;
+    }
+    let function = engine.hash_function(hash_id)
+        .ok_or_else(|| exception!(ExceptionCode::RangeCheckError, "unknown hash function {}", hash_id))?;
+    fetch_stack(engine, 1)?;
+    let reserved = how.bit(APPEND) as usize;
+    let count = engine.cmd.last_var()?.as_integer()?.into(0..=engine.cc.stack.depth().saturating_sub(reserved))?;
+    engine.use_gas(HASH_EXT_ENTRY_GAS * count as i64);
+    fetch_stack(engine, count + reserved)?;
+    let first = engine.cmd.var_count() - count - reserved;
+    let mut data = Vec::new();
+    let mut bits = 0;
+    for i in 0..count {
+        // vars are in order of popping, so the deepest entry is the last
+        let index = match how.bit(REVERSE) {
+            true => first + i,
+            false => first + count - 1 - i
+        };
+        match engine.cmd.var(index) {
+            StackItem::Slice(slice) => {
+                let len = slice.remaining_bits();
+                append_bits(&mut data, &mut bits, &slice.get_bytestring(0), len)
+            }
+            StackItem::Builder(builder) => {
+                append_bits(&mut data, &mut bits, builder.data(), builder.length_in_bits())
+            }
+            item => return err!(ExceptionCode::TypeCheckError, "item {} is not a slice or builder", item)
+        }
+    }
+    if bits % 8 != 0 {
+        return err!(ExceptionCode::CellUnderflow, "hashed data has {} bits", bits)
+    }
+    engine.try_use_gas((data.len() / function.bytes_per_gas) as i64)?;
+    let hash = (function.digest)(&data);
+    if how.bit(APPEND) {
+        let index = engine.cmd.var_count() - 1;
+        let builder = engine.cmd.var(index).as_builder()?;
+        if !engine.builder_limits().check_space(builder, hash.len() * 8, 0) {
+            return err!(ExceptionCode::CellOverflow)
+        }
+        let mut builder = engine.cmd.var_mut(index).as_builder_mut()?;
+        builder.append_raw(&hash, hash.len() * 8)?;
+        engine.cc.stack.push_builder(builder);
+    } else if hash.len() <= 32 {
+        engine.cc.stack.push(StackItem::integer(IntegerData::from_unsigned_bytes_be(hash)));
+    } else {
+        let parts = hash.chunks(32)
+            .map(|part| StackItem::integer(IntegerData::from_unsigned_bytes_be(part)))
+            .collect::<Vec<_>>();
+        engine.use_gas(engine.gas_prices().tuple(parts.len()));
+        engine.cc.stack.push_tuple(parts);
+    }
+    Ok(())
+}
+
+pub(super) fn execute_hashext(engine: &mut Engine) -> Status {
+    hash_ext(engine, "HASHEXT", 0)
+}
+
+pub(super) fn execute_hashextr(engine: &mut Engine) -> Status {
+    hash_ext(engine, "HASHEXTR", REVERSE)
+}
+
+pub(super) fn execute_hashexta(engine: &mut Engine) -> Status {
+    hash_ext(engine, "HASHEXTA", APPEND)
+}
+
+pub(super) fn execute_hashextar(engine: &mut Engine) -> Status {
+    hash_ext(engine, "HASHEXTAR", APPEND | REVERSE)
+}
+
+#[cfg(test)]
+#[path = "../tests/test_hash.rs"]
+mod tests;
@@ -0,0 +1,29 @@
+/*
+* Copyright (C) 2019-2024 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+use crate::{
+    executor::{engine::Engine, types::{Instruction, InstructionOptions}},
+    types::Status
+};
+
+/// HOSTCALL nn (x_1 ... x_n - y_1 ... y_m)
+/// calls host function nn set by Engine::set_host_interface, charging gas reported by host.
+/// The handler is registered by Engine::set_host_interface, so without host the opcode is unknown.
+pub(in crate::executor) fn execute_hostcall(engine: &mut Engine) -> Status {
+    engine.load_instruction(
+        Instruction::new("HOSTCALL").set_opts(InstructionOptions::Integer(0..256))
+    )?;
+    let id = engine.cmd.integer() as u8;
+    let gas = engine.call_host(id)?;
+    engine.try_use_gas(gas)
+}
@@ -0,0 +1,323 @@
+/*
+* Copyright (C) 2019-2024 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+use crate::executor::{engine::Engine, types::InstructionOptions};
+use ever_block::{BuilderData, Cell, Result, SliceData, UInt256};
+use std::{collections::HashSet, ops::RangeInclusive};
+
+/*
+Instruction table is built from the dispatch table of code page 0: every handler is
+decoded once on a zero filled code without execution. Mnemonic and operands are taken
+from the decoded instruction, required capabilities are the ones gating its handler.
+Instructions are decoded by a single engine with all handlers enabled, so neither the
+table nor code validation build an engine or a dispatch table per instruction.
+*/
+
+const PROBE_OPERAND_BYTES: usize = 32;
+const PROBE_REFERENCES: usize = 4;
+
+/// Kind of immediate operands encoded in the instruction
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Operands {
+    None,
+    ArgumentConstraints,
+    ArgumentAndReturnConstraints,
+    BigInteger,
+    Bytestring,
+    Bitstring,
+    ControlRegister,
+    Dictionary,
+    DivisionMode,
+    Integer,
+    Length,
+    LengthAndIndex,
+    Pargs,
+    Rargs,
+    StackRegister,
+    StackRegisterPair,
+    StackRegisterTrio,
+}
+
+impl From<Option<&InstructionOptions>> for Operands {
+    fn from(opts: Option<&InstructionOptions>) -> Self {
+        match opts {
+            None => Operands::None,
+            Some(InstructionOptions::ArgumentConstraints) => Operands::ArgumentConstraints,
+            Some(InstructionOptions::ArgumentAndReturnConstraints) => Operands::ArgumentAndReturnConstraints,
+            Some(InstructionOptions::BigInteger) => Operands::BigInteger,
+            Some(InstructionOptions::Bytestring(..)) => Operands::Bytestring,
+            Some(InstructionOptions::Bitstring(..)) => Operands::Bitstring,
+            Some(InstructionOptions::ControlRegister) => Operands::ControlRegister,
+            Some(InstructionOptions::Dictionary(..)) => Operands::Dictionary,
+            Some(InstructionOptions::DivisionMode) => Operands::DivisionMode,
+            Some(InstructionOptions::Integer(_)) => Operands::Integer,
+            Some(InstructionOptions::Length(_)) |
+            Some(InstructionOptions::LengthMinusOne(_)) => Operands::Length,
+            Some(InstructionOptions::LengthAndIndex) |
+            Some(InstructionOptions::LengthMinusOneAndIndexMinusOne) |
+            Some(InstructionOptions::LengthMinusTwoAndIndex) => Operands::LengthAndIndex,
+            Some(InstructionOptions::Pargs(_)) => Operands::Pargs,
+            Some(InstructionOptions::Rargs(_)) => Operands::Rargs,
+            Some(InstructionOptions::StackRegister(_)) => Operands::StackRegister,
+            Some(InstructionOptions::StackRegisterPair(_)) => Operands::StackRegisterPair,
+            Some(InstructionOptions::StackRegisterTrio(_)) => Operands::StackRegisterTrio,
+        }
+    }
+}
+
+/// Description of instruction or group of instructions differing in the last opcode byte
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct InstructionInfo {
+    /// Mnemonic as it is shown in trace
+    pub mnemonic: String,
+    /// Opcode bytes except the last one
+    pub prefix: Vec<u8>,
+    /// Values of the last opcode byte
+    pub last_byte: RangeInclusive<u8>,
+    /// Immediate operands following or embedded into the opcode
+    pub operands: Operands,
+    /// Gas charged for instruction decoding without stack dependent part
+    pub base_gas: i64,
+    /// Mask of GlobalCapabilities required to decode the instruction
+    pub capabilities: u64,
+    /// Mask of VmCapabilities required to decode the instruction
+    pub vm_capabilities: u64,
+}
+
+impl InstructionInfo {
+    /// Checks if the instruction is available with given global and VM capabilities
+    pub fn is_supported(&self, capabilities: u64, vm_capabilities: u64) -> bool {
+        capabilities & self.capabilities == self.capabilities
+            && vm_capabilities & self.vm_capabilities == self.vm_capabilities
+    }
+    /// Full opcode of the first instruction of the group
+    pub fn opcode(&self) -> Vec<u8> {
+        let mut opcode = self.prefix.clone();
+        opcode.push(*self.last_byte.start());
+        opcode
+    }
+}
+
+struct Decoded {
+    mnemonic: String,
+    operands: Operands,
+    gas: i64,
+    capabilities: (u64, u64),
+    inline_code: Option<SliceData>,
+    rest: SliceData,
+}
+
+fn decode(decoder: &mut Engine, code: SliceData) -> Option<Decoded> {
+    *decoder.cc.code_mut() = code.clone();
+    let gas = decoder.gas_used();
+    let opcode_bits = decoder.decode_instruction().ok()??;
+    let capabilities = Engine::opcode_capabilities(&code.get_bytestring(0)[..opcode_bits / 8])?;
+    let proto = &decoder.cmd.proto;
+    let inline_code = match proto.name {
+        "PUSHCONT" => decoder.cmd.slice_raw().cloned(),
+        _ => None
+    };
+    Some(Decoded {
+        mnemonic: format!("{}{}", proto.name_prefix.unwrap_or_default(), proto.name),
+        operands: Operands::from(proto.opts.as_ref()),
+        gas: decoder.gas_used() - gas,
+        capabilities,
+        inline_code,
+        rest: decoder.cc.code().clone(),
+    })
+}
+
+fn probe(decoder: &mut Engine, opcode: &[u8]) -> Result<Option<Decoded>> {
+    let mut builder = BuilderData::with_raw(opcode.to_vec(), opcode.len() * 8)?;
+    builder.append_raw(&[0; PROBE_OPERAND_BYTES], PROBE_OPERAND_BYTES * 8)?;
+    for _ in 0..PROBE_REFERENCES {
+        builder.checked_append_reference(Cell::default())?;
+    }
+    Ok(decode(decoder, SliceData::load_builder(builder)?))
+}
+
+fn build_instructions() -> Result<Vec<InstructionInfo>> {
+    let mut instructions: Vec<InstructionInfo> = Vec::new();
+    let mut last_handler = None;
+    let mut decoder = Engine::decoder();
+    for (opcode, handler) in Engine::code_page_0_opcodes() {
+        let probe = match probe(&mut decoder, &opcode)? {
+            Some(probe) => probe,
+            // instruction is not available in standalone VM, e.g. HOSTCALL without host interface
+            None => continue
+        };
+        let (capabilities, vm_capabilities) = probe.capabilities;
+        let (last_byte, prefix) = opcode.split_last().expect("opcode is not empty");
+        if let Some(info) = instructions.last_mut() {
+            if last_handler == Some(handler as usize)
+                && info.prefix == prefix
+                && *info.last_byte.end() as usize + 1 == *last_byte as usize
+                && info.mnemonic == probe.mnemonic
+                && info.capabilities == capabilities
+                && info.vm_capabilities == vm_capabilities {
+                info.last_byte = *info.last_byte.start()..=*last_byte;
+                continue
+            }
+        }
+        last_handler = Some(handler as usize);
+        instructions.push(InstructionInfo {
+            mnemonic: probe.mnemonic,
+            prefix: prefix.to_vec(),
+            last_byte: *last_byte..=*last_byte,
+            operands: probe.operands,
+            base_gas: probe.gas,
+            capabilities,
+            vm_capabilities,
+        })
+    }
+    Ok(instructions)
+}
+
+lazy_static::lazy_static! {
+    static ref INSTRUCTIONS: Vec<InstructionInfo> = build_instructions()
+        .expect("instruction table must be built");
+}
+
+/// Table of all instructions supported by the VM
+pub fn instructions() -> &'static [InstructionInfo] {
+    &INSTRUCTIONS
+}
+
+/// Finds instruction by full opcode
+pub fn find_by_opcode(opcode: &[u8]) -> Option<&'static InstructionInfo> {
+    let (last_byte, prefix) = opcode.split_last()?;
+    instructions().iter().find(|info| info.prefix == prefix && info.last_byte.contains(last_byte))
+}
+
+// instructions taking references from code after decoding: mnemonic, count, references are code
+const REFERENCE_OPERANDS: &[(&str, usize, bool)] = &[
+    ("PUSHREF", 1, false),
+    ("PUSHREFSLICE", 1, false),
+    ("PUSHREFCONT", 1, true),
+    ("STREFCONST", 1, false),
+    ("STREF2CONST", 2, false),
+    ("CALLREF", 1, true),
+    ("JMPREF", 1, true),
+    ("JMPREFDATA", 1, true),
+    ("IFREF", 1, true),
+    ("IFNOTREF", 1, true),
+    ("IFJMPREF", 1, true),
+    ("IFNOTJMPREF", 1, true),
+    ("IFREFELSE", 1, true),
+    ("IFELSEREF", 1, true),
+    ("IFREFELSEREF", 2, true),
+    ("IFBITJMPREF", 1, true),
+    ("IFNBITJMPREF", 1, true),
+];
+
+/// Problem found by validate_code
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum CodeIssue {
+    /// Instruction requires global or VM capabilities absent in the given sets
+    MissingCapabilities { mnemonic: String, capabilities: u64, vm_capabilities: u64 },
+    /// Instruction can not be decoded, the rest of the code cell is not checked
+    InvalidOpcode,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CodeDiagnostic {
+    /// Representation hash of the cell containing the instruction
+    pub cell_hash: UInt256,
+    /// Bit offset of the instruction in the cell
+    pub offset: usize,
+    pub issue: CodeIssue,
+}
+
+struct Validator {
+    decoder: Engine,
+    capabilities: u64,
+    vm_capabilities: u64,
+    visited: HashSet<UInt256>,
+    diagnostics: Vec<CodeDiagnostic>,
+}
+
+impl Validator {
+    fn validate_cell(&mut self, cell: Cell) -> Result<()> {
+        if self.visited.insert(cell.repr_hash()) {
+            self.validate_slice(SliceData::load_cell(cell)?)?;
+        }
+        Ok(())
+    }
+
+    fn validate_slice(&mut self, mut code: SliceData) -> Result<()> {
+        loop {
+            if code.remaining_bits() == 0 {
+                // implicit jump to the first reference
+                if code.remaining_references() != 0 {
+                    self.validate_cell(code.reference(0)?)?;
+                }
+                return Ok(())
+            }
+            let cell_hash = code.cell_opt().map(|cell| cell.repr_hash()).unwrap_or_default();
+            let offset = code.pos();
+            let decoded = match decode(&mut self.decoder, code.clone()) {
+                Some(decoded) => decoded,
+                None => {
+                    self.diagnostics.push(CodeDiagnostic { cell_hash, offset, issue: CodeIssue::InvalidOpcode });
+                    return Ok(())
+                }
+            };
+            let capabilities = decoded.capabilities.0 & !self.capabilities;
+            let vm_capabilities = decoded.capabilities.1 & !self.vm_capabilities;
+            if capabilities != 0 || vm_capabilities != 0 {
+                self.diagnostics.push(CodeDiagnostic {
+                    cell_hash,
+                    offset,
+                    issue: CodeIssue::MissingCapabilities {
+                        mnemonic: decoded.mnemonic.clone(),
+                        capabilities,
+                        vm_capabilities,
+                    }
+                });
+            }
+            if let Some(inline_code) = decoded.inline_code {
+                self.validate_slice(inline_code)?;
+            }
+            code = decoded.rest;
+            if let Some((_, count, is_code)) = REFERENCE_OPERANDS.iter()
+                .find(|(mnemonic, _, _)| *mnemonic == decoded.mnemonic) {
+                let count = (*count).min(code.remaining_references());
+                if *is_code {
+                    for index in 0..count {
+                        self.validate_cell(code.reference(index)?)?;
+                    }
+                }
+                code.shrink_references(count..);
+            }
+        }
+    }
+}
+
+/// Checks that all instructions of the code are supported with given global and VM capabilities.
+/// Code reached by implicit jumps, inline continuations and references used as
+/// continuations is checked as well, dictionaries with methods are not traversed
+pub fn validate_code(code: &SliceData, capabilities: u64, vm_capabilities: u64) -> Result<Vec<CodeDiagnostic>> {
+    let mut validator = Validator {
+        decoder: Engine::decoder(),
+        capabilities,
+        vm_capabilities,
+        visited: HashSet::new(),
+        diagnostics: Vec::new(),
+    };
+    validator.validate_slice(code.clone())?;
+    Ok(validator.diagnostics)
+}
+
+#[cfg(test)]
+#[path = "../tests/test_isa.rs"]
+mod tests;
@@ -902,3 +902,57 @@ where
 {
     binary::<T>(engine, "XOR", |y, x| x.xor::<T>(y))
 }
+
+// Fixed-point arithmetic *****************************************************
+
+// (x y - x*y/2^96), rounded to nearest
+pub(super) fn execute_fixmul96<T>(engine: &mut Engine) -> Status
+where
+    T: OperationBehavior
+{
+    binary::<T>(engine, "FIXMUL96", |y, x| x.mul_shr_round::<T>(y, 96))
+}
+
+// (x y - x*2^96/y), rounded to nearest
+pub(super) fn execute_fixdiv96<T>(engine: &mut Engine) -> Status
+where
+    T: OperationBehavior
+{
+    binary::<T>(engine, "FIXDIV96", |y, x| x.shl_div_round::<T>(y, 96))
+}
+
+// (x y - x*y/2^128), rounded to nearest
+pub(super) fn execute_fixmul128<T>(engine: &mut Engine) -> Status
+where
+    T: OperationBehavior
+{
+    binary::<T>(engine, "FIXMUL128", |y, x| x.mul_shr_round::<T>(y, 128))
+}
+
+// (x y - x*2^128/y), rounded to nearest
+pub(super) fn execute_fixdiv128<T>(engine: &mut Engine) -> Status
+where
+    T: OperationBehavior
+{
+    binary::<T>(engine, "FIXDIV128", |y, x| x.shl_div_round::<T>(y, 128))
+}
+
+// (x - floor(sqrt(x))), overflows for negative x
+pub(super) fn execute_isqrt<T>(engine: &mut Engine) -> Status
+where
+    T: OperationBehavior
+{
+    unary::<T>(engine, "ISQRT", |x| x.sqrt::<T>())
+}
+
+// (x - floor(log2(x))), overflows for non positive x
+pub(super) fn execute_ilog2<T>(engine: &mut Engine) -> Status
+where
+    T: OperationBehavior
+{
+    unary::<T>(engine, "ILOG2", |x| x.log2::<T>())
+}
+
+#[cfg(test)]
+#[path = "../tests/test_math.rs"]
+mod tests;
@@ -21,7 +21,6 @@ mod serialization;
 mod deserialization;
 mod continuation;
 mod crypto;
-mod currency;
 mod dictionary;
 mod exceptions;
 mod globals;
@@ -30,16 +29,29 @@ mod slice_comparison;
 mod stack;
 mod tuple;
 mod types;
+pub mod actions;
+pub mod batch;
 pub mod gas;
+pub mod harness;
+pub mod hash;
+pub mod isa;
+pub mod proof;
+pub mod replay;
+pub mod vm_log;
 mod dump;
 mod null;
-mod config;
+pub mod config;
+pub mod currency;
 mod rand;
 mod bls;
+mod host;
+mod runvm;
 #[cfg(feature = "gosh")]
 mod diff;
 
 pub use engine::*;
+pub use dictionary::pfx;
+pub use blockchain::compute_fwd_fee;
 use ever_block::{BuilderData, Cell, IBitstring, Result};
 
 #[cfg(test)]
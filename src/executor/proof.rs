@@ -0,0 +1,163 @@
+/*
+* Copyright (C) 2019-2024 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+use crate::{
+    executor::{
+        engine::{Engine, storage::fetch_stack}, types::Instruction
+    },
+    stack::{StackItem, integer::serialization::UnsignedIntegerBigEndianEncoding},
+    types::Status
+};
+use ever_block::{Cell, MerkleProof, MerkleUpdate, Result, Serializable, UInt256};
+use std::collections::{HashMap, HashSet};
+
+/// Cells of a tree to be kept in Merkle proof
+#[derive(Debug, Default)]
+pub struct ProofCells {
+    /// Hashes of root, target cells and cells on paths from root to them
+    pub included: HashSet<UInt256>,
+    /// Number of cells in proof: included cells, pruned branches and proof cell itself
+    pub proof_size: usize,
+}
+
+fn mark_paths(
+    cell: &Cell,
+    targets: &HashSet<UInt256>,
+    marked: &mut HashMap<UInt256, bool>,
+    proof: &mut ProofCells,
+    on_visit: &mut impl FnMut(&Cell) -> Result<()>,
+) -> Result<bool> {
+    let hash = cell.repr_hash();
+    if let Some(found) = marked.get(&hash) {
+        return Ok(*found)
+    }
+    on_visit(cell)?;
+    let mut found = targets.contains(&hash);
+    let mut pruned = 0;
+    for i in 0..cell.references_count() {
+        if mark_paths(&cell.reference(i)?, targets, marked, proof, on_visit)? {
+            found = true;
+        } else {
+            pruned += 1;
+        }
+    }
+    if found {
+        proof.included.insert(hash.clone());
+        proof.proof_size += 1 + pruned;
+    }
+    marked.insert(hash, found);
+    Ok(found)
+}
+
+/// Collects cells needed to prove target cells of the tree, root is always included.
+/// on_visit is called once for every distinct cell of the tree
+pub fn collect_proof_cells(
+    root: &Cell,
+    targets: &HashSet<UInt256>,
+    mut on_visit: impl FnMut(&Cell) -> Result<()>,
+) -> Result<ProofCells> {
+    let mut proof = ProofCells::default();
+    let mut marked = HashMap::new();
+    if !mark_paths(root, targets, &mut marked, &mut proof, &mut on_visit)? {
+        proof.included.insert(root.repr_hash());
+        proof.proof_size += root.references_count();
+    }
+    proof.proof_size += 1;
+    Ok(proof)
+}
+
+/// Builds Merkle proof cell of the tree keeping given cells, other branches are pruned
+pub fn create_proof_of_cells(root: &Cell, included: &HashSet<UInt256>) -> Result<Cell> {
+    MerkleProof::create(root, |hash| included.contains(hash))?.serialize()
+}
+
+/// Builds Merkle proof cell of the tree with target cells and paths to them,
+/// e.g. proof of account state for cells loaded by contract during execution
+pub fn create_proof(root: &Cell, targets: &HashSet<UInt256>) -> Result<Cell> {
+    let proof = collect_proof_cells(root, targets, |_| Ok(()))?;
+    create_proof_of_cells(root, &proof.included)
+}
+
+/// Builds Merkle update cell transforming old tree to new one
+pub fn create_update(old: &Cell, new: &Cell) -> Result<Cell> {
+    MerkleUpdate::create(old, new)?.serialize()
+}
+
+fn visit_distinct_cells(
+    cell: &Cell,
+    visited: &mut HashSet<UInt256>,
+    on_visit: &mut impl FnMut() -> Result<()>,
+) -> Result<()> {
+    if visited.insert(cell.repr_hash()) {
+        on_visit()?;
+        for i in 0..cell.references_count() {
+            visit_distinct_cells(&cell.reference(i)?, visited, on_visit)?;
+        }
+    }
+    Ok(())
+}
+
+/// CREATEPROOF (c t - p)
+/// builds Merkle proof cell p of tree c keeping cells with hashes listed in tuple t
+/// and cells on paths from c to them, other branches are replaced by pruned branch cells.
+/// Gas is charged for loading of every distinct cell of c and creation of every cell of p.
+/// CTOS opens p to the root of c only under CapResolveMerkleCell, otherwise it throws
+/// cell underflow as for any exotic cell; XCTOS loads p itself.
+pub(super) fn execute_createproof(engine: &mut Engine) -> Status {
+    engine.load_instruction(Instruction::new("CREATEPROOF"))?;
+    fetch_stack(engine, 2)?;
+    let count = engine.cmd.var(0).as_tuple()?.len();
+    engine.try_use_gas(engine.gas_prices().tuple(count))?;
+    let mut targets = HashSet::with_capacity(count);
+    for item in engine.cmd.var(0).as_tuple()? {
+        let hash = item.as_integer()?.as_builder::<UnsignedIntegerBigEndianEncoding>(256)?;
+        targets.insert(UInt256::from_slice(hash.data()));
+    }
+    let root = engine.cmd.var(1).as_cell()?.clone();
+    let load_price = engine.gas_prices().load_cell(true);
+    let proof = collect_proof_cells(&root, &targets, |_| engine.try_use_gas(load_price))?;
+    engine.try_use_gas(engine.gas_prices().cell_create * proof.proof_size as i64)?;
+    let proof = create_proof_of_cells(&root, &proof.included)?;
+    engine.cc.stack.push(StackItem::Cell(proof));
+    Ok(())
+}
+
+/// CREATEUPDATE (c1 c2 - u)
+/// builds Merkle update cell u transforming tree c1 into tree c2.
+/// Gas is charged for loading of every distinct cell of c1 and c2 and creation of every cell of u.
+/// CTOS opens u to the root of c2 only under CapResolveMerkleCell.
+pub(super) fn execute_createupdate(engine: &mut Engine) -> Status {
+    engine.load_instruction(Instruction::new("CREATEUPDATE"))?;
+    fetch_stack(engine, 2)?;
+    let new = engine.cmd.var(0).as_cell()?.clone();
+    let old = engine.cmd.var(1).as_cell()?.clone();
+    let load_price = engine.gas_prices().load_cell(true);
+    let mut visited = HashSet::new();
+    visit_distinct_cells(&old, &mut visited, &mut || engine.try_use_gas(load_price))?;
+    visit_distinct_cells(&new, &mut visited, &mut || engine.try_use_gas(load_price))?;
+    // every part of update is not bigger than its tree, so the update is not built
+    // if gas is not enough for the worst case
+    let create_price = engine.gas_prices().cell_create;
+    let max_price = create_price.saturating_mul(2 * visited.len() as i64 + 1);
+    if max_price > engine.get_gas().get_gas_available() {
+        engine.try_use_gas(max_price)?;
+    }
+    let update = create_update(&old, &new)?;
+    visit_distinct_cells(&update, &mut HashSet::new(), &mut || engine.try_use_gas(create_price))?;
+    engine.cc.stack.push(StackItem::Cell(update));
+    Ok(())
+}
+
+#[cfg(test)]
+#[path = "../tests/test_proof.rs"]
+mod tests;
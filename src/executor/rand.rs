@@ -12,7 +12,7 @@
 */
 
 use crate::{
-    executor::{engine::{Engine, storage::fetch_stack}, types::Instruction},
+    executor::{engine::{Engine, VmCapabilities, storage::fetch_stack}, types::Instruction},
     stack::{
         StackItem,
         integer::{
@@ -24,46 +24,70 @@ use crate::{
     },
     types::Status
 };
-use ever_block::{Sha256, sha512_digest};
+use ever_block::{ExceptionCode, Result, Sha256, sha512_digest};
 
-// (x - )
-pub(crate) fn execute_addrand(engine: &mut Engine) -> Status {
-    engine.load_instruction(Instruction::new("ADDRAND"))?;
-    fetch_stack(engine, 1)?;
+// seed and mixed values are 256-bit unsigned integers
+fn check_seed(engine: &Engine, value: &IntegerData) -> Status {
+    if engine.has_vm_capability(VmCapabilities::CapStrictRandSeed) && !value.ufits_in(256)? {
+        return err!(ExceptionCode::RangeCheckError, "random seed {} is not 256-bit unsigned", value)
+    }
+    Ok(())
+}
+
+// seed := sha256(seed . x)
+fn mix_seed(engine: &mut Engine, x: &[u8]) -> Status {
     let mut hasher = Sha256::new();
     hasher.update(engine.rand()?
         .as_builder::<UnsignedIntegerBigEndianEncoding>(256)?.data());
-    hasher.update(engine.cmd.var(0).as_integer()?
-        .as_builder::<UnsignedIntegerBigEndianEncoding>(256)?.data());
+    hasher.update(x);
     let sha256 = hasher.finalize();
     engine.set_rand(UnsignedIntegerBigEndianEncoding::new(256)
-        .deserialize(&sha256))?;
-    Ok(())
+        .deserialize(&sha256))
+}
+
+// seed' . x := sha512(seed), returns x; external entropy is mixed in first if it is plugged
+fn next_random(engine: &mut Engine) -> Result<[u8; 32]> {
+    if let Some(entropy) = engine.external_entropy() {
+        mix_seed(engine, &entropy)?;
+    }
+    let sha512 = sha512_digest(engine.rand()?
+        .as_builder::<UnsignedIntegerBigEndianEncoding>(256)?.data());
+    engine.set_rand(UnsignedIntegerBigEndianEncoding::new(256)
+        .deserialize(&sha512[..32]))?;
+    let mut x = [0; 32];
+    x.copy_from_slice(&sha512[32..]);
+    Ok(x)
+}
+
+// (x - )
+pub(crate) fn execute_addrand(engine: &mut Engine) -> Status {
+    engine.load_instruction(Instruction::new("ADDRAND"))?;
+    fetch_stack(engine, 1)?;
+    let x = engine.cmd.var(0).as_integer()?.clone();
+    check_seed(engine, &x)?;
+    mix_seed(engine, x.as_builder::<UnsignedIntegerBigEndianEncoding>(256)?.data())
 }
 
 // (y - z)
 pub(crate) fn execute_rand(engine: &mut Engine) -> Status {
     engine.load_instruction(Instruction::new("RAND"))?;
     fetch_stack(engine, 1)?;
-    let sha512 = sha512_digest(engine.rand()?
-        .as_builder::<UnsignedIntegerBigEndianEncoding>(256)?.data());
-    let value = IntegerData::from_unsigned_bytes_be(&sha512[32..]);
-    let rand = value.mul_shr256::<Signaling>(engine.cmd.var(0).as_integer()?)?;
+    let y = engine.cmd.var(0).as_integer()?.clone();
+    if y.is_nan() {
+        return err!(ExceptionCode::IntegerOverflow)
+    }
+    let value = IntegerData::from_unsigned_bytes_be(next_random(engine)?);
+    let rand = value.mul_shr256::<Signaling>(&y)?;
     engine.cc.stack.push(StackItem::integer(rand));
-    engine.set_rand(UnsignedIntegerBigEndianEncoding::new(256)
-        .deserialize(&sha512[..32]))?;
     Ok(())
 }
 
 // ( - x)
 pub(crate) fn execute_randu256(engine: &mut Engine) -> Status {
     engine.load_instruction(Instruction::new("RANDU256"))?;
-    let sha512 = sha512_digest(engine.rand()?
-        .as_builder::<UnsignedIntegerBigEndianEncoding>(256)?.data());
-    engine.set_rand(UnsignedIntegerBigEndianEncoding::new(256)
-        .deserialize(&sha512[..32]))?;
+    let x = next_random(engine)?;
     engine.cc.stack.push(StackItem::int(UnsignedIntegerBigEndianEncoding::new(256)
-        .deserialize(&sha512[32..])));
+        .deserialize(&x)));
     Ok(())
 }
 
@@ -72,6 +96,7 @@ pub(crate) fn execute_setrand(engine: &mut Engine) -> Status {
     engine.load_instruction(Instruction::new("SETRAND"))?;
     fetch_stack(engine, 1)?;
     let rand = engine.cmd.var(0).as_integer()?.clone();
+    check_seed(engine, &rand)?;
     engine.set_rand(rand)?;
     Ok(())
 }
@@ -0,0 +1,255 @@
+/*
+* Copyright (C) 2019-2024 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+use crate::{
+    executor::{engine::Engine, gas::gas_state::{Gas, GasPrices}},
+    stack::{savelist::SaveList, Stack, StackItem},
+};
+use ever_block::{
+    fail, read_single_root_boc, write_boc, BuilderData, Cell, HashmapE, HashmapType, IBitstring,
+    Result, SliceData,
+};
+use std::path::Path;
+
+/*
+Replay bundle is a single cell with everything needed to reproduce execution:
+    magic:uint32 capabilities:uint64 vm_capabilities:uint64 unlimited:Bool
+    gas_limit:int64 gas_credit:int64 gas_limit_max:int64 gas_price:int64
+    seed:(Maybe bits256)
+    ^[code data c7 libraries gas_prices] ^stack
+The first reference is a tuple serialized as stack item, libraries are a tuple of dictionary roots,
+gas prices are a tuple of integers in order of fields of GasPrices.
+Stack is serialized as stack of continuation, see Stack::serialize()
+*/
+const REPLAY_MAGIC: usize = 0x72706c32; // "rpl2"
+
+/// Self-contained input of execution, see Engine::replay_bundle() and Engine::replay()
+#[derive(Clone, Debug)]
+pub struct ReplayBundle {
+    pub code: SliceData,
+    pub data: Cell,
+    pub c7: StackItem,
+    pub stack: Stack,
+    pub capabilities: u64,
+    pub vm_capabilities: u64,
+    pub gas: Gas,
+    pub gas_prices: GasPrices,
+    /// Random seed set to c7 before execution, None keeps seed of c7 as is
+    pub seed: Option<[u8; 32]>,
+    pub libraries: Vec<HashmapE>,
+}
+
+fn gas_prices_item(prices: &GasPrices) -> StackItem {
+    StackItem::tuple(vec![
+        StackItem::int(prices.instruction_base),
+        StackItem::int(prices.cell_load),
+        StackItem::int(prices.cell_reload),
+        StackItem::int(prices.exotic_cell_load),
+        StackItem::int(prices.big_cell_byte),
+        StackItem::int(prices.cell_create),
+        StackItem::int(prices.library_lookup),
+        StackItem::int(prices.exception),
+        StackItem::int(prices.tuple_entry),
+        StackItem::int(prices.implicit_jmpref),
+        StackItem::int(prices.implicit_ret),
+        StackItem::int(prices.free_stack_depth as u64),
+        StackItem::int(prices.stack_entry),
+        StackItem::int(prices.check_signature_threshold as u64),
+        StackItem::int(prices.check_signature),
+        StackItem::int(prices.free_cell_depth as u32),
+        StackItem::int(prices.cell_depth),
+        StackItem::int(prices.proof_depth),
+    ])
+}
+
+fn read_gas_prices(item: &StackItem) -> Result<GasPrices> {
+    let fields = item.as_tuple()?;
+    if fields.len() != 18 {
+        fail!("gas prices of replay bundle have {} fields instead of 18", fields.len())
+    }
+    let field = |index: usize| fields[index].as_integer()?.into(i64::MIN..=i64::MAX);
+    Ok(GasPrices {
+        instruction_base: field(0)?,
+        cell_load: field(1)?,
+        cell_reload: field(2)?,
+        exotic_cell_load: field(3)?,
+        big_cell_byte: field(4)?,
+        cell_create: field(5)?,
+        library_lookup: field(6)?,
+        exception: field(7)?,
+        tuple_entry: field(8)?,
+        implicit_jmpref: field(9)?,
+        implicit_ret: field(10)?,
+        free_stack_depth: fields[11].as_small_integer()?,
+        stack_entry: field(12)?,
+        check_signature_threshold: fields[13].as_small_integer()?,
+        check_signature: field(14)?,
+        free_cell_depth: fields[15].as_integer()?.into(0..=u16::MAX)?,
+        cell_depth: field(16)?,
+        proof_depth: field(17)?,
+    })
+}
+
+impl ReplayBundle {
+    pub fn serialize(&self) -> Result<Cell> {
+        let libraries = self.libraries.iter()
+            .map(|library| library.data().map_or(StackItem::None, |root| StackItem::cell(root.clone())))
+            .collect();
+        let payload = StackItem::tuple(vec![
+            StackItem::slice(self.code.clone()),
+            StackItem::cell(self.data.clone()),
+            self.c7.clone(),
+            StackItem::tuple(libraries),
+            gas_prices_item(&self.gas_prices),
+        ]);
+        let mut builder = BuilderData::new();
+        builder.append_bits(REPLAY_MAGIC, 32)?;
+        builder.append_u64(self.capabilities)?;
+        builder.append_u64(self.vm_capabilities)?;
+        builder.append_bit_bool(self.gas.is_unlimited())?;
+        builder.append_i64(self.gas.get_gas_limit())?;
+        builder.append_i64(self.gas.get_gas_credit())?;
+        builder.append_i64(self.gas.get_gas_limit_max())?;
+        builder.append_i64(self.gas.get_gas_price())?;
+        match &self.seed {
+            Some(seed) => {
+                builder.append_bit_one()?;
+                builder.append_raw(seed, 256)?;
+            }
+            None => {
+                builder.append_bit_zero()?;
+            }
+        }
+        builder.checked_append_reference(payload.serialize(&mut 0)?.into_cell()?)?;
+        builder.checked_append_reference(self.stack.serialize(&mut 0)?.into_cell()?)?;
+        builder.into_cell()
+    }
+
+    pub fn deserialize(cell: Cell) -> Result<Self> {
+        let mut slice = SliceData::load_cell(cell)?;
+        if slice.get_next_int(32)? as usize != REPLAY_MAGIC {
+            fail!("cell is not replay bundle")
+        }
+        let capabilities = slice.get_next_u64()?;
+        let vm_capabilities = slice.get_next_u64()?;
+        let unlimited = slice.get_next_bit()?;
+        let gas_limit = slice.get_next_i64()?;
+        let gas_credit = slice.get_next_i64()?;
+        let gas_limit_max = slice.get_next_i64()?;
+        let gas_price = slice.get_next_i64()?;
+        let seed = match slice.get_next_bit()? {
+            true => {
+                let mut seed = [0; 32];
+                seed.copy_from_slice(&slice.get_next_bytes(32)?);
+                Some(seed)
+            }
+            false => None
+        };
+        let payload = StackItem::deserialize(SliceData::load_cell(slice.checked_drain_reference()?)?, &mut 0)?;
+        let payload = payload.as_tuple()?;
+        if payload.len() != 5 {
+            fail!("replay bundle has {} items instead of 5", payload.len())
+        }
+        let stack = Stack::deserialize(&mut SliceData::load_cell(slice.checked_drain_reference()?)?, &mut 0)?;
+        let mut libraries = Vec::new();
+        for library in payload[3].as_tuple()? {
+            let root = match library.is_null() {
+                true => None,
+                false => Some(library.as_cell()?.clone())
+            };
+            libraries.push(HashmapE::with_hashmap(256, root));
+        }
+        let mut gas = Gas::new(gas_limit, gas_credit, gas_limit_max, gas_price);
+        gas.set_unlimited(unlimited);
+        Ok(Self {
+            code: payload[0].as_slice()?.clone(),
+            data: payload[1].as_cell()?.clone(),
+            c7: payload[2].clone(),
+            stack,
+            capabilities,
+            vm_capabilities,
+            gas,
+            gas_prices: read_gas_prices(&payload[4])?,
+            seed,
+            libraries,
+        })
+    }
+
+    pub fn write_to_file(&self, path: impl AsRef<Path>) -> Result<()> {
+        std::fs::write(path, write_boc(&self.serialize()?)?)?;
+        Ok(())
+    }
+
+    pub fn read_from_file(path: impl AsRef<Path>) -> Result<Self> {
+        Self::deserialize(read_single_root_boc(std::fs::read(path)?)?)
+    }
+}
+
+impl Engine {
+    /// Captures current input of execution: code of cc, stack, c4, c7, capabilities, gas and its prices.
+    /// It is to be called after setup and before execution
+    pub fn replay_bundle(&self) -> Result<ReplayBundle> {
+        let gas = self.get_gas();
+        let mut captured = Gas::new(gas.get_gas_limit(), gas.get_gas_credit(), gas.get_gas_limit_max(), gas.get_gas_price());
+        captured.set_unlimited(gas.is_unlimited());
+        Ok(ReplayBundle {
+            code: self.cc.code().clone(),
+            data: self.ctrl(4)?.as_cell()?.clone(),
+            c7: self.ctrl(7)?.clone(),
+            stack: self.cc.stack.clone(),
+            capabilities: self.capabilities(),
+            vm_capabilities: self.vm_capabilities(),
+            gas: captured,
+            gas_prices: self.gas_prices().clone(),
+            seed: None,
+            libraries: self.libraries.clone(),
+        })
+    }
+
+    /// Sets up new engine with input captured by replay_bundle()
+    pub fn replay(bundle: ReplayBundle) -> Result<Engine> {
+        let mut ctrls = SaveList::new();
+        ctrls.put(4, &mut StackItem::cell(bundle.data))?;
+        ctrls.put(7, &mut bundle.c7.clone())?;
+        let mut engine = Engine::with_capabilities(bundle.capabilities)
+            .with_vm_capabilities(bundle.vm_capabilities)
+            .with_gas_prices(bundle.gas_prices);
+        if bundle.gas.is_unlimited() {
+            engine = engine.with_unlimited_gas();
+        }
+        let mut engine = engine.setup_with_libraries(
+            bundle.code, Some(ctrls), Some(bundle.stack), Some(bundle.gas), bundle.libraries
+        );
+        if let Some(seed) = bundle.seed {
+            engine.set_rand_seed(seed)?;
+        }
+        Ok(engine)
+    }
+
+    /// Executes code like execute(), replay bundle is written to the file if execution fails.
+    /// Failure to write the bundle is only logged, result of execution is returned anyway
+    pub fn execute_or_dump(&mut self, path: impl AsRef<Path>) -> Result<i32> {
+        let bundle = self.replay_bundle()?;
+        let result = self.execute();
+        if result.is_err() {
+            if let Err(err) = bundle.write_to_file(path.as_ref()) {
+                tvm_log!(warn, crate::logging::targets::TVM, "replay bundle is not written to {:?}: {}", path.as_ref(), err);
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+#[path = "../tests/test_replay.rs"]
+mod tests;
@@ -0,0 +1,183 @@
+/*
+* Copyright (C) 2019-2024 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+use crate::{
+    error::tvm_exception_full,
+    executor::{
+        engine::Engine, gas::gas_state::Gas,
+        types::{Instruction, InstructionOptions}
+    },
+    stack::{
+        Stack, StackItem, continuation::{ContinuationData, ContinuationType},
+        integer::IntegerData, savelist::SaveList
+    },
+    types::Status
+};
+use ever_block::{Cell, ExceptionCode, Result};
+
+// RUNVM flags
+const SAME_C3: usize = 0x001;      // set c3 to code
+const PUSH_0: usize = 0x002;       // push 0 before running the code
+const LOAD_C4: usize = 0x004;      // pop c4 and return final committed c4
+const LOAD_GAS: usize = 0x008;     // pop gas limit and return consumed gas
+const LOAD_C7: usize = 0x010;      // pop c7
+const RETURN_C5: usize = 0x020;    // return final committed c5
+const LOAD_GAS_MAX: usize = 0x040; // pop hard gas limit
+const ISOLATE_GAS: usize = 0x080;  // child VM does not share loaded cells with parent
+const RETURN_VALS: usize = 0x100;  // pop number of returned values
+const ALL_FLAGS: usize = 0x1FF;
+
+struct ChildResult {
+    exit_code: i32,
+    stack: Vec<StackItem>,
+    c4: StackItem,
+    c5: StackItem,
+    gas_used: i64,
+}
+
+// x_1 ... x_n n code [r] [c4] [c7] [g_l] [g_m] - x'_1 ... x'_m exit_code [c4'] [c5] [g_c]
+fn runvm(engine: &mut Engine, flags: usize) -> Status {
+    if flags & !ALL_FLAGS != 0 {
+        return err!(ExceptionCode::RangeCheckError, "invalid RUNVM flags {:X}", flags)
+    }
+    engine.try_use_gas(Gas::runvm_price())?;
+    let gas_remaining = engine.get_gas().get_gas_available();
+    let gas_max = if flags & LOAD_GAS_MAX != 0 {
+        engine.cc.stack.drop(0)?.as_integer()?.into(0..=i64::MAX)?
+    } else {
+        i64::MAX
+    };
+    let gas_limit = if flags & LOAD_GAS != 0 {
+        engine.cc.stack.drop(0)?.as_integer()?.into(0..=i64::MAX)?
+    } else {
+        i64::MAX
+    };
+    let gas_limit = gas_limit.min(gas_remaining);
+    let gas_max = gas_max.max(gas_limit).min(gas_remaining);
+    let mut c7 = if flags & LOAD_C7 != 0 {
+        let c7 = engine.cc.stack.drop(0)?;
+        c7.as_tuple()?;
+        c7
+    } else {
+        StackItem::tuple(vec![])
+    };
+    let c4 = if flags & LOAD_C4 != 0 {
+        engine.cc.stack.drop(0)?.as_cell()?.clone()
+    } else {
+        Cell::default()
+    };
+    let ret_vals = if flags & RETURN_VALS != 0 {
+        Some(engine.cc.stack.drop(0)?.as_integer()?.into(-1i32..=255)?)
+    } else {
+        None
+    };
+    let code = engine.cc.stack.drop(0)?.as_slice()?.clone();
+    let depth = engine.cc.stack.depth();
+    let n = engine.cc.stack.drop(0)?.as_integer()?.into(0..=depth.saturating_sub(1))?;
+    let mut args = engine.cc.stack.drop_range_straight(0..n)?;
+    engine.use_gas(engine.gas_prices().stack(n));
+    if flags & PUSH_0 != 0 {
+        args.push(int!(0));
+    }
+
+    let mut ctrls = SaveList::new();
+    if flags & SAME_C3 == 0 {
+        // the same as default c3 of the standalone VM in the reference implementation
+        let quit = ContinuationData::with_type(ContinuationType::Quit(11));
+        ctrls.put(3, &mut StackItem::continuation(quit))?;
+    }
+    ctrls.put(4, &mut StackItem::cell(c4))?;
+    ctrls.put(7, &mut c7)?;
+    let mut child = engine.child_engine()?.setup_with_libraries(
+        code,
+        Some(ctrls),
+        Some(Stack::with_storage(args)),
+        Some(Gas::new(gas_limit, 0, gas_max, engine.get_gas().get_gas_price())),
+        engine.libraries().clone()
+    );
+    let isolate = flags & ISOLATE_GAS != 0;
+    if !isolate {
+        engine.swap_visited_cells(&mut child);
+    }
+    let result = run_child(&mut child);
+    if !isolate {
+        engine.swap_visited_cells(&mut child);
+    }
+    let mut result = result?;
+
+    engine.use_gas(result.gas_used);
+    if let Some(ret_vals) = ret_vals {
+        if ret_vals >= 0 {
+            let ret_vals = ret_vals as usize;
+            if result.stack.len() < ret_vals {
+                result.exit_code = !(ExceptionCode::StackUnderflow as i32);
+                result.stack = vec![int!(0)];
+            } else {
+                result.stack.drain(..result.stack.len() - ret_vals);
+            }
+        }
+    }
+    engine.use_gas(engine.gas_prices().stack(result.stack.len()));
+    engine.cc.stack.append(&mut result.stack);
+    engine.cc.stack.push(int!(result.exit_code));
+    if flags & LOAD_C4 != 0 {
+        engine.cc.stack.push(result.c4);
+    }
+    if flags & RETURN_C5 != 0 {
+        engine.cc.stack.push(result.c5);
+    }
+    if flags & LOAD_GAS != 0 {
+        engine.cc.stack.push(int!(result.gas_used));
+    }
+    Ok(())
+}
+
+fn run_child(child: &mut Engine) -> Result<ChildResult> {
+    let (exit_code, stack) = match child.execute() {
+        Ok(exit_code) => (exit_code, child.withdraw_stack().storage),
+        Err(err) => match tvm_exception_full(&err) {
+            Some(exception) => (exception.exit_code(), vec![exception.value]),
+            None => return Err(err)
+        }
+    };
+    let (c4, c5) = match child.get_committed_state().is_committed() {
+        true => (
+            child.get_committed_state().get_root().clone(),
+            child.get_committed_state().get_actions().clone()
+        ),
+        false => (StackItem::None, StackItem::None)
+    };
+    Ok(ChildResult {
+        exit_code,
+        stack,
+        c4,
+        c5,
+        gas_used: child.gas_used(),
+    })
+}
+
+/// RUNVM flags (x_1 ... x_n n code [r] [c4] [c7] [g_l] [g_m] - x'_1 ... x'_m exit_code [c4'] [c5] [g_c])
+pub(super) fn execute_runvm(engine: &mut Engine) -> Status {
+    engine.load_instruction(
+        Instruction::new("RUNVM").set_opts(InstructionOptions::Integer(0..4096))
+    )?;
+    let flags = engine.cmd.integer() as usize;
+    runvm(engine, flags)
+}
+
+/// RUNVMX (x_1 ... x_n n code [r] [c4] [c7] [g_l] [g_m] flags - x'_1 ... x'_m exit_code [c4'] [c5] [g_c])
+pub(super) fn execute_runvmx(engine: &mut Engine) -> Status {
+    engine.load_instruction(Instruction::new("RUNVMX"))?;
+    let flags = engine.cc.stack.drop(0)?.as_integer()?.into(0..=ALL_FLAGS)?;
+    runvm(engine, flags)
+}
@@ -14,7 +14,7 @@
 use crate::{
     executor::{
         Mask, engine::{Engine, data::convert, storage::{fetch_stack, fetch_reference}},
-        gas::gas_state::Gas, microcode::{BUILDER, CC, CELL, VAR},
+        microcode::{BUILDER, CC, CELL, VAR},
         types::{InstructionOptions, Instruction}
     },
     stack::{
@@ -48,13 +48,14 @@ fn size_b(engine: &mut Engine, name: &'static str, how: u8) -> Status {
         Instruction::new(name)
     )?;
     fetch_stack(engine, 1)?;
+    let limits = *engine.builder_limits();
     match engine.cmd.var(0).as_builder()? {
         b if how.bit(INV) => {
             if how.bit(BITS) {
-                engine.cc.stack.push(int!(b.bits_free()));
+                engine.cc.stack.push(int!(limits.bits_free(b)));
             }
             if how.bit(REFS) {
-                engine.cc.stack.push(int!(b.references_free()));
+                engine.cc.stack.push(int!(limits.references_free(b)));
             }
         }
         b => {
@@ -120,7 +121,7 @@ pub fn execute_endxc(engine: &mut Engine) -> Status {
     let mut b = engine.cmd.var_mut(1).as_builder_mut()?;
     if special {
         if b.length_in_bits() < 8 {
-            engine.use_gas(Gas::finalize_price());
+            engine.use_gas(engine.gas_prices().cell_create);
             return err!(ExceptionCode::CellOverflow, "Not enough data for a special cell")
         }
         let cell_type = CellType::try_from(b.data()[0])
@@ -155,11 +156,11 @@ fn store_data(engine: &mut Engine, var: usize, x: Result<BuilderData>, quiet: bo
     let result = match x {
         Ok(x) => {
             let b = engine.cmd.var(var).as_builder()?;
-            if b.can_append(&x) {
+            if engine.builder_limits().check_space(b, x.bits_used(), x.references_used()) {
                 let mut b = engine.cmd.var_mut(var).as_builder_mut()?;
                 b.append_builder(&x)?;
                 if finalize {
-                    engine.try_use_gas(Gas::finalize_price())?;
+                    engine.try_use_gas(engine.gas_prices().cell_create)?;
                 }
                 engine.cc.stack.push_builder(b);
                 0
@@ -368,26 +369,21 @@ fn check_b(engine: &mut Engine, name: &'static str, how: u8) -> Status {
     engine.load_instruction(instruction)?;
     fetch_stack(engine, params)?;
     // TODO: right order of type check
+    let limits = *engine.builder_limits();
     let l = if how.bit(CMD) {
         engine.cmd.length()
     } else if how.bit(BITS) {
-        engine.cmd.var(params - 2).as_integer()?.into(0..=1023)?
+        engine.cmd.var(params - 2).as_integer()?.into(0..=limits.max_data_bits)?
     } else {
         0
     };
     let r = if how.bit(REFS) {
-        engine.cmd.var(0).as_integer()?.into(0..=4)?
+        engine.cmd.var(0).as_integer()?.into(0..=limits.max_references)?
     } else {
         0
     };
     let b = engine.cmd.var(params - 1).as_builder()?;
-    let mut status = true;
-    if how.bit(BITS) {
-        status &= b.check_enough_space(l)
-    }
-    if how.bit(REFS) {
-        status &= b.check_enough_refs(r)
-    }
+    let status = limits.check_space(b, l, r);
     if how.bit(QUIET) {
         engine.cc.stack.push(boolean!(status));
     } else if !status {
@@ -559,24 +555,17 @@ fn store_l<T: Encoding>(engine: &mut Engine, name: &'static str, bits: usize) ->
     store_data(engine, 0, x, false, false)
 }
 
-/// STILE4 (x b - b`), stores a little-endian signed 32-bit integer.
-pub fn execute_stile4(engine: &mut Engine) -> Status {
-    store_l::<SignedIntegerLittleEndianEncoding>(engine, "STILE4", 32)
-}
-
-/// STULE4 (x b - b`), stores a little-endian unsigned 32-bit integer.
-pub fn execute_stule4(engine: &mut Engine) -> Status {
-    store_l::<UnsignedIntegerLittleEndianEncoding>(engine, "STULE4", 32)
-}
-
-/// STILE8 (x b - b`), stores a little-endian signed 64-bit integer.
-pub fn execute_stile8(engine: &mut Engine) -> Status {
-    store_l::<SignedIntegerLittleEndianEncoding>(engine, "STILE8", 64)
-}
+const STLE_NAMES: [&str; 8] = ["STILE4", "STULE4", "STILE8", "STULE8", "STILE16", "STULE16", "STILE32", "STULE32"];
 
-/// STULE8 (x b - b`), stores a little-endian unsigned 64-bit integer.
-pub fn execute_stule8(engine: &mut Engine) -> Status {
-    store_l::<UnsignedIntegerLittleEndianEncoding>(engine, "STULE8", 64)
+/// STILE4..STULE32 (x b - b`), stores a little-endian integer of 4, 8, 16 or 32 bytes.
+/// Bit 0 of opcode selects unsigned integer, the next two bits give the size.
+pub fn execute_stle(engine: &mut Engine) -> Status {
+    let cmd = engine.last_cmd() as usize & 7;
+    let bits = 32 << (cmd >> 1);
+    match cmd & 1 {
+        0 => store_l::<SignedIntegerLittleEndianEncoding>(engine, STLE_NAMES[cmd], bits),
+        _ => store_l::<UnsignedIntegerLittleEndianEncoding>(engine, STLE_NAMES[cmd], bits)
+    }
 }
 
 fn store_bits(mut builder: BuilderData, n: usize, bit: bool) -> Result<BuilderData> {
@@ -593,7 +582,7 @@ fn stbits(engine: &mut Engine, name: &'static str, bit: bool) -> Status {
     fetch_stack(engine, 2)?;
     let n = engine.cmd.var(0).as_integer()?;
     engine.cmd.var(1).as_builder()?;
-    let n = n.into(0..=1023)?;
+    let n = n.into(0..=engine.builder_limits().max_data_bits)?;
     let b = engine.cmd.var_mut(1).as_builder_mut()?;
     engine.cc.stack.push_builder(store_bits(b, n, bit)?);
     Ok(())
@@ -618,7 +607,7 @@ pub fn execute_stsame(engine: &mut Engine) -> Status {
     let n = engine.cmd.var(1).as_integer()?;
     engine.cmd.var(2).as_builder()?;
     let x = x.into(0..=1)?;
-    let n = n.into(0..=1023)?;
+    let n = n.into(0..=engine.builder_limits().max_data_bits)?;
     let b = engine.cmd.var_mut(2).as_builder_mut()?;
     engine.cc.stack.push_builder(store_bits(b, n, x != 0)?);
     Ok(())
@@ -700,6 +689,37 @@ pub fn execute_cdepth(engine: &mut Engine) -> Status {
     Ok(())
 }
 
+/// CLEVELMASK (c - m), returns the level mask of Cell c.
+pub fn execute_clevelmask(engine: &mut Engine) -> Status {
+    engine.load_instruction(Instruction::new("CLEVELMASK"))?;
+    fetch_stack(engine, 1)?;
+    let mask = engine.cmd.var(0).as_cell()?.level_mask().mask();
+    engine.cc.stack.push(int!(mask));
+    Ok(())
+}
+
+/// CHASHI i (c - x), returns the i-th higher hash of Cell c.
+pub fn execute_chashi(engine: &mut Engine) -> Status {
+    engine.load_instruction(
+        Instruction::new("CHASHI").set_opts(InstructionOptions::Length(0..4))
+    )?;
+    fetch_stack(engine, 1)?;
+    let hash = engine.cmd.var(0).as_cell()?.hash(engine.cmd.length());
+    engine.cc.stack.push(StackItem::integer(IntegerData::from_unsigned_bytes_be(hash.as_slice())));
+    Ok(())
+}
+
+/// CDEPTHI i (c - x), returns the i-th higher depth of Cell c.
+pub fn execute_cdepthi(engine: &mut Engine) -> Status {
+    engine.load_instruction(
+        Instruction::new("CDEPTHI").set_opts(InstructionOptions::Length(0..4))
+    )?;
+    fetch_stack(engine, 1)?;
+    let depth = engine.cmd.var(0).as_cell()?.depth(engine.cmd.length());
+    engine.cc.stack.push(int!(depth));
+    Ok(())
+}
+
 /// SDEPTH (s - x), returns the depth of Slice s.
 pub fn execute_sdepth(engine: &mut Engine) -> Status {
     engine.load_instruction(Instruction::new("SDEPTH"))?;
@@ -724,7 +744,7 @@ pub fn execute_stcont(engine: &mut Engine) -> Status {
     let cont = if engine.check_capabilities(ever_block::GlobalCapabilities::CapStcontNewFormat as u64) {
         cont.as_continuation()?.serialize(engine)?
     } else {
-        let (cont, gas) = cont.as_continuation()?.serialize_old()?;
+        let (cont, gas) = cont.as_continuation()?.serialize_old(engine.gas_prices())?;
         engine.use_gas(gas);
         cont
     };
@@ -13,7 +13,7 @@
 
 use crate::{
     executor::{
-        Mask, engine::{Engine, storage::fetch_stack}, gas::gas_state::Gas,
+        Mask, engine::{Engine, VmCapabilities, storage::fetch_stack},
         types::{InstructionOptions, Instruction, WhereToGetParams}
     },
     stack::{StackItem, integer::IntegerData},
@@ -38,7 +38,7 @@ fn tuple(engine: &mut Engine, name: &'static str, how: u8) -> Status {
     let vars = engine.cmd.var_count();
     let mut tuple = engine.cmd.vars.split_off(vars - params);
     tuple.reverse();
-    engine.use_gas(Gas::tuple_gas_price(tuple.len()));
+    engine.use_gas(engine.gas_prices().tuple(tuple.len()));
     engine.cc.stack.push_tuple(tuple);
     Ok(())
 }
@@ -172,7 +172,7 @@ fn untuple(engine: &mut Engine, name: &'static str, how: u8) -> Status {
     if how.mask(CMP) == MORE {
         n = len;
     }
-    engine.use_gas(Gas::tuple_gas_price(n));
+    engine.use_gas(engine.gas_prices().tuple(n));
     let mut vars = engine.cmd.var_mut(params - 1).withdraw_tuple_part(n)?;
     vars.drain(..).for_each(|v| {engine.cc.stack.push(v);});
     if how.bit(COUNT) {
@@ -247,7 +247,8 @@ fn set_index_v2(engine: &mut Engine, name: &'static str, how: u8) -> Status {
     } else {
         unreachable!("internal error in set_index, how = {}", how)
     };
-    let mut tuple = if how.bit(QUIET) && engine.cmd.var(params - 1).is_null() {
+    let null_tuple = how.bit(QUIET) && engine.cmd.var(params - 1).is_null();
+    let mut tuple = if null_tuple {
         vec![]
     } else {
         engine.cmd.var_mut(params - 1).as_tuple_mut()?
@@ -256,12 +257,19 @@ fn set_index_v2(engine: &mut Engine, name: &'static str, how: u8) -> Status {
     let len = tuple.len();
     if n < len {
         tuple[n] = var;
-        engine.use_gas(Gas::tuple_gas_price(len));
+        engine.use_gas(engine.gas_prices().tuple(len));
     } else if how.bit(QUIET) {
         if !var.is_null() {
             tuple.append(&mut vec![StackItem::None; n - len]);
             tuple.push(var);
-            engine.use_gas(Gas::tuple_gas_price(n + 1));
+            engine.use_gas(engine.gas_prices().tuple(n + 1));
+        } else if engine.has_vm_capability(VmCapabilities::CapExactTupleGas) {
+            // as in the reference VM: unchanged tuple is paid too and null stays null
+            engine.use_gas(engine.gas_prices().tuple(len));
+            if null_tuple {
+                engine.cc.stack.push(StackItem::None);
+                return Ok(())
+            }
         }
     } else {
         return err!(ExceptionCode::RangeCheckError, "set_index failed {} >= {}", n, len)
@@ -306,7 +314,7 @@ fn set_index_v1(engine: &mut Engine, name: &'static str, how: u8) -> Status {
         return err!(ExceptionCode::RangeCheckError, "set_index failed {} >= {}", n, len)
     }
     if !value_is_null {
-        engine.use_gas(Gas::tuple_gas_price(tuple.len()));
+        engine.use_gas(engine.gas_prices().tuple(tuple.len()));
     }
     engine.cc.stack.push_tuple(tuple);
     Ok(())
@@ -353,6 +361,31 @@ pub(super) fn execute_tuple_len_quiet(engine: &mut Engine) -> Status {
     tuple_length(engine, "QTLEN", QUIET)
 }
 
+// LINDEXVAR (t k – x)
+// returns k-th item of long tuple t following chain of nested tuples
+pub(super) fn execute_long_tuple_indexvar(engine: &mut Engine) -> Status {
+    engine.load_instruction(Instruction::new("LINDEXVAR"))?;
+    fetch_stack(engine, 2)?;
+    let index = engine.cmd.var(0).as_integer()?.into(0..=u32::MAX as usize)?;
+    let (value, hops) = engine.cmd.var(1).tuple_item_deep_with_hops(index)?;
+    let value = value.clone();
+    // every nested tuple passed is charged as tuple entry
+    engine.try_use_gas(engine.gas_prices().tuple(hops))?;
+    engine.cc.stack.push(value);
+    Ok(())
+}
+
+// LTLEN (t – n)
+// returns number of items in long tuple t
+pub(super) fn execute_long_tuple_len(engine: &mut Engine) -> Status {
+    engine.load_instruction(Instruction::new("LTLEN"))?;
+    fetch_stack(engine, 1)?;
+    let (len, hops) = engine.cmd.var(0).long_tuple_len_with_hops()?;
+    engine.try_use_gas(engine.gas_prices().tuple(hops))?;
+    engine.cc.stack.push(int!(len));
+    Ok(())
+}
+
 // LAST (t – x)
 pub(super) fn execute_tuple_last(engine: &mut Engine) -> Status {
     engine.load_instruction(Instruction::new("LAST"))?;
@@ -377,7 +410,7 @@ pub(super) fn execute_tuple_push(engine: &mut Engine) -> Status {
     let mut tuple = engine.cmd.var_mut(1).as_tuple_mut()?;
     let value = engine.cmd.var(0).clone();
     tuple.push(value);
-    engine.use_gas(Gas::tuple_gas_price(tuple.len()));
+    engine.use_gas(engine.gas_prices().tuple(tuple.len()));
     engine.cc.stack.push_tuple(tuple);
     Ok(())
 }
@@ -388,7 +421,7 @@ pub(super) fn execute_tuple_pop(engine: &mut Engine) -> Status {
     fetch_stack(engine, 1)?;
     let mut tuple = engine.cmd.var_mut(0).as_tuple_mut()?;
     let value = tuple.pop().ok_or(ExceptionCode::TypeCheckError)?;
-    engine.use_gas(Gas::tuple_gas_price(tuple.len()));
+    engine.use_gas(engine.gas_prices().tuple(tuple.len()));
     engine.cc.stack.push_tuple(tuple);
     engine.cc.stack.push(value);
     Ok(())
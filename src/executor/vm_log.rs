@@ -0,0 +1,118 @@
+/*
+* Copyright (C) 2019-2024 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+//! Trace formatter producing the VM log of the reference node:
+//! ```text
+//! stack: [ 1 2 ]
+//! code cell hash: 5F0C...83A1 offset: 0
+//! execute ADD
+//! gas remaining: 999982
+//! ```
+//! Stack is printed before every step like in the reference log, so the stack after the last
+//! step is not printed. Steps of loops are not logged, the reference VM has no such steps.
+
+use crate::{
+    error::tvm_exception_full,
+    executor::engine::{Engine, EngineTraceInfo, EngineTraceInfoType},
+    stack::{Stack, StackItem},
+};
+use std::sync::Mutex;
+
+/// Stack item as it is printed by the reference VM
+pub fn dump_item(item: &StackItem) -> String {
+    match item {
+        StackItem::None => "()".to_string(),
+        StackItem::Tuple(items) => {
+            format!("[ {}]", items.iter().map(|item| dump_item(item) + " ").collect::<String>())
+        }
+        item => item.dump_as_fift()
+    }
+}
+
+/// Stack as it is printed by the reference VM, the top item is the last one
+pub fn dump_stack(stack: &Stack) -> String {
+    format!("stack: [ {}] ", stack.iter().map(|item| dump_item(item) + " ").collect::<String>())
+}
+
+/// Writes VM log lines to the sink, it is to be plugged with Engine::set_trace_callback()
+pub struct VmLog<F: Fn(&str) + Send + Sync> {
+    sink: F,
+    stack: Mutex<Option<String>>, // printed when the next step starts
+}
+
+impl<F: Fn(&str) + Send + Sync> VmLog<F> {
+    pub fn new(sink: F) -> Self {
+        Self { sink, stack: Mutex::new(None) }
+    }
+
+    fn start_step(&self) {
+        if let Some(stack) = self.stack.lock().unwrap().take() {
+            (self.sink)(&stack)
+        }
+    }
+
+    pub fn trace(&self, engine: &Engine, info: &EngineTraceInfo) {
+        match info.info_type {
+            EngineTraceInfoType::Start => {
+                *self.stack.lock().unwrap() = Some(dump_stack(info.stack));
+                return
+            }
+            EngineTraceInfoType::Normal => {
+                self.start_step();
+                if let Some(cell) = info.cmd_code.cell_opt() {
+                    (self.sink)(&format!("code cell hash: {:X} offset: {}", cell.repr_hash(), info.cmd_code.pos()));
+                }
+                (self.sink)(&format!("execute {}", info.cmd_str));
+            }
+            EngineTraceInfoType::Implicit => match info.cmd_str.as_str() {
+                "IMPLICIT JMPREF" | "implicit RET" => {
+                    self.start_step();
+                    (self.sink)(&format!("execute implicit {}", &info.cmd_str[9..]));
+                }
+                _ => return
+            }
+            EngineTraceInfoType::Exception => {
+                *self.stack.lock().unwrap() = None;
+                if let Some(exception) = info.error.and_then(tvm_exception_full) {
+                    (self.sink)(&format!(
+                        "default exception handler, terminating vm with exit code {}",
+                        exception.exception_or_custom_code()
+                    ));
+                }
+                return
+            }
+            EngineTraceInfoType::Finish | EngineTraceInfoType::Dump => return
+        }
+        if let Some(exception) = info.error.and_then(tvm_exception_full) {
+            let message = match exception.exception_code() {
+                Some(code) => code.to_string(),
+                None => "user exception".to_string()
+            };
+            (self.sink)(&format!("handling exception code {}: {}", exception.exception_or_custom_code(), message));
+        }
+        (self.sink)(&format!("gas remaining: {}", engine.gas_remaining()));
+        *self.stack.lock().unwrap() = Some(dump_stack(info.stack));
+    }
+}
+
+impl Engine {
+    /// Plugs trace callback writing VM log in the format of the reference node
+    pub fn set_vm_log(&mut self, sink: impl Fn(&str) + Send + Sync + 'static) {
+        let log = VmLog::new(sink);
+        self.set_trace_callback(move |engine, info| log.trace(engine, info));
+    }
+}
+
+#[cfg(test)]
+#[path = "../tests/test_vm_log.rs"]
+mod tests;
@@ -13,6 +13,8 @@
 
 #![cfg_attr(feature = "ci_run", deny(warnings))]
 
+#[macro_use]
+pub mod logging;
 #[macro_use]
 pub mod types;
 #[macro_use]
@@ -20,9 +22,27 @@ pub mod stack;
 #[macro_use]
 pub mod executor;
 
+#[cfg(feature = "abi")]
+pub mod abi;
+pub mod addr;
+pub mod cache;
+#[cfg(feature = "coverage")]
+pub mod coverage;
+#[cfg(feature = "debug")]
+pub mod watchpoints;
+pub mod debug_info;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+pub mod profiler;
+pub mod reference;
 pub mod smart_contract_info;
+#[cfg(any(test, feature = "testgen"))]
+pub mod testgen;
 pub use self::smart_contract_info::SmartContractInfo;
 pub mod error;
+pub mod platform;
 pub mod utils;
+#[cfg(feature = "serde_json")]
+pub mod vectors;
 
 include!("../common/src/info.rs");
@@ -0,0 +1,84 @@
+/*
+* Copyright (C) 2019-2024 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+//! Log targets of the VM. Records go to `log` by default and to `tracing` with the feature
+//! of the same name, in the latter case instructions and continuation switches are also
+//! wrapped into spans. All targets are nested into "tvm", so filters set for it keep working.
+
+/// Stable hierarchy of log targets
+pub mod targets {
+    pub const TVM: &str = "tvm";
+    /// Executed instructions
+    pub const CODE: &str = "tvm::code";
+    pub const GAS: &str = "tvm::gas";
+    pub const STACK: &str = "tvm::stack";
+    pub const CTRLS: &str = "tvm::ctrls";
+    /// Output of debug primitives
+    pub const DEBUG: &str = "tvm::debug";
+    /// Unknown opcodes
+    pub const DECODE: &str = "tvm::decode";
+    pub const EXCEPTION: &str = "tvm::exception";
+    /// Spans of instructions
+    pub const EXEC: &str = "tvm::exec";
+    /// Spans of continuation switches
+    pub const CONT: &str = "tvm::cont";
+    /// Elector and depool lookups
+    pub const ACCOUNTS: &str = "tvm::accounts";
+    pub const SMCI: &str = "tvm::smci";
+}
+
+macro_rules! tvm_log {
+    ($level:ident, $target:expr, $($arg:tt)+) => {{
+        #[cfg(feature = "tracing")]
+        tracing::$level!(target: $target, $($arg)+);
+        #[cfg(not(feature = "tracing"))]
+        log::$level!(target: $target, $($arg)+);
+    }};
+}
+
+// guards formatting of costly error messages
+macro_rules! tvm_error_enabled {
+    ($target:expr) => {{
+        #[cfg(feature = "tracing")]
+        let enabled = tracing::enabled!(target: $target, tracing::Level::ERROR);
+        #[cfg(not(feature = "tracing"))]
+        let enabled = log::log_enabled!(target: $target, log::Level::Error);
+        enabled
+    }};
+}
+
+/// Returns true if any target of the trace printer is enabled at any level
+#[cfg(not(feature = "tracing"))]
+pub(crate) fn trace_enabled() -> bool {
+    use targets::*;
+    [TVM, CODE, GAS, STACK, CTRLS, DEBUG, EXCEPTION].iter().any(|&target| {
+        log::log_enabled!(target: target, log::Level::Error)
+            || log::log_enabled!(target: target, log::Level::Warn)
+            || log::log_enabled!(target: target, log::Level::Info)
+            || log::log_enabled!(target: target, log::Level::Debug)
+            || log::log_enabled!(target: target, log::Level::Trace)
+    })
+}
+
+/// Returns true if any target of the trace printer is enabled at any level
+#[cfg(feature = "tracing")]
+pub(crate) fn trace_enabled() -> bool {
+    use targets::*;
+    tracing::enabled!(target: TVM, tracing::Level::ERROR)
+        || tracing::enabled!(target: CODE, tracing::Level::ERROR)
+        || tracing::enabled!(target: GAS, tracing::Level::ERROR)
+        || tracing::enabled!(target: STACK, tracing::Level::ERROR)
+        || tracing::enabled!(target: CTRLS, tracing::Level::ERROR)
+        || tracing::enabled!(target: DEBUG, tracing::Level::ERROR)
+        || tracing::enabled!(target: EXCEPTION, tracing::Level::ERROR)
+}
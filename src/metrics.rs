@@ -0,0 +1,73 @@
+/*
+* Copyright (C) 2019-2024 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+//! Counters of execution reported to the recorder of `metrics` crate installed by embedder,
+//! e.g. Prometheus exporter. Counters are collected by Engine::enable_metrics()
+
+use crate::error::tvm_exception_full;
+use ever_block::Error;
+use std::collections::HashMap;
+
+pub const INSTRUCTIONS: &str = "tvm_instructions_total";
+pub const GAS_USED: &str = "tvm_gas_used_total";
+pub const EXCEPTIONS: &str = "tvm_exceptions_total";
+pub const CELLS_LOADED: &str = "tvm_cells_loaded_total";
+pub const CELLS_CREATED: &str = "tvm_cells_created_total";
+
+/// Registers descriptions of counters, it is to be called once after recorder is installed
+pub fn describe_metrics() {
+    ::metrics::describe_counter!(INSTRUCTIONS, "Instructions executed, labeled by opcode");
+    ::metrics::describe_counter!(GAS_USED, "Gas used by executions");
+    ::metrics::describe_counter!(EXCEPTIONS, "Exceptions raised by instructions, labeled by exit code");
+    ::metrics::describe_counter!(CELLS_LOADED, "Cells loaded to slices");
+    ::metrics::describe_counter!(CELLS_CREATED, "Cells created from builders");
+}
+
+/// Counters of one execution, they are accumulated locally and reported when it is finished
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ExecutionMetrics {
+    pub instructions: HashMap<&'static str, u64>,
+    pub gas_used: u64,
+    pub exceptions: HashMap<i32, u64>,
+    pub cells_loaded: u64,
+    pub cells_created: u64,
+}
+
+impl ExecutionMetrics {
+    /// Opcode is None if instruction is not decoded
+    pub(crate) fn record_instruction(&mut self, opcode: Option<&'static str>, error: Option<&Error>) {
+        if let Some(opcode) = opcode {
+            *self.instructions.entry(opcode).or_default() += 1;
+        }
+        if let Some(exception) = error.and_then(tvm_exception_full) {
+            *self.exceptions.entry(exception.exception_or_custom_code()).or_default() += 1;
+        }
+    }
+
+    /// Adds counters to the installed recorder
+    pub fn report(&self) {
+        for (&opcode, &count) in &self.instructions {
+            ::metrics::counter!(INSTRUCTIONS, "opcode" => opcode).increment(count);
+        }
+        ::metrics::counter!(GAS_USED).increment(self.gas_used);
+        for (code, &count) in &self.exceptions {
+            ::metrics::counter!(EXCEPTIONS, "code" => code.to_string()).increment(count);
+        }
+        ::metrics::counter!(CELLS_LOADED).increment(self.cells_loaded);
+        ::metrics::counter!(CELLS_CREATED).increment(self.cells_created);
+    }
+}
+
+#[cfg(test)]
+#[path = "tests/test_metrics.rs"]
+mod tests;
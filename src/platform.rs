@@ -0,0 +1,51 @@
+/*
+* Copyright (C) 2019-2024 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+//! Services of the platform the VM runs on. There are no threads and no clock on wasm32,
+//! so jobs are executed sequentially and time never passes there unless embedder sets clock,
+//! see Engine::set_clock().
+//! Logs go through `log` facade to the logger installed by embedder, VM writes no files.
+
+/// Worker threads can be spawned
+pub const THREADS_AVAILABLE: bool = !cfg!(target_arch = "wasm32");
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use std::time::Instant;
+
+/// Clock which never advances, time limits are never exceeded with it
+#[cfg(target_arch = "wasm32")]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Instant;
+
+#[cfg(target_arch = "wasm32")]
+impl Instant {
+    pub fn now() -> Self {
+        Self
+    }
+
+    pub fn elapsed(&self) -> std::time::Duration {
+        std::time::Duration::ZERO
+    }
+}
+
+/// Milliseconds elapsed since the first call
+#[cfg(not(target_arch = "wasm32"))]
+pub fn monotonic_millis() -> u64 {
+    static BASE: std::sync::OnceLock<Instant> = std::sync::OnceLock::new();
+    BASE.get_or_init(Instant::now).elapsed().as_millis() as u64
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn monotonic_millis() -> u64 {
+    0
+}
@@ -0,0 +1,92 @@
+/*
+* Copyright (C) 2019-2024 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+use ever_block::UInt256;
+use std::{collections::HashMap, sync::Mutex};
+
+/// Aggregated statistics of calls
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct CallStats {
+    pub calls: u64,
+    /// Calls finished with exception
+    pub failures: u64,
+    pub gas_used: i64,
+    pub max_gas_used: i64,
+}
+
+impl CallStats {
+    fn add(&mut self, other: &CallStats) {
+        self.calls += other.calls;
+        self.failures += other.failures;
+        self.gas_used += other.gas_used;
+        self.max_gas_used = self.max_gas_used.max(other.max_gas_used);
+    }
+}
+
+/// Key of profiled call: hash of code cell and method id taken from the top of initial stack.
+/// Method id is None if the stack is empty or its top is not an integer fitting into 64 bits,
+/// calls by messages are keyed by selector of the message: 0 for internal and -1 for external ones
+pub type CallKey = (UInt256, Option<i64>);
+
+/// Profiler shared by many Engine runs of a session, see Engine::set_profiler()
+#[derive(Debug, Default)]
+pub struct SessionProfiler {
+    stats: Mutex<HashMap<CallKey, CallStats>>,
+}
+
+impl SessionProfiler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&self, code_hash: UInt256, method_id: Option<i64>, gas_used: i64, failed: bool) {
+        let mut stats = self.stats.lock().unwrap();
+        let stats = stats.entry((code_hash, method_id)).or_default();
+        stats.add(&CallStats {
+            calls: 1,
+            failures: failed as u64,
+            gas_used,
+            max_gas_used: gas_used,
+        });
+    }
+
+    /// Statistics of every method of every code
+    pub fn by_method(&self) -> HashMap<CallKey, CallStats> {
+        self.stats.lock().unwrap().clone()
+    }
+
+    /// Statistics of every code summed up over its methods
+    pub fn by_code(&self) -> HashMap<UInt256, CallStats> {
+        let mut result = HashMap::<UInt256, CallStats>::new();
+        for ((code_hash, _), stats) in self.stats.lock().unwrap().iter() {
+            result.entry(code_hash.clone()).or_default().add(stats);
+        }
+        result
+    }
+
+    /// Codes with the most gas used in total, the most expensive first
+    pub fn top_by_gas(&self, count: usize) -> Vec<(UInt256, CallStats)> {
+        let mut codes = self.by_code().into_iter().collect::<Vec<_>>();
+        codes.sort_by(|(_, x), (_, y)| y.gas_used.cmp(&x.gas_used));
+        codes.truncate(count);
+        codes
+    }
+
+    pub fn reset(&self) {
+        self.stats.lock().unwrap().clear()
+    }
+}
+
+#[cfg(test)]
+#[path = "tests/test_profiler.rs"]
+mod tests;
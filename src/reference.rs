@@ -0,0 +1,190 @@
+/*
+* Copyright (C) 2019-2024 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+use crate::{
+    error::tvm_exception_full,
+    executor::{engine::Engine, gas::gas_state::Gas},
+    stack::{Stack, StackItem},
+};
+use ever_block::{fail, write_boc, Cell, Result, SliceData};
+use std::{path::PathBuf, process::Command, sync::atomic::{AtomicUsize, Ordering}};
+
+// runvmx mode: c3 is set to code, gas limit is passed and gas consumed is returned
+const RUNVMX_MODE: u32 = 1 | 8;
+const DEFAULT_GAS_LIMIT: i64 = 1_000_000;
+// price of gas unit in basechain
+const DEFAULT_GAS_PRICE: i64 = 10;
+
+static SCRIPT_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Observable result of execution compared between implementations
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ReferenceOutcome {
+    pub exit_code: i32,
+    pub gas_used: i64,
+    /// Result stack in fift notation, the top is the last
+    pub stack: String,
+}
+
+/// Difference between this VM and the reference implementation
+#[derive(Clone, Debug)]
+pub struct Mismatch {
+    pub local: ReferenceOutcome,
+    pub reference: ReferenceOutcome,
+}
+
+/// Runs code in this VM and in reference implementation via fift
+/// (runvmx is used there) and compares exit codes, gas and result stacks
+pub struct ReferenceCompare {
+    fift_path: PathBuf,
+    fift_lib_path: Option<PathBuf>,
+    gas_limit: i64,
+    gas_price: i64,
+    capabilities: u64,
+}
+
+impl ReferenceCompare {
+    pub fn new(fift_path: impl Into<PathBuf>) -> Self {
+        Self {
+            fift_path: fift_path.into(),
+            fift_lib_path: None,
+            gas_limit: DEFAULT_GAS_LIMIT,
+            gas_price: DEFAULT_GAS_PRICE,
+            capabilities: 0,
+        }
+    }
+
+    /// Directory with Fift.fif passed to fift as -I option
+    pub fn with_lib_path(mut self, fift_lib_path: impl Into<PathBuf>) -> Self {
+        self.fift_lib_path = Some(fift_lib_path.into());
+        self
+    }
+
+    pub fn with_gas_limit(mut self, gas_limit: i64) -> Self {
+        self.gas_limit = gas_limit;
+        self
+    }
+
+    /// Price of gas unit in nanograms used by this VM, e.g. the one of masterchain
+    pub fn with_gas_price(mut self, gas_price: i64) -> Self {
+        self.gas_price = gas_price;
+        self
+    }
+
+    pub fn with_capabilities(mut self, capabilities: u64) -> Self {
+        self.capabilities = capabilities;
+        self
+    }
+
+    /// Executes code with stack in this VM
+    pub fn run_local(&self, code: &Cell, stack: &Stack) -> Result<ReferenceOutcome> {
+        let gas = Gas::new(self.gas_limit, 0, self.gas_limit, self.gas_price);
+        let mut engine = Engine::with_capabilities(self.capabilities).setup_with_libraries(
+            SliceData::load_cell_ref(code)?,
+            None,
+            Some(stack.clone()),
+            Some(gas),
+            vec![]
+        );
+        let exit_code = match engine.execute() {
+            Ok(exit_code) => exit_code,
+            Err(err) => match tvm_exception_full(&err) {
+                Some(exception) => {
+                    let exit_code = exception.exit_code();
+                    let mut stack = Stack::new();
+                    stack.push(exception.value);
+                    *engine.stack_mut() = stack;
+                    exit_code
+                }
+                None => return Err(err)
+            }
+        };
+        Ok(ReferenceOutcome {
+            exit_code,
+            gas_used: engine.gas_used(),
+            stack: engine.get_stack_result_fift(),
+        })
+    }
+
+    /// Executes code with stack in reference implementation
+    pub fn run_reference(&self, code: &Cell, stack: &Stack) -> Result<ReferenceOutcome> {
+        let id = SCRIPT_COUNTER.fetch_add(1, Ordering::Relaxed);
+        let base = std::env::temp_dir().join(format!("ever-vm-ref-{}-{}", std::process::id(), id));
+        let boc_path = base.with_extension("boc");
+        let script_path = base.with_extension("fif");
+        std::fs::write(&boc_path, write_boc(code)?)?;
+        let mut script = String::from("\"Fift.fif\" include\n");
+        for item in stack.iter() {
+            script += &fift_literal(item)?;
+            script += " ";
+        }
+        script += &format!(
+            "\n{:?} file>B B>boc <s {} {} runvmx .s\n",
+            boc_path.to_string_lossy(), self.gas_limit, RUNVMX_MODE
+        );
+        std::fs::write(&script_path, script)?;
+        let mut command = Command::new(&self.fift_path);
+        if let Some(lib_path) = &self.fift_lib_path {
+            command.arg("-I").arg(lib_path);
+        }
+        let output = command.arg("-s").arg(&script_path).output();
+        let _ = std::fs::remove_file(&boc_path);
+        let _ = std::fs::remove_file(&script_path);
+        let output = output?;
+        if !output.status.success() {
+            fail!("fift failed: {}", String::from_utf8_lossy(&output.stderr))
+        }
+        parse_fift_output(&String::from_utf8_lossy(&output.stdout))
+    }
+
+    /// Runs code in both implementations, returns None if outcomes are equal
+    pub fn compare(&self, code: &Cell, stack: &Stack) -> Result<Option<Mismatch>> {
+        let local = self.run_local(code, stack)?;
+        let reference = self.run_reference(code, stack)?;
+        if local == reference {
+            Ok(None)
+        } else {
+            Ok(Some(Mismatch { local, reference }))
+        }
+    }
+}
+
+fn fift_literal(item: &StackItem) -> Result<String> {
+    match item {
+        StackItem::None => Ok("null".to_string()),
+        StackItem::Integer(value) if !value.is_nan() => Ok(value.to_string()),
+        StackItem::Tuple(items) => {
+            let items = items.iter().map(fift_literal).collect::<Result<Vec<_>>>()?;
+            Ok(format!("{} {} tuple", items.join(" "), items.len()))
+        }
+        _ => fail!("only integers, nulls and tuples can be passed to reference VM, got {}", item)
+    }
+}
+
+/// Parses output of `.s`: result stack followed by exit code and gas used
+fn parse_fift_output(output: &str) -> Result<ReferenceOutcome> {
+    let mut tokens = output.split_whitespace().collect::<Vec<_>>();
+    let (gas_used, exit_code) = match (tokens.pop(), tokens.pop()) {
+        (Some(gas_used), Some(exit_code)) => (gas_used.parse()?, exit_code.parse()?),
+        _ => fail!("unexpected fift output: {}", output)
+    };
+    Ok(ReferenceOutcome {
+        exit_code,
+        gas_used,
+        stack: tokens.join(" "),
+    })
+}
+
+#[cfg(test)]
+#[path = "tests/test_reference.rs"]
+mod tests;
@@ -11,11 +11,11 @@
 * limitations under the License.
 */
 
-use crate::stack::{
-    StackItem,
-    integer::IntegerData,
+use crate::{
+    executor::VmCapabilities,
+    stack::{StackItem, integer::IntegerData},
 };
-use ever_block::{GlobalCapabilities, CurrencyCollection};
+use ever_block::{BlockIdExt, GlobalCapabilities, CurrencyCollection};
 use ever_block::{Cell, HashmapE, HashmapType, SliceData, types::UInt256, Sha256};
 
 /*
@@ -45,7 +45,13 @@ pub struct SmartContractInfo {
     pub mycode: Cell,
     pub init_code_hash: UInt256,
     pub storage_fee_collected: u128,
+    pub prev_mc_blocks: Vec<BlockIdExt>, // last masterchain blocks, the latest first
+    pub prev_key_block: Option<BlockIdExt>,
+    pub global_id: i32,
+    pub incoming_value: CurrencyCollection, // value of inbound message
+    pub due_payment: Option<u128>, // storage fees debt of account
     pub capabilities: u64,
+    pub vm_capabilities: u64, // VmCapabilities of parameters without network capability
 }
 
 impl SmartContractInfo{
@@ -128,7 +134,7 @@ impl SmartContractInfo{
             IntegerData::from_unsigned_bytes_be(sha256)
         } else {
             // if the user forgot to set the rand_seed_block value, then this 0 will be clearly visible on tests
-            log::warn!(target: "tvm", "Not set rand_seed_block");
+            tvm_log!(warn, crate::logging::targets::SMCI, "Not set rand_seed_block");
             IntegerData::zero()
         };
     }
@@ -151,10 +157,56 @@ impl SmartContractInfo{
         self.storage_fee_collected = storage_fee;
     }
 
+    pub fn set_prev_blocks_info(&mut self, prev_mc_blocks: Vec<BlockIdExt>, prev_key_block: Option<BlockIdExt>) {
+        self.prev_mc_blocks = prev_mc_blocks;
+        self.prev_key_block = prev_key_block;
+    }
+
+    pub fn set_global_id(&mut self, global_id: i32) {
+        self.global_id = global_id;
+    }
+
+    pub fn set_incoming_value(&mut self, incoming_value: CurrencyCollection) {
+        self.incoming_value = incoming_value;
+    }
+
+    pub fn set_due_payment(&mut self, due_payment: Option<u128>) {
+        self.due_payment = due_payment;
+    }
+
+    // [ grams:Integer other:Maybe Cell ]
+    fn currency_item(value: &CurrencyCollection) -> StackItem {
+        StackItem::tuple(vec![
+            int!(value.grams.as_u128()),
+            value.other_as_hashmap().data().cloned().map_or(StackItem::None, StackItem::Cell)
+        ])
+    }
+
+    // [ wc:Integer shard:Integer seqno:Integer root_hash:Integer file_hash:Integer ]
+    fn block_id_item(block_id: &BlockIdExt) -> StackItem {
+        StackItem::tuple(vec![
+            int!(block_id.shard_id.workchain_id()),
+            int!(block_id.shard_id.shard_prefix_with_tag()),
+            int!(block_id.seq_no),
+            StackItem::int(IntegerData::from_unsigned_bytes_be(block_id.root_hash.as_slice())),
+            StackItem::int(IntegerData::from_unsigned_bytes_be(block_id.file_hash.as_slice())),
+        ])
+    }
+
+    // [ last_mc_blocks:[BlockId...] prev_key_block:BlockId ]
+    fn prev_blocks_info_item(&self) -> StackItem {
+        StackItem::tuple(vec![
+            StackItem::tuple(self.prev_mc_blocks.iter().map(Self::block_id_item).collect()),
+            self.prev_key_block.as_ref().map_or(StackItem::None, Self::block_id_item),
+        ])
+    }
+
     pub fn into_temp_data_item(self) -> StackItem {
         debug_assert_eq!(self.balance_remaining_grams, 0, "use balance instead old");
         debug_assert!(self.balance_remaining_other.data().is_none(), "use balance instead old");
 
+        let prev_blocks_info = self.prev_blocks_info_item();
+        let incoming_value = Self::currency_item(&self.incoming_value);
         let balance = std::cmp::max(self.balance_remaining_grams, self.balance.grams.as_u128());
         let balance_other = self.balance_remaining_other.data().cloned()
             .or_else(|| self.balance.other_as_hashmap().data().cloned());
@@ -174,15 +226,21 @@ impl SmartContractInfo{
             StackItem::Slice(self.myself),
             self.config_params.map_or(StackItem::None, StackItem::Cell),
         ];
+        let has = |cap: GlobalCapabilities| self.capabilities & cap as u64 != 0;
+        let has_vm = |cap: VmCapabilities| self.vm_capabilities & cap as u64 != 0;
         let mut additional_params = vec![
-            (GlobalCapabilities::CapMycode, StackItem::cell(self.mycode.clone())),
-            (GlobalCapabilities::CapInitCodeHash, StackItem::int(IntegerData::from_unsigned_bytes_be(self.init_code_hash.as_slice()))),
-            (GlobalCapabilities::CapStorageFeeToTvm, StackItem::int(self.storage_fee_collected)),
-            (GlobalCapabilities::CapDelections, StackItem::int(self.seq_no)),
+            (has(GlobalCapabilities::CapMycode), StackItem::cell(self.mycode.clone())),
+            (has(GlobalCapabilities::CapInitCodeHash), StackItem::int(IntegerData::from_unsigned_bytes_be(self.init_code_hash.as_slice()))),
+            (has(GlobalCapabilities::CapStorageFeeToTvm), StackItem::int(self.storage_fee_collected)),
+            (has(GlobalCapabilities::CapDelections), StackItem::int(self.seq_no)),
+            (has_vm(VmCapabilities::CapBlockchainInfo), prev_blocks_info),
+            (has_vm(VmCapabilities::CapBlockchainInfo), StackItem::int(self.global_id)),
+            (has_vm(VmCapabilities::CapTransactionInfo), incoming_value),
+            (has_vm(VmCapabilities::CapTransactionInfo), self.due_payment.map_or(StackItem::None, StackItem::int)),
         ];
         let add_params = &mut Vec::new();
-        for (i, (caps, f)) in additional_params.drain(..).enumerate() {
-            if (self.capabilities & caps as u64) != 0 {
+        for (i, (enabled, f)) in additional_params.drain(..).enumerate() {
+            if enabled {
                 for _ in add_params.len()..i {
                     add_params.push(StackItem::default());
                 }
@@ -190,7 +248,7 @@ impl SmartContractInfo{
             }
         }
         params.append(add_params);
-        debug_assert!(params.len() <= 14, "{:?} caps: {:X}", params, self.capabilities);
+        debug_assert!(params.len() <= 18, "{:?} caps: {:X}", params, self.capabilities);
         StackItem::tuple(vec![StackItem::tuple(params)])
     }
 
@@ -0,0 +1,450 @@
+/*
+* Copyright (C) 2019-2024 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+//! Augmented dictionaries: every node keeps extra data aggregated over its subtree,
+//! as balances of shard accounts are. Extras are read and aggregated by pluggable AugExtra,
+//! root cell is kept on stack the same way as of ordinary dictionary.
+
+use crate::stack::integer::{
+    IntegerData, behavior::Signaling,
+    serialization::{Encoding, UnsignedIntegerBigEndianEncoding}
+};
+use ever_block::{
+    hm_label, Augmentable, BuilderData, Cell, Deserializable, ExceptionCode, GasConsumer, IBitstring,
+    Result, Serializable, SliceData,
+};
+use std::{fmt, marker::PhantomData};
+
+/// Format of extras kept in nodes of augmented dictionary and their aggregation in forks
+pub trait AugExtra {
+    type Extra: Clone + fmt::Debug + PartialEq;
+    fn read(&self, slice: &mut SliceData) -> Result<Self::Extra>;
+    fn write(&self, extra: &Self::Extra, builder: &mut BuilderData) -> Result<()>;
+    /// Extra of fork with the given extras of its children
+    fn fork(&self, left: &Self::Extra, right: &Self::Extra) -> Result<Self::Extra>;
+    /// Extra of empty dictionary
+    fn empty(&self) -> Self::Extra;
+}
+
+/// Unsigned integers of fixed bit length summed up in forks, used by AUGDICT* instructions
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct UintExtra(pub usize);
+
+impl AugExtra for UintExtra {
+    type Extra = IntegerData;
+    fn read(&self, slice: &mut SliceData) -> Result<IntegerData> {
+        let bits = slice.get_next_bits(self.0)?;
+        Ok(UnsignedIntegerBigEndianEncoding::new(self.0).deserialize(&bits))
+    }
+    fn write(&self, extra: &IntegerData, builder: &mut BuilderData) -> Result<()> {
+        builder.append_builder(&extra.as_builder::<UnsignedIntegerBigEndianEncoding>(self.0)?)?;
+        Ok(())
+    }
+    fn fork(&self, left: &IntegerData, right: &IntegerData) -> Result<IntegerData> {
+        left.add::<Signaling>(right)
+    }
+    fn empty(&self) -> IntegerData {
+        IntegerData::zero()
+    }
+}
+
+/// Extras of block structures aggregated by their own rules, e.g. CurrencyCollection
+#[derive(Debug)]
+pub struct TypedExtra<Y>(PhantomData<Y>);
+
+impl<Y> TypedExtra<Y> {
+    pub const fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<Y> Clone for TypedExtra<Y> {
+    fn clone(&self) -> Self {
+        Self::new()
+    }
+}
+
+impl<Y> AugExtra for TypedExtra<Y>
+where Y: Augmentable + Serializable + Deserializable + Default + Clone + fmt::Debug + PartialEq {
+    type Extra = Y;
+    fn read(&self, slice: &mut SliceData) -> Result<Y> {
+        Y::construct_from(slice)
+    }
+    fn write(&self, extra: &Y, builder: &mut BuilderData) -> Result<()> {
+        extra.write_to(builder)
+    }
+    fn fork(&self, left: &Y, right: &Y) -> Result<Y> {
+        let mut extra = left.clone();
+        extra.calc(right)?;
+        Ok(extra)
+    }
+    fn empty(&self) -> Y {
+        Y::default()
+    }
+}
+
+// cells are built and loaded for free by helpers constructing dictionaries
+struct NoGas;
+
+impl GasConsumer for NoGas {
+    fn finalize_cell(&mut self, builder: BuilderData) -> Result<Cell> {
+        builder.into_cell()
+    }
+    fn load_cell(&mut self, cell: Cell) -> Result<SliceData> {
+        SliceData::load_cell(cell)
+    }
+    fn finalize_cell_and_load(&mut self, builder: BuilderData) -> Result<SliceData> {
+        SliceData::load_cell(builder.into_cell()?)
+    }
+}
+
+enum Node<X> {
+    Leaf { extra: X, value: SliceData },
+    Fork { children: [Cell; 2], extra: X },
+}
+
+impl<X> Node<X> {
+    fn extra(&self) -> &X {
+        match self {
+            Node::Leaf { extra, .. } | Node::Fork { extra, .. } => extra
+        }
+    }
+}
+
+// subtree after removal if key is found with extra and value of removed leaf
+type Removed<X> = (Option<(Cell, X)>, X, SliceData);
+
+fn suffix(key: &SliceData, from: usize) -> SliceData {
+    let mut key = key.clone();
+    key.shrink_data(from..);
+    key
+}
+
+/// Augmented dictionary with keys of bit_len bits and extras in format of E.
+/// Leaves keep extra followed by value, forks keep references to children followed by extra.
+/// Extra of root is kept along with it, so it is known without loading root cell
+#[derive(Clone, Debug)]
+pub struct AugDict<E: AugExtra> {
+    bit_len: usize,
+    format: E,
+    root: Option<Cell>,
+    root_extra: Option<E::Extra>,
+}
+
+impl<E: AugExtra> PartialEq for AugDict<E> {
+    fn eq(&self, other: &Self) -> bool {
+        self.bit_len == other.bit_len && self.root == other.root
+    }
+}
+
+impl<E: AugExtra> AugDict<E> {
+    /// Dictionary with extra of root unknown until it is loaded
+    pub fn with_root(bit_len: usize, format: E, root: Option<Cell>) -> Self {
+        Self { bit_len, format, root, root_extra: None }
+    }
+
+    /// Reads dictionary serialized as HashmapAugE: root reference if any followed by extra of root
+    pub fn read_from(bit_len: usize, format: E, slice: &mut SliceData) -> Result<Self> {
+        let root = match slice.get_next_bit()? {
+            true => Some(slice.checked_drain_reference()?),
+            false => None
+        };
+        let root_extra = Some(format.read(slice)?);
+        Ok(Self { bit_len, format, root, root_extra })
+    }
+
+    /// Writes dictionary as HashmapAugE, root is loaded if its extra is unknown
+    pub fn write_to(&self, builder: &mut BuilderData, gas: &mut dyn GasConsumer) -> Result<()> {
+        let extra = self.extra(gas)?;
+        match &self.root {
+            Some(root) => {
+                builder.append_bit_one()?;
+                builder.checked_append_reference(root.clone())?;
+            }
+            None => {
+                builder.append_bit_zero()?;
+            }
+        }
+        self.format.write(&extra, builder)
+    }
+
+    pub fn bit_len(&self) -> usize {
+        self.bit_len
+    }
+
+    pub fn format(&self) -> &E {
+        &self.format
+    }
+
+    pub fn root(&self) -> Option<&Cell> {
+        self.root.as_ref()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    fn check_key(&self, key: &SliceData) -> Result<()> {
+        if key.remaining_bits() != self.bit_len {
+            return err!(
+                ExceptionCode::CellUnderflow,
+                "key of augmented dictionary must have {} bits, got {}", self.bit_len, key.remaining_bits()
+            )
+        }
+        Ok(())
+    }
+
+    fn load_node(&self, cell: Cell, pos: usize, gas: &mut dyn GasConsumer) -> Result<(SliceData, Node<E::Extra>)> {
+        let mut slice = gas.load_cell(cell)?;
+        let label = slice.get_label(self.bit_len - pos)?;
+        let node = if pos + label.remaining_bits() == self.bit_len {
+            let extra = self.format.read(&mut slice)?;
+            Node::Leaf { extra, value: slice }
+        } else {
+            let children = [slice.checked_drain_reference()?, slice.checked_drain_reference()?];
+            let extra = self.format.read(&mut slice)?;
+            Node::Fork { children, extra }
+        };
+        Ok((label, node))
+    }
+
+    fn make_node(&self, label: &SliceData, pos: usize, node: &Node<E::Extra>, gas: &mut dyn GasConsumer) -> Result<Cell> {
+        let mut builder = hm_label(label, self.bit_len - pos)?;
+        match node {
+            Node::Leaf { extra, value } => {
+                self.format.write(extra, &mut builder)?;
+                builder.append_builder(&value.as_builder())?;
+            }
+            Node::Fork { children, extra } => {
+                for child in children {
+                    builder.checked_append_reference(child.clone())?;
+                }
+                self.format.write(extra, &mut builder)?;
+            }
+        }
+        gas.finalize_cell(builder)
+    }
+
+    // fork with children at the given position of key, its extra is aggregated from theirs
+    fn make_fork(
+        &self,
+        label: &SliceData,
+        pos: usize,
+        children: [(Cell, E::Extra); 2],
+        gas: &mut dyn GasConsumer,
+    ) -> Result<(Cell, E::Extra)> {
+        let [(left, left_extra), (right, right_extra)] = children;
+        let extra = self.format.fork(&left_extra, &right_extra)?;
+        let node = Node::Fork { children: [left, right], extra };
+        let cell = self.make_node(label, pos, &node, gas)?;
+        Ok((cell, node.extra().clone()))
+    }
+
+    // child of fork with its extra
+    fn child(&self, cell: Cell, pos: usize, gas: &mut dyn GasConsumer) -> Result<(Cell, E::Extra)> {
+        let extra = self.load_node(cell.clone(), pos, gas)?.1.extra().clone();
+        Ok((cell, extra))
+    }
+
+    // position in label where key diverges from it
+    fn diverges(label: &SliceData, key: &SliceData, pos: usize) -> Result<Option<usize>> {
+        for i in 0..label.remaining_bits() {
+            if label.get_bit(i)? != key.get_bit(pos + i)? {
+                return Ok(Some(i))
+            }
+        }
+        Ok(None)
+    }
+
+    /// Aggregated extra of all leaves, empty extra of the format for empty dictionary
+    pub fn extra(&self, gas: &mut dyn GasConsumer) -> Result<E::Extra> {
+        if let Some(extra) = &self.root_extra {
+            return Ok(extra.clone())
+        }
+        match &self.root {
+            Some(root) => Ok(self.load_node(root.clone(), 0, gas)?.1.extra().clone()),
+            None => Ok(self.format.empty())
+        }
+    }
+
+    /// Returns value and extra of the key
+    pub fn get(&self, key: &SliceData, gas: &mut dyn GasConsumer) -> Result<Option<(SliceData, E::Extra)>> {
+        self.check_key(key)?;
+        let mut cell = match &self.root {
+            Some(root) => root.clone(),
+            None => return Ok(None)
+        };
+        let mut pos = 0;
+        loop {
+            let (label, node) = self.load_node(cell, pos, gas)?;
+            if Self::diverges(&label, key, pos)?.is_some() {
+                return Ok(None)
+            }
+            pos += label.remaining_bits();
+            match node {
+                Node::Leaf { extra, value } => return Ok(Some((value, extra))),
+                Node::Fork { children, .. } => {
+                    cell = children[key.get_bit(pos)? as usize].clone();
+                    pos += 1;
+                }
+            }
+        }
+    }
+
+    fn set_node(
+        &self,
+        cell: Option<Cell>,
+        pos: usize,
+        key: &SliceData,
+        leaf: &Node<E::Extra>,
+        gas: &mut dyn GasConsumer,
+    ) -> Result<(Cell, E::Extra)> {
+        let cell = match cell {
+            Some(cell) => cell,
+            None => {
+                let cell = self.make_node(&suffix(key, pos), pos, leaf, gas)?;
+                return Ok((cell, leaf.extra().clone()))
+            }
+        };
+        let (label, node) = self.load_node(cell, pos, gas)?;
+        match Self::diverges(&label, key, pos)? {
+            None => match node {
+                Node::Leaf { .. } => {
+                    let cell = self.make_node(&label, pos, leaf, gas)?;
+                    Ok((cell, leaf.extra().clone()))
+                }
+                Node::Fork { children: [left, right], .. } => {
+                    let fork = pos + label.remaining_bits();
+                    let children = match key.get_bit(fork)? {
+                        false => [self.set_node(Some(left), fork + 1, key, leaf, gas)?, self.child(right, fork + 1, gas)?],
+                        true => [self.child(left, fork + 1, gas)?, self.set_node(Some(right), fork + 1, key, leaf, gas)?]
+                    };
+                    self.make_fork(&label, pos, children, gas)
+                }
+            }
+            Some(common) => {
+                // node is split by new fork with the common part of label
+                let fork = pos + common;
+                let existing = self.make_node(&suffix(&label, common + 1), fork + 1, &node, gas)?;
+                let existing = (existing, node.extra().clone());
+                let added = self.make_node(&suffix(key, fork + 1), fork + 1, leaf, gas)?;
+                let added = (added, leaf.extra().clone());
+                let children = match key.get_bit(fork)? {
+                    false => [added, existing],
+                    true => [existing, added]
+                };
+                let mut prefix = label.clone();
+                prefix.shrink_data(..common);
+                self.make_fork(&prefix, pos, children, gas)
+            }
+        }
+    }
+
+    /// Sets value with extra for the key, extras of forks on the path are recalculated
+    pub fn set(
+        &mut self,
+        key: &SliceData,
+        value: &SliceData,
+        extra: &E::Extra,
+        gas: &mut dyn GasConsumer,
+    ) -> Result<()> {
+        self.check_key(key)?;
+        let leaf = Node::Leaf { extra: extra.clone(), value: value.clone() };
+        let (root, root_extra) = self.set_node(self.root.clone(), 0, key, &leaf, gas)?;
+        self.root = Some(root);
+        self.root_extra = Some(root_extra);
+        Ok(())
+    }
+
+    fn remove_node(
+        &self,
+        cell: Cell,
+        pos: usize,
+        key: &SliceData,
+        gas: &mut dyn GasConsumer,
+    ) -> Result<Option<Removed<E::Extra>>> {
+        let (label, node) = self.load_node(cell, pos, gas)?;
+        if Self::diverges(&label, key, pos)?.is_some() {
+            return Ok(None)
+        }
+        let children = match node {
+            Node::Leaf { extra, value } => return Ok(Some((None, extra, value))),
+            Node::Fork { children, .. } => children
+        };
+        let fork = pos + label.remaining_bits();
+        let bit = key.get_bit(fork)? as usize;
+        let (child, removed, value) = match self.remove_node(children[bit].clone(), fork + 1, key, gas)? {
+            Some(removed) => removed,
+            None => return Ok(None)
+        };
+        let sibling = children[1 - bit].clone();
+        let subtree = match child {
+            Some(child) => {
+                let sibling = self.child(sibling, fork + 1, gas)?;
+                let children = match bit {
+                    0 => [child, sibling],
+                    _ => [sibling, child]
+                };
+                self.make_fork(&label, pos, children, gas)?
+            }
+            None => {
+                // fork is replaced by remaining child with joined label
+                let (sibling_label, sibling) = self.load_node(sibling, fork + 1, gas)?;
+                let mut joined = label.as_builder();
+                joined.append_bit_bool(bit == 0)?;
+                joined.append_bytestring(&sibling_label)?;
+                let cell = self.make_node(&SliceData::load_builder(joined)?, pos, &sibling, gas)?;
+                (cell, sibling.extra().clone())
+            }
+        };
+        Ok(Some((Some(subtree), removed, value)))
+    }
+
+    /// Removes the key returning its value and extra
+    pub fn remove(&mut self, key: &SliceData, gas: &mut dyn GasConsumer) -> Result<Option<(SliceData, E::Extra)>> {
+        self.check_key(key)?;
+        let root = match &self.root {
+            Some(root) => root.clone(),
+            None => return Ok(None)
+        };
+        match self.remove_node(root, 0, key, gas)? {
+            Some((subtree, extra, value)) => {
+                match subtree {
+                    Some((root, root_extra)) => {
+                        self.root = Some(root);
+                        self.root_extra = Some(root_extra);
+                    }
+                    None => {
+                        self.root = None;
+                        self.root_extra = Some(self.format.empty());
+                    }
+                }
+                Ok(Some((value, extra)))
+            }
+            None => Ok(None)
+        }
+    }
+}
+
+/// Builds augmented dictionary from (key, value, extra) entries, e.g. to be used in tests
+pub fn aug_dict_from_entries<E: AugExtra>(
+    bit_len: usize,
+    format: E,
+    entries: impl IntoIterator<Item = (SliceData, SliceData, E::Extra)>,
+) -> Result<AugDict<E>> {
+    let mut dict = AugDict::with_root(bit_len, format, None);
+    for (key, value, extra) in entries {
+        dict.set(&key, &value, &extra, &mut NoGas)?;
+    }
+    Ok(dict)
+}
@@ -12,7 +12,7 @@
 */
 
 use crate::{
-    executor::gas::gas_state::Gas,
+    executor::gas::gas_state::GasPrices,
     stack::{SliceData, Stack, StackItem, savelist::SaveList},
     types::ResultOpt,
 };
@@ -87,6 +87,27 @@ impl ContinuationData {
         &self.code
     }
 
+    /// Name of continuation type as it is printed by Fift
+    pub fn type_name(&self) -> &'static str {
+        match self.type_of {
+            ContinuationType::AgainLoopBody(_) => "vmc_again",
+            ContinuationType::TryCatch => "vmc_trycatch",
+            ContinuationType::CatchRevert(_) => "vmc_catchrevert",
+            ContinuationType::Ordinary => "vmc_std",
+            ContinuationType::PushInt(_) => "vmc_pushint",
+            ContinuationType::Quit(_) => "vmc_quit",
+            ContinuationType::RepeatLoopBody(..) => "vmc_repeat",
+            ContinuationType::UntilLoopCondition(_) => "vmc_until",
+            ContinuationType::WhileLoopCondition(..) => "vmc_while_cond",
+            ContinuationType::ExcQuit => "vmc_quit_exc",
+        }
+    }
+
+    /// Control registers saved in continuation with their values
+    pub fn saved_ctrls(&self) -> impl Iterator<Item = (usize, &StackItem)> {
+        SaveList::REGS.iter().filter_map(|&index| Some((index, self.savelist.get(index)?)))
+    }
+
     pub fn code_mut(&mut self) -> &mut SliceData {
         &mut self.code
     }
@@ -317,7 +338,7 @@ impl ContinuationData {
 }
 
 impl ContinuationData {
-    pub fn serialize_old(&self) -> Result<(BuilderData, i64)> {
+    pub fn serialize_old(&self, prices: &GasPrices) -> Result<(BuilderData, i64)> {
         let mut gas = 0;
         let mut builder = BuilderData::new();
         match &self.type_of {
@@ -367,11 +388,11 @@ impl ContinuationData {
         let mut stack_list = BuilderData::new();
         for item in self.stack.iter().rev() {
             let mut cons = BuilderData::new();
-            let (serialized, gas2) = item.serialize_old()?;
+            let (serialized, gas2) = item.serialize_old(prices)?;
             gas += gas2;
             cons.append_builder(&serialized)?;
             cons.checked_append_reference(stack_list.into_cell()?)?;
-            gas += Gas::finalize_price();
+            gas += prices.cell_create;
             stack_list = cons;
         }
         stack.append_builder(&stack_list)?;
@@ -383,7 +404,7 @@ impl ContinuationData {
             builder.append_bit_one()?;
             builder.append_builder(&stack)?;
         }
-        let (serialized, gas2) = self.savelist.serialize_old()?;
+        let (serialized, gas2) = self.savelist.serialize_old(prices)?;
         gas += gas2;
         builder.append_builder(&serialized)?;
         builder.append_bits(0, 16)?; // codepage
@@ -391,7 +412,7 @@ impl ContinuationData {
         Ok((builder, gas))
     }
 
-    pub fn deserialize_old(slice: &mut SliceData) -> Result<(Self, i64)> {
+    pub fn deserialize_old(slice: &mut SliceData, prices: &GasPrices) -> Result<(Self, i64)> {
         let mut gas = 0;
         let cont_type = match slice.get_next_int(2)? {
             0 => Ok(ContinuationType::Ordinary),
@@ -418,10 +439,10 @@ impl ContinuationData {
                     0 => {
                         let mut cond_slice = SliceData::load_cell(slice.checked_drain_reference()?)?;
                         let cond: SliceData = slice_deserialize(&mut cond_slice)?;
-                        gas += Gas::load_cell_price(true);
+                        gas += prices.load_cell(true);
                         let mut body_slice = SliceData::load_cell(slice.checked_drain_reference()?)?;
                         let body: SliceData = slice_deserialize(&mut body_slice)?;
-                        gas += Gas::load_cell_price(true);
+                        gas += prices.load_cell(true);
                         Ok(ContinuationType::WhileLoopCondition(body, cond))
                     }
                     1 => {
@@ -455,13 +476,13 @@ impl ContinuationData {
                 let depth = slice.get_next_int(24)? as usize;
                 let mut stack = vec![];
                 if depth > 0 {
-                    let (item, gas2) = StackItem::deserialize_old(slice)?;
+                    let (item, gas2) = StackItem::deserialize_old(slice, prices)?;
                     gas += gas2;
                     stack.push(item);
                     let mut cell = slice.checked_drain_reference()?;
                     for _ in 1..depth {
                         let mut slice = SliceData::load_cell(cell)?;
-                        let (item, gas2) = StackItem::deserialize_old(&mut slice)?;
+                        let (item, gas2) = StackItem::deserialize_old(&mut slice, prices)?;
                         stack.push(item);
                         gas += gas2;
                         cell = slice.checked_drain_reference().unwrap_or_default();
@@ -470,11 +491,11 @@ impl ContinuationData {
                 stack
             }
         };
-        let (save, gas2) = SaveList::deserialize_old(slice)?;
+        let (save, gas2) = SaveList::deserialize_old(slice, prices)?;
         gas += gas2;
         slice.get_next_int(16)?; // codepage
         let code = slice_deserialize(slice)?;
-        gas += Gas::load_cell_price(true);
+        gas += prices.load_cell(true);
         Ok((ContinuationData {
             code,
             nargs,
@@ -487,6 +508,114 @@ impl ContinuationData {
     }
 }
 
+/// Layout of serialized continuation
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ContinuationFormat {
+    /// Layout of STCONT without CapStcontNewFormat
+    Old = 0,
+    /// Layout of STCONT with CapStcontNewFormat
+    New = 1,
+}
+
+impl ContinuationFormat {
+    fn from_version(version: usize) -> Result<Self> {
+        match version {
+            0 => Ok(ContinuationFormat::Old),
+            1 => Ok(ContinuationFormat::New),
+            _ => err!(ExceptionCode::UnknownError, "unknown continuation format version {}", version)
+        }
+    }
+}
+
+// 0101 is not a valid continuation type prefix in any format,
+// so framed continuation can not be confused with unframed one
+const FRAME_TAG: usize = 0b0101;
+
+impl ContinuationData {
+    /// Serializes continuation in given format returning gas not charged via gas_consumer,
+    /// the old format is paid by prices
+    pub fn serialize_with_format(
+        &self,
+        format: ContinuationFormat,
+        gas_consumer: &mut dyn GasConsumer,
+        prices: &GasPrices
+    ) -> Result<(BuilderData, i64)> {
+        match format {
+            ContinuationFormat::Old => self.serialize_old(prices),
+            ContinuationFormat::New => Ok((self.serialize(gas_consumer)?, 0))
+        }
+    }
+
+    /// Deserializes continuation in given format returning gas not charged via gas_consumer,
+    /// the old format is paid by prices
+    pub fn deserialize_with_format(
+        slice: &mut SliceData,
+        format: ContinuationFormat,
+        gas_consumer: &mut dyn GasConsumer,
+        prices: &GasPrices
+    ) -> Result<(Self, i64)> {
+        match format {
+            ContinuationFormat::Old => ContinuationData::deserialize_old(slice, prices),
+            ContinuationFormat::New => Ok((ContinuationData::deserialize(slice, gas_consumer)?, 0))
+        }
+    }
+
+    /// Serializes continuation prefixed with format version: frame$0101 version:(## 4) cont
+    pub fn serialize_framed(
+        &self,
+        format: ContinuationFormat,
+        gas_consumer: &mut dyn GasConsumer,
+        prices: &GasPrices
+    ) -> Result<(BuilderData, i64)> {
+        let mut builder = BuilderData::new();
+        builder.append_bits(FRAME_TAG, 4)?;
+        builder.append_bits(format as usize, 4)?;
+        let (cont, gas) = self.serialize_with_format(format, gas_consumer, prices)?;
+        builder.append_builder(&cont)?;
+        Ok((builder, gas))
+    }
+
+    /// Deserializes framed or unframed continuation detecting its format.
+    /// Unframed data is tried in the default format first, then in the other one
+    pub fn deserialize_any(
+        slice: &mut SliceData,
+        default: ContinuationFormat,
+        gas_consumer: &mut dyn GasConsumer,
+        prices: &GasPrices
+    ) -> Result<(Self, ContinuationFormat, i64)> {
+        let mut frame = slice.clone();
+        if frame.remaining_bits() >= 8 && frame.get_next_int(4)? as usize == FRAME_TAG {
+            let format = ContinuationFormat::from_version(frame.get_next_int(4)? as usize)?;
+            *slice = frame;
+            let (cont, gas) = ContinuationData::deserialize_with_format(slice, format, gas_consumer, prices)?;
+            return Ok((cont, format, gas))
+        }
+        let other = match default {
+            ContinuationFormat::Old => ContinuationFormat::New,
+            ContinuationFormat::New => ContinuationFormat::Old
+        };
+        let mut attempt = slice.clone();
+        if let Ok((cont, gas)) = ContinuationData::deserialize_with_format(&mut attempt, default, gas_consumer, prices) {
+            *slice = attempt;
+            return Ok((cont, default, gas))
+        }
+        let (cont, gas) = ContinuationData::deserialize_with_format(slice, other, gas_consumer, prices)?;
+        Ok((cont, other, gas))
+    }
+
+    /// Reads continuation in any format and writes it framed in the new format
+    pub fn migrate(
+        slice: &mut SliceData,
+        default: ContinuationFormat,
+        gas_consumer: &mut dyn GasConsumer,
+        prices: &GasPrices
+    ) -> Result<(BuilderData, i64)> {
+        let (cont, _, gas) = ContinuationData::deserialize_any(slice, default, gas_consumer, prices)?;
+        let (builder, gas2) = cont.serialize_framed(ContinuationFormat::New, gas_consumer, prices)?;
+        Ok((builder, gas + gas2))
+    }
+}
+
 impl Default for ContinuationData {
     fn default() -> Self {
         Self::new_empty()
@@ -14,6 +14,8 @@
 use crate::types::Status;
 use ever_block::{types::ExceptionCode};
 
+/// Reaction of arithmetic operation on NaN arguments, overflows and range check errors:
+/// signaling operations throw an exception, quiet ones produce NaN
 pub trait OperationBehavior {
     fn quiet() -> bool;
     fn name_prefix() -> Option<&'static str>;
@@ -22,7 +24,9 @@ pub trait OperationBehavior {
     fn on_range_check_error(file: &'static str, line: u32) -> Status;
 }
 
+/// Behavior of regular instructions
 pub struct Signaling {}
+/// Behavior of Q-prefixed instructions
 pub struct Quiet {}
 
 #[macro_export]
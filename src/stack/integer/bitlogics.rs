@@ -18,10 +18,14 @@ use crate::stack::integer::{
 use ever_block::Result;
 
 impl IntegerData {
+    /// Bitwise AND of two's complement values
     pub fn and<T>(&self, other: &IntegerData) -> Result<IntegerData>
     where
         T: OperationBehavior
     {
+        if let Some(result) = self.small_binary(other, |x, y| Some(x & y)) {
+            return Ok(result)
+        }
         binary_op::<T, _, _, _, _, _>(
             self,
             other,
@@ -31,10 +35,14 @@ impl IntegerData {
         )
     }
 
+    /// Bitwise OR of two's complement values
     pub fn or<T>(&self, other: &IntegerData) -> Result<IntegerData>
     where
         T: OperationBehavior
     {
+        if let Some(result) = self.small_binary(other, |x, y| Some(x | y)) {
+            return Ok(result)
+        }
         binary_op::<T, _, _, _, _, _>(
             self,
             other,
@@ -44,10 +52,14 @@ impl IntegerData {
         )
     }
 
+    /// Bitwise XOR of two's complement values
     pub fn xor<T>(&self, other: &IntegerData) -> Result<IntegerData>
     where
         T: OperationBehavior
     {
+        if let Some(result) = self.small_binary(other, |x, y| Some(x ^ y)) {
+            return Ok(result)
+        }
         binary_op::<T, _, _, _, _, _>(
             self,
             other,
@@ -57,10 +69,14 @@ impl IntegerData {
         )
     }
 
+    /// Bitwise NOT, equals to -x-1
     pub fn not<T>(&self) -> Result<IntegerData>
     where
         T: OperationBehavior
     {
+        if let Some(result) = self.small_unary(|x| Some(!x)) {
+            return Ok(result)
+        }
         unary_op::<T, _, _, _, _, _>(
             self,
             |x| !x,
@@ -69,6 +85,7 @@ impl IntegerData {
         )
     }
 
+    /// Multiplies value by 2^shift, result must fit into 257 bits
     pub fn shl<T>(&self, shift: usize) -> Result<IntegerData>
     where
         T: OperationBehavior
@@ -81,10 +98,15 @@ impl IntegerData {
         )
     }
 
+    /// Divides value by 2^shift rounding towards negative infinity
     pub fn shr<T>(&self, shift: usize) -> Result<IntegerData>
     where
         T: OperationBehavior
     {
+        // arithmetic shift rounds towards negative infinity as for Int
+        if let Some(result) = self.small_unary(|x| Some(x >> shift.min(127))) {
+            return Ok(result)
+        }
         unary_op::<T, _, _, _, _, _>(
             self,
             |x| x >> shift,
@@ -23,72 +23,42 @@ impl IntegerData {
     /// Constructs new IntegerData from u32 in a fastest way.
     #[inline]
     pub fn from_u32(value: u32) -> IntegerData {
-        if value == 0 {
-            return Self::zero();
-        }
-        IntegerData {
-            value: IntegerValue::Value(
-                Int::new(num::bigint::Sign::Plus, vec![value])
-            )
-        }
+        Self::small(value as i128)
     }
 
     /// Constructs new IntegerData from i32 in a fastest way.
     #[inline]
     pub fn from_i32(value: i32) -> IntegerData {
-        if value == 0 {
-            return Self::zero();
-        }
-        IntegerData {
-            value: IntegerValue::Value(
-                Int::new(
-                    if value < 0 {
-                        num::bigint::Sign::Minus
-                    } else {
-                        num::bigint::Sign::Plus
-                    }, vec![value.unsigned_abs()])
-            )
-        }
+        Self::small(value as i128)
     }
 
     /// Constructs new IntegerData from u64 in a fastest way.
     #[inline]
     pub fn from_u64(value: u64) -> IntegerData {
-        IntegerData {
-            value: IntegerValue::Value(
-                Int::from(value)
-            )
-        }
+        Self::small(value as i128)
     }
 
     /// Constructs new IntegerData from i64 in a fastest way.
     #[inline]
     pub fn from_i64(value: i64) -> IntegerData {
-        IntegerData {
-            value: IntegerValue::Value(
-                Int::from(value)
-            )
-        }
+        Self::small(value as i128)
     }
 
     /// Constructs new IntegerData from u128 in a fastest way.
     #[inline]
     pub fn from_u128(value: u128) -> IntegerData {
-        IntegerData {
-            value: IntegerValue::Value(
-                Int::from(value)
-            )
+        match i128::try_from(value) {
+            Ok(value) => Self::small(value),
+            Err(_) => IntegerData {
+                value: IntegerValue::Value(Int::from(value))
+            }
         }
     }
 
     /// Constructs new IntegerData from i128 in a fastest way.
     #[inline]
     pub fn from_i128(value: i128) -> IntegerData {
-        IntegerData {
-            value: IntegerValue::Value(
-                Int::from(value)
-            )
-        }
+        Self::small(value)
     }
 
     /// Constructs new IntegerData value from the given one of another supported type.
@@ -97,7 +67,7 @@ impl IntegerData {
         let bigint = value.into();
         match check_overflow(&bigint) {
             true => {
-                let value = IntegerValue::Value(bigint);
+                let value = IntegerValue::from_int(bigint);
                 Ok(IntegerData { value })
             }
             false => err!(ExceptionCode::IntegerOverflow)
@@ -109,7 +79,7 @@ impl IntegerData {
     #[inline]
     pub fn from_vec_le_unchecked(sign: num::bigint::Sign, digits: Vec<u32>) -> IntegerData {
         IntegerData {
-            value: IntegerValue::Value(Int::new(sign, digits))
+            value: IntegerValue::from_int(Int::new(sign, digits))
         }
     }
 
@@ -122,7 +92,7 @@ impl IntegerData {
             return err!(ExceptionCode::IntegerOverflow);
         }
         Ok(IntegerData {
-            value: IntegerValue::Value(bigint)
+            value: IntegerValue::from_int(bigint)
         })
     }
 
@@ -141,6 +111,14 @@ impl IntegerData {
     {
         match self.value {
             IntegerValue::NaN => err!(ExceptionCode::RangeCheckError, "not a number"),
+            IntegerValue::Small(value) => {
+                T::from_small(value).and_then(|ret| {
+                    if *range.start() > ret || *range.end() < ret {
+                        return err!(ExceptionCode::RangeCheckError, "{} is not in the range {}..={}", ret, range.start(), range.end());
+                    }
+                    Ok(ret)
+                })
+            }
             IntegerValue::Value(ref value) => {
                 T::from_int(value).and_then(|ret| {
                     if *range.start() > ret || *range.end() < ret {
@@ -157,6 +135,13 @@ impl IntegerData {
     pub fn take_value_of<T>(&self, convert: impl Fn(&Int) -> Option<T>) -> Result<T> {
         match self.value {
             IntegerValue::NaN => err!(ExceptionCode::IntegerOverflow, "not a number"),
+            IntegerValue::Small(value) => {
+                if let Some(value) = convert(&Int::from(value)) {
+                    Ok(value)
+                } else {
+                    err!(ExceptionCode::RangeCheckError, "cannot convert {}", value)
+                }
+            }
             IntegerValue::Value(ref value) => {
                 if let Some(value) = convert(value) {
                     Ok(value)
@@ -168,6 +153,28 @@ impl IntegerData {
     }
 }
 
+impl IntegerData {
+    /// Constructs value from 256-bit two's complement big-endian integer.
+    pub fn from_i256_bytes_be(bytes: &[u8; 32]) -> IntegerData {
+        IntegerData {
+            value: IntegerValue::from_int(Int::from_signed_bytes_be(bytes))
+        }
+    }
+
+    /// Returns value as 256-bit two's complement big-endian integer.
+    /// Range check error is returned for NaN and values not fitting into 256 signed bits.
+    pub fn to_i256_bytes_be(&self) -> Result<[u8; 32]> {
+        if self.is_nan() || !self.fits_in(256)? {
+            return err!(ExceptionCode::RangeCheckError, "{} does not fit into 256 bits", self)
+        }
+        let bytes = self.take_value_of(|x| Some(x.to_signed_bytes_be()))?;
+        let fill = if self.is_neg() { 0xFF } else { 0 };
+        let mut result = [fill; 32];
+        result[32 - bytes.len()..].copy_from_slice(&bytes);
+        Ok(result)
+    }
+}
+
 impl std::str::FromStr for IntegerData {
     type Err = Error;
     fn from_str(s: &str) -> Result<Self> {
@@ -263,6 +270,13 @@ pub trait FromInt {
     fn from_int(value: &Int) -> Result<Self>
     where
         Self: std::marker::Sized;
+
+    fn from_small(value: i128) -> Result<Self>
+    where
+        Self: std::marker::Sized
+    {
+        Self::from_int(&Int::from(value))
+    }
 }
 
 macro_rules! auto_from_int {
@@ -277,6 +291,14 @@ macro_rules! auto_from_int {
                         )
                     })
                 }
+                fn from_small(value: i128) -> Result<$to> {
+                    <dyn num::ToPrimitive>::$f(&value).ok_or_else(|| {
+                        exception!(
+                            ExceptionCode::RangeCheckError,
+                            "{} cannot be converted to {}", value, std::any::type_name::<$to>()
+                        )
+                    })
+                }
             }
         )*
     }
@@ -22,6 +22,8 @@ impl IntegerData {
     pub fn to_str_radix(&self, radix: u32) -> String {
         match self.value {
             IntegerValue::NaN => "NaN".to_string(),
+            IntegerValue::Small(value) if radix == 10 => value.to_string(),
+            IntegerValue::Small(value) => super::Int::from(value).to_str_radix(radix),
             IntegerValue::Value(ref value) => value.to_str_radix(radix),
         }
     }
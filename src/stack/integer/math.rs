@@ -11,10 +11,10 @@
 * limitations under the License.
 */
 
-use std::ops::AddAssign;
+use std::borrow::Cow;
 
 use crate::stack::integer::{
-    IntegerData, IntegerValue, behavior::OperationBehavior,
+    Int, IntegerData, IntegerValue, behavior::OperationBehavior,
     utils::{
         binary_op, construct_double_nan, construct_single_nan, process_double_result,
         process_single_result, unary_op
@@ -25,6 +25,7 @@ use ever_block::{Result, Status};
 
 // [x / y] -> (q, r)  :  q*y + r = x  :  |r| < |y|
 #[derive(Copy, Clone, Eq, PartialEq)]
+/// Rounding mode of division
 pub enum Round {
     Ceil = 0,                    // r and y have opposite sign
     FloorToNegativeInfinity = 1, // r has the same sign as y
@@ -33,8 +34,34 @@ pub enum Round {
 }
 
 impl IntegerData {
+    /// Applies operation to inline value, None means the slow path must be taken.
+    /// Results of operations on i128 always fit into 257 bits, so no overflow check is needed
+    #[inline]
+    pub(super) fn small_unary(&self, op: impl Fn(i128) -> Option<i128>) -> Option<IntegerData> {
+        match self.value {
+            IntegerValue::Small(x) => op(x).map(IntegerData::small),
+            _ => None
+        }
+    }
+
+    /// Applies operation to inline values, None means the slow path must be taken.
+    #[inline]
+    pub(super) fn small_binary(
+        &self,
+        other: &IntegerData,
+        op: impl Fn(i128, i128) -> Option<i128>
+    ) -> Option<IntegerData> {
+        match (&self.value, &other.value) {
+            (IntegerValue::Small(x), IntegerValue::Small(y)) => op(*x, *y).map(IntegerData::small),
+            _ => None
+        }
+    }
+
     /// Creates and returns a copy of the same value with a sign changed to an opposite.
     pub fn neg<T: OperationBehavior>(&self) -> Result<IntegerData> {
+        if let Some(result) = self.small_unary(i128::checked_neg) {
+            return Ok(result)
+        }
         unary_op::<T, _, _, _, _, _>(
             self,
             |x| -x,
@@ -43,7 +70,11 @@ impl IntegerData {
         )
     }
 
+    /// Sum of values, result must fit into 257 bits as results of all operations
     pub fn add<T: OperationBehavior>(&self, other: &IntegerData) -> Result<IntegerData> {
+        if let Some(result) = self.small_binary(other, i128::checked_add) {
+            return Ok(result)
+        }
         binary_op::<T, _, _, _, _, _>(
             self,
             other,
@@ -53,32 +84,23 @@ impl IntegerData {
         )
     }
 
+    /// Adds other value in place
     pub fn add_assign<T: OperationBehavior>(&mut self, other: &IntegerData) -> Status {
-        let lhs = match self.value {
-            IntegerValue::NaN => {
-                on_nan_parameter!(T)?;
-                *self = construct_single_nan();
-                return Ok(())
-            },
-            IntegerValue::Value(ref mut v) => v,
-        };
-        let rhs = match other.value {
-            IntegerValue::NaN => {
-                on_nan_parameter!(T)?;
-                *self = construct_single_nan();
+        if let (IntegerValue::Small(x), IntegerValue::Small(y)) = (&mut self.value, &other.value) {
+            if let Some(result) = x.checked_add(*y) {
+                *x = result;
                 return Ok(())
-            },
-            IntegerValue::Value(ref v) => v,
-        };
-        lhs.add_assign(rhs);
-        if !super::utils::check_overflow(lhs) {
-            on_integer_overflow!(T)?;
-            *self = construct_single_nan();
+            }
         }
+        *self = self.add::<T>(other)?;
         Ok(())
     }
 
+    /// Sum with small constant
     pub fn add_i8<T: OperationBehavior>(&self, other: &i8) -> Result<IntegerData> {
+        if let Some(result) = self.small_unary(|x| x.checked_add(*other as i128)) {
+            return Ok(result)
+        }
         unary_op::<T, _, _, _, _, _>(
             self,
             |x| x + other,
@@ -87,7 +109,11 @@ impl IntegerData {
         )
     }
 
+    /// Difference of values
     pub fn sub<T: OperationBehavior>(&self, other: &IntegerData) -> Result<IntegerData> {
+        if let Some(result) = self.small_binary(other, i128::checked_sub) {
+            return Ok(result)
+        }
         binary_op::<T, _, _, _, _, _>(
             self,
             other,
@@ -97,7 +123,11 @@ impl IntegerData {
         )
     }
 
+    /// Difference with small constant
     pub fn sub_i8<T: OperationBehavior>(&self, other: &i8) -> Result<IntegerData> {
+        if let Some(result) = self.small_unary(|x| x.checked_sub(*other as i128)) {
+            return Ok(result)
+        }
         unary_op::<T, _, _, _, _, _>(
             self,
             |x| x - other,
@@ -106,7 +136,11 @@ impl IntegerData {
         )
     }
 
+    /// Product of values
     pub fn mul<T: OperationBehavior>(&self, other: &IntegerData) -> Result<IntegerData> {
+        if let Some(result) = self.small_binary(other, i128::checked_mul) {
+            return Ok(result)
+        }
         binary_op::<T, _, _, _, _, _>(
             self,
             other,
@@ -116,6 +150,7 @@ impl IntegerData {
         )
     }
 
+    /// Product of values divided by 2^256 rounding towards negative infinity
     pub fn mul_shr256<T: OperationBehavior>(&self, other: &IntegerData) -> Result<IntegerData> {
         binary_op::<T, _, _, _, _, _>(
             self,
@@ -126,7 +161,11 @@ impl IntegerData {
         )
     }
 
+    /// Product with small constant
     pub fn mul_i8<T: OperationBehavior>(&self, other: &i8) -> Result<IntegerData> {
+        if let Some(result) = self.small_unary(|x| x.checked_mul(*other as i128)) {
+            return Ok(result)
+        }
         unary_op::<T, _, _, _, _, _>(
             self,
             |x| x * other,
@@ -135,6 +174,7 @@ impl IntegerData {
         )
     }
 
+    /// Quotient and remainder with given rounding, division by zero overflows
     pub fn div<T: OperationBehavior>(&self, divisor: &IntegerData, rounding: Round)
                                      -> Result<(IntegerData, IntegerData)>
     {
@@ -146,12 +186,13 @@ impl IntegerData {
 
         unary_op::<T, _, _, _, _, _>(
             self,
-            |dividend| utils::divmod(dividend, divisor, rounding),
+            |dividend| utils::divmod(dividend, &divisor, rounding),
             construct_double_nan,
             process_double_result::<T, _>
         )
     }
 
+    /// Quotient and remainder of division by 2^shift with given rounding
     pub fn div_by_shift<T: OperationBehavior>(&self, shift: usize, rounding: Round)
                                               -> Result<(IntegerData, IntegerData)>
     {
@@ -164,6 +205,67 @@ impl IntegerData {
     }
 }
 
+// Fixed-point arithmetic ****************************************************
+
+impl IntegerData {
+    /// Fixed-point product x * y / 2^shift rounded to nearest, intermediate product is not bounded
+    pub fn mul_shr_round<T: OperationBehavior>(&self, other: &IntegerData, shift: usize) -> Result<IntegerData> {
+        binary_op::<T, _, _, _, _, _>(
+            self,
+            other,
+            |x, y| utils::div_by_shift(&(x * y), shift, Round::Nearest).0,
+            construct_single_nan,
+            process_single_result::<T, _>
+        )
+    }
+
+    /// Fixed-point quotient x * 2^shift / y rounded to nearest, division by zero overflows
+    pub fn shl_div_round<T: OperationBehavior>(&self, divisor: &IntegerData, shift: usize) -> Result<IntegerData> {
+        let divisor = extract_value!(T, divisor, construct_single_nan);
+        if divisor.is_zero() {
+            on_integer_overflow!(T)?;
+            return Ok(construct_single_nan());
+        }
+        unary_op::<T, _, _, _, _, _>(
+            self,
+            |x| utils::divmod(&(x << shift), &divisor, Round::Nearest).0,
+            construct_single_nan,
+            process_single_result::<T, _>
+        )
+    }
+
+    /// Square root rounded down, negative value overflows
+    pub fn sqrt<T: OperationBehavior>(&self) -> Result<IntegerData> {
+        if self.is_neg() {
+            on_integer_overflow!(T)?;
+            return Ok(construct_single_nan());
+        }
+        if let Some(result) = self.small_unary(|x| Some(num::integer::Roots::sqrt(&x))) {
+            return Ok(result)
+        }
+        unary_op::<T, _, _, _, _, _>(
+            self,
+            |x| x.sqrt(),
+            construct_single_nan,
+            process_single_result::<T, _>
+        )
+    }
+
+    /// Binary logarithm rounded down, zero and negative values overflow
+    pub fn log2<T: OperationBehavior>(&self) -> Result<IntegerData> {
+        if self.is_neg() || self.is_zero() {
+            on_integer_overflow!(T)?;
+            return Ok(construct_single_nan());
+        }
+        unary_op::<T, _, _, _, _, _>(
+            self,
+            |x| Int::from(x.bits() - 1),
+            construct_single_nan,
+            process_single_result::<T, _>
+        )
+    }
+}
+
 pub mod utils {
 
     use crate::stack::integer::{Int, math::Round};
@@ -22,8 +22,8 @@ use crate::{
 use ever_block::{BuilderData, ExceptionCode, Result, SliceData};
 
 use core::mem;
-use num_traits::{One, Signed, Zero};
-use std::cmp;
+use num_traits::{One, Signed, ToPrimitive, Zero};
+use std::{borrow::Cow, cmp};
 use std::cmp::Ordering;
 
 #[macro_use]
@@ -32,19 +32,39 @@ mod fmt;
 
 type Int = num::BigInt;
 
+// Values fitting in i128 are always kept inline to avoid allocations,
+// so Value holds only numbers out of i128 range and representation is unique
 #[derive(Clone, Debug, PartialEq, Eq)]
 enum IntegerValue {
     NaN,
+    Small(i128),
     Value(Int)
 }
 
+impl IntegerValue {
+    #[inline]
+    fn from_int(value: Int) -> Self {
+        match value.to_i128() {
+            Some(value) => IntegerValue::Small(value),
+            None => IntegerValue::Value(value)
+        }
+    }
+
+    #[inline]
+    fn as_int(&self) -> Option<Cow<Int>> {
+        match self {
+            IntegerValue::NaN => None,
+            IntegerValue::Small(value) => Some(Cow::Owned(Int::from(*value))),
+            IntegerValue::Value(value) => Some(Cow::Borrowed(value))
+        }
+    }
+}
+
 impl cmp::PartialOrd for IntegerValue {
     fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
         match (self, other) {
-            (IntegerValue::Value(x), IntegerValue::Value(y)) => {
-                x.partial_cmp(y)
-            },
-            _ => None
+            (IntegerValue::Small(x), IntegerValue::Small(y)) => x.partial_cmp(y),
+            _ => self.as_int()?.partial_cmp(&other.as_int()?)
         }
     }
 }
@@ -71,29 +91,26 @@ impl IntegerData {
     /// Constructs new (set to 0) value.
     #[inline]
     pub fn zero() -> IntegerData {
-        IntegerData {
-            value: IntegerValue::Value(Int::zero())
-        }
+        Self::small(0)
     }
 
     /// Constructs new (set to 1) value.
     #[inline]
     pub fn one() -> IntegerData {
-        IntegerData {
-            value: IntegerValue::Value(Int::one())
-        }
+        Self::small(1)
     }
 
     /// Constructs new (set to -1) value.
     #[inline]
     pub fn minus_one() -> IntegerData {
+        Self::small(-1)
+    }
+
+    /// Constructs value stored inline.
+    #[inline]
+    const fn small(value: i128) -> IntegerData {
         IntegerData {
-            value: IntegerValue::Value(
-                Int::from_biguint(
-                    num::bigint::Sign::Minus,
-                    num::BigUint::one()
-                )
-            )
+            value: IntegerValue::Small(value)
         }
     }
 
@@ -136,6 +153,7 @@ impl IntegerData {
     pub fn is_neg(&self) -> bool {
         match &self.value {
             IntegerValue::NaN => false,
+            IntegerValue::Small(value) => *value < 0,
             IntegerValue::Value(ref value) => value.is_negative()
         }
     }
@@ -143,6 +161,13 @@ impl IntegerData {
     pub fn check_neg(&self) -> Result<()> {
         match self.value {
             IntegerValue::NaN => err!(ExceptionCode::RangeCheckError, "not a number"),
+            IntegerValue::Small(value) => {
+                if value < 0 {
+                    err!(ExceptionCode::RangeCheckError, "{} is negative", value)
+                } else {
+                    Ok(())
+                }
+            }
             IntegerValue::Value(ref value) => {
                 if value.is_negative() {
                     err!(ExceptionCode::RangeCheckError, "{} is negative", value)
@@ -158,6 +183,7 @@ impl IntegerData {
     pub fn is_zero(&self) -> bool {
         match &self.value {
             IntegerValue::NaN => false,
+            IntegerValue::Small(value) => *value == 0,
             IntegerValue::Value(ref value) => value.is_zero()
         }
     }
@@ -165,19 +191,21 @@ impl IntegerData {
     /// constuct
     pub fn from_unsigned_bytes_be(data: impl AsRef<[u8]>) -> Self {
         Self {
-            value: IntegerValue::Value(Int::from_bytes_be(num::bigint::Sign::Plus, data.as_ref()))
+            value: IntegerValue::from_int(Int::from_bytes_be(num::bigint::Sign::Plus, data.as_ref()))
         }
     }
 
-    /// Compares value with another taking in account behavior of operation.
+    /// Compares value with another taking in account behavior of operation,
+    /// None is returned for NaN in quiet mode.
     #[inline]
-    pub(crate) fn compare<T: OperationBehavior>(&self, other: &IntegerData) -> ResultOpt<Ordering> {
+    pub fn compare<T: OperationBehavior>(&self, other: &IntegerData) -> ResultOpt<Ordering> {
         match (&self.value, &other.value) {
-            (IntegerValue::Value(l), IntegerValue::Value(r)) => Ok(Some(l.cmp(r))),
-            _ => {
+            (IntegerValue::Small(l), IntegerValue::Small(r)) => Ok(Some(l.cmp(r))),
+            (IntegerValue::NaN, _) | (_, IntegerValue::NaN) => {
                 on_nan_parameter!(T)?;
                 Ok(None)
             }
+            (l, r) => Ok(l.as_int().zip(r.as_int()).map(|(l, r)| l.cmp(&r)))
         }
     }
 
@@ -196,6 +224,10 @@ impl IntegerData {
     /// Determines a fewest bits necessary to express signed value.
     #[inline]
     pub fn bitsize(&self) -> Result<usize> {
+        if let IntegerValue::Small(value) = self.value {
+            let value = if value < 0 { !value } else { value };
+            return Ok(129 - value.leading_zeros() as usize)
+        }
         utils::process_value(self, |value| {
             Ok(utils::bitsize(value))
         })
@@ -204,6 +236,10 @@ impl IntegerData {
     /// Determines a fewest bits necessary to express unsigned value.
     #[inline]
     pub fn ubitsize(&self) -> Result<usize> {
+        if let IntegerValue::Small(value) = self.value {
+            debug_assert!(value >= 0);
+            return Ok(128 - value.unsigned_abs().leading_zeros() as usize)
+        }
         utils::process_value(self, |value| {
             debug_assert!(!value.is_negative());
             Ok(value.bits() as usize)
@@ -220,8 +256,12 @@ impl IntegerData {
         }
         T::new(bits).try_serialize(self)
     }
+
+    /// Returns big-endian bytes of non-negative value without leading zeros, zero is one zero byte.
+    /// Range check error is returned for NaN and negative values.
     pub fn as_unsigned_bytes_be(&self) -> Result<Vec<u8>> {
-        unimplemented!()
+        self.check_neg()?;
+        self.take_value_of(|x| Some(x.to_bytes_be().1))
     }
 }
 
@@ -246,12 +286,13 @@ pub mod utils {
             IntegerValue::NaN => {
                 err!(ExceptionCode::IntegerOverflow)
             }
+            IntegerValue::Small(value) => call_on_valid(&Int::from(value)),
             IntegerValue::Value(ref value) => call_on_valid(value),
         }
     }
 
     /// This macro extracts internal Int value from IntegerData using given NaN behavior
-    /// and NaN constructor.
+    /// and NaN constructor. Inline value is converted to Int.
     macro_rules! extract_value {
         ($T: ident, $v: ident, $nan_constructor: ident) => {
             match $v.value {
@@ -259,7 +300,8 @@ pub mod utils {
                     on_nan_parameter!($T)?;
                     return Ok($nan_constructor());
                 },
-                IntegerValue::Value(ref $v) => $v,
+                IntegerValue::Small($v) => Cow::Owned(Int::from($v)),
+                IntegerValue::Value(ref $v) => Cow::Borrowed($v),
             }
         }
     }
@@ -280,7 +322,7 @@ pub mod utils {
     {
         let lhs = extract_value!(T, lhs, nan_constructor);
 
-        result_processor(callback(lhs), nan_constructor)
+        result_processor(callback(&lhs), nan_constructor)
     }
 
     /// Binary operation. Checks lhs & rhs for NaN, unwraps them, calls closure and returns wrapped result.
@@ -301,7 +343,7 @@ pub mod utils {
         let lhs = extract_value!(T, lhs, nan_constructor);
         let rhs = extract_value!(T, rhs, nan_constructor);
 
-        result_processor(callback(lhs, rhs), nan_constructor)
+        result_processor(callback(&lhs, &rhs), nan_constructor)
     }
 
     #[inline]
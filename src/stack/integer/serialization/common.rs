@@ -11,6 +11,10 @@
 * limitations under the License.
 */
 
+use crate::stack::integer::{IntegerData, IntegerValue};
+use num::{bigint::Sign, BigInt, BigUint, Signed};
+use ever_block::{ExceptionCode, Result};
+
 /// Calculates fewest byte count needed to fit a given bit count.
 #[inline]
 pub fn bits_to_bytes(length_in_bits: usize) -> usize {
@@ -24,34 +28,141 @@ pub fn calc_excess_bits(length_in_bits: usize) -> usize {
 }
 
 
+/// Calculates fewest 32-bit word count needed to fit a given bit count.
+#[inline]
+pub fn bits_to_words(length_in_bits: usize) -> usize {
+    (length_in_bits + 31) >> 5
+}
+
 #[inline]
-fn get_fill(is_negative: bool) -> u8 {
+fn get_fill(is_negative: bool) -> u32 {
     if is_negative {
-        0xFF
+        u32::MAX
     } else {
         0
     }
 }
 
-/// Extends buffer, if needed (big-endian).
+/// Allocates words to hold an encoding of a given bit count padded up to whole bytes.
 #[inline]
-pub fn extend_buffer_be(mut buffer: Vec<u8>, length_in_bits: usize, is_negative: bool) -> Vec<u8> {
-    let new_len = bits_to_bytes(length_in_bits);
-    if new_len > buffer.len() {
-        let mut new_buffer = vec![get_fill(is_negative); new_len - buffer.len()];
-        new_buffer.append(&mut buffer);
-        new_buffer
+pub fn words_for_bits(length_in_bits: usize) -> Vec<u32> {
+    vec![0; bits_to_words(bits_to_bytes(length_in_bits) * 8)]
+}
+
+/// Writes two's complement representation of the value into words (least significant first)
+/// sign-extending it to the whole buffer. Value must be checked to fit in beforehand.
+pub fn to_words(value: &IntegerData, words: &mut [u32]) -> Result<()> {
+    match value.value {
+        IntegerValue::NaN => return err!(ExceptionCode::IntegerOverflow, "not a number"),
+        IntegerValue::Small(value) => {
+            let fill = get_fill(value < 0);
+            for (i, word) in words.iter_mut().enumerate() {
+                *word = if i < 4 { (value >> (i * 32)) as u32 } else { fill };
+            }
+        }
+        IntegerValue::Value(ref value) => {
+            words.fill(0);
+            for (word, digit) in words.iter_mut().zip(value.iter_u32_digits()) {
+                *word = digit;
+            }
+            if value.is_negative() {
+                negate_words(words);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Builds integer from two's complement words (least significant first).
+pub fn from_words(mut words: Vec<u32>, is_negative: bool) -> Result<IntegerData> {
+    let fill = get_fill(is_negative);
+    let word = |i: usize| words.get(i).copied().unwrap_or(fill);
+    if (word(3) >> 31 != 0) == is_negative && words.iter().skip(4).all(|w| *w == fill) {
+        let value = (0..4).fold(0u128, |acc, i| acc | (word(i) as u128) << (i * 32));
+        return Ok(IntegerData::small(value as i128))
+    }
+    let sign = if is_negative {
+        negate_words(&mut words);
+        Sign::Minus
     } else {
-        buffer
+        Sign::Plus
+    };
+    IntegerData::from(BigInt::from_biguint(sign, BigUint::new(words)))
+}
+
+fn negate_words(words: &mut [u32]) {
+    let mut carry = true;
+    for word in words {
+        (*word, carry) = (!*word).overflowing_add(carry as u32);
     }
 }
 
-/// Extends buffer, if needed (little-endian).
-#[inline]
-pub fn extend_buffer_le(mut buffer: Vec<u8>, length_in_bits: usize, is_negative: bool) -> Vec<u8> {
-    let new_len = bits_to_bytes(length_in_bits);
-    if new_len > buffer.len() {
-        buffer.resize(new_len, get_fill(is_negative));
+/// Shifts words left by less than word size.
+pub fn shl_words(words: &mut [u32], shift: usize) {
+    debug_assert!(shift < 32);
+    if shift == 0 {
+        return
+    }
+    let mut carry = 0;
+    for word in words {
+        let next = *word >> (32 - shift);
+        *word = (*word << shift) | carry;
+        carry = next;
     }
+}
+
+/// Shifts words right by less than word size propagating sign.
+pub fn shr_words(words: &mut [u32], shift: usize, is_negative: bool) {
+    debug_assert!(shift < 32);
+    if shift == 0 {
+        return
+    }
+    let mut carry = get_fill(is_negative) << (32 - shift);
+    for word in words.iter_mut().rev() {
+        let next = *word << (32 - shift);
+        *word = (*word >> shift) | carry;
+        carry = next;
+    }
+}
+
+/// Emits lowest length_in_bytes bytes of words in big-endian order.
+pub fn words_to_bytes_be(words: &[u32], length_in_bytes: usize) -> Vec<u8> {
+    let mut buffer = Vec::with_capacity(words.len() * 4);
+    for word in words.iter().rev() {
+        buffer.extend_from_slice(&word.to_be_bytes());
+    }
+    debug_assert!(buffer.len() >= length_in_bytes);
+    buffer.drain(..buffer.len() - length_in_bytes);
+    buffer
+}
+
+/// Emits lowest length_in_bytes bytes of words in little-endian order.
+pub fn words_to_bytes_le(words: &[u32], length_in_bytes: usize) -> Vec<u8> {
+    let mut buffer = Vec::with_capacity(words.len() * 4);
+    for word in words {
+        buffer.extend_from_slice(&word.to_le_bytes());
+    }
+    debug_assert!(buffer.len() >= length_in_bytes);
+    buffer.truncate(length_in_bytes);
     buffer
 }
+
+/// Reads big-endian bytes into words (least significant first), sign-extending the top word.
+pub fn words_from_bytes_be(data: &[u8], is_negative: bool) -> Vec<u32> {
+    let fill = get_fill(is_negative).to_be_bytes();
+    data.rchunks(4).map(|chunk| {
+        let mut word = fill;
+        word[4 - chunk.len()..].copy_from_slice(chunk);
+        u32::from_be_bytes(word)
+    }).collect()
+}
+
+/// Reads little-endian bytes into words (least significant first), sign-extending the top word.
+pub fn words_from_bytes_le(data: &[u8], is_negative: bool) -> Vec<u32> {
+    let fill = get_fill(is_negative).to_le_bytes();
+    data.chunks(4).map(|chunk| {
+        let mut word = fill;
+        word[..chunk.len()].copy_from_slice(chunk);
+        u32::from_le_bytes(word)
+    }).collect()
+}
@@ -25,11 +25,13 @@ mod signed_big_endian;
 mod unsigned_big_endian;
 mod signed_little_endian;
 mod unsigned_little_endian;
+mod var_integer;
 
 pub use self::unsigned_little_endian::UnsignedIntegerLittleEndianEncoding;
 pub use self::unsigned_big_endian::UnsignedIntegerBigEndianEncoding;
 pub use self::signed_big_endian::SignedIntegerBigEndianEncoding;
 pub use self::signed_little_endian::SignedIntegerLittleEndianEncoding;
+pub use self::var_integer::VarIntegerEncoding;
 
 #[cfg(test)]
 #[path = "tests/test_integer_encoding.rs"]
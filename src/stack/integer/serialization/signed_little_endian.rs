@@ -15,7 +15,10 @@ use crate::{
     stack::{
         integer::{
             serialization::{
-                common::extend_buffer_le,
+                common::{
+                    bits_to_bytes, from_words, to_words, words_for_bits, words_from_bytes_le,
+                    words_to_bytes_le
+                },
                 Encoding,
             },
             IntegerData,
@@ -23,7 +26,6 @@ use crate::{
         serialization::{Deserializer, Serializer},
     },
 };
-use num::{bigint::ToBigInt, Signed};
 use ever_block::{BuilderData, ExceptionCode, Result};
 
 pub struct SignedIntegerLittleEndianEncoding {
@@ -47,9 +49,9 @@ impl Serializer<IntegerData> for SignedIntegerLittleEndianEncoding {
             return err!(ExceptionCode::RangeCheckError, "{} is not fit in {}", value, self.length_in_bits)
        }
 
-        let value = value.take_value_of(|x| x.to_bigint())?;
-        let mut bytes = value.to_signed_bytes_le();
-        bytes = extend_buffer_le(bytes, self.length_in_bits, value.is_negative());
+        let mut words = words_for_bits(self.length_in_bits);
+        to_words(value, &mut words)?;
+        let bytes = words_to_bytes_le(&words, bits_to_bytes(self.length_in_bits));
 
         BuilderData::with_raw(bytes, self.length_in_bits)
     }
@@ -59,7 +61,8 @@ impl Deserializer<IntegerData> for SignedIntegerLittleEndianEncoding {
     fn deserialize(&self, data: &[u8]) -> IntegerData {
         debug_assert!(data.len() * 8 >= self.length_in_bits);
 
-        let value = num::BigInt::from_signed_bytes_le(data);
-        IntegerData::from(value).expect("Should always fit")
+        let is_negative = data.last().map_or(false, |byte| byte & 0x80 != 0);
+        let words = words_from_bytes_le(data, is_negative);
+        from_words(words, is_negative).expect("Should always fit")
     }
 }
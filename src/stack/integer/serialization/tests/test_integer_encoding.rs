@@ -13,7 +13,7 @@
 
 use crate::stack::{
     BuilderData, SliceData,
-    integer::{IntegerData, serialization::{Encoding, SignedIntegerBigEndianEncoding}},
+    integer::{IntegerData, serialization::{Encoding, SignedIntegerBigEndianEncoding, VarIntegerEncoding}},
     serialization::{Serializer, Deserializer}
 };
 
@@ -72,3 +72,29 @@ fn encoding_two_negative_bytes() {
     let value = encoding.deserialize(&a.get_next_bits(16).unwrap());
     assert_eq!(src, value);
 }
+
+#[test]
+fn encoding_var_integer() {
+    let grams = VarIntegerEncoding::unsigned(16);
+    let a = grams.try_serialize(&IntegerData::from_u32(0x1234)).unwrap();
+    assert_eq!(a, BuilderData::with_raw(vec![0x21, 0x23, 0x40], 20).unwrap());
+    let mut slice = SliceData::load_builder(a).unwrap();
+    assert_eq!(grams.read(&mut slice).unwrap(), IntegerData::from_u32(0x1234));
+    assert_eq!(slice.remaining_bits(), 0);
+
+    // zero has no value bytes
+    assert_eq!(grams.try_serialize(&IntegerData::zero()).unwrap(), BuilderData::with_raw(vec![0], 4).unwrap());
+    assert!(grams.try_serialize(&IntegerData::from_i32(-1)).is_err());
+
+    let signed = VarIntegerEncoding::signed(32);
+    let a = signed.try_serialize(&IntegerData::from_i32(-2)).unwrap();
+    assert_eq!(a, BuilderData::with_raw(vec![0x0F, 0xF0], 13).unwrap());
+    let mut slice = SliceData::load_builder(a).unwrap();
+    assert_eq!(signed.read(&mut slice).unwrap(), IntegerData::from_i32(-2));
+
+    // 31 bytes at most
+    let max = IntegerData::from_str_radix(&"F".repeat(62), 16).unwrap();
+    assert_eq!(VarIntegerEncoding::unsigned(32).try_serialize(&max).unwrap().bits_used(), 5 + 248);
+    let too_big = IntegerData::from_str_radix(&"1".repeat(63), 16).unwrap();
+    assert!(VarIntegerEncoding::unsigned(32).try_serialize(&too_big).is_err());
+}
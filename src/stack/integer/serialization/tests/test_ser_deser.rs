@@ -53,4 +53,50 @@ where T: Encoding
     let resulted = encoding.deserialize(&data.get_next_bits(46).unwrap());
 
     assert_eq!(initial, resulted);
-}
\ No newline at end of file
+}
+
+fn reference_be(value: &num::BigInt, length_in_bits: usize) -> Vec<u8> {
+    let len = (length_in_bits + 7) / 8;
+    let value = value << (len * 8 - length_in_bits);
+    let mut bytes = value.to_signed_bytes_be();
+    let fill = if value < num::BigInt::from(0) { 0xFF } else { 0 };
+    while bytes.len() < len {
+        bytes.insert(0, fill);
+    }
+    bytes.split_off(bytes.len() - len)
+}
+
+#[test]
+fn test_word_encoding_matches_reference() {
+    let mut values = vec![num::BigInt::from(0)];
+    for k in 0..=256 {
+        let power = num::BigInt::from(1) << k;
+        for value in [&power - 1, power.clone(), &power + 1] {
+            values.push(-&value);
+            values.push(value);
+        }
+    }
+    for length_in_bits in 1..=257 {
+        let signed = SignedIntegerBigEndianEncoding::new(length_in_bits);
+        let unsigned = UnsignedIntegerBigEndianEncoding::new(length_in_bits);
+        for value in &values {
+            let integer = IntegerData::from(value.clone()).unwrap();
+            let expected = reference_be(value, length_in_bits);
+            if let Ok(builder) = signed.try_serialize(&integer) {
+                assert_eq!(builder.data(), &expected[..], "{} in {} bits", value, length_in_bits);
+                assert_eq!(signed.deserialize(builder.data()), integer);
+            }
+            if let Ok(builder) = unsigned.try_serialize(&integer) {
+                assert_eq!(builder.data(), &expected[..], "{} in {} bits", value, length_in_bits);
+                assert_eq!(unsigned.deserialize(builder.data()), integer);
+            }
+            let mut expected = expected;
+            expected.reverse();
+            if length_in_bits % 8 == 0 {
+                if let Ok(builder) = SignedIntegerLittleEndianEncoding::new(length_in_bits).try_serialize(&integer) {
+                    assert_eq!(builder.data(), &expected[..]);
+                }
+            }
+        }
+    }
+}
@@ -15,7 +15,10 @@ use crate::{
     stack::{
         integer::{
             serialization::{
-                common::{calc_excess_bits, extend_buffer_be},
+                common::{
+                    bits_to_bytes, calc_excess_bits, from_words, shl_words, shr_words, to_words,
+                    words_for_bits, words_from_bytes_be, words_to_bytes_be
+                },
                 Encoding,
             },
             IntegerData,
@@ -48,16 +51,15 @@ impl Serializer<IntegerData> for UnsignedIntegerBigEndianEncoding {
             return err!(ExceptionCode::RangeCheckError, "{} cannot fit in {}", value, self.length_in_bits)
         }
 
-        let mut value = value.take_value_of(|x| x.to_biguint())?;
+        let mut words = words_for_bits(self.length_in_bits);
+        to_words(value, &mut words)?;
 
         let excess_bits = calc_excess_bits(self.length_in_bits);
         if excess_bits != 0 {
-            value <<= 8 - excess_bits;
+            shl_words(&mut words, 8 - excess_bits);
         }
 
-        let mut buffer = value.to_bytes_be();
-        buffer = extend_buffer_be(buffer, self.length_in_bits, false);
-
+        let buffer = words_to_bytes_be(&words, bits_to_bytes(self.length_in_bits));
         BuilderData::with_raw(buffer, self.length_in_bits)
     }
 }
@@ -66,12 +68,12 @@ impl Deserializer<IntegerData> for UnsignedIntegerBigEndianEncoding {
     fn deserialize(&self, data: &[u8]) -> IntegerData {
         debug_assert!(data.len() * 8 >= self.length_in_bits);
 
-        let mut value = num::BigInt::from_bytes_be(num::bigint::Sign::Plus, data);
+        let mut words = words_from_bytes_be(data, false);
         let excess_bits = calc_excess_bits(self.length_in_bits);
         if excess_bits != 0 {
-            value >>= 8 - excess_bits;
+            shr_words(&mut words, 8 - excess_bits, false);
         }
 
-        IntegerData::from(value).expect("Should always fit")
+        from_words(words, false).expect("Should always fit")
     }
 }
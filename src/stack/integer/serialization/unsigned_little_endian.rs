@@ -14,7 +14,10 @@
 use crate::stack::{
     integer::{
         serialization::{
-            common::bits_to_bytes,
+            common::{
+                bits_to_bytes, from_words, to_words, words_for_bits, words_from_bytes_le,
+                words_to_bytes_le
+            },
             Encoding,
         },
         IntegerData,
@@ -44,11 +47,9 @@ impl Serializer<IntegerData> for UnsignedIntegerLittleEndianEncoding {
             return err!(ExceptionCode::RangeCheckError, "{} is not fit in {}", value, self.length_in_bits)
         }
 
-        let value = value.take_value_of(|x| x.to_biguint())?;
-        let mut buffer = value.to_bytes_le();
-        let expected_buffer_size = bits_to_bytes(self.length_in_bits);
-        debug_assert!(expected_buffer_size >= buffer.len());
-        buffer.resize(expected_buffer_size, 0);
+        let mut words = words_for_bits(self.length_in_bits);
+        to_words(value, &mut words)?;
+        let buffer = words_to_bytes_le(&words, bits_to_bytes(self.length_in_bits));
 
         BuilderData::with_raw(buffer, self.length_in_bits)
     }
@@ -58,8 +59,8 @@ impl Deserializer<IntegerData> for UnsignedIntegerLittleEndianEncoding {
     fn deserialize(&self, data: &[u8]) -> IntegerData {
         debug_assert!(data.len() * 8 >= self.length_in_bits);
 
-        let value = num::BigInt::from_bytes_le(num::bigint::Sign::Plus, data);
-        IntegerData::from(value).unwrap_or_default()
+        let words = words_from_bytes_le(data, false);
+        from_words(words, false).unwrap_or_default()
     }
 }
 
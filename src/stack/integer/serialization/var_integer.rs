@@ -0,0 +1,86 @@
+/*
+* Copyright (C) 2019-2024 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+use crate::stack::{
+    integer::IntegerData,
+    serialization::Serializer,
+    BuilderData, SliceData,
+};
+use ever_block::{ExceptionCode, IBitstring, Result};
+use num::{BigInt, bigint::Sign};
+
+/// VarUInteger n and VarInteger n: length in bytes taking ceil(log2 n) bits followed by
+/// big endian value of that length, zero is stored with zero length.
+/// Unlike other encodings length of serialized value varies, so it is read from slice
+pub struct VarIntegerEncoding {
+    max_bytes: usize,
+    signed: bool,
+}
+
+impl VarIntegerEncoding {
+    /// VarUInteger n, n is 16 for grams and 32 for extra currencies
+    pub fn unsigned(max_bytes: usize) -> Self {
+        debug_assert!(max_bytes.is_power_of_two() && max_bytes > 1);
+        Self { max_bytes, signed: false }
+    }
+
+    /// VarInteger n
+    pub fn signed(max_bytes: usize) -> Self {
+        debug_assert!(max_bytes.is_power_of_two() && max_bytes > 1);
+        Self { max_bytes, signed: true }
+    }
+
+    /// Bit length of length field
+    pub fn len_bits(&self) -> usize {
+        self.max_bytes.trailing_zeros() as usize
+    }
+
+    /// Max bit length of value, length field can hold at most n - 1 bytes
+    pub fn max_bits(&self) -> usize {
+        (self.max_bytes - 1) * 8
+    }
+
+    /// Reads value moving slice after it
+    pub fn read(&self, slice: &mut SliceData) -> Result<IntegerData> {
+        let bytes = slice.get_next_int(self.len_bits())? as usize;
+        let vec = slice.get_next_bytes(bytes)?;
+        let value = match self.signed {
+            true => BigInt::from_signed_bytes_be(&vec),
+            false => BigInt::from_bytes_be(Sign::Plus, &vec)
+        };
+        IntegerData::from(value)
+    }
+}
+
+impl Serializer<IntegerData> for VarIntegerEncoding {
+    fn try_serialize(&self, value: &IntegerData) -> Result<BuilderData> {
+        let (bits, vec) = match self.signed {
+            false => {
+                value.check_neg()?;
+                (value.ubitsize()?, value.take_value_of(|x| Some(x.to_bytes_be().1))?)
+            }
+            true => (value.bitsize()?, value.take_value_of(|x| Some(x.to_signed_bytes_be()))?)
+        };
+        if bits > self.max_bits() {
+            return err!(ExceptionCode::RangeCheckError, "{} has {} bits, but max is {}", value, bits, self.max_bits())
+        }
+        let bytes = match bits {
+            0 => 0,
+            _ => vec.len()
+        };
+        let mut builder = BuilderData::new();
+        builder.append_bits(bytes, self.len_bits())?;
+        builder.append_raw(&vec, bytes * 8)?;
+        Ok(builder)
+    }
+}
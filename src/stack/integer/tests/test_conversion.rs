@@ -23,4 +23,24 @@ fn test_into() {
     assert_eq!(crate::error::tvm_exception_code(&IntegerData::into(&one, 0..=0).unwrap_err()), Some(ExceptionCode::RangeCheckError));
     assert_eq!(crate::error::tvm_exception_code(&IntegerData::into(&one, 2..=2).unwrap_err()), Some(ExceptionCode::RangeCheckError));
     assert_eq!(crate::error::tvm_exception_code(&IntegerData::into(&nan, 0..=0).unwrap_err()), Some(ExceptionCode::RangeCheckError));
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_bytes_conversion() {
+    assert_eq!(IntegerData::from_u32(0x1234).as_unsigned_bytes_be().unwrap(), vec![0x12, 0x34]);
+    assert_eq!(IntegerData::zero().as_unsigned_bytes_be().unwrap(), vec![0]);
+    assert_eq!(crate::error::tvm_exception_code(&IntegerData::minus_one().as_unsigned_bytes_be().unwrap_err()), Some(ExceptionCode::RangeCheckError));
+    assert_eq!(crate::error::tvm_exception_code(&IntegerData::nan().as_unsigned_bytes_be().unwrap_err()), Some(ExceptionCode::RangeCheckError));
+
+    let mut bytes = [0xFF; 32];
+    bytes[31] = 0xFE;
+    assert_eq!(IntegerData::from_i256_bytes_be(&bytes), IntegerData::from_i32(-2));
+    assert_eq!(IntegerData::from_i32(-2).to_i256_bytes_be().unwrap(), bytes);
+    let mut bytes = [0xFF; 32];
+    bytes[0] = 0x7F;
+    let max = IntegerData::from_i256_bytes_be(&bytes);
+    assert_eq!(max.to_i256_bytes_be().unwrap(), bytes);
+    // 2^255 needs 257 signed bits
+    let too_big = max.add::<crate::stack::integer::behavior::Signaling>(&IntegerData::one()).unwrap();
+    assert!(too_big.to_i256_bytes_be().is_err());
+}
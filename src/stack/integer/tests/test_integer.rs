@@ -287,4 +287,67 @@ mod test_bitlogics {
         test_shr(-12, 5);
     }
 
-}
\ No newline at end of file
+}
+
+mod test_inline_values {
+
+    use crate::stack::integer::{IntegerData, behavior::Signaling};
+
+    #[test]
+    fn test_crossing_i128_boundary() {
+        let max = IntegerData::from_i128(i128::MAX);
+        let big = max.add::<Signaling>(&IntegerData::one()).unwrap();
+        assert_eq!(big, IntegerData::from_u128(1u128 << 127));
+        assert_eq!(big.bitsize().unwrap(), 129);
+        let back = big.sub::<Signaling>(&IntegerData::one()).unwrap();
+        assert_eq!(back, max);
+        assert_eq!(back.bitsize().unwrap(), 128);
+
+        let min = IntegerData::from_i128(i128::MIN);
+        assert_eq!(min.neg::<Signaling>().unwrap(), big);
+        assert_eq!(min.bitsize().unwrap(), 128);
+        assert_eq!(max.mul::<Signaling>(&max).unwrap().to_str(), num::BigInt::from(i128::MAX).pow(2).to_string());
+        assert_eq!(min.compare::<Signaling>(&max).unwrap(), Some(std::cmp::Ordering::Less));
+        assert_eq!(IntegerData::from_i32(-5).shr::<Signaling>(300).unwrap(), IntegerData::minus_one());
+        assert!(IntegerData::from_i32(300).into::<u8>(0..=255).is_err());
+    }
+}
+
+mod test_fixed_point {
+
+    use crate::stack::integer::{IntegerData, behavior::{Signaling, Quiet}};
+
+    fn pow2(shift: usize) -> IntegerData {
+        IntegerData::one().shl::<Signaling>(shift).unwrap()
+    }
+
+    #[test]
+    fn test_mul_div() {
+        let one_and_half = IntegerData::from_u32(3).mul::<Signaling>(&pow2(95)).unwrap();
+        let square = IntegerData::from_u32(9).mul::<Signaling>(&pow2(94)).unwrap();
+        assert_eq!(one_and_half.mul_shr_round::<Signaling>(&one_and_half, 96).unwrap(), square);
+        assert_eq!(square.shl_div_round::<Signaling>(&one_and_half, 96).unwrap(), one_and_half);
+
+        // halves are rounded up
+        assert_eq!(IntegerData::from_i32(3).mul_shr_round::<Signaling>(&IntegerData::one(), 1).unwrap(), IntegerData::from_i32(2));
+        assert_eq!(IntegerData::from_i32(-3).mul_shr_round::<Signaling>(&IntegerData::one(), 1).unwrap(), IntegerData::from_i32(-1));
+
+        assert!(IntegerData::one().shl_div_round::<Signaling>(&IntegerData::zero(), 128).is_err());
+        assert!(IntegerData::one().shl_div_round::<Quiet>(&IntegerData::zero(), 128).unwrap().is_nan());
+        // result must fit into 257 bits
+        assert!(pow2(200).shl_div_round::<Signaling>(&IntegerData::one(), 128).is_err());
+    }
+
+    #[test]
+    fn test_sqrt_log2() {
+        assert_eq!(IntegerData::from_u32(17).sqrt::<Signaling>().unwrap(), IntegerData::from_u32(4));
+        let x = pow2(200).add::<Signaling>(&IntegerData::one()).unwrap();
+        assert_eq!(x.sqrt::<Signaling>().unwrap(), pow2(100));
+        assert!(IntegerData::minus_one().sqrt::<Signaling>().is_err());
+
+        assert_eq!(IntegerData::one().log2::<Signaling>().unwrap(), IntegerData::zero());
+        assert_eq!(x.log2::<Signaling>().unwrap(), IntegerData::from_u32(200));
+        assert!(IntegerData::zero().log2::<Signaling>().is_err());
+        assert!(IntegerData::zero().log2::<Quiet>().unwrap().is_nan());
+    }
+}
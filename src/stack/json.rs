@@ -0,0 +1,88 @@
+/*
+* Copyright (C) 2019-2024 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+use crate::stack::{Stack, StackItem, integer::IntegerData};
+use ever_block::{fail, read_single_root_boc, write_boc, Cell, Result, SliceData};
+use serde_json::{json, Value};
+
+/*
+JSON representation of stack items used by command line and WASM runners:
+numbers or numeric strings (decimal or 0x-prefixed hex) are integers, null is null,
+arrays are tuples, {"cell": "<hex boc>"}, {"slice": "<hex boc>"} are cells and slices.
+Results use the same format with integers always written as decimal strings.
+*/
+
+fn parse_boc_hex(value: &Value) -> Result<Cell> {
+    match value.as_str() {
+        Some(hex) => read_single_root_boc(hex::decode(hex)?),
+        None => fail!("hex string with boc expected, got {}", value)
+    }
+}
+
+pub fn parse_stack_item(value: &Value) -> Result<StackItem> {
+    match value {
+        Value::Null => Ok(StackItem::None),
+        Value::Number(number) => Ok(StackItem::int(number.to_string().parse::<IntegerData>()?)),
+        Value::String(number) => match number.strip_prefix("0x") {
+            Some(hex) => Ok(StackItem::int(IntegerData::from_str_radix(hex, 16)?)),
+            None => Ok(StackItem::int(number.parse::<IntegerData>()?))
+        }
+        Value::Array(items) => Ok(StackItem::tuple(
+            items.iter().map(parse_stack_item).collect::<Result<Vec<_>>>()?
+        )),
+        Value::Object(object) => match (object.get("cell"), object.get("slice")) {
+            (Some(cell), None) => Ok(StackItem::cell(parse_boc_hex(cell)?)),
+            (None, Some(slice)) => Ok(StackItem::Slice(SliceData::load_cell(parse_boc_hex(slice)?)?)),
+            _ => fail!("unknown stack item {}", value)
+        }
+        Value::Bool(_) => fail!("booleans are not supported, use -1 or 0")
+    }
+}
+
+/// Parses stack from JSON array, the last item is the top of the stack
+pub fn parse_stack(json: &str) -> Result<Stack> {
+    match serde_json::from_str::<Value>(json)? {
+        Value::Array(items) => Ok(Stack::with_storage(
+            items.iter().map(parse_stack_item).collect::<Result<Vec<_>>>()?
+        )),
+        _ => fail!("stack must be JSON array")
+    }
+}
+
+pub fn stack_item_to_json(item: &StackItem) -> Result<Value> {
+    Ok(match item {
+        StackItem::None => Value::Null,
+        StackItem::Integer(value) => Value::String(value.to_string()),
+        StackItem::Cell(cell) => json!({ "cell": hex::encode(write_boc(cell)?) }),
+        StackItem::Slice(slice) => {
+            let cell = slice.as_builder().into_cell()?;
+            json!({ "slice": hex::encode(write_boc(&cell)?) })
+        }
+        StackItem::Builder(builder) => {
+            let cell = builder.as_ref().clone().into_cell()?;
+            json!({ "builder": hex::encode(write_boc(&cell)?) })
+        }
+        StackItem::Continuation(cont) => json!({ "continuation": cont.type_of.to_string() }),
+        StackItem::Tuple(items) => Value::Array(
+            items.iter().map(stack_item_to_json).collect::<Result<Vec<_>>>()?
+        ),
+    })
+}
+
+pub fn stack_to_json(stack: &Stack) -> Result<Value> {
+    Ok(Value::Array(stack.iter().map(stack_item_to_json).collect::<Result<Vec<_>>>()?))
+}
+
+#[cfg(test)]
+#[path = "../tests/test_json.rs"]
+mod tests;
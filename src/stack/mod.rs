@@ -12,12 +12,14 @@
 */
 
 use crate::{
-    executor::gas::gas_state::Gas,
+    executor::gas::gas_state::GasPrices,
     types::{ResultMut, ResultOpt, ResultRef, ResultVec, Status},
 };
 use self::{savelist::SaveList, continuation::ContinuationData, integer::IntegerData};
 use std::{fmt, mem, ops::Range, slice::Iter, sync::Arc, cmp::Ordering};
-use integer::serialization::{Encoding, SignedIntegerBigEndianEncoding};
+use integer::serialization::{
+    Encoding, SignedIntegerBigEndianEncoding, UnsignedIntegerBigEndianEncoding
+};
 use serialization::Deserializer;
 use ever_block::{
     MAX_DATA_BITS, MAX_REFERENCES_COUNT,
@@ -25,12 +27,19 @@ use ever_block::{
     Result, SliceData, GasConsumer, HashmapE
 };
 
+pub mod aug;
 pub mod serialization;
 pub mod savelist;
 pub mod continuation;
+#[cfg(feature = "serde_json")]
+pub mod json;
 #[macro_use]
 pub mod integer;
 
+/// Maximal number of items in tuple, longer sequences are kept as long tuples:
+/// chains of full tuples with the rest of items nested as the last item
+pub const MAX_TUPLE_SIZE: usize = 255;
+
 #[macro_export]
 macro_rules! int {
     (nan) => {
@@ -488,6 +497,86 @@ impl StackItem {
         }
     }
 
+    /// new stack item as long tuple, it is ordinary tuple if items fit into it
+    pub fn long_tuple(mut items: Vec<StackItem>) -> Self {
+        let chunk = MAX_TUPLE_SIZE - 1;
+        let full = items.len().saturating_sub(1) / chunk;
+        let mut tuple = StackItem::tuple(items.split_off(full * chunk));
+        while !items.is_empty() {
+            let mut head = items.split_off(items.len() - chunk);
+            head.push(tuple);
+            tuple = StackItem::tuple(head);
+        }
+        tuple
+    }
+
+    // next tuple of long tuple chain: last item of full tuple if it is a tuple itself,
+    // any other last item is an ordinary one
+    fn long_tuple_next(tuple: &[StackItem]) -> Option<&[StackItem]> {
+        match tuple.get(MAX_TUPLE_SIZE - 1) {
+            Some(StackItem::Tuple(next)) if tuple.len() == MAX_TUPLE_SIZE => Some(next.as_slice()),
+            _ => None
+        }
+    }
+
+    /// Returns item of long tuple following chain of nested tuples
+    pub fn tuple_item_deep(&self, index: usize) -> ResultRef<StackItem> {
+        Ok(self.tuple_item_deep_with_hops(index)?.0)
+    }
+
+    /// Returns item of long tuple along with number of nested tuples passed to reach it
+    pub fn tuple_item_deep_with_hops(&self, mut index: usize) -> Result<(&StackItem, usize)> {
+        let mut tuple = self.as_tuple()?;
+        let mut length = 0;
+        let mut hops = 0;
+        while index >= MAX_TUPLE_SIZE - 1 {
+            match Self::long_tuple_next(tuple) {
+                Some(next) => tuple = next,
+                None => break
+            }
+            index -= MAX_TUPLE_SIZE - 1;
+            length += MAX_TUPLE_SIZE - 1;
+            hops += 1;
+        }
+        match tuple.get(index) {
+            Some(value) => Ok((value, hops)),
+            None => err!(
+                ExceptionCode::RangeCheckError,
+                "long tuple index is {} but length is {}", length + index, length + tuple.len()
+            )
+        }
+    }
+
+    /// Number of items in long tuple
+    pub fn long_tuple_len(&self) -> Result<usize> {
+        Ok(self.long_tuple_len_with_hops()?.0)
+    }
+
+    /// Number of items in long tuple along with number of nested tuples in its chain
+    pub fn long_tuple_len_with_hops(&self) -> Result<(usize, usize)> {
+        let mut tuple = self.as_tuple()?;
+        let mut length = 0;
+        let mut hops = 0;
+        while let Some(next) = Self::long_tuple_next(tuple) {
+            length += MAX_TUPLE_SIZE - 1;
+            hops += 1;
+            tuple = next;
+        }
+        Ok((length + tuple.len(), hops))
+    }
+
+    /// Items of long tuple as flat sequence
+    pub fn long_tuple_items(&self) -> ResultVec<StackItem> {
+        let mut tuple = self.as_tuple()?;
+        let mut items = Vec::new();
+        while let Some(next) = Self::long_tuple_next(tuple) {
+            items.extend_from_slice(&tuple[..MAX_TUPLE_SIZE - 1]);
+            tuple = next;
+        }
+        items.extend_from_slice(tuple);
+        Ok(items)
+    }
+
     /// Extracts tuple to modify, exceptions should not be after
     /// If is single reference it will not clone on write
     pub fn as_tuple_mut(&mut self) -> ResultVec<StackItem> {
@@ -520,6 +609,36 @@ impl StackItem {
         self.as_integer()?.into(0..=(1u128<<120)-1)
     }
 
+    pub fn as_u64(&self) -> Result<u64> {
+        self.as_integer()?.into(0..=u64::MAX)
+    }
+
+    pub fn as_i128(&self) -> Result<i128> {
+        self.as_integer()?.into(i128::MIN..=i128::MAX)
+    }
+
+    /// Returns unsigned 256-bit integer as big-endian bytes, e.g. hash or public key
+    pub fn as_bytes32(&self) -> Result<[u8; 32]> {
+        let builder = self.as_integer()?.as_builder::<UnsignedIntegerBigEndianEncoding>(256)?;
+        Ok(builder.data().try_into()?)
+    }
+
+    /// Returns utf8 string stored in slice and chain of cells linked by the first reference
+    pub fn as_string(&self) -> Result<String> {
+        let mut slice = self.as_slice()?.clone();
+        let mut data = Vec::new();
+        loop {
+            if slice.remaining_bits() % 8 != 0 {
+                return err!(ExceptionCode::CellUnderflow, "string data is not byte aligned")
+            }
+            data.extend_from_slice(&slice.get_bytestring(0));
+            if slice.remaining_references() == 0 {
+                return crate::utils::bytes_to_string(data)
+            }
+            slice = SliceData::load_cell(slice.reference(0)?)?;
+        }
+    }
+
     pub fn is_null(&self) -> bool {
         matches!(self, StackItem::None)
     }
@@ -540,12 +659,30 @@ impl StackItem {
         mem::take(self)
     }
 
+    /// Returns item in canonical form for comparison: builders are finalized to cells,
+    /// slices are repacked to own cells, all NaNs are the same, tuples are normalized recursively
+    pub fn normalize(&self) -> Result<StackItem> {
+        Ok(match self {
+            StackItem::Builder(data) => StackItem::Cell(data.as_ref().clone().into_cell()?),
+            StackItem::Slice(data) => StackItem::Slice(SliceData::load_builder(data.as_builder())?),
+            StackItem::Integer(data) if data.is_nan() => StackItem::nan(),
+            StackItem::Tuple(data) => {
+                let mut tuple = Vec::with_capacity(data.len());
+                for item in data.iter() {
+                    tuple.push(item.normalize()?);
+                }
+                StackItem::tuple(tuple)
+            }
+            item => item.clone()
+        })
+    }
+
     pub fn dump_as_fift(&self) -> String {
         match self {
             StackItem::None => "(null)".to_string(),
             StackItem::Integer(data) => data.clone().to_string(),
             StackItem::Cell(data) => format!("C{{{:X}}}", data.repr_hash()),
-            StackItem::Continuation(_data) => "???".to_string(),
+            StackItem::Continuation(data) => format!("Cont{{{}}}", data.type_name()),
             StackItem::Builder(data) => {
                 let bits = data.length_in_bits();
                 let mut bytes = vec![data.references_used() as u8];
@@ -697,7 +834,7 @@ impl StackItem {
 }
 
 impl StackItem {
-    pub fn serialize_old(&self) -> Result<(BuilderData, i64)> {
+    pub fn serialize_old(&self, prices: &GasPrices) -> Result<(BuilderData, i64)> {
         let mut builder = BuilderData::new();
         let mut gas = 0;
         match self {
@@ -719,7 +856,7 @@ impl StackItem {
             },
             StackItem::Continuation(data) => {
                 builder.append_bits(0x06, 8)?;
-                let (serialized, gas2) = data.serialize_old()?;
+                let (serialized, gas2) = data.serialize_old(prices)?;
                 gas += gas2;
                 builder.append_builder(&serialized)?;
             },
@@ -727,7 +864,7 @@ impl StackItem {
                 builder.append_bits(0x05, 8)?;
                 let cell = data.as_ref().clone().into_cell()?;
                 builder.checked_append_reference(cell)?;
-                gas += Gas::finalize_price();
+                gas += prices.cell_create;
             },
             StackItem::Slice(data) => {
                 builder.append_bits(0x04, 8)?;
@@ -740,11 +877,11 @@ impl StackItem {
                 let mut tuple_list = BuilderData::new();
                 for item in data.iter().rev() {
                     let mut cons = BuilderData::new();
-                    let (serialized, gas2) = item.serialize_old()?;
+                    let (serialized, gas2) = item.serialize_old(prices)?;
                     gas += gas2;
                     cons.append_builder(&serialized)?;
                     cons.checked_append_reference(tuple_list.into_cell()?)?;
-                    gas += Gas::finalize_price();
+                    gas += prices.cell_create;
                     tuple_list = cons;
                 }
                 tuple.append_builder(&tuple_list)?;
@@ -754,7 +891,7 @@ impl StackItem {
         Ok((builder, gas))
     }
 
-    pub fn deserialize_old(slice: &mut SliceData) -> Result<(StackItem, i64)> {
+    pub fn deserialize_old(slice: &mut SliceData, prices: &GasPrices) -> Result<(StackItem, i64)> {
         let mut gas = 0;
         match slice.get_next_byte()? {
             0x00 => Ok((StackItem::None, gas)),
@@ -776,12 +913,12 @@ impl StackItem {
             },
             0x03 => Ok((StackItem::cell(slice.checked_drain_reference()?), gas)),
             0x04 => {
-                gas += Gas::load_cell_price(true);
+                gas += prices.load_cell(true);
                 Ok((StackItem::slice(slice_deserialize(slice)?), gas))
             },
             0x05 => Ok((StackItem::builder(BuilderData::from_cell(&slice.checked_drain_reference()?)?), gas)),
             0x06 => {
-                let (cont, gas2) = ContinuationData::deserialize_old(slice)?;
+                let (cont, gas2) = ContinuationData::deserialize_old(slice, prices)?;
                 gas += gas2;
                 Ok((StackItem::continuation(cont), gas))
             },
@@ -789,15 +926,15 @@ impl StackItem {
                 let mut tuple = vec![];
                 let len = slice.get_next_int(8)? as usize;
                 if len > 0 {
-                    let (item, gas2) = StackItem::deserialize_old(slice)?;
+                    let (item, gas2) = StackItem::deserialize_old(slice, prices)?;
                     tuple.push(item);
                     gas += gas2;
                 }
                 let mut cell = slice.checked_drain_reference()?;
                 for _ in 1..len {
                     let mut slice = SliceData::load_cell(cell)?;
-                    gas += Gas::load_cell_price(true);
-                    let (item, gas2) = StackItem::deserialize_old(&mut slice)?;
+                    gas += prices.load_cell(true);
+                    let (item, gas2) = StackItem::deserialize_old(&mut slice, prices)?;
                     tuple.push(item);
                     gas += gas2;
                     cell = slice.checked_drain_reference()?;
@@ -831,6 +968,35 @@ impl fmt::Display for StackItem {
     }
 }
 
+/// Conversion of stack item to Rust type, used by Stack::pop_typed
+pub trait FromStackItem: Sized {
+    fn from_stack_item(item: &StackItem) -> Result<Self>;
+}
+
+macro_rules! from_stack_item {
+    ($($type:ty => $convert:expr),+) => {
+        $(
+            impl FromStackItem for $type {
+                fn from_stack_item(item: &StackItem) -> Result<Self> {
+                    $convert(item)
+                }
+            }
+        )*
+    }
+}
+
+from_stack_item!{
+    bool => StackItem::as_bool,
+    u64 => StackItem::as_u64,
+    i128 => StackItem::as_i128,
+    [u8; 32] => StackItem::as_bytes32,
+    String => StackItem::as_string,
+    IntegerData => |item: &StackItem| item.as_integer().cloned(),
+    Cell => |item: &StackItem| item.as_cell().cloned(),
+    SliceData => |item: &StackItem| item.as_slice().cloned(),
+    StackItem => |item: &StackItem| Ok(item.clone())
+}
+
 #[derive(Clone, Debug, Default)]
 pub struct Stack {
     pub storage: Vec<StackItem>,
@@ -848,6 +1014,7 @@ impl Stack {
         Stack { storage }
     }
 
+
     // Swaps blocks (0...j-1) and (j...j+i-1)
     // e.g. block_swap(i=2, j=4): (8 7 6 {5 4} {3 2 1 0} -> 8 7 6 {3 2 1 0} {5 4})
     pub fn block_swap(&mut self, i: usize, j: usize) -> Status {
@@ -873,8 +1040,8 @@ impl Stack {
     pub fn drop_top(&mut self, n: usize) {
         let depth = self.depth();
         if depth < n {
-            log::error!(
-                 target: "tvm",
+            tvm_log!(
+                 error, crate::logging::targets::STACK,
                  "Corrupted stack state. This method can only be called \
                   when stack state is well known."
             );
@@ -883,6 +1050,16 @@ impl Stack {
         }
     }
 
+    /// Pops top item converted to T, the item is left on stack if conversion fails
+    pub fn pop_typed<T: FromStackItem>(&mut self) -> Result<T> {
+        let value = match self.storage.last() {
+            Some(item) => T::from_stack_item(item)?,
+            None => return err!(ExceptionCode::StackUnderflow)
+        };
+        self.storage.pop();
+        Ok(value)
+    }
+
     pub fn drop(&mut self, i: usize) -> Result<StackItem> {
         let depth = self.depth();
         if i >= depth {
@@ -1071,6 +1248,34 @@ impl Stack {
         self.storage.iter()
     }
 
+    /// Returns copy of the stack with all items in canonical form, see StackItem::normalize
+    pub fn normalize(&self) -> Result<Stack> {
+        let storage = self.storage.iter().map(|item| item.normalize()).collect::<Result<Vec<_>>>()?;
+        Ok(Stack::with_storage(storage))
+    }
+
+    /// Compares stacks in canonical form
+    pub fn eq_normalized(&self, stack: &Stack) -> Result<bool> {
+        Ok(self.normalize()?.eq(&stack.normalize()?))
+    }
+
+    /// Serializes items as stack of continuation: depth:(## 24) and reference to list of items
+    pub(crate) fn serialize(&self, gas_consumer: &mut dyn GasConsumer) -> Result<BuilderData> {
+        let list = items_serialize(self.iter().map(SerializeItem::Item).collect(), gas_consumer)?;
+        let mut builder = BuilderData::new();
+        builder.append_bits(self.depth(), 24)?;
+        let cell = gas_consumer.finalize_cell(list)?;
+        builder.checked_append_reference(cell)?;
+        Ok(builder)
+    }
+
+    pub fn deserialize(slice: &mut SliceData, gas_consumer: &mut dyn GasConsumer) -> Result<Stack> {
+        let depth = slice.get_next_int(24)? as usize;
+        let list = gas_consumer.load_cell(slice.checked_drain_reference()?)?;
+        let storage = items_deserialize(vec!(DeserializeItem::Items(depth, list)), gas_consumer)?;
+        Ok(Stack::with_storage(storage))
+    }
+
 }
 
 impl PartialEq for Stack {
@@ -12,16 +12,34 @@
 */
 
 use crate::{
-    executor::gas::gas_state::Gas,
-    stack::StackItem,
+    executor::gas::gas_state::GasPrices,
+    stack::{continuation::ContinuationData, StackItem},
     types::ResultOpt,
 };
-use std::fmt;
-use ever_block::{ExceptionCode, Result, SliceData, HashmapE, HashmapType, BuilderData, IBitstring};
+use std::{fmt, sync::Arc};
+use ever_block::{Cell, ExceptionCode, Result, SliceData, HashmapE, HashmapType, BuilderData, IBitstring};
 
-#[derive(Clone, Debug, PartialEq)]
+type Registers = [Option<StackItem>; SaveList::NUMREGS];
+
+/// Value of control register with the type allowed for it
+#[derive(Debug, PartialEq)]
+pub enum Register<'a> {
+    /// c0..c3
+    Continuation(&'a ContinuationData),
+    /// c2 only
+    Null,
+    /// c4 and c5
+    Cell(&'a Cell),
+    /// c7
+    Tuple(&'a [StackItem]),
+}
+
+/// Control registers saved in continuation.
+/// Registers are shared between clones until one of them is modified,
+/// empty savelist does not allocate
+#[derive(Clone, Debug)]
 pub struct SaveList {
-    storage: [Option<StackItem>; Self::NUMREGS],
+    storage: Option<Arc<Registers>>,
 }
 
 impl Default for SaveList {
@@ -39,9 +57,15 @@ impl SaveList {
 
     pub fn new() -> Self {
         Self {
-            storage: Default::default()
+            storage: None
         }
     }
+    fn slot(&self, index: usize) -> Option<&StackItem> {
+        self.storage.as_ref()?[index].as_ref()
+    }
+    fn registers_mut(&mut self) -> &mut Registers {
+        Arc::make_mut(self.storage.get_or_insert_with(Default::default))
+    }
     pub fn can_put(index: usize, value: &StackItem) -> bool {
         match index {
             0 | 1 | 3 => value.as_continuation().is_ok(),
@@ -59,18 +83,35 @@ impl SaveList {
         }
     }
     pub fn get(&self, index: usize) -> Option<&StackItem> {
-        self.storage[Self::adjust(index)].as_ref()
+        self.slot(Self::adjust(index))
+    }
+    /// Register value converted to its type, None if register is not set
+    pub fn get_typed(&self, index: usize) -> ResultOpt<Register<'_>> {
+        if !Self::REGS.contains(&index) {
+            return err!(ExceptionCode::RangeCheckError, "no control register c{}", index)
+        }
+        let item = match self.get(index) {
+            Some(item) => item,
+            None => return Ok(None)
+        };
+        let register = match index {
+            0..=3 if item.is_null() => Register::Null,
+            0..=3 => Register::Continuation(item.as_continuation()?),
+            4 | 5 => Register::Cell(item.as_cell()?),
+            _ => Register::Tuple(item.as_tuple()?),
+        };
+        Ok(Some(register))
     }
     pub fn get_mut(&mut self, index: usize) -> Option<&mut StackItem> {
-        self.storage[Self::adjust(index)].as_mut()
+        let index = Self::adjust(index);
+        self.slot(index)?;
+        self.registers_mut()[index].as_mut()
     }
     pub fn is_empty(&self) -> bool {
-        for v in &self.storage {
-            if v.is_some() {
-                return false;
-            }
+        match &self.storage {
+            Some(registers) => registers.iter().all(Option::is_none),
+            None => true
         }
-        true
     }
     pub fn put(&mut self, index: usize, value: &mut StackItem) -> ResultOpt<StackItem> {
         Self::check_can_put(index, value)?;
@@ -78,30 +119,56 @@ impl SaveList {
     }
     pub fn put_opt(&mut self, index: usize, value: &mut StackItem) -> Option<StackItem> {
         debug_assert!(Self::can_put(index, value));
-        std::mem::replace(&mut self.storage[Self::adjust(index)], Some(value.withdraw()))
+        std::mem::replace(&mut self.registers_mut()[Self::adjust(index)], Some(value.withdraw()))
+    }
+    /// Puts continuation to one of c0..c3
+    pub fn set_cont(&mut self, index: usize, cont: ContinuationData) -> ResultOpt<StackItem> {
+        self.put(index, &mut StackItem::continuation(cont))
+    }
+    /// Set registers in ascending order with their numbers
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &StackItem)> {
+        (0..Self::NUMREGS).filter_map(|index| Some((Self::REGS[index], self.slot(index)?)))
     }
     pub fn apply(&mut self, other: &mut Self) {
-        for index in 0..Self::NUMREGS {
-            if other.storage[index].is_some() {
-                self.storage[index] = std::mem::take(&mut other.storage[index]);
+        let other = match other.storage.take() {
+            Some(other) => other,
+            None => return
+        };
+        if self.is_empty() {
+            self.storage = Some(other);
+            return
+        }
+        let registers = self.registers_mut();
+        match Arc::try_unwrap(other) {
+            Ok(mut other) => for index in 0..Self::NUMREGS {
+                if other[index].is_some() {
+                    registers[index] = std::mem::take(&mut other[index]);
+                }
+            }
+            Err(other) => for index in 0..Self::NUMREGS {
+                if other[index].is_some() {
+                    registers[index] = other[index].clone();
+                }
             }
         }
     }
     pub fn remove(&mut self, index: usize) -> Option<StackItem> {
-        std::mem::take(&mut self.storage[Self::adjust(index)])
+        let index = Self::adjust(index);
+        self.slot(index)?;
+        self.registers_mut()[index].take()
     }
 }
 
 impl SaveList {
-    pub fn serialize_old(&self) -> Result<(BuilderData, i64)> {
+    pub fn serialize_old(&self, prices: &GasPrices) -> Result<(BuilderData, i64)> {
         let mut gas = 0;
         let mut dict = HashmapE::with_bit_len(4);
         for index in 0..Self::NUMREGS {
-            if let Some(ref item) = self.storage[index] {
+            if let Some(item) = self.slot(index) {
                 let mut builder = BuilderData::new();
                 builder.append_bits(if index == 6 { 7 } else { index }, 4)?;
                 let key = SliceData::load_builder(builder)?;
-                let (value, gas2) = item.serialize_old()?;
+                let (value, gas2) = item.serialize_old(prices)?;
                 gas += gas2;
                 dict.set_builder(key, &value)?;
             }
@@ -111,7 +178,7 @@ impl SaveList {
             Some(cell) => {
                 builder.append_bit_one()?;
                 builder.checked_append_reference(cell.clone())?;
-                gas += Gas::finalize_price();
+                gas += prices.cell_create;
             }
             None => {
                 builder.append_bit_zero()?;
@@ -119,17 +186,17 @@ impl SaveList {
         }
         Ok((builder, gas))
     }
-    pub fn deserialize_old(slice: &mut SliceData) -> Result<(Self, i64)> {
+    pub fn deserialize_old(slice: &mut SliceData, prices: &GasPrices) -> Result<(Self, i64)> {
         let mut gas = 0;
         match slice.get_next_bit()? {
             false => Ok((Self::new(), gas)),
             true => {
                 let dict = HashmapE::with_hashmap(4, slice.checked_drain_reference().ok());
-                gas += Gas::load_cell_price(true);
+                gas += prices.load_cell(true);
                 let mut savelist = SaveList::new();
                 dict.iterate_slices(|mut key, mut value| {
                     let key = key.get_next_int(4)? as usize;
-                    let (mut value, gas2) = StackItem::deserialize_old(&mut value)?;
+                    let (mut value, gas2) = StackItem::deserialize_old(&mut value, prices)?;
                     gas += gas2;
                     savelist.put(key, &mut value)?;
                     Ok(true)
@@ -140,11 +207,17 @@ impl SaveList {
     }
 }
 
+impl PartialEq for SaveList {
+    fn eq(&self, other: &Self) -> bool {
+        (0..Self::NUMREGS).all(|index| self.slot(index) == other.slot(index))
+    }
+}
+
 impl fmt::Display for SaveList {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         writeln!(f, "--- Control registers ------------------")?;
         for i in 0..Self::NUMREGS {
-            if let Some(item) = &self.storage[i] {
+            if let Some(item) = self.slot(i) {
                 writeln!(f, "{}: {}", i, item)?
             }
         }
@@ -0,0 +1,176 @@
+/*
+* Copyright (C) 2019-2024 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+use ever_block::{BuilderData, Result, SliceData};
+use std::fmt;
+
+/*
+Generator of random straight-line programs for property-based testing of Engine.
+Programs contain no jumps and loops, so they always terminate, and fit in one cell.
+Stack depth is tracked to make most of instructions applicable, but exceptions
+(e.g. division by zero or type check on null) are still possible and are valid outcomes.
+*/
+
+// every instruction takes no more than 16 bits
+const MAX_INSTRUCTIONS: usize = 63;
+
+struct Opcode {
+    name: &'static str,
+    code: &'static [u8],
+    pops: usize,
+    pushes: usize,
+}
+
+const fn op(name: &'static str, code: &'static [u8], pops: usize, pushes: usize) -> Opcode {
+    Opcode { name, code, pops, pushes }
+}
+
+const OPCODES: &[Opcode] = &[
+    op("SWAP", &[0x01], 2, 2),
+    op("DUP", &[0x20], 1, 2),
+    op("OVER", &[0x21], 2, 3),
+    op("DROP", &[0x30], 1, 0),
+    op("NIP", &[0x31], 2, 1),
+    op("ROT", &[0x58], 3, 3),
+    op("PUSHNULL", &[0x6D], 0, 1),
+    op("ISNULL", &[0x6E], 1, 1),
+    op("ADD", &[0xA0], 2, 1),
+    op("SUB", &[0xA1], 2, 1),
+    op("SUBR", &[0xA2], 2, 1),
+    op("NEGATE", &[0xA3], 1, 1),
+    op("INC", &[0xA4], 1, 1),
+    op("DEC", &[0xA5], 1, 1),
+    op("MUL", &[0xA8], 2, 1),
+    op("DIV", &[0xA9, 0x04], 2, 1),
+    op("MOD", &[0xA9, 0x08], 2, 1),
+    op("MIN", &[0xB6, 0x08], 2, 1),
+    op("MAX", &[0xB6, 0x09], 2, 1),
+    op("ABS", &[0xB6, 0x0B], 1, 1),
+    op("AND", &[0xB0], 2, 1),
+    op("OR", &[0xB1], 2, 1),
+    op("XOR", &[0xB2], 2, 1),
+    op("NOT", &[0xB3], 1, 1),
+    op("LESS", &[0xB9], 2, 1),
+    op("EQUAL", &[0xBA], 2, 1),
+    op("GREATER", &[0xBC], 2, 1),
+    op("CMP", &[0xBF], 2, 1),
+];
+
+/// One generated instruction with its mnemonic and encoding
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct GeneratedInstruction {
+    pub text: String,
+    pub code: Vec<u8>,
+}
+
+/// Generated program, it can be shrunk by removing instructions
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Program {
+    pub instructions: Vec<GeneratedInstruction>,
+}
+
+impl Program {
+    /// Code slice of program
+    pub fn code(&self) -> Result<SliceData> {
+        let mut builder = BuilderData::new();
+        for instruction in &self.instructions {
+            builder.append_raw(&instruction.code, instruction.code.len() * 8)?;
+        }
+        SliceData::load_builder(builder)
+    }
+
+    /// Programs with one instruction removed, the shortest candidates go first
+    pub fn shrink(&self) -> impl Iterator<Item = Program> + '_ {
+        (0..self.instructions.len()).rev().map(move |index| {
+            let mut instructions = self.instructions.clone();
+            instructions.remove(index);
+            Program { instructions }
+        })
+    }
+
+    /// Minimizes program while the predicate is true, e.g. while test is failing
+    pub fn minimize(&self, mut predicate: impl FnMut(&Program) -> bool) -> Program {
+        let mut program = self.clone();
+        while let Some(smaller) = program.shrink().find(|candidate| predicate(candidate)) {
+            program = smaller;
+        }
+        program
+    }
+}
+
+impl fmt::Display for Program {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for instruction in &self.instructions {
+            writeln!(f, "{}", instruction.text)?;
+        }
+        Ok(())
+    }
+}
+
+/// Deterministic generator: the same seed produces the same programs
+pub struct Generator {
+    state: u64,
+}
+
+impl Generator {
+    pub fn new(seed: u64) -> Self {
+        // zero state is a fixed point of xorshift
+        Self { state: seed ^ 0x9E37_79B9_7F4A_7C15 }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    fn push_int(&mut self) -> GeneratedInstruction {
+        if self.below(4) == 0 {
+            let value = self.next_u64() as i8;
+            GeneratedInstruction { text: format!("PUSHINT {}", value), code: vec![0x80, value as u8] }
+        } else {
+            let value = self.below(16) as i8 - 5;
+            GeneratedInstruction { text: format!("PUSHINT {}", value), code: vec![0x70 | (value as u8 & 0x0F)] }
+        }
+    }
+
+    /// Generates program with up to len instructions,
+    /// it expects initial_depth items on the stack
+    pub fn program(&mut self, len: usize, initial_depth: usize) -> Program {
+        let mut depth = initial_depth;
+        let mut instructions = Vec::new();
+        for _ in 0..len.min(MAX_INSTRUCTIONS) {
+            let opcode = &OPCODES[self.below(OPCODES.len())];
+            if opcode.pops > depth || self.below(3) == 0 {
+                instructions.push(self.push_int());
+                depth += 1;
+            } else {
+                instructions.push(GeneratedInstruction {
+                    text: opcode.name.to_string(),
+                    code: opcode.code.to_vec()
+                });
+                depth = depth - opcode.pops + opcode.pushes;
+            }
+        }
+        Program { instructions }
+    }
+}
+
+#[cfg(test)]
+#[path = "tests/test_testgen.rs"]
+mod tests;
@@ -0,0 +1,53 @@
+/*
+* Copyright (C) 2019-2024 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+use super::*;
+use ever_block::BuilderData;
+
+#[test]
+fn test_get_method_id() {
+    assert_eq!(get_method_id("seqno"), 85143);
+    assert_eq!(get_method_id("get_public_key"), 78748);
+}
+
+#[test]
+fn test_run_get_method_typed() {
+    // DROP ADD TRUE
+    let code = BuilderData::with_raw(vec![0x30, 0xA0, 0x7F], 24).unwrap().into_cell().unwrap();
+    let params = [
+        Value::Null,
+        Value::Uint(BigInt::from(2)),
+        Value::Int(BigInt::from(-3)),
+    ];
+    let result = run_get_method_typed(code.clone(), Cell::default(), "get_sum", &params).unwrap();
+    assert_eq!(result, vec![Value::Null, Value::Int(BigInt::from(-1)), Value::Int(BigInt::from(-1))]);
+
+    assert!(run_get_method_typed(code.clone(), Cell::default(), "get_sum", &[Value::Uint(BigInt::from(-1))]).is_err());
+
+    let outputs = [ValueType::Null, ValueType::Int, ValueType::Bool];
+    let result = run_get_method_typed_as(code.clone(), Cell::default(), "get_sum", &params, &outputs).unwrap();
+    assert_eq!(result, vec![Value::Null, Value::Int(BigInt::from(-1)), Value::Bool(true)]);
+    // negative result of uint and wrong number of outputs
+    let outputs = [ValueType::Null, ValueType::Uint, ValueType::Bool];
+    assert!(run_get_method_typed_as(code.clone(), Cell::default(), "get_sum", &params, &outputs).is_err());
+    assert!(run_get_method_typed_as(code, Cell::default(), "get_sum", &params, &outputs[1..]).is_err());
+}
+
+#[test]
+fn test_run_get_method_uint_result() {
+    // DROP ADD
+    let code = BuilderData::with_raw(vec![0x30, 0xA0], 16).unwrap().into_cell().unwrap();
+    let params = [Value::Uint(BigInt::from(2)), Value::Uint(BigInt::from(3))];
+    let result = run_get_method_typed_as(code, Cell::default(), "get_sum", &params, &[ValueType::Uint]).unwrap();
+    assert_eq!(result, vec![Value::Uint(BigInt::from(5))]);
+}
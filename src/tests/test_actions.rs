@@ -0,0 +1,146 @@
+/*
+* Copyright (C) 2019-2024 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+use super::*;
+use crate::{
+    executor::engine::{Engine, VmCapabilities},
+    stack::{Stack, StackItem, integer::IntegerData, savelist::SaveList},
+    SmartContractInfo
+};
+use ever_block::{
+    BuilderData, CurrencyCollection, GlobalCapabilities, Message, MsgAddressInt, Serializable, SliceData
+};
+
+#[test]
+fn test_decode_actions() {
+    let message = Message::default();
+    let message_cell = message.serialize().unwrap();
+    let code = BuilderData::with_raw(vec![0xAA], 8).unwrap().into_cell().unwrap();
+    let stack = Stack::with_storage(vec![
+        StackItem::Cell(code.clone()),
+        int!(1000),
+        int!(2),
+        StackItem::Cell(message_cell),
+        int!(3),
+    ]);
+    // SENDRAWMSG RAWRESERVE SETCODE
+    let program = SliceData::new(vec![0xFB, 0x00, 0xFB, 0x02, 0xFB, 0x04, 0x80]);
+    let mut engine = Engine::with_capabilities(0)
+        .setup_with_libraries(program, None, Some(stack), None, vec![]);
+    engine.execute().unwrap();
+
+    let c5 = engine.get_committed_state().get_actions().as_cell().unwrap().clone();
+    assert_eq!(decode_actions(&c5).unwrap(), vec![
+        OutAction::SendMsg { mode: 3, out_msg: message },
+        OutAction::ReserveCurrency { mode: 2, value: CurrencyCollection::with_grams(1000) },
+        OutAction::SetCode { new_code: code },
+    ]);
+    assert!(decode_actions(&ever_block::Cell::default()).unwrap().is_empty());
+}
+
+#[test]
+fn test_decode_library_actions() {
+    let capabilities = GlobalCapabilities::CapSetLibCode as u64
+        | GlobalCapabilities::CapCopyleft as u64;
+    let myself = MsgAddressInt::with_standart(None, 0, SliceData::from_raw(vec![0x11; 32], 256)).unwrap();
+    let sci = SmartContractInfo {
+        capabilities,
+        myself: SliceData::load_cell(myself.serialize().unwrap()).unwrap(),
+        ..Default::default()
+    };
+    let mut ctrls = SaveList::new();
+    ctrls.put(7, &mut sci.into_temp_data_item()).unwrap();
+    let library = BuilderData::with_raw(vec![0xAA], 8).unwrap().into_cell().unwrap();
+    let run = |vm_capabilities, mode| {
+        let stack = Stack::with_storage(vec![
+            StackItem::Slice(SliceData::from_raw(vec![0x22; 32], 256)),
+            int!(7),
+            int!(5),
+            int!(mode),
+            StackItem::Cell(library.clone()),
+            int!(1),
+        ]);
+        // SETLIBCODE CHANGELIB COPYLEFT
+        let program = SliceData::new(vec![0xFB, 0x06, 0xFB, 0x07, 0xFB, 0x0A, 0x80]);
+        let mut engine = Engine::with_capabilities(capabilities)
+            .with_vm_capabilities(vm_capabilities)
+            .setup_with_libraries(program, Some(ctrls.clone()), Some(stack), None, vec![]);
+        engine.execute().map(|_| engine.get_committed_state().get_actions().as_cell().unwrap().clone())
+    };
+
+    let library_bounce = VmCapabilities::CapLibraryBounce as u64;
+    let c5 = run(library_bounce, 18).unwrap();
+    let mut hash = [0; 32];
+    hash[31] = 5;
+    let actions = decode_actions(&c5).unwrap();
+    assert_eq!(actions.len(), 3);
+    assert!(matches!(&actions[0], OutAction::ChangeLibrary { code: Some(code), hash: None, .. } if code == &library));
+    assert!(matches!(&actions[1], OutAction::ChangeLibrary { code: None, hash: Some(value), .. } if value.as_slice() == &hash));
+    assert!(matches!(&actions[2], OutAction::CopyLeft { license: 7, address } if address == &SliceData::from_raw(vec![0x22; 32], 256)));
+    // bounce flag of library mode is not known before CapLibraryBounce
+    assert!(run(0, 18).is_err());
+    assert!(run(library_bounce, 3).is_err());
+}
+
+#[test]
+fn test_predict_reserve() {
+    use reserve_mode::*;
+    let outcome = |reserved, remaining| Ok(ReserveOutcome { reserved, remaining });
+    // original balance 1000, remaining 700
+    let cases = [
+        (0, 100, outcome(100, 600)),
+        (ALL_BUT, 100, outcome(600, 100)),
+        (IGNORE_ERROR, 100, outcome(100, 600)),
+        (PLUS_ORIGINAL, 100, Err(RESULT_NOT_ENOUGH_FUNDS)),
+        (PLUS_ORIGINAL | IGNORE_ERROR, 100, outcome(700, 0)),
+        (PLUS_ORIGINAL | REVERSE, 400, outcome(600, 100)),
+        (PLUS_ORIGINAL | REVERSE | ALL_BUT, 400, outcome(100, 600)),
+        (REVERSE, 100, Err(RESULT_INVALID_ACTION)),
+        (BOUNCE_ON_FAIL, 100, outcome(100, 600)),
+        (ALL_BUT | IGNORE_ERROR, 800, outcome(0, 700)),
+        (MAX + 1, 100, Err(RESULT_INVALID_ACTION)),
+    ];
+    for (mode, amount, expected) in cases {
+        assert_eq!(predict_reserve(mode, amount, 1000, 700), expected, "mode {}", mode);
+    }
+    // amount exceeds original balance in reverse mode
+    assert_eq!(predict_reserve(PLUS_ORIGINAL | REVERSE, 2000, 1000, 700), Err(RESULT_INVALID_ACTION));
+    // every valid mode either reserves within remaining balance or fails with known code
+    for mode in 0..=MAX {
+        match predict_reserve(mode, 300, 1000, 700) {
+            Ok(outcome) => assert_eq!(outcome.reserved + outcome.remaining, 700),
+            Err(code) => assert!(code == RESULT_INVALID_ACTION || code == RESULT_NOT_ENOUGH_FUNDS)
+        }
+    }
+}
+
+#[test]
+fn test_rawreserve_modes() {
+    let run = |vm_capabilities, mode: i32| {
+        // RAWRESERVE
+        let program = SliceData::new(vec![0xFB, 0x02, 0x80]);
+        let stack = Stack::with_storage(vec![int!(1000), int!(mode)]);
+        let mut engine = Engine::with_capabilities(GlobalCapabilities::CapTvmV20 as u64)
+            .with_vm_capabilities(vm_capabilities)
+            .setup_with_libraries(program, None, Some(stack), None, vec![]);
+        engine.execute()?;
+        let c5 = engine.get_committed_state().get_actions().as_cell()?.clone();
+        decode_actions(&c5)
+    };
+    assert_eq!(run(0, 15).unwrap(), vec![OutAction::ReserveCurrency { mode: 15, value: CurrencyCollection::with_grams(1000) }]);
+    assert!(run(0, 16).is_err());
+    let bounce = crate::executor::VmCapabilities::CapReserveBounce as u64;
+    assert_eq!(run(bounce, 31).unwrap(), vec![OutAction::ReserveCurrency { mode: 31, value: CurrencyCollection::with_grams(1000) }]);
+    let err = run(bounce, 32).unwrap_err();
+    assert_eq!(crate::error::tvm_exception_code(&err), Some(ever_block::ExceptionCode::RangeCheckError));
+}
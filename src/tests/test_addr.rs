@@ -0,0 +1,46 @@
+/*
+* Copyright (C) 2019-2024 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+use super::*;
+use ever_block::{Deserializable, MsgAddressInt};
+
+#[test]
+fn test_std_addr_round_trip() {
+    let hash = UInt256::with_array([0x11; 32]);
+    let slice = std_addr(-1, &hash).unwrap();
+    assert_eq!(slice.remaining_bits(), 2 + 1 + 8 + 256);
+    assert_eq!(parse_std_addr(&slice).unwrap(), (-1, hash.clone()));
+
+    let addr = MsgAddressInt::construct_from(&mut slice.clone()).unwrap();
+    assert_eq!(addr.workchain_id(), -1);
+    assert!(addr.is_masterchain());
+}
+
+#[test]
+fn test_var_addr_round_trip() {
+    let address = SliceData::new(vec![0xAB, 0xCD, 0x80]);
+    let slice = var_addr(1000, &address).unwrap();
+    let (workchain_id, parsed) = parse_var_addr(&slice).unwrap();
+    assert_eq!(workchain_id, 1000);
+    assert_eq!(parsed, address);
+    parse_std_addr(&slice).expect_err("addr_var must not be parsed as addr_std");
+}
+
+#[test]
+fn test_parse_addr_with_tail() {
+    let slice = std_addr(0, &UInt256::default()).unwrap();
+    let mut builder = slice.as_builder();
+    builder.append_bit_one().unwrap();
+    let slice = SliceData::load_builder(builder).unwrap();
+    parse_std_addr(&slice).expect_err("extra bits must be rejected");
+}
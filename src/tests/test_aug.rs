@@ -0,0 +1,136 @@
+/*
+* Copyright (C) 2019-2024 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+use super::*;
+use crate::{
+    error::tvm_exception_code,
+    executor::VmCapabilities,
+    stack::{Stack, aug::{aug_dict_from_entries, AugDict, TypedExtra}, integer::IntegerData},
+};
+use ever_block::{BuilderData, CurrencyCollection, ExceptionCode, SliceData};
+
+fn key(k: u8) -> SliceData {
+    SliceData::from_raw(vec![k], 8)
+}
+
+fn value(v: u8) -> SliceData {
+    SliceData::from_raw(vec![v], 8)
+}
+
+fn engine(vm_capabilities: u64, code: Vec<u8>, stack: Vec<StackItem>) -> Engine {
+    Engine::with_capabilities(0).with_vm_capabilities(vm_capabilities).setup_with_libraries(
+        SliceData::new(code), None, Some(Stack::with_storage(stack)), None, vec![]
+    )
+}
+
+fn run(code: Vec<u8>, stack: Vec<StackItem>) -> Result<Stack> {
+    let mut engine = engine(VmCapabilities::CapAugDicts as u64, code, stack);
+    engine.execute()?;
+    Ok(engine.stack().clone())
+}
+
+#[test]
+fn test_aug_dict() {
+    let mut gas = engine(0, vec![0x80], vec![]);
+    let mut dict = aug_dict_from_entries(
+        8, UintExtra(16), (1..=20).map(|k| (key(k * 7), value(k), IntegerData::from_u32(k as u32)))
+    ).unwrap();
+    assert_eq!(dict.extra(&mut gas).unwrap(), IntegerData::from_u32(210));
+    let (found, extra) = dict.get(&key(49), &mut gas).unwrap().unwrap();
+    assert_eq!(found.get_bytestring(0), vec![7]);
+    assert_eq!(extra, IntegerData::from_u32(7));
+    assert_eq!(dict.get(&key(50), &mut gas).unwrap(), None);
+
+    // replaced extra is recalculated on the path
+    dict.set(&key(49), &value(0), &IntegerData::from_u32(100), &mut gas).unwrap();
+    assert_eq!(dict.extra(&mut gas).unwrap(), IntegerData::from_u32(303));
+
+    for k in 1..=20 {
+        assert!(dict.remove(&key(k * 7), &mut gas).unwrap().is_some());
+        assert!(dict.remove(&key(k * 7), &mut gas).unwrap().is_none());
+    }
+    assert!(dict.is_empty());
+    assert_eq!(dict.extra(&mut gas).unwrap(), IntegerData::zero());
+
+    // order of insertion does not matter
+    let entries = [(3, 1), (200, 2), (17, 3), (16, 4)]
+        .map(|(k, v)| (key(k), value(v), IntegerData::from_u32(v as u32)));
+    let forward = aug_dict_from_entries(8, UintExtra(16), entries.clone()).unwrap();
+    let backward = aug_dict_from_entries(8, UintExtra(16), entries.into_iter().rev()).unwrap();
+    assert_eq!(forward, backward);
+
+    // wrong key length raises exception
+    let err = forward.get(&SliceData::from_raw(vec![3], 7), &mut gas).unwrap_err();
+    assert_eq!(tvm_exception_code(&err), Some(ExceptionCode::CellUnderflow));
+}
+
+#[test]
+fn test_aug_dict_typed_extra() {
+    let mut gas = engine(0, vec![0x80], vec![]);
+    let grams = |v: u64| CurrencyCollection::with_grams(v);
+    let mut dict = aug_dict_from_entries(
+        8, TypedExtra::<CurrencyCollection>::new(), (1..=10).map(|k| (key(k), value(k), grams(k as u64)))
+    ).unwrap();
+    assert_eq!(dict.extra(&mut gas).unwrap(), grams(55));
+    assert_eq!(dict.remove(&key(10), &mut gas).unwrap().unwrap().1, grams(10));
+    assert_eq!(dict.extra(&mut gas).unwrap(), grams(45));
+
+    // root extra is serialized along with root as HashmapAugE
+    let mut builder = BuilderData::new();
+    dict.write_to(&mut builder, &mut gas).unwrap();
+    let mut slice = SliceData::load_builder(builder).unwrap();
+    let read = AugDict::read_from(8, TypedExtra::<CurrencyCollection>::new(), &mut slice).unwrap();
+    assert_eq!(read, dict);
+    // extra of root is known without loading it
+    let mut engine = engine(0, vec![0x80], vec![]);
+    assert_eq!(read.extra(&mut engine).unwrap(), grams(45));
+    assert_eq!(engine.gas_used(), 0);
+}
+
+#[test]
+fn test_augdict_opcodes() {
+    let expected = aug_dict_from_entries(8, UintExtra(16), [(key(3), value(9), IntegerData::from_u32(5))]).unwrap();
+    let params = |dict: StackItem| vec![dict, StackItem::int(8), StackItem::int(16)];
+
+    // AUGDICTUSET
+    let mut stack = vec![StackItem::Slice(value(9)), StackItem::int(5), StackItem::int(3)];
+    stack.extend(params(StackItem::None));
+    let result = run(vec![0xF4, 0xC6, 0x80], stack.clone()).unwrap();
+    let dict = result.get(0).clone();
+    assert_eq!(dict.as_cell().unwrap(), expected.root().unwrap());
+
+    // AUGDICTUGET
+    let mut stack = vec![StackItem::int(3)];
+    stack.extend(params(dict.clone()));
+    let result = run(vec![0xF4, 0xC2, 0x80], stack).unwrap();
+    assert_eq!(result.get(0), &StackItem::int(-1));
+    assert_eq!(result.get(1), &StackItem::int(5));
+    assert_eq!(result.get(2).as_slice().unwrap().get_bytestring(0), vec![9]);
+
+    // AUGDICTEXTRA
+    let result = run(vec![0xF4, 0xCC, 0x80], params(dict.clone())).unwrap();
+    assert_eq!(result.get(0), &StackItem::int(5));
+
+    // AUGDICTUDEL
+    let mut stack = vec![StackItem::int(3)];
+    stack.extend(params(dict));
+    let result = run(vec![0xF4, 0xCA, 0x80], stack).unwrap();
+    assert_eq!(result.get(0), &StackItem::int(-1));
+    assert_eq!(result.get(1), &StackItem::None);
+
+    // opcodes require capability
+    let mut stack = vec![StackItem::Slice(value(9)), StackItem::int(5), StackItem::int(3)];
+    stack.extend(params(StackItem::None));
+    let err = engine(0, vec![0xF4, 0xC6, 0x80], stack).execute().unwrap_err();
+    assert_eq!(tvm_exception_code(&err), Some(ExceptionCode::InvalidOpcode));
+}
@@ -0,0 +1,46 @@
+/*
+* Copyright (C) 2019-2024 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+use super::*;
+use ever_block::BuilderData;
+
+#[test]
+fn test_run_many_keeps_order() {
+    // DROP PUSHINT 7
+    let ok = BuilderData::with_raw(vec![0x30, 0x77], 16).unwrap().into_cell().unwrap();
+    // THROW 42
+    let throw = BuilderData::with_raw(vec![0xF2, 0x2A], 16).unwrap().into_cell().unwrap();
+    let params = Arc::new(HarnessParams::default());
+    let jobs = (0..100).map(|i| {
+        let code = if i % 2 == 0 { ok.clone() } else { throw.clone() };
+        Job::get_method(code, Cell::default(), i, vec![int!(i)], params.clone())
+    }).collect::<Vec<_>>();
+
+    let outcomes = run_many_with_threads(jobs.clone(), 4);
+    assert_eq!(outcomes.len(), 100);
+    for (i, outcome) in outcomes.iter().enumerate() {
+        let result = outcome.as_ref().unwrap();
+        if i % 2 == 0 {
+            assert_eq!(result.exit_code, 0);
+            assert_eq!(result.stack.storage, vec![int!(i), int!(7)]);
+        } else {
+            assert_eq!(result.exit_code, 42);
+        }
+    }
+
+    let sequential = run_many_with_threads(jobs, 1);
+    for (parallel, sequential) in outcomes.iter().zip(sequential.iter()) {
+        assert_eq!(parallel.as_ref().unwrap().gas_used, sequential.as_ref().unwrap().gas_used);
+    }
+    assert!(run_many(Vec::new()).is_empty());
+}
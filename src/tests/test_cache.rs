@@ -0,0 +1,76 @@
+/*
+* Copyright (C) 2019-2024 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+use super::*;
+
+fn key(state: u8, message: u8, config: u8) -> ExecutionKey {
+    ExecutionKey::new(
+        UInt256::with_array([state; 32]),
+        UInt256::with_array([message; 32]),
+        UInt256::with_array([config; 32])
+    )
+}
+
+#[test]
+fn test_execution_cache_invalidation() {
+    let mut cache = ExecutionCache::default();
+    cache.insert(key(1, 1, 0), 10);
+    cache.insert(key(1, 2, 0), 20);
+    cache.insert(key(2, 1, 1), 30);
+
+    let mut executed = false;
+    let result = cache.get_or_try_insert_with(key(1, 1, 0), || {
+        executed = true;
+        Err::<i32, ()>(())
+    });
+    assert_eq!(result, Ok(&10));
+    assert!(!executed);
+
+    assert_eq!(cache.invalidate_state(&UInt256::with_array([1; 32])), 2);
+    assert_eq!(cache.get(&key(1, 1, 0)), None);
+    assert_eq!(cache.get(&key(2, 1, 1)), Some(&30));
+
+    assert_eq!(cache.invalidate_config(&UInt256::with_array([1; 32])), 1);
+    assert!(cache.is_empty());
+    assert_eq!(cache.invalidate_state(&UInt256::with_array([2; 32])), 0);
+}
+
+#[test]
+fn test_execution_cache_capacity() {
+    let mut cache = ExecutionCache::with_capacity(2);
+    cache.insert(key(1, 1, 0), 1);
+    cache.insert(key(1, 2, 0), 2);
+    cache.insert(key(1, 2, 0), 3);
+    assert_eq!(cache.len(), 2);
+    cache.insert(key(1, 3, 0), 4);
+    assert_eq!(cache.len(), 1);
+    assert_eq!(cache.get(&key(1, 3, 0)), Some(&4));
+}
+
+#[test]
+fn test_execution_cache_zero_capacity() {
+    let mut cache = ExecutionCache::with_capacity(0);
+    assert_eq!(cache.insert(key(1, 1, 0), 1), None);
+    assert!(cache.is_empty());
+
+    let mut executed = 0;
+    for _ in 0..2 {
+        let result = cache.get_or_try_insert_with(key(1, 1, 0), || {
+            executed += 1;
+            Ok::<i32, ()>(executed)
+        });
+        assert_eq!(result, Ok(&executed));
+    }
+    assert_eq!(executed, 2);
+    assert!(cache.is_empty());
+}
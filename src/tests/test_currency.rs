@@ -0,0 +1,104 @@
+/*
+* Copyright (C) 2019-2024 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+use super::*;
+use crate::{executor::engine::VmCapabilities, stack::Stack};
+
+fn extra(currencies: &[(u32, u64)]) -> HashmapE {
+    let mut dict = HashmapE::with_bit_len(EXTRA_CURRENCY_KEY_BITS);
+    for (id, amount) in currencies {
+        let value = write_extra_amount(&BigInt::from(*amount)).unwrap();
+        dict.set_builder(extra_currency_key(*id).unwrap(), &value).unwrap();
+    }
+    dict
+}
+
+fn run_with_gas(code: Vec<u8>, stack: Vec<StackItem>) -> Result<(Stack, i64)> {
+    let mut engine = Engine::with_capabilities(0)
+        .with_vm_capabilities(VmCapabilities::CapExtraCurrency as u64)
+        .setup_with_libraries(SliceData::new(code), None, Some(Stack::with_storage(stack)), None, vec![]);
+    engine.execute()?;
+    Ok((engine.stack().clone(), engine.gas_used()))
+}
+
+fn run(code: Vec<u8>, stack: Vec<StackItem>) -> Result<Stack> {
+    run_with_gas(code, stack).map(|(stack, _)| stack)
+}
+
+#[test]
+fn test_extra_currency_arithmetic() {
+    let d1 = StackItem::dict(&extra(&[(1, 100), (2, 5)]));
+    let d2 = StackItem::dict(&extra(&[(2, 5), (3, 7)]));
+    let sum = StackItem::dict(&extra(&[(1, 100), (2, 10), (3, 7)]));
+
+    // EXTRACURRENCYADD
+    let stack = run(vec![0xFA, 0x0A, 0x80], vec![d1.clone(), d2.clone()]).unwrap();
+    assert_eq!(stack.get(0), &sum);
+
+    // EXTRACURRENCYSUB
+    let stack = run(vec![0xFA, 0x0B, 0x80], vec![sum.clone(), d2.clone()]).unwrap();
+    assert_eq!(stack.get(0), &boolean!(true));
+    assert_eq!(stack.get(1), &d1);
+    let stack = run(vec![0xFA, 0x0B, 0x80], vec![d1.clone(), d2.clone()]).unwrap();
+    assert_eq!(stack.get(0), &boolean!(false));
+    assert_eq!(stack.get(1), &d1);
+    let stack = run(vec![0xFA, 0x0B, 0x80], vec![d2.clone(), d2.clone()]).unwrap();
+    assert_eq!(stack.get(1), &StackItem::None);
+
+    // EXTRACURRENCYGET
+    let stack = run(vec![0xFA, 0x09, 0x80], vec![d1.clone(), int!(1)]).unwrap();
+    assert_eq!(stack.get(0), &int!(100));
+    let stack = run(vec![0xFA, 0x09, 0x80], vec![StackItem::None, int!(1)]).unwrap();
+    assert_eq!(stack.get(0), &int!(0));
+}
+
+#[test]
+fn test_extra_currency_overflow() {
+    let max = (BigInt::from(1) << 248) - 1;
+    assert!(write_extra_amount(&max).is_ok());
+    assert!(write_extra_amount(&(max.clone() + 1)).is_err());
+    assert!(write_extra_amount(&BigInt::from(-1)).is_err());
+
+    let mut dict = HashmapE::with_bit_len(EXTRA_CURRENCY_KEY_BITS);
+    dict.set_builder(extra_currency_key(1).unwrap(), &write_extra_amount(&max).unwrap()).unwrap();
+    let stack = vec![StackItem::dict(&dict), StackItem::dict(&extra(&[(1, 1)]))];
+    // EXTRACURRENCYADD
+    assert!(run(vec![0xFA, 0x0A, 0x80], stack).is_err());
+}
+
+#[test]
+fn test_load_extra_currency() {
+    let dict = extra(&[(1, 100)]);
+    let mut builder = BuilderData::new();
+    builder.append_bit_one().unwrap();
+    builder.checked_append_reference(dict.data().unwrap().clone()).unwrap();
+    builder.append_bits(0x5, 3).unwrap();
+    let slice = SliceData::load_builder(builder).unwrap();
+    // LDEXTRACURRENCY
+    let stack = run(vec![0xFA, 0x08, 0x80], vec![StackItem::Slice(slice)]).unwrap();
+    assert_eq!(stack.get(1), &StackItem::dict(&dict));
+    assert_eq!(stack.get(0), &StackItem::Slice(SliceData::from_raw(vec![0xA0], 3)));
+    // not available without CapExtraCurrency
+    let mut engine = Engine::with_capabilities(GlobalCapabilities::CapTvmV20 as u64)
+        .setup_with_libraries(SliceData::new(vec![0xFA, 0x08, 0x80]), None, None, None, vec![]);
+    assert!(engine.execute().is_err());
+}
+
+#[test]
+fn test_extra_currency_other_cells_charged() {
+    // EXTRACURRENCYADD of empty collection: every cell of the other one is loaded once
+    let other = extra(&[(1, 1), (2, 2)]);
+    let (_, empty) = run_with_gas(vec![0xFA, 0x0A, 0x80], vec![StackItem::None, StackItem::None]).unwrap();
+    let (_, gas) = run_with_gas(vec![0xFA, 0x0A, 0x80], vec![StackItem::None, StackItem::dict(&other)]).unwrap();
+    assert!(gas - empty >= 3 * 100);
+}
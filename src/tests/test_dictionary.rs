@@ -0,0 +1,93 @@
+/*
+* Copyright (C) 2019-2024 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+use super::*;
+use crate::stack::Stack;
+use ever_block::{Cell, HashmapType};
+
+fn key(key: u8) -> SliceData {
+    SliceData::from_raw(vec![key], 8)
+}
+
+fn value(data: u8) -> Cell {
+    BuilderData::with_raw(vec![data], 8).unwrap().into_cell().unwrap()
+}
+
+fn dict_of_refs(keys: &[u8]) -> HashmapE {
+    let mut dict = HashmapE::with_bit_len(8);
+    for k in keys {
+        dict.setref(key(*k), &value(*k)).unwrap();
+    }
+    dict
+}
+
+fn run(code: Vec<u8>, stack: Vec<StackItem>) -> Result<(Stack, i64)> {
+    let mut engine = Engine::with_capabilities(0)
+        .setup_with_libraries(SliceData::new(code), None, Some(Stack::with_storage(stack)), None, vec![]);
+    engine.execute()?;
+    Ok((engine.stack().clone(), engine.gas_used()))
+}
+
+#[test]
+fn test_dictgetoptref() {
+    let dict = StackItem::dict(&dict_of_refs(&[0x10, 0x20]));
+    // DICTUGETOPTREF
+    let (stack, _) = run(vec![0xF4, 0x6B, 0x80], vec![int!(0x20), dict.clone(), int!(8)]).unwrap();
+    assert_eq!(stack.storage, vec![StackItem::Cell(value(0x20))]);
+    let (stack, _) = run(vec![0xF4, 0x6B, 0x80], vec![int!(0x30), dict.clone(), int!(8)]).unwrap();
+    assert_eq!(stack.storage, vec![StackItem::None]);
+    let (stack, _) = run(vec![0xF4, 0x6B, 0x80], vec![int!(0x30), StackItem::None, int!(8)]).unwrap();
+    assert_eq!(stack.storage, vec![StackItem::None]);
+    // DICTGETOPTREF with slice key
+    let (stack, _) = run(vec![0xF4, 0x69, 0x80], vec![StackItem::Slice(key(0x10)), dict, int!(8)]).unwrap();
+    assert_eq!(stack.storage, vec![StackItem::Cell(value(0x10))]);
+}
+
+#[test]
+fn test_dictsetgetoptref() {
+    let dict = StackItem::dict(&dict_of_refs(&[0x10, 0x20]));
+    // DICTUSETGETOPTREF replaces value returning old one
+    let stack = vec![StackItem::Cell(value(0x77)), int!(0x10), dict.clone(), int!(8)];
+    let (stack, _) = run(vec![0xF4, 0x6F, 0x80], stack).unwrap();
+    assert_eq!(stack.get(0), &StackItem::Cell(value(0x10)));
+    let mut expected = dict_of_refs(&[0x20]);
+    expected.setref(key(0x10), &value(0x77)).unwrap();
+    assert_eq!(stack.get(1), &StackItem::dict(&expected));
+    // null value removes key
+    let stack = vec![StackItem::None, int!(0x20), dict.clone(), int!(8)];
+    let (stack, _) = run(vec![0xF4, 0x6F, 0x80], stack).unwrap();
+    assert_eq!(stack.storage, vec![StackItem::dict(&dict_of_refs(&[0x10])), StackItem::Cell(value(0x20))]);
+    // and does nothing for absent one
+    let stack = vec![StackItem::None, int!(0x30), dict.clone(), int!(8)];
+    let (stack, _) = run(vec![0xF4, 0x6F, 0x80], stack).unwrap();
+    assert_eq!(stack.storage, vec![dict, StackItem::None]);
+}
+
+#[test]
+fn test_subdict() {
+    let dict = StackItem::dict(&dict_of_refs(&[0x10, 0x11, 0x20]));
+    // SUBDICTUGET keeps full keys with prefix 0x1
+    let (stack, gas_with_prefix) = run(vec![0xF4, 0xB3, 0x80], vec![int!(1), int!(4), dict.clone(), int!(8)]).unwrap();
+    assert_eq!(stack.storage, vec![StackItem::dict(&dict_of_refs(&[0x10, 0x11]))]);
+    // SUBDICTURPGET removes prefix from keys
+    let (stack, _) = run(vec![0xF4, 0xB7, 0x80], vec![int!(1), int!(4), dict.clone(), int!(8)]).unwrap();
+    let subdict = HashmapE::with_hashmap(4, stack.get(0).as_dict().unwrap().cloned());
+    assert_eq!(subdict.len().unwrap(), 2);
+    assert_eq!(subdict.get(SliceData::from_raw(vec![0x10], 4)).unwrap().unwrap().reference(0).unwrap(), value(0x11));
+    // SUBDICTGET with absent prefix gives empty dictionary
+    let stack = vec![StackItem::Slice(SliceData::from_raw(vec![0x30], 4)), int!(4), dict, int!(8)];
+    let (stack, gas_without_prefix) = run(vec![0xF4, 0xB1, 0x80], stack).unwrap();
+    assert_eq!(stack.storage, vec![StackItem::None]);
+    // new root of subdictionary is paid
+    assert!(gas_with_prefix > gas_without_prefix);
+}
@@ -52,3 +52,32 @@ fn test_update_error() {
     assert_eq!(tvm_exception_code(&err), None);
     assert_eq!(tvm_exception_or_custom_code(&err), 112);
 }
+
+#[test]
+fn test_vm_status_code() {
+    let status = VmStatusCode::from_error(&exception!(ExceptionCode::RangeCheckError));
+    assert_eq!(status, VmStatusCode::Exception(ExceptionCode::RangeCheckError));
+    assert_eq!(status.code(), 5);
+    assert_eq!(status.category(), VmStatusCategory::Exception);
+
+    let err = || -> Result<()> { custom_err!(112, "custom code {}", 112) }().unwrap_err();
+    assert_eq!(VmStatusCode::from(&err), VmStatusCode::Custom(112));
+
+    // custom code equal to exception code has its own status code
+    let err = || -> Result<()> { custom_err!(5, "custom code {}", 5) }().unwrap_err();
+    let custom = VmStatusCode::from(&err);
+    assert_eq!(custom, VmStatusCode::Custom(5));
+    assert_eq!(custom.code(), VmStatusCode::CUSTOM_BASE + 5);
+    assert_ne!(custom.code(), status.code());
+    assert_eq!(custom.exit_code(), status.exit_code());
+    let err = VmError { error: err, context: None, backtrace: Vec::new() };
+    assert_eq!(err.status(), custom);
+
+    let err = Error::from(TvmError::InvalidArg(1));
+    assert_eq!(VmStatusCode::from(&err).code(), VmStatusCode::INVALID_ARG);
+    assert_eq!(VmStatusCode::from(&err).category(), VmStatusCategory::Setup);
+
+    let err = || -> Result<()> { fail!("just a text") }().unwrap_err();
+    assert_eq!(VmStatusCode::from(&err), VmStatusCode::Unknown);
+    assert_eq!(VmStatusCode::from_result(&Ok(1)).category(), VmStatusCategory::Success);
+}
@@ -13,7 +13,7 @@
 
 use crate::{
     executor::{
-        serialize_currency_collection, engine::Engine, math::DivMode,
+        serialize_currency_collection, engine::Engine, gas::gas_state::{Gas, GasPrices}, math::DivMode,
         types::{Instruction, InstructionOptions}
     },
     stack::{
@@ -23,7 +23,7 @@ use crate::{
     types::Status
 };
 use std::collections::HashSet;
-use ever_block::{BuilderData, IBitstring, SliceData};
+use ever_block::{BuilderData, GlobalCapabilities, IBitstring, SliceData};
 
 #[test]
 fn test_assert_stack() {
@@ -258,3 +258,1640 @@ fn test_currency_collection_ser() {
     assert_eq!(b1, b2);
 }
 
+
+#[test]
+fn test_resume_after_out_of_gas() {
+    let code = SliceData::new(vec![0x71, 0x72, 0xA0, 0x80]); // PUSHINT 1 PUSHINT 2 ADD
+    let mut engine = Engine::with_capabilities(0)
+        .setup_with_libraries(code.clone(), None, None, None, vec![]);
+    engine.execute().unwrap();
+    let gas_used = engine.gas_used();
+
+    let mut engine = Engine::with_capabilities(0)
+        .setup_with_libraries(code, None, None, Some(Gas::test_with_limit(20)), vec![]);
+    assert_eq!(engine.execute_resumable().unwrap(), None);
+    assert!(engine.is_suspended());
+    assert_eq!(engine.stack().depth(), 1);
+    engine.add_gas(100);
+    assert_eq!(engine.execute_resumable().unwrap(), Some(0));
+    assert!(!engine.is_suspended());
+    assert_eq!(engine.stack().get(0), &int!(3));
+    assert_eq!(engine.gas_used(), gas_used);
+}
+
+#[test]
+fn test_resume_replays_instructions_after_checkpoint() {
+    // 40 x PUSHINT 1, 39 x ADD
+    let mut data = vec![0x71; 40];
+    data.extend_from_slice(&[0xA0; 39]);
+    data.push(0x80);
+    let code = SliceData::new(data);
+    let mut engine = Engine::with_capabilities(0)
+        .setup_with_libraries(code.clone(), None, None, None, vec![]);
+    engine.execute().unwrap();
+    let gas_used = engine.gas_used();
+
+    let mut engine = Engine::with_capabilities(0)
+        .setup_with_libraries(code, None, None, Some(Gas::test_with_limit(100)), vec![]);
+    let mut resumed = 0;
+    while engine.execute_resumable().unwrap().is_none() {
+        assert!(engine.is_suspended());
+        engine.add_gas(100);
+        resumed += 1;
+    }
+    assert!(resumed > 1);
+    assert_eq!(engine.stack().depth(), 1);
+    assert_eq!(engine.stack().get(0), &int!(40));
+    assert_eq!(engine.gas_used(), gas_used);
+}
+
+#[test]
+fn test_bls_verify_gas() {
+    use crate::executor::VmCapabilities;
+    let code = SliceData::new(vec![0xF9, 0x30, 0x00, 0x80]); // BLS_VERIFY
+    let run = |vm_capabilities: u64| {
+        let mut stack = Stack::new();
+        stack.push(StackItem::Slice(SliceData::from_raw(vec![0; 48], 384)));
+        stack.push(StackItem::Slice(SliceData::new(vec![0x80])));
+        stack.push(StackItem::Slice(SliceData::from_raw(vec![0; 96], 768)));
+        let mut engine = Engine::with_capabilities(GlobalCapabilities::CapTvmV20 as u64)
+            .with_vm_capabilities(vm_capabilities)
+            .setup_with_libraries(code.clone(), None, Some(stack), None, vec![]);
+        engine.execute().unwrap();
+        assert_eq!(engine.stack().get(0), &boolean!(false));
+        engine.gas_used()
+    };
+    // instruction, price and implicit RET
+    let basic = Gas::basic_gas_price(24, 0) + 5;
+    assert_eq!(run(0), basic + 2 * Gas::bls_verify_gas_price());
+    assert_eq!(run(VmCapabilities::CapBlsGasFix as u64), basic + Gas::bls_verify_gas_price());
+
+    let mut engine = Engine::with_capabilities(0)
+        .setup_with_libraries(code, None, None, None, vec![]);
+    engine.execute().expect_err("BLS_VERIFY must not be available without CapTvmV20");
+}
+
+#[test]
+fn test_chksigns_batch() {
+    use crate::executor::VmCapabilities;
+    use ed25519_dalek::{Signer, SigningKey};
+    let mut triples = vec![];
+    for i in 1..=3u8 {
+        let key = SigningKey::from_bytes(&[i; 32]);
+        let hash = [i * 16; 32];
+        let signature = key.sign(&hash).to_bytes();
+        triples.push(StackItem::tuple(vec![
+            StackItem::integer(IntegerData::from_unsigned_bytes_be(hash)),
+            StackItem::Slice(SliceData::from_raw(signature.to_vec(), 512)),
+            StackItem::integer(IntegerData::from_unsigned_bytes_be(key.verifying_key().as_bytes())),
+        ]));
+    }
+    let v19 = GlobalCapabilities::CapTvmV19 as u64;
+    let run = |triples: Vec<StackItem>, checked_before: usize, capabilities: u64| {
+        let mut stack = Stack::new();
+        stack.push(StackItem::tuple(triples));
+        let code = SliceData::new(vec![0xF9, 0x18, 0x80]); // CHKSIGNS_BATCH
+        let mut engine = Engine::with_capabilities(capabilities)
+            .with_vm_capabilities(VmCapabilities::CapBatchSignatures as u64)
+            .setup_with_libraries(code, None, Some(stack), None, vec![]);
+        engine.checked_signatures_count = checked_before;
+        engine.execute().unwrap();
+        (engine.stack().get(0).clone(), engine.gas_used(), engine.checked_signatures_count)
+    };
+    // instruction and implicit RET
+    let basic = Gas::basic_gas_price(16, 0) + 5;
+    assert_eq!(run(triples.clone(), 0, v19), (boolean!(true), basic, 3));
+    assert_eq!(run(vec![], 0, v19), (boolean!(true), basic, 0));
+    // each signature is counted and charged like CHKSIGNU above the threshold of free checks
+    let price = Gas::check_signature_price(usize::MAX);
+    assert_eq!(run(triples.clone(), 4, v19), (boolean!(true), basic + 2 * price, 7));
+    // without CapTvmV19 signatures are neither counted nor charged as by CHKSIGNU
+    assert_eq!(run(triples.clone(), 10, 0), (boolean!(true), basic, 10));
+
+    // checks stop at the first invalid signature
+    let mut broken = triples[1].as_tuple().unwrap().to_vec();
+    broken[0] = int!(0);
+    triples[1] = StackItem::tuple(broken);
+    assert_eq!(run(triples.clone(), 0, v19).0, boolean!(false));
+    assert_eq!(run(triples, 10, v19).1, basic + 2 * price);
+
+    let stack = Stack::with_storage(vec![StackItem::tuple(vec![])]);
+    let mut engine = Engine::with_capabilities(u64::MAX)
+        .setup_with_libraries(SliceData::new(vec![0xF9, 0x18, 0x80]), None, Some(stack), None, vec![]);
+    let err = engine.execute().unwrap_err();
+    assert_eq!(crate::error::tvm_exception_code(&err), Some(ever_block::ExceptionCode::InvalidOpcode));
+}
+
+#[test]
+fn test_ecrecover() {
+    use crate::executor::VmCapabilities;
+    use secp256k1::{Message, SecretKey, SECP256K1};
+    let uint = |bytes: &[u8]| StackItem::integer(IntegerData::from_unsigned_bytes_be(bytes));
+    let run = |hash: &[u8], v: i32, signature: &[u8], vm_capabilities: u64| {
+        let stack = vec![uint(hash), int!(v), uint(&signature[..32]), uint(&signature[32..])];
+        let mut engine = Engine::with_capabilities(0).with_vm_capabilities(vm_capabilities)
+            .setup_with_libraries(SliceData::new(vec![0xF9, 0x12, 0x80]), None, Some(Stack::with_storage(stack)), None, vec![]);
+        engine.execute().map(|_| engine.stack().storage.clone())
+    };
+    let caps = VmCapabilities::CapEcrecover as u64;
+    // public key of private key 1 is the generator point of secp256k1
+    let mut key = [0; 32];
+    key[31] = 1;
+    let key = SecretKey::from_slice(&key).unwrap();
+    let gx = hex::decode("79BE667EF9DCBBAC55A06295CE870B07029BFCDB2DCE28D959F2815B16F81798").unwrap();
+    let gy = hex::decode("483ADA7726A3C4655DA4FBFC0E1108A8FD17B448A68554199C47D08FFB10D4B8").unwrap();
+    let hash = [0x5A; 32];
+    let (v, signature) = SECP256K1.sign_ecdsa_recoverable(&Message::from_slice(&hash).unwrap(), &key)
+        .serialize_compact();
+    let v = v.to_i32();
+    assert_eq!(
+        run(&hash, v, &signature, caps).unwrap(),
+        vec![int!(4), uint(&gx), uint(&gy), boolean!(true)]
+    );
+
+    // other hash recovers other key
+    let stack = run(&[0xA5; 32], v, &signature, caps).unwrap();
+    assert_eq!(stack.last(), Some(&boolean!(true)));
+    assert_ne!(stack[1], uint(&gx));
+    // wrong recovery id and zero r are not recovered
+    assert_eq!(run(&hash, 4, &signature, caps).unwrap(), vec![boolean!(false)]);
+    let mut zero_r = signature;
+    zero_r[..32].fill(0);
+    assert_eq!(run(&hash, v, &zero_r, caps).unwrap(), vec![boolean!(false)]);
+    // v out of range
+    let err = run(&hash, 256, &signature, caps).unwrap_err();
+    assert_eq!(crate::error::tvm_exception_code(&err), Some(ever_block::ExceptionCode::RangeCheckError));
+
+    let err = run(&hash, v, &signature, 0).unwrap_err();
+    assert_eq!(crate::error::tvm_exception_code(&err), Some(ever_block::ExceptionCode::InvalidOpcode));
+}
+
+#[test]
+fn test_p256_chksign() {
+    use num::BigUint;
+    use p256::ecdsa::{signature::Signer, Signature, SigningKey};
+    let key = SigningKey::from_slice(&[7; 32]).unwrap();
+    let public = key.verifying_key().to_encoded_point(true).as_bytes().to_vec();
+    let data = b"authenticator data".to_vec();
+    let signature: Signature = key.sign(&data);
+    let signature = signature.normalize_s().unwrap_or(signature).to_bytes().to_vec();
+    // the same signature with s replaced by n - s is valid for plain ECDSA and accepted too
+    let order = BigUint::parse_bytes(b"FFFFFFFF00000000FFFFFFFFFFFFFFFFBCE6FAADA7179E84F3B9CAC2FC632551", 16).unwrap();
+    let high_s = (order - BigUint::from_bytes_be(&signature[32..])).to_bytes_be();
+    let mut malleable = signature[..32].to_vec();
+    malleable.resize(64 - high_s.len(), 0);
+    malleable.extend_from_slice(&high_s);
+    let run = |code: u8, data: StackItem, signature: &[u8], public: &[u8]| {
+        let stack = Stack::with_storage(vec![
+            data,
+            StackItem::Slice(SliceData::from_raw(signature.to_vec(), signature.len() * 8)),
+            StackItem::Slice(SliceData::from_raw(public.to_vec(), public.len() * 8)),
+        ]);
+        let code = SliceData::new(vec![0xF9, code, 0x80]);
+        let mut engine = Engine::with_capabilities(0)
+            .with_vm_capabilities(crate::executor::VmCapabilities::CapP256Signatures as u64)
+            .setup_with_libraries(code, None, Some(stack), None, vec![]);
+        engine.execute().map(|_| engine.stack().get(0).clone())
+    };
+    let slice = |data: &[u8]| StackItem::Slice(SliceData::from_raw(data.to_vec(), data.len() * 8));
+    // P256_CHKSIGNS
+    assert_eq!(run(0x15, slice(&data), &signature, &public).unwrap(), boolean!(true));
+    assert_eq!(run(0x15, slice(&data), &malleable, &public).unwrap(), boolean!(true));
+    assert_eq!(run(0x15, slice(b"other data"), &signature, &public).unwrap(), boolean!(false));
+    assert_eq!(run(0x15, slice(&data), &signature, &[0x05; 33]).unwrap(), boolean!(false));
+    assert!(run(0x15, slice(&data), &signature[..63], &public).is_err());
+    assert!(run(0x15, slice(&data), &signature, &public[..32]).is_err());
+    // P256_CHKSIGNU signs 32 bytes of the hash
+    let hash = [0x5A; 32];
+    let signature: Signature = key.sign(&hash);
+    let signature = signature.normalize_s().unwrap_or(signature).to_bytes().to_vec();
+    let hash = StackItem::integer(IntegerData::from_unsigned_bytes_be(hash));
+    assert_eq!(run(0x14, hash.clone(), &signature, &public).unwrap(), boolean!(true));
+    assert_eq!(run(0x14, int!(0), &signature, &public).unwrap(), boolean!(false));
+}
+
+#[test]
+fn test_rist255() {
+    let run = |code: Vec<u8>, stack: Vec<StackItem>| {
+        let mut engine = Engine::with_capabilities(0)
+            .with_vm_capabilities(crate::executor::VmCapabilities::CapCrypto as u64)
+            .setup_with_libraries(SliceData::new(code), None, Some(Stack::with_storage(stack)), None, vec![]);
+        engine.execute().map(|_| engine.stack().storage.clone())
+    };
+    let base = StackItem::integer(IntegerData::from_str_radix(
+        "e2f2ae0a6abc4e71a884a961c500515f58e30b6aa582dd8db6a65945e08d2d76", 16
+    ).unwrap());
+    // RIST255_PUSHL INC RIST255_MULBASE
+    assert_eq!(run(vec![0xF9, 0x26, 0xA4, 0xF9, 0x25, 0x80], vec![]).unwrap(), vec![base.clone()]);
+    // RIST255_MULBASE RIST255_ADD: g + g*(-1) is identity encoded as zero
+    assert_eq!(run(vec![0xF9, 0x25, 0xF9, 0x22, 0x80], vec![base.clone(), int!(-1)]).unwrap(), vec![int!(0)]);
+    // RIST255_MUL and DUP RIST255_SUB with RIST255_ADD
+    let double = run(vec![0xF9, 0x24, 0x80], vec![base.clone(), int!(2)]).unwrap();
+    assert_eq!(run(vec![0x20, 0xF9, 0x22, 0x80], vec![base.clone()]).unwrap(), double);
+    assert_eq!(run(vec![0xF9, 0x23, 0x80], vec![double[0].clone(), base.clone()]).unwrap(), vec![base.clone()]);
+    // RIST255_VALIDATE
+    assert_eq!(run(vec![0xF9, 0x21, 0x80], vec![base.clone()]).unwrap(), vec![]);
+    for invalid in [int!(1), int!(-1), StackItem::integer(IntegerData::mask(256))] {
+        let err = run(vec![0xF9, 0x21, 0x80], vec![invalid]).unwrap_err();
+        assert_eq!(crate::error::tvm_exception_code(&err), Some(ever_block::ExceptionCode::RangeCheckError));
+    }
+    // RIST255_FROMHASH gives valid points
+    let point = run(vec![0xF9, 0x20, 0x80], vec![int!(1), int!(2)]).unwrap();
+    assert_eq!(run(vec![0xF9, 0x21, 0x80], point).unwrap(), vec![]);
+    // scalars are reduced modulo group order: RIST255_PUSHL ADD RIST255_MULBASE
+    let l_plus_one = run(vec![0xF9, 0x26, 0xA0, 0xF9, 0x25, 0x80], vec![int!(1)]).unwrap();
+    assert_eq!(l_plus_one, vec![base.clone()]);
+    // RIST255_PUSHL NEGATE DEC RIST255_MUL: -l - 1 is -1
+    let minus = run(vec![0xF9, 0x26, 0xA3, 0xA5, 0xF9, 0x24, 0x80], vec![base.clone()]).unwrap();
+    assert_eq!(run(vec![0xF9, 0x22, 0x80], vec![minus[0].clone(), base.clone()]).unwrap(), vec![int!(0)]);
+
+    // RIST255_QVALIDATE, RIST255_QADD, RIST255_QMUL
+    assert_eq!(run(vec![0xB7, 0xF9, 0x21, 0x80], vec![base.clone()]).unwrap(), vec![boolean!(true)]);
+    assert_eq!(run(vec![0xB7, 0xF9, 0x21, 0x80], vec![int!(1)]).unwrap(), vec![boolean!(false)]);
+    assert_eq!(run(vec![0xB7, 0xF9, 0x22, 0x80], vec![base.clone(), base.clone()]).unwrap(), vec![double[0].clone(), boolean!(true)]);
+    assert_eq!(run(vec![0xB7, 0xF9, 0x22, 0x80], vec![base.clone(), int!(1)]).unwrap(), vec![boolean!(false)]);
+    assert_eq!(run(vec![0xB7, 0xF9, 0x24, 0x80], vec![int!(1), int!(2)]).unwrap(), vec![boolean!(false)]);
+    assert_eq!(run(vec![0xB7, 0xF9, 0x25, 0x80], vec![int!(1)]).unwrap(), vec![base.clone(), boolean!(true)]);
+    // type errors are not quiet
+    assert!(run(vec![0xB7, 0xF9, 0x21, 0x80], vec![StackItem::None]).is_err());
+}
+
+#[test]
+fn test_vrfverify() {
+    // vectors of ECVRF-EDWARDS25519-SHA512-TAI from RFC 9381
+    let vectors = [(
+        "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a",
+        "8657106690b5526245a92b003bb079ccd1a92130477671f6fc01ad16f26f723f\
+         26f8a57ccaed74ee1b190bed1f479d9727d2d0f9b005a6e456a35d4fb0daab12\
+         68a1b0db10836d9826a528ca76567805",
+        "",
+        "90cf1df3b703cce59e2a35b925d411164068269d7b2d29f3301c03dd757876ff\
+         66b71dda49d2de59d03450451af026798e8f81cd2e333de5cdf4f3e140fdd8ae",
+    ), (
+        "3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c",
+        "f3141cd382dc42909d19ec5110469e4feae18300e94f304590abdced48aed593\
+         3bf0864a62558b3ed7f2fea45c92a465301b3bbf5e3e54ddf2d935be3b67926d\
+         a3ef39226bbc355bdc9850112c8f4b02",
+        "72",
+        "eb4440665d3891d668e7e0fcaf587f1b4bd7fbfe99d0eb2211ccec90496310eb\
+         5e33821bc613efb94db5e5b54c70a848a0bef4553a41befc57663b56373a5031",
+    )];
+    let slice = |data: &[u8]| StackItem::Slice(SliceData::from_raw(data.to_vec(), data.len() * 8));
+    let run = |data: &[u8], proof: &[u8], key: &str| {
+        let stack = Stack::with_storage(vec![
+            slice(data), slice(proof), StackItem::integer(IntegerData::from_str_radix(key, 16).unwrap())
+        ]);
+        let code = SliceData::new(vec![0xF9, 0x16, 0x80]); // VRFVERIFY
+        let mut engine = Engine::with_capabilities(0)
+            .with_vm_capabilities(crate::executor::VmCapabilities::CapVrfVerify as u64)
+            .setup_with_libraries(code, None, Some(stack), None, vec![]);
+        engine.execute().map(|_| engine.stack().storage.clone())
+    };
+    for (key, proof, alpha, output) in vectors {
+        let proof = hex::decode(proof).unwrap();
+        let alpha = hex::decode(alpha).unwrap();
+        let output = hex::decode(output).unwrap();
+        assert_eq!(run(&alpha, &proof, key).unwrap(), vec![slice(&output), boolean!(true)]);
+        assert_eq!(run(b"other", &proof, key).unwrap(), vec![boolean!(false)]);
+        let mut broken = proof.clone();
+        broken[40] ^= 1;
+        assert_eq!(run(&alpha, &broken, key).unwrap(), vec![boolean!(false)]);
+        assert!(run(&alpha, &proof[..79], key).is_err());
+    }
+    // small order public key
+    let (_, proof, alpha, _) = vectors[0];
+    let proof = hex::decode(proof).unwrap();
+    let identity = "0100000000000000000000000000000000000000000000000000000000000000";
+    assert_eq!(run(&hex::decode(alpha).unwrap(), &proof, identity).unwrap(), vec![boolean!(false)]);
+    // priced as signature checks: free below threshold, full price above it
+    let (key, proof, alpha, _) = vectors[0];
+    let stack = Stack::with_storage(vec![
+        slice(&hex::decode(alpha).unwrap()), slice(&hex::decode(proof).unwrap()),
+        StackItem::integer(IntegerData::from_str_radix(key, 16).unwrap())
+    ]);
+    let gas_used = |checked_before: usize| {
+        let mut engine = Engine::with_capabilities(0)
+            .with_vm_capabilities(crate::executor::VmCapabilities::CapVrfVerify as u64)
+            .setup_with_libraries(SliceData::new(vec![0xF9, 0x16, 0x80]), None, Some(stack.clone()), None, vec![]);
+        engine.checked_signatures_count = checked_before;
+        engine.execute().unwrap();
+        engine.gas_used()
+    };
+    assert_eq!(gas_used(100) - gas_used(0), Gas::check_signature_price(101));
+}
+
+fn execute_custom_answer(engine: &mut Engine) -> Status {
+    engine.load_custom_instruction("ANSWER")?;
+    engine.try_use_gas(100)?;
+    engine.stack_mut().push(int!(42));
+    Ok(())
+}
+
+#[test]
+fn test_register_custom_handler() {
+    let mut engine = Engine::with_capabilities(0);
+    engine.register_handler(&[0x00], execute_custom_answer).expect_err("NOP must not be replaced");
+    engine.register_handler(&[0xF9, 0x00], execute_custom_answer).expect_err("HASHCU must not be replaced");
+    engine.register_handler(&[0xF9, 0xA0], execute_custom_answer).unwrap();
+    engine.register_handler(&[0xF9, 0xA0], execute_custom_answer).expect_err("double registration");
+
+    let code = SliceData::new(vec![0xF9, 0xA0, 0x80]);
+    let mut engine = engine.setup_with_libraries(code.clone(), None, None, None, vec![]);
+    engine.execute().unwrap();
+    assert_eq!(engine.stack().get(0), &int!(42));
+    assert!(engine.gas_used() > 100);
+
+    let mut engine = Engine::with_capabilities(0)
+        .setup_with_libraries(code, None, None, None, vec![]);
+    engine.execute().expect_err("custom opcode is unknown without registration");
+}
+
+struct TestLibraryProvider(ever_block::Cell);
+
+impl crate::executor::LibraryProvider for TestLibraryProvider {
+    fn get_library(&self, hash: &ever_block::UInt256) -> ever_block::Result<Option<ever_block::Cell>> {
+        Ok(Some(self.0.clone()).filter(|lib| &lib.repr_hash() == hash))
+    }
+}
+
+#[test]
+fn test_library_provider() {
+    let lib = BuilderData::with_raw(vec![0x71, 0x80], 8).unwrap().into_cell().unwrap();
+    let mut builder = BuilderData::with_raw(vec![2], 8).unwrap();
+    builder.append_raw(lib.repr_hash().as_slice(), 256).unwrap();
+    builder.set_type(ever_block::CellType::LibraryReference);
+    let lib_ref = builder.into_cell().unwrap();
+
+    let mut engine = Engine::with_capabilities(GlobalCapabilities::CapSetLibCode as u64)
+        .setup_with_libraries(SliceData::default(), None, None, None, vec![]);
+    engine.load_library_cell(lib_ref.clone()).expect_err("library is not available");
+
+    engine.set_library_provider(std::sync::Arc::new(TestLibraryProvider(lib.clone())));
+    let gas_used = engine.gas_used();
+    assert_eq!(engine.load_library_cell(lib_ref).unwrap(), lib);
+    assert_eq!(engine.gas_used() - gas_used, Gas::library_lookup_price());
+}
+
+struct TestHost;
+
+impl crate::executor::HostInterface for TestHost {
+    fn call(&self, id: u8, stack: &mut Stack, _gas_remaining: i64) -> ever_block::Result<i64> {
+        let x = stack.drop(0)?.as_integer()?.into(0..=255u8)?;
+        stack.push(int!(x as u32 + id as u32));
+        Ok(50)
+    }
+}
+
+#[test]
+fn test_hostcall() {
+    let code = SliceData::new(vec![0x75, 0xFE, 0xD0, 0x07, 0x80]); // PUSHINT 5 HOSTCALL 7
+    let mut engine = Engine::with_capabilities(0)
+        .setup_with_libraries(code.clone(), None, None, None, vec![]);
+    engine.execute().expect_err("HOSTCALL must be unknown without host interface");
+
+    let mut engine = Engine::with_capabilities(0)
+        .setup_with_libraries(code.clone(), None, None, None, vec![]);
+    engine.set_host_interface(Box::new(TestHost)).unwrap();
+    engine.execute().unwrap();
+    assert_eq!(engine.stack().get(0), &int!(12));
+
+    // RUNVM 0: the child calls the host of its parent
+    let stack = vec![int!(0), StackItem::Slice(code)];
+    let mut engine = Engine::with_capabilities(0)
+        .with_vm_capabilities(crate::executor::VmCapabilities::CapRunVm as u64)
+        .setup_with_libraries(SliceData::new(vec![0xDB, 0x40, 0x00, 0x80]), None, Some(Stack::with_storage(stack)), None, vec![]);
+    engine.set_host_interface(Box::new(TestHost)).unwrap();
+    engine.execute().unwrap();
+    assert_eq!(engine.stack().get(0), &int!(0));
+    assert_eq!(engine.stack().get(1), &int!(12));
+}
+
+#[test]
+fn test_stack_limit() {
+    let code = SliceData::new(vec![0x71, 0x72, 0x73, 0x80]); // PUSHINT 1 PUSHINT 2 PUSHINT 3
+    let mut engine = Engine::with_capabilities(0)
+        .setup_with_libraries(code.clone(), None, None, None, vec![]);
+    engine.set_stack_limit(3);
+    engine.execute().unwrap();
+
+    let mut engine = Engine::with_capabilities(0)
+        .setup_with_libraries(code, None, None, None, vec![]);
+    engine.set_stack_limit(2);
+    let err = engine.execute().expect_err("stack limit must be exceeded");
+    assert_eq!(crate::error::tvm_exception_code(&err), Some(ever_block::ExceptionCode::StackOverflow));
+}
+
+#[test]
+fn test_error_context() {
+    let code = SliceData::new(vec![0x71, 0x72, 0xF2, 0x2A, 0x80]); // PUSHINT 1 PUSHINT 2 THROW 42
+    let mut engine = Engine::with_capabilities(0)
+        .setup_with_libraries(code, None, None, None, vec![]);
+    let err = engine.execute_with_context().expect_err("exception must be thrown");
+    let context = err.context.clone().expect("context must be collected");
+    assert_eq!(context.exception_code, 42);
+    assert_eq!(context.opcode, "THROW");
+    assert_eq!(context.bit_offset, 16);
+    assert_eq!(context.stack_top, vec![int!(2), int!(1)]);
+    assert!(err.to_string().contains("code 42 at THROW"));
+}
+
+#[test]
+fn test_vm_backtrace() {
+    // PUSHCONT { THROW 42 } PUSHCONT { THROW 43 } TRY
+    let code = SliceData::new(vec![0x92, 0xF2, 0x2A, 0x92, 0xF2, 0x2B, 0xF2, 0xFF, 0x80]);
+    let code_hash = code.cell_opt().unwrap().repr_hash();
+    let mut engine = Engine::with_capabilities(0)
+        .setup_with_libraries(code, None, None, None, vec![]);
+    let err = engine.execute_with_context().expect_err("exception must be thrown");
+    assert_eq!(err.context.as_ref().unwrap().exception_code, 43);
+    let backtrace = err.vm_backtrace();
+    assert_eq!(backtrace.len(), 1);
+    assert_eq!(backtrace[0].context.exception_code, 42);
+    assert_eq!(backtrace[0].context.opcode, "THROW");
+    assert_eq!(backtrace[0].handler_hash, code_hash);
+    assert_eq!(backtrace[0].handler_type, "vmc_std");
+}
+
+#[test]
+fn test_error_context_only_for_unhandled_exception() {
+    // PUSHCONT { THROW 42 } PUSHCONT { } TRY
+    let code = SliceData::new(vec![0x92, 0xF2, 0x2A, 0x90, 0xF2, 0xFF, 0x80]);
+    let mut engine = Engine::with_capabilities(0)
+        .setup_with_libraries(code, None, None, None, vec![]);
+    engine.execute().unwrap();
+    assert!(engine.error_context().is_none());
+    assert!(engine.vm_backtrace().is_empty());
+
+    let code = SliceData::new(vec![0xF2, 0x2A, 0x80]); // THROW 42
+    let mut engine = Engine::with_capabilities(0)
+        .setup_with_libraries(code, None, None, None, vec![]);
+    engine.execute().expect_err("exception must be thrown");
+    assert_eq!(engine.error_context().unwrap().exception_code, 42);
+}
+
+#[test]
+fn test_debug_info_source_position() {
+    use crate::debug_info::{DebugInfo, SourcePosition};
+    let code = SliceData::new(vec![0x71, 0xF2, 0x2A, 0x80]); // PUSHINT 1 THROW 42
+    let position = SourcePosition { filename: "contract.sol".to_string(), line: 7 };
+    let debug_info = vec![(code.cell_opt().unwrap().repr_hash(), 8, position.clone())].into_iter().collect::<DebugInfo>();
+    let mut engine = Engine::with_capabilities(0)
+        .setup_with_libraries(code, None, None, None, vec![]);
+    engine.set_debug_info(std::sync::Arc::new(debug_info));
+    let err = engine.execute_with_context().expect_err("exception must be thrown");
+    assert_eq!(err.context.as_ref().unwrap().source, Some(position));
+    assert!(!err.error.to_string().contains("contract.sol:7"));
+    assert!(err.to_string().contains("source contract.sol:7"));
+}
+
+#[test]
+fn test_unlimited_gas_dry_run() {
+    let code = SliceData::new(vec![0x71, 0x72, 0xA0, 0x80]); // PUSHINT 1 PUSHINT 2 ADD
+    let mut engine = Engine::with_capabilities(0)
+        .setup_with_libraries(code.clone(), None, None, None, vec![]);
+    engine.execute().unwrap();
+    let gas_used = engine.gas_used();
+
+    let mut engine = Engine::with_capabilities(0).with_unlimited_gas()
+        .setup_with_libraries(code, None, None, Some(Gas::test_with_limit(10)), vec![]);
+    assert_eq!(engine.execute().unwrap(), 0);
+    assert_eq!(engine.stack().get(0), &int!(3));
+    assert_eq!(engine.gas_used(), gas_used);
+}
+
+#[test]
+fn test_unlimited_gas_runvm_child() {
+    use crate::executor::VmCapabilities;
+    // RUNVM 0 with child PUSHINT 1 PUSHINT 2 ADD: child is not cut by gas limit of parent
+    let child = SliceData::new(vec![0x71, 0x72, 0xA0, 0x80]);
+    let run = |unlimited: bool| {
+        let mut engine = Engine::with_capabilities(0).with_vm_capabilities(VmCapabilities::CapRunVm as u64);
+        if unlimited {
+            engine = engine.with_unlimited_gas();
+        }
+        let stack = vec![int!(0), StackItem::Slice(child.clone())];
+        let mut engine = engine.setup_with_libraries(
+            SliceData::new(vec![0xDB, 0x40, 0x00, 0x80]), None, Some(Stack::with_storage(stack)),
+            Some(Gas::test_with_limit(Gas::runvm_price() + 40)), vec![]
+        );
+        let result = engine.execute();
+        (result, engine)
+    };
+    let (result, _) = run(false);
+    let err = result.expect_err("child must run out of gas of parent");
+    assert_eq!(crate::error::tvm_exception_code(&err), Some(ever_block::ExceptionCode::OutOfGas));
+
+    let (result, engine) = run(true);
+    assert_eq!(result.unwrap(), 0);
+    assert_eq!(engine.stack().get(0), &int!(0));
+    assert_eq!(engine.stack().get(1), &int!(3));
+}
+
+#[cfg(feature = "coverage")]
+#[test]
+fn test_coverage() {
+    let code = SliceData::new(vec![0x71, 0x72, 0xA0, 0x80]); // PUSHINT 1 PUSHINT 2 ADD
+    let hash = code.cell_opt().unwrap().repr_hash();
+    let mut engine = Engine::with_capabilities(0)
+        .setup_with_libraries(code, None, None, None, vec![]);
+    engine.execute().unwrap();
+    let coverage = engine.take_coverage();
+    assert_eq!(coverage.instructions_count(), 3);
+    assert!(coverage.is_covered(&hash, 16));
+    assert!(!coverage.is_covered(&hash, 4));
+    assert_eq!(coverage.opcodes.get("PUSHINT"), Some(&2));
+    assert!(engine.take_coverage().is_empty());
+}
+
+#[test]
+fn test_dispatch_respects_capabilities() {
+    let code = SliceData::new(vec![0xF8, 0x07, 0x80]); // GASCONSUMED
+    let mut engine = Engine::with_capabilities(u64::MAX)
+        .setup_with_libraries(code.clone(), None, None, None, vec![]);
+    let err = engine.execute().expect_err("GASCONSUMED must be unknown without capability");
+    assert_eq!(crate::error::tvm_exception_code(&err), Some(ever_block::ExceptionCode::InvalidOpcode));
+
+    let mut engine = Engine::with_capabilities(0)
+        .with_vm_capabilities(crate::executor::VmCapabilities::CapBlockchainInfo as u64)
+        .setup_with_libraries(code, None, None, None, vec![]);
+    assert_eq!(engine.execute().unwrap(), 0);
+    assert_eq!(engine.stack().depth(), 1);
+}
+
+#[test]
+fn test_custom_gas_prices() {
+    // PUSHINT 1 PUSHINT 1000 MULCONST 3 ADD
+    let code = SliceData::new(vec![0x71, 0x81, 0x03, 0xE8, 0xA7, 0x03, 0xA0, 0x80]);
+    let mut engine = Engine::with_capabilities(0)
+        .setup_with_libraries(code.clone(), None, None, None, vec![]);
+    engine.execute().unwrap();
+    let default_gas = engine.gas_used();
+
+    let prices = GasPrices { instruction_base: 20, implicit_ret: 0, ..GasPrices::default() };
+    let mut engine = Engine::with_capabilities(0).with_gas_prices(prices)
+        .setup_with_libraries(code, None, None, None, vec![]);
+    engine.execute().unwrap();
+    assert_eq!(engine.gas_used(), default_gas + 4 * 10 - Gas::implicit_ret_price());
+    assert_eq!(engine.stack().get(0), &int!(3001));
+}
+
+#[test]
+fn test_create_too_deep_cell() {
+    let mut cell = BuilderData::new().into_cell().unwrap();
+    for _ in 0..100 {
+        let mut b = BuilderData::new();
+        b.checked_append_reference(cell).unwrap();
+        cell = b.into_cell().unwrap();
+    }
+    assert_eq!(cell.depth(0), 100);
+    // NEWC STREF ENDC
+    let code = SliceData::new(vec![0xC8, 0xCC, 0xC9, 0x80]);
+    let gas_used = |vm_capabilities| {
+        let stack = Stack::with_storage(vec![StackItem::Cell(cell.clone())]);
+        let mut engine = Engine::with_capabilities(GlobalCapabilities::CapTvmV20 as u64)
+            .with_vm_capabilities(vm_capabilities)
+            .setup_with_libraries(code.clone(), None, Some(stack), None, vec![]);
+        engine.execute().unwrap();
+        assert_eq!(engine.stack().get(0).as_cell().unwrap().depth(0), 101);
+        engine.gas_used()
+    };
+    let prices = GasPrices::default();
+    let extra = prices.cell_depth * (101 - prices.free_cell_depth as i64);
+    assert_eq!(gas_used(crate::executor::VmCapabilities::CapCellDepthGas as u64), gas_used(0) + extra);
+}
+
+#[test]
+fn test_commit_history() {
+    // COMMIT PUSHINT 1 NEWC STU 8 ENDC POPROOT
+    let code = SliceData::new(vec![0xF8, 0x0F, 0x71, 0xC8, 0xCB, 0x07, 0xC9, 0xED, 0x54, 0x80]);
+    let mut engine = Engine::with_capabilities(0)
+        .setup_with_libraries(code, None, None, None, vec![]);
+    let hashes = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let observed = hashes.clone();
+    engine.set_commit_callback(move |c4, c5| observed.lock().unwrap().push((c4.clone(), c5.clone())));
+    engine.execute().unwrap();
+
+    let new_root = BuilderData::with_raw(vec![1], 8).unwrap().into_cell().unwrap();
+    let empty = ever_block::Cell::default().repr_hash();
+    assert_eq!(*hashes.lock().unwrap(), vec![
+        (empty.clone(), empty.clone()),
+        (new_root.repr_hash(), empty),
+    ]);
+    let history = engine.commit_history();
+    assert_eq!(history.len(), 2);
+    assert_eq!(history[1].get_root(), &StackItem::Cell(new_root));
+    assert_eq!(history[1].get_root(), engine.get_committed_state().get_root());
+}
+
+#[test]
+fn test_incoming_value_and_due_payment() {
+    use crate::executor::VmCapabilities;
+    let mut sci = crate::SmartContractInfo {
+        vm_capabilities: VmCapabilities::CapTransactionInfo as u64,
+        ..Default::default()
+    };
+    sci.set_incoming_value(ever_block::CurrencyCollection::with_grams(1000));
+    sci.set_due_payment(Some(5));
+    let mut ctrls = crate::stack::savelist::SaveList::new();
+    ctrls.put(7, &mut sci.into_temp_data_item()).unwrap();
+    // INCOMINGVALUE DUEPAYMENT
+    let code = SliceData::new(vec![0xF8, 0x31, 0xF8, 0x2F, 0x80]);
+    let run = |vm_capabilities| {
+        let mut engine = Engine::with_capabilities(0).with_vm_capabilities(vm_capabilities)
+            .setup_with_libraries(code.clone(), Some(ctrls.clone()), None, None, vec![]);
+        engine.execute().map(|_| engine.stack().clone())
+    };
+    let stack = run(VmCapabilities::CapTransactionInfo as u64).unwrap();
+    assert_eq!(stack.get(0), &int!(5));
+    assert_eq!(stack.get(1), &StackItem::tuple(vec![int!(1000), StackItem::None]));
+    let err = run(0).expect_err("instructions must be unknown without capability");
+    assert_eq!(crate::error::tvm_exception_code(&err), Some(ever_block::ExceptionCode::InvalidOpcode));
+}
+
+#[test]
+fn test_config_gas_prices() {
+    let prices = ever_block::GasLimitsPrices {
+        gas_price: 65536000,
+        gas_limit: 1000000,
+        gas_credit: 10000,
+        ..Default::default()
+    };
+    let mut config = ever_block::ConfigParams::default();
+    config.set_config(ever_block::ConfigParamEnum::ConfigParam20(prices.clone())).unwrap();
+    let sci = crate::SmartContractInfo {
+        config_params: config.config_params.data().cloned(),
+        ..Default::default()
+    };
+    let mut ctrls = crate::stack::savelist::SaveList::new();
+    ctrls.put(7, &mut sci.into_temp_data_item()).unwrap();
+    // TRUE CONFIGGASPRICES FALSE CONFIGGASPRICES
+    let code = SliceData::new(vec![0x7F, 0xF8, 0x3E, 0x70, 0xF8, 0x3E, 0x80]);
+    let mut engine = Engine::with_capabilities(0)
+        .with_vm_capabilities(crate::executor::VmCapabilities::CapConfigParams as u64)
+        .setup_with_libraries(code, Some(ctrls), None, None, vec![]);
+    engine.execute().unwrap();
+    assert!(engine.stack().get(0).is_null());
+    assert_eq!(engine.stack().get(1), &crate::executor::config::gas_prices_item(&prices));
+    assert_eq!(engine.stack().get(1).as_tuple().unwrap()[0], int!(65536000));
+}
+
+#[test]
+fn test_randu256_chain() {
+    let seed = [7u8; 32];
+    let randu256 = |capabilities, entropy: Option<[u8; 32]>| {
+        // RANDU256
+        let code = SliceData::new(vec![0xF8, 0x10, 0x80]);
+        let mut engine = Engine::with_capabilities(capabilities)
+            .setup_with_libraries(code, None, None, None, vec![]);
+        engine.set_rand_seed(seed).unwrap();
+        if let Some(entropy) = entropy {
+            engine.set_entropy_source(move || entropy);
+        }
+        engine.execute().unwrap();
+        (engine.stack().get(0).clone(), engine.rand().unwrap().clone())
+    };
+    let sha512 = ever_block::sha512_digest(&seed);
+    let expected = (
+        StackItem::int(IntegerData::from_unsigned_bytes_be(&sha512[32..])),
+        IntegerData::from_unsigned_bytes_be(&sha512[..32])
+    );
+    assert_eq!(randu256(0, None), expected);
+    assert_eq!(randu256(GlobalCapabilities::CapsTvmBugfixes2022 as u64, None), expected);
+    assert_ne!(randu256(0, Some([1; 32])), expected);
+
+    // PUSHINT -1 SETRAND
+    let code = SliceData::new(vec![0x7F, 0xF8, 0x14, 0x80]);
+    let mut engine = Engine::with_capabilities(0)
+        .setup_with_libraries(code.clone(), None, None, None, vec![]);
+    engine.execute().unwrap();
+    let mut engine = Engine::with_capabilities(0)
+        .with_vm_capabilities(crate::executor::VmCapabilities::CapStrictRandSeed as u64)
+        .setup_with_libraries(code, None, None, None, vec![]);
+    let err = engine.execute().expect_err("negative seed must be rejected");
+    assert_eq!(crate::error::tvm_exception_code(&err), Some(ever_block::ExceptionCode::RangeCheckError));
+}
+
+#[cfg(feature = "debug")]
+#[test]
+fn test_watchpoints_pause_execution() {
+    use crate::watchpoints::Watchpoint;
+    // PUSHINT 1 PUSHINT 2 ADD DROP
+    let code = SliceData::new(vec![0x71, 0x72, 0xA0, 0x30, 0x80]);
+    let mut engine = Engine::with_capabilities(0)
+        .setup_with_libraries(code, None, None, None, vec![]);
+    let sum = engine.add_watchpoint(Watchpoint::IntegerPushed(IntegerData::from_u32(3)));
+    let empty = engine.add_watchpoint(Watchpoint::StackDepthBelow(1));
+
+    assert_eq!(engine.execute_resumable().unwrap(), None);
+    assert_eq!(engine.triggered_watchpoint(), Some(sum));
+    assert_eq!(engine.stack().get(0), &int!(3));
+
+    assert_eq!(engine.execute_resumable().unwrap(), None);
+    assert_eq!(engine.triggered_watchpoint(), Some(empty));
+    assert_eq!(engine.stack().depth(), 0);
+
+    engine.remove_watchpoint(empty);
+    assert_eq!(engine.execute_resumable().unwrap(), Some(0));
+    assert_eq!(engine.triggered_watchpoint(), None);
+}
+
+#[test]
+fn test_step_back() {
+    // PUSHINT 1 PUSHINT 2 ADD
+    let code = SliceData::new(vec![0x71, 0x72, 0xA0, 0x80]);
+    let mut engine = Engine::with_capabilities(0)
+        .setup_with_libraries(code, None, None, None, vec![]);
+    engine.set_trace_callback(|_, _| {});
+    engine.set_history_window(2);
+    assert_eq!(engine.execute().unwrap(), 0);
+    let gas_used = engine.gas_used();
+    assert_eq!(engine.history_len(), 2);
+
+    // the last step is termination by implicit RET
+    assert!(engine.step_back());
+    assert_eq!(engine.stack().get(0), &int!(3));
+    assert!(engine.step_back());
+    assert_eq!(engine.stack().depth(), 2);
+    assert_eq!(engine.stack().get(0), &int!(2));
+    assert!(engine.gas_used() < gas_used);
+    assert!(!engine.step_back());
+
+    assert_eq!(engine.execute_resumable().unwrap(), Some(0));
+    assert_eq!(engine.stack().get(0), &int!(3));
+    assert_eq!(engine.gas_used(), gas_used);
+}
+
+#[test]
+fn test_step_limit() {
+    // AGAINEND NOP
+    let code = SliceData::new(vec![0xEB, 0x00, 0x80]);
+    let mut engine = Engine::with_capabilities(0).with_unlimited_gas()
+        .setup_with_libraries(code, None, None, None, vec![]);
+    engine.set_limits(crate::executor::Limits { max_steps: Some(1000), max_millis: Some(60_000) });
+    let err = engine.execute().expect_err("infinite loop must be aborted");
+    assert!(matches!(err.downcast_ref::<crate::error::TvmError>(), Some(crate::error::TvmError::LimitExceeded(_))));
+    assert_eq!(crate::error::VmStatusCode::from(&err).code(), crate::error::VmStatusCode::LIMIT_EXCEEDED);
+    assert!((1000..1010).contains(&engine.steps()));
+}
+
+#[test]
+fn test_time_limit_with_clock() {
+    use std::sync::atomic::{AtomicU64, Ordering};
+    // AGAINEND NOP
+    let code = SliceData::new(vec![0xEB, 0x00, 0x80]);
+    let mut engine = Engine::with_capabilities(0).with_unlimited_gas()
+        .setup_with_libraries(code, None, None, None, vec![]);
+    let time = AtomicU64::new(1000);
+    engine.set_clock(move || time.fetch_add(10, Ordering::Relaxed));
+    engine.set_limits(crate::executor::Limits { max_steps: None, max_millis: Some(100) });
+    let err = engine.execute().expect_err("infinite loop must be aborted");
+    assert!(matches!(err.downcast_ref::<crate::error::TvmError>(), Some(crate::error::TvmError::LimitExceeded(_))));
+    assert!((10..20).contains(&engine.steps()));
+}
+
+#[test]
+fn test_tuple_gas_vectors() {
+    let gas_used = |code: Vec<u8>, stack: Vec<StackItem>, vm_capabilities: u64| {
+        let mut engine = Engine::with_capabilities(GlobalCapabilities::CapFixTupleIndexBug as u64)
+            .with_vm_capabilities(vm_capabilities)
+            .setup_with_libraries(SliceData::new(code), None, Some(Stack::with_storage(stack)), None, vec![]);
+        engine.execute().unwrap();
+        (engine.gas_used(), engine.stack().get(0).clone())
+    };
+    let base = gas_used(vec![0x80], vec![], 0).0;
+    let tuple = StackItem::tuple(vec![StackItem::None; 100]);
+    // code, stack, legacy gas, gas under CapExactTupleGas excluding implicit RET
+    let vectors = vec![
+        // TUPLE 15
+        (vec![0x6F, 0x0F, 0x80], vec![int!(1); 15], 41, 41),
+        // UNTUPLE 2
+        (vec![0x6F, 0x22, 0x80], vec![StackItem::tuple(vec![int!(1), int!(2)])], 28, 28),
+        // INDEX 0
+        (vec![0x6F, 0x10, 0x80], vec![tuple.clone()], 26, 26),
+        // SETINDEXVARQ out of range with null value
+        (vec![0x6F, 0x87, 0x80], vec![tuple.clone(), StackItem::None, int!(200)], 26, 126),
+    ];
+    for (code, stack, legacy, exact) in vectors {
+        assert_eq!(gas_used(code.clone(), stack.clone(), 0).0, base + legacy);
+        assert_eq!(gas_used(code, stack, crate::executor::VmCapabilities::CapExactTupleGas as u64).0, base + exact);
+    }
+    let (_, result) = gas_used(vec![0x6F, 0x87, 0x80], vec![tuple.clone(), StackItem::None, int!(200)], 0);
+    assert_eq!(result, tuple);
+    // null tuple stays null in new mode
+    let stack = vec![StackItem::None, StackItem::None, int!(5)];
+    assert_eq!(gas_used(vec![0x6F, 0x87, 0x80], stack.clone(), 0).1, StackItem::tuple(vec![]));
+    assert_eq!(gas_used(vec![0x6F, 0x87, 0x80], stack, crate::executor::VmCapabilities::CapExactTupleGas as u64).1, StackItem::None);
+}
+
+#[test]
+fn test_buildmsg() {
+    let dest = crate::addr::std_addr(0, &ever_block::UInt256::with_array([0x11; 32])).unwrap();
+    let run = |dest: SliceData| {
+        let stack = Stack::with_storage(vec![
+            StackItem::builder(BuilderData::new()),
+            StackItem::Slice(dest),
+            int!(1000),
+            StackItem::None,
+            int!(-1),
+        ]);
+        // BUILDMSG
+        let code = SliceData::new(vec![0xFA, 0x48, 0x80]);
+        let mut engine = Engine::with_capabilities(0)
+            .with_vm_capabilities(crate::executor::VmCapabilities::CapBuildMsg as u64)
+            .setup_with_libraries(code, None, Some(stack), None, vec![]);
+        engine.execute().map(|_| engine.stack().get(0).as_builder().unwrap().clone())
+    };
+    let builder = run(dest.clone()).unwrap();
+    let mut slice = SliceData::load_builder(builder).unwrap();
+    let info = <ever_block::CommonMsgInfo as ever_block::Deserializable>::construct_from(&mut slice).unwrap();
+    assert_eq!(slice.remaining_bits(), 0);
+    match info {
+        ever_block::CommonMsgInfo::IntMsgInfo(header) => {
+            assert!(header.ihr_disabled);
+            assert!(header.bounce);
+            assert!(!header.bounced);
+            let dst = <ever_block::MsgAddressInt as ever_block::Deserializable>::construct_from(&mut dest.clone());
+            assert_eq!(header.dst, dst.unwrap());
+            assert_eq!(header.value, ever_block::CurrencyCollection::with_grams(1000));
+        }
+        _ => panic!("internal message header expected")
+    }
+    // anycast of zero depth
+    let mut builder = BuilderData::new();
+    builder.append_bits(0b101, 3).unwrap();
+    builder.append_bits(0, 5).unwrap();
+    builder.append_i8(0).unwrap();
+    builder.append_raw(&[0x11; 32], 256).unwrap();
+    let anycast = SliceData::load_builder(builder).unwrap();
+    assert!(run(anycast.clone()).is_err());
+    // LDMSGADDRQ checks depth of anycast only under CapStrictAnycast
+    let ldmsgaddrq = |vm_capabilities| {
+        let mut engine = Engine::with_capabilities(0)
+            .with_vm_capabilities(vm_capabilities)
+            .setup_with_libraries(
+                SliceData::new(vec![0xFA, 0x41, 0x80]), None,
+                Some(Stack::with_storage(vec![StackItem::Slice(anycast.clone())])), None, vec![]
+            );
+        engine.execute().unwrap();
+        engine.stack().get(0).clone()
+    };
+    assert_eq!(ldmsgaddrq(0), boolean!(true));
+    assert_eq!(ldmsgaddrq(crate::executor::VmCapabilities::CapStrictAnycast as u64), boolean!(false));
+    // external address
+    assert!(run(SliceData::from_raw(vec![0x00], 2)).is_err());
+}
+
+#[test]
+fn test_sendmsg() {
+    let prices = ever_block::MsgForwardPrices {
+        lump_price: 1000,
+        bit_price: 65536,
+        cell_price: 65536 * 100,
+        ihr_price_factor: 65536 * 3 / 2,
+        ..Default::default()
+    };
+    let mut config = ever_block::ConfigParams::default();
+    config.set_config(ever_block::ConfigParamEnum::ConfigParam25(prices.clone())).unwrap();
+    let myself = crate::addr::std_addr(0, &ever_block::UInt256::with_array([0x11; 32])).unwrap();
+    let sci = crate::SmartContractInfo {
+        capabilities: GlobalCapabilities::CapTvmV20 as u64,
+        config_params: config.config_params.data().cloned(),
+        myself: myself.clone(),
+        ..Default::default()
+    };
+    // body of 2 cells
+    let leaf = BuilderData::with_raw(vec![0xFF], 8).unwrap().into_cell().unwrap();
+    let mut body = BuilderData::with_raw(vec![0; 13], 100).unwrap();
+    body.checked_append_reference(leaf).unwrap();
+    let body = body.into_cell().unwrap();
+    let message = |header: BuilderData| {
+        let mut message = header;
+        message.checked_append_reference(body.clone()).unwrap();
+        message.into_cell().unwrap()
+    };
+    let external = message(BuilderData::with_raw(vec![0xC0], 8).unwrap());
+    let internal = |ihr_disabled: bool| {
+        let mut header = BuilderData::new();
+        header.append_bit_zero().unwrap();
+        header.append_bit_bool(ihr_disabled).unwrap();
+        header.append_bits(0, 2).unwrap();
+        header.append_builder(&myself.as_builder()).unwrap();
+        header.append_builder(&myself.as_builder()).unwrap();
+        message(header)
+    };
+    let run = |message: ever_block::Cell, mode: i32, vm_capabilities: u64| {
+        let mut ctrls = crate::stack::savelist::SaveList::new();
+        ctrls.put(7, &mut sci.clone().into_temp_data_item()).unwrap();
+        let stack = Stack::with_storage(vec![StackItem::Cell(message), int!(mode)]);
+        // SENDMSG
+        let code = SliceData::new(vec![0xFB, 0x08, 0x80]);
+        let mut engine = Engine::with_capabilities(GlobalCapabilities::CapTvmV20 as u64)
+            .with_vm_capabilities(vm_capabilities)
+            .setup_with_libraries(code, Some(ctrls), Some(stack), None, vec![]);
+        engine.execute().map(|_| (engine.stack().get(0).clone(), engine.get_actions()))
+    };
+    let cap = crate::executor::VmCapabilities::CapSendMsg as u64;
+    let err = run(external.clone(), 1, 0).unwrap_err();
+    assert_eq!(crate::error::tvm_exception_code(&err), Some(ever_block::ExceptionCode::InvalidOpcode));
+    // 1000 + 108 bits + 2 cells * 100
+    assert_eq!(crate::executor::compute_fwd_fee(&prices, 2, 108), 1308);
+    let (fee, actions) = run(external.clone(), 1024 + 1, cap).unwrap();
+    assert_eq!(fee, int!(1308));
+    assert_eq!(actions, StackItem::Cell(ever_block::Cell::default()));
+    let (fee, actions) = run(external, 1, cap).unwrap();
+    assert_eq!(fee, int!(1308));
+    assert_ne!(actions, StackItem::Cell(ever_block::Cell::default()));
+    // internal message is charged IHR fee of 1308 * 3 / 2 unless IHR is disabled
+    let (fee, _) = run(internal(false), 1024, cap).unwrap();
+    assert_eq!(fee, int!(1308 + 1962));
+    let (fee, _) = run(internal(true), 1024, cap).unwrap();
+    assert_eq!(fee, int!(1308));
+    // ext_in_msg_info$10 can not be sent
+    let err = run(message(BuilderData::with_raw(vec![0x80], 8).unwrap()), 1024, cap).unwrap_err();
+    assert_eq!(crate::error::tvm_exception_code(&err), Some(ever_block::ExceptionCode::UnknownError));
+}
+
+#[test]
+fn test_cell_level_instructions() {
+    let child = BuilderData::new().into_cell().unwrap();
+    let mut root = BuilderData::new();
+    root.checked_append_reference(child).unwrap();
+    let root = root.into_cell().unwrap();
+    let run = |vm_capabilities: u64| {
+        // CDEPTHI 0
+        let mut engine = Engine::with_capabilities(GlobalCapabilities::CapTvmV20 as u64)
+            .with_vm_capabilities(vm_capabilities)
+            .setup_with_libraries(
+                SliceData::new(vec![0xD7, 0x6C, 0x80]), None,
+                Some(Stack::with_storage(vec![StackItem::Cell(root.clone())])), None, vec![]
+            );
+        engine.execute().map(|_| engine.stack().get(0).clone())
+    };
+    assert_eq!(run(crate::executor::VmCapabilities::CapCellLevels as u64).unwrap(), int!(1));
+    let err = run(0).unwrap_err();
+    assert_eq!(crate::error::tvm_exception_code(&err), Some(ever_block::ExceptionCode::InvalidOpcode));
+}
+
+#[test]
+fn test_continuation_introspection() {
+    let mut cont = crate::stack::continuation::ContinuationData::with_code(SliceData::new(vec![0x71, 0x80]));
+    cont.nargs = 2;
+    cont.stack.push(int!(5));
+    cont.put_to_savelist(4, &mut StackItem::Cell(ever_block::Cell::default())).unwrap();
+    let cont = StackItem::continuation(cont);
+    let run = |code: Vec<u8>, stack: Vec<StackItem>| {
+        let mut engine = Engine::with_capabilities(0)
+            .with_vm_capabilities(crate::executor::VmCapabilities::CapContIntrospection as u64)
+            .setup_with_libraries(SliceData::new(code), None, Some(Stack::with_storage(stack)), None, vec![]);
+        engine.execute().map(|_| engine.stack().get(0).clone())
+    };
+    // CONTCODE
+    assert_eq!(run(vec![0xED, 0x13, 0x80], vec![cont.clone()]).unwrap(), StackItem::Slice(SliceData::new(vec![0x71, 0x80])));
+    // CONTNARGS
+    assert_eq!(run(vec![0xED, 0x14, 0x80], vec![cont.clone()]).unwrap(), int!(2));
+    // CONTSTACK
+    assert_eq!(run(vec![0xED, 0x15, 0x80], vec![cont.clone()]).unwrap(), StackItem::tuple(vec![int!(5)]));
+    // CONTGETCTR
+    assert_eq!(run(vec![0xED, 0x16, 0x80], vec![cont.clone(), int!(4)]).unwrap(), StackItem::Cell(ever_block::Cell::default()));
+    assert_eq!(run(vec![0xED, 0x16, 0x80], vec![cont.clone(), int!(5)]).unwrap(), StackItem::None);
+    assert!(run(vec![0xED, 0x16, 0x80], vec![cont.clone(), int!(6)]).is_err());
+    assert!(run(vec![0xED, 0x14, 0x80], vec![int!(1)]).is_err());
+    let mut engine = Engine::with_capabilities(GlobalCapabilities::CapTvmV20 as u64)
+        .setup_with_libraries(SliceData::new(vec![0xED, 0x14, 0x80]), None, Some(Stack::with_storage(vec![cont])), None, vec![]);
+    let err = engine.execute().expect_err("CONTNARGS must not be available without CapContIntrospection");
+    assert_eq!(crate::error::tvm_exception_code(&err), Some(ever_block::ExceptionCode::InvalidOpcode));
+}
+
+#[test]
+fn test_trykeepargs() {
+    // PUSHCONT { PUSHINT 5 THROW 42 } PUSHCONT { } TRYKEEPARGS 2, 4
+    let code = SliceData::new(vec![0x93, 0x75, 0xF2, 0x2A, 0x90, 0xF2, 0xFD, 0x24, 0x80]);
+    let stack = Stack::with_storage(vec![int!(10), int!(20), int!(30)]);
+    let mut engine = Engine::with_capabilities(GlobalCapabilities::CapsTvmBugfixes2022 as u64)
+        .with_vm_capabilities(crate::executor::VmCapabilities::CapTryKeepArgs as u64)
+        .setup_with_libraries(code.clone(), None, Some(stack.clone()), None, vec![]);
+    engine.execute().unwrap();
+    assert_eq!(engine.stack().storage, vec![int!(10), int!(20), int!(30), int!(0), int!(42)]);
+
+    let mut engine = Engine::with_capabilities(GlobalCapabilities::CapsTvmBugfixes2022 as u64)
+        .setup_with_libraries(code, None, Some(stack), None, vec![]);
+    assert!(engine.execute().is_err());
+}
+
+#[test]
+fn test_custom_signature_domain() {
+    use ed25519_dalek::{Signer, SigningKey};
+    use crate::executor::SignatureDomain;
+    let key = SigningKey::from_bytes(&[7; 32]);
+    let hash = [0x33; 32];
+    let prefix = b"private network".to_vec();
+    let sign = |prefix: &[u8]| key.sign(&[prefix, &hash].concat()).to_bytes();
+    let run_with = |signature: [u8; 64], domain: SignatureDomain, capabilities: u64| {
+        let stack = Stack::with_storage(vec![
+            StackItem::integer(IntegerData::from_unsigned_bytes_be(hash)),
+            StackItem::Slice(SliceData::from_raw(signature.to_vec(), 512)),
+            StackItem::integer(IntegerData::from_unsigned_bytes_be(key.verifying_key().as_bytes())),
+        ]);
+        let code = SliceData::new(vec![0xF9, 0x10, 0x80]); // CHKSIGNU
+        let mut engine = Engine::with_capabilities(capabilities)
+            .setup_with_libraries(code, None, Some(stack), None, vec![]);
+        engine.set_signature_domain(domain);
+        engine.execute().unwrap();
+        engine.stack().get(0).clone()
+    };
+    let run = |domain| run_with(sign(&prefix), domain, 0);
+    assert_eq!(run(SignatureDomain::Custom(prefix.clone())), boolean!(true));
+    assert_eq!(run(SignatureDomain::None), boolean!(false));
+    // network id is used only with CapSignatureWithId
+    assert_eq!(run(SignatureDomain::GlobalId(42)), boolean!(false));
+    // and only in builds with feature signature_with_id
+    let with_id = GlobalCapabilities::CapSignatureWithId as u64;
+    let expected = cfg!(feature = "signature_with_id");
+    assert_eq!(run_with(sign(&42i32.to_be_bytes()), SignatureDomain::GlobalId(42), with_id), boolean!(expected));
+    assert_eq!(run_with(sign(&[]), SignatureDomain::GlobalId(42), with_id), boolean!(!expected));
+}
+
+#[test]
+fn test_exotic_cell_load() {
+    use crate::executor::proof::create_proof;
+    let cell = |data: u8, references: Vec<ever_block::Cell>| {
+        let mut builder = BuilderData::with_raw(vec![data], 8).unwrap();
+        references.into_iter().for_each(|reference| builder.checked_append_reference(reference).unwrap());
+        builder.into_cell().unwrap()
+    };
+    let leaf = cell(2, vec![]);
+    let root = cell(0, vec![cell(1, vec![]), leaf.clone()]);
+    let proof = create_proof(&root, &[leaf.repr_hash()].into_iter().collect()).unwrap();
+    let pruned = proof.reference(0).unwrap().reference(0).unwrap();
+    let run = |code: Vec<u8>, cell: &ever_block::Cell, vm_capabilities: u64| {
+        let stack = Stack::with_storage(vec![StackItem::Cell(cell.clone())]);
+        let mut engine = Engine::with_capabilities(0)
+            .with_vm_capabilities(vm_capabilities)
+            .setup_with_libraries(SliceData::new(code), None, Some(stack), None, vec![]);
+        engine.execute().map(|_| (engine.gas_used(), engine.stack().storage.clone()))
+    };
+    let xload = crate::executor::VmCapabilities::CapXLoad as u64;
+    let exotic_gas = crate::executor::VmCapabilities::CapExoticLoadGas as u64;
+    // XLOAD opens Merkle proof without CapResolveMerkleCell while CTOS does not
+    let (_, stack) = run(vec![0xD7, 0x3A, 0x80], &proof, xload).unwrap();
+    assert_eq!(stack[0].as_cell().unwrap().repr_hash(), root.repr_hash());
+    assert!(run(vec![0xD0, 0x80], &proof, xload).is_err());
+    assert!(run(vec![0xD7, 0x3A, 0x80], &proof, 0).is_err());
+    // pruned branch can not be loaded
+    assert!(run(vec![0xD7, 0x3A, 0x80], &pruned, xload).is_err());
+    let (_, stack) = run(vec![0xD7, 0x3B, 0x80], &pruned, xload).unwrap();
+    assert_eq!(stack, vec![StackItem::Cell(pruned.clone()), boolean!(false)]);
+    // XCTOS and XLOAD charge exotic cells with its own price
+    let exotic_extra = GasPrices::DEFAULT.exotic_cell_load - GasPrices::DEFAULT.cell_load;
+    let legacy = run(vec![0xD7, 0x39, 0x80], &pruned, 0).unwrap().0;
+    assert_eq!(run(vec![0xD7, 0x39, 0x80], &pruned, exotic_gas).unwrap().0, legacy + exotic_extra);
+    let legacy = run(vec![0xD7, 0x39, 0x80], &leaf, 0).unwrap().0;
+    assert_eq!(run(vec![0xD7, 0x39, 0x80], &leaf, exotic_gas).unwrap().0, legacy);
+    let legacy = run(vec![0xD7, 0x3A, 0x80], &proof, xload).unwrap().0;
+    assert_eq!(run(vec![0xD7, 0x3A, 0x80], &proof, xload | exotic_gas).unwrap().0, legacy + exotic_extra);
+    // while other instructions loading cells as is do not: PUSHINT 10 CDATASIZE
+    let legacy = run(vec![0x7A, 0xF9, 0x41, 0x80], &pruned, 0).unwrap().0;
+    assert_eq!(run(vec![0x7A, 0xF9, 0x41, 0x80], &pruned, exotic_gas).unwrap().0, legacy);
+}
+
+#[test]
+fn test_big_cells_limits() {
+    use crate::executor::{engine::{BuilderLimits, MAX_BIG_CELL_DATA_BITS}, VmCapabilities};
+    let big_cells = GlobalCapabilities::CapBigCells as u64;
+    let big_limits = VmCapabilities::CapBigCellLimits as u64;
+    assert_eq!(BuilderLimits::for_capabilities(0, 0), BuilderLimits::default());
+    assert_eq!(BuilderLimits::for_capabilities(big_cells, 0), BuilderLimits::default());
+    assert_eq!(BuilderLimits::for_capabilities(0, big_limits), BuilderLimits::default());
+    assert_eq!(BuilderLimits::for_capabilities(big_cells, big_limits).max_data_bits, MAX_BIG_CELL_DATA_BITS);
+    let run = |code: Vec<u8>, stack: Vec<StackItem>, capabilities: u64, vm_capabilities: u64| {
+        let mut engine = Engine::with_capabilities(capabilities).with_vm_capabilities(vm_capabilities)
+            .setup_with_libraries(SliceData::new(code), None, Some(Stack::with_storage(stack)), None, vec![]);
+        crate::error::tvm_exception_code(&engine.execute().unwrap_err())
+    };
+    // SDSKIPFIRST checks length against active limits
+    let sdskipfirst = |capabilities, vm_capabilities| run(
+        vec![0xD7, 0x21, 0x80],
+        vec![StackItem::Slice(SliceData::new(vec![0xFF, 0x80])), int!(1024)],
+        capabilities, vm_capabilities
+    );
+    assert_eq!(sdskipfirst(0, 0), Some(ever_block::ExceptionCode::RangeCheckError));
+    assert_eq!(sdskipfirst(big_cells, 0), Some(ever_block::ExceptionCode::RangeCheckError));
+    assert_eq!(sdskipfirst(0, big_limits), Some(ever_block::ExceptionCode::RangeCheckError));
+    assert_eq!(sdskipfirst(big_cells, big_limits), Some(ever_block::ExceptionCode::CellUnderflow));
+    // STZEROES checks length against builder limits
+    let stzeroes = |capabilities, vm_capabilities| run(
+        vec![0xCF, 0x40, 0x80],
+        vec![StackItem::builder(BuilderData::new()), int!(1024)],
+        capabilities, vm_capabilities
+    );
+    assert_eq!(stzeroes(0, 0), Some(ever_block::ExceptionCode::RangeCheckError));
+    assert_eq!(stzeroes(big_cells, 0), Some(ever_block::ExceptionCode::RangeCheckError));
+    assert_ne!(stzeroes(big_cells, big_limits), Some(ever_block::ExceptionCode::RangeCheckError));
+}
+
+#[test]
+fn test_builder_limits() {
+    use crate::executor::engine::BuilderLimits;
+    let limits = BuilderLimits { max_data_bits: 8, max_references: 1, ..BuilderLimits::default() };
+    let b = BuilderData::with_raw(vec![0xF0], 4).unwrap();
+    assert!(limits.check_space(&b, 4, 1));
+    assert!(!limits.check_space(&b, 5, 0));
+    assert!(!limits.check_space(&b, 0, 2));
+    assert_eq!(limits.bits_free(&b), 4);
+    assert_eq!(limits.references_free(&b), 1);
+    // limits above the ones of cells do not allow more than cell can keep
+    let limits = BuilderLimits { max_data_bits: 2048, max_references: 8, ..BuilderLimits::default() };
+    assert!(!limits.check_space(&b, 1020, 0));
+    assert_eq!(limits.bits_free(&b), 1019);
+    assert_eq!(limits.references_free(&b), 4);
+
+    // NEWC PUSHINT 9 BCHKBITSQ NEWC BREMBITS NEWC PUSHINT 2 BCHKREFSQ
+    let code = vec![0xC8, 0x79, 0xCF, 0x3D, 0xC8, 0xCF, 0x35, 0xC8, 0x72, 0xCF, 0x3E, 0x80];
+    let run = |limits: Option<BuilderLimits>| {
+        let mut engine = Engine::with_capabilities(0)
+            .setup_with_libraries(SliceData::new(code.clone()), None, None, None, vec![]);
+        if let Some(limits) = limits {
+            engine.set_builder_limits(limits);
+        }
+        engine.execute().unwrap();
+        engine.stack().storage.clone()
+    };
+    assert_eq!(run(None), vec![int!(-1), int!(1023), int!(-1)]);
+    assert_eq!(run(Some(BuilderLimits { max_data_bits: 8, max_references: 1, ..BuilderLimits::default() })), vec![int!(0), int!(8), int!(0)]);
+}
+
+#[test]
+fn test_cell_depth_limits() {
+    use crate::executor::{engine::{BuilderLimits, MAX_CELL_DEPTH}, EngineBuilder};
+    // NEWC ENDC (NEWC STREF ENDC) x 3 builds cell of depth 3
+    let code = vec![0xC8, 0xC9, 0xC8, 0xCC, 0xC9, 0xC8, 0xCC, 0xC9, 0xC8, 0xCC, 0xC9, 0x80];
+    let run = |limits: BuilderLimits| {
+        let mut engine = Engine::with_capabilities(0)
+            .setup_with_libraries(SliceData::new(code.clone()), None, None, None, vec![]);
+        engine.set_builder_limits(limits);
+        engine.execute().map(|_| engine.stack().get(0).as_cell().unwrap().depth(0))
+    };
+    assert_eq!(run(BuilderLimits::default()).unwrap(), 3);
+    let err = run(BuilderLimits { max_cell_depth: 2, ..BuilderLimits::default() }).unwrap_err();
+    assert_eq!(crate::error::tvm_exception_code(&err), Some(ever_block::ExceptionCode::CellOverflow));
+
+    // depth is taken from size limits of configuration
+    let size_limits = ever_block::SizeLimitsConfig { max_vm_data_depth: 2, ..Default::default() };
+    let limits = BuilderLimits::default().with_size_limits(&size_limits);
+    assert_eq!(limits, BuilderLimits { max_cell_depth: 2, ..BuilderLimits::default() });
+    assert_eq!(BuilderLimits::default().max_cell_depth, MAX_CELL_DEPTH);
+    assert!(run(limits).is_err());
+
+    // references limit can not exceed the one of cells
+    let builder = || EngineBuilder::new(0).code(SliceData::new(code.clone())).data(ever_block::Cell::default());
+    let limits = BuilderLimits { max_references: 5, ..BuilderLimits::default() };
+    assert!(builder().builder_limits(limits).build().is_err());
+    let limits = BuilderLimits { max_references: 2, ..BuilderLimits::default() };
+    let engine = builder().builder_limits(limits).build().unwrap();
+    assert_eq!(engine.builder_limits(), &limits);
+}
+
+#[test]
+fn test_long_tuple_instructions() {
+    use crate::executor::VmCapabilities;
+    let tuple = StackItem::long_tuple((0..600).map(StackItem::int).collect());
+    let run_with = |vm_capabilities: u64, code: Vec<u8>, stack: Vec<StackItem>| {
+        let mut engine = Engine::with_capabilities(0).with_vm_capabilities(vm_capabilities)
+            .setup_with_libraries(SliceData::new(code), None, Some(Stack::with_storage(stack)), None, vec![]);
+        engine.execute().map(|_| (engine.stack().storage.clone(), engine.gas_used()))
+    };
+    let run = |code: Vec<u8>, stack: Vec<StackItem>| {
+        run_with(VmCapabilities::CapLongTuples as u64, code, stack).map(|(stack, _)| stack)
+    };
+    // LINDEXVAR
+    assert_eq!(run(vec![0x6F, 0x8E, 0x80], vec![tuple.clone(), int!(280)]).unwrap(), vec![int!(280)]);
+    assert!(run(vec![0x6F, 0x8E, 0x80], vec![tuple.clone(), int!(600)]).is_err());
+    // LTLEN
+    assert_eq!(run(vec![0x6F, 0x8F, 0x80], vec![tuple.clone()]).unwrap(), vec![int!(600)]);
+
+    // every nested tuple passed is charged
+    let gas = |stack: Vec<StackItem>| run_with(VmCapabilities::CapLongTuples as u64, vec![0x6F, 0x8E, 0x80], stack).unwrap().1;
+    let hops = gas(vec![tuple.clone(), int!(599)]) - gas(vec![tuple.clone(), int!(0)]);
+    assert_eq!(hops, 2 * GasPrices::default().tuple_entry);
+
+    // full tuple with last item which is not a tuple is an ordinary one
+    let items = (0..255).map(StackItem::int).collect::<Vec<_>>();
+    let full = StackItem::tuple(items);
+    assert_eq!(run(vec![0x6F, 0x8F, 0x80], vec![full.clone()]).unwrap(), vec![int!(255)]);
+    assert_eq!(run(vec![0x6F, 0x8E, 0x80], vec![full.clone(), int!(254)]).unwrap(), vec![int!(254)]);
+    assert!(run(vec![0x6F, 0x8E, 0x80], vec![full, int!(255)]).is_err());
+
+    // instructions require capability
+    let err = run_with(0, vec![0x6F, 0x8F, 0x80], vec![tuple]).unwrap_err();
+    assert_eq!(crate::error::tvm_exception_code(&err), Some(ever_block::ExceptionCode::InvalidOpcode));
+}
+
+#[test]
+fn test_globals_extended_range() {
+    use crate::executor::VmCapabilities;
+    // SETGLOBVAR PUSHINT 600 GETGLOBVAR
+    let code = vec![0xF8, 0x60, 0x81, 0x02, 0x58, 0xF8, 0x40, 0x80];
+    let stack = vec![int!(42), int!(600)];
+    let mut engine = Engine::with_capabilities(0).with_vm_capabilities(VmCapabilities::CapLongGlobals as u64)
+        .setup_with_libraries(SliceData::new(code.clone()), None, Some(Stack::with_storage(stack.clone())), None, vec![]);
+    engine.execute().unwrap();
+    assert_eq!(engine.stack().storage, vec![int!(42)]);
+    let c7 = engine.ctrl(7).unwrap().as_tuple().unwrap();
+    assert_eq!(c7.len(), 255);
+    assert!(c7[254].as_tuple().is_ok());
+
+    let mut engine = Engine::with_capabilities(0)
+        .setup_with_libraries(SliceData::new(code), None, Some(Stack::with_storage(stack)), None, vec![]);
+    let err = engine.execute().unwrap_err();
+    assert_eq!(crate::error::tvm_exception_code(&err), Some(ever_block::ExceptionCode::RangeCheckError));
+
+    // PUSHINT 254 GETGLOBVAR PUSHINT 600 GETGLOBVAR
+    let code = vec![0x80, 0xFE, 0xF8, 0x40, 0x81, 0x02, 0x58, 0xF8, 0x40, 0x80];
+    let mut c7 = vec![StackItem::None; 254];
+    c7.push(int!(7));
+    let mut ctrls = crate::stack::savelist::SaveList::new();
+    ctrls.put(7, &mut StackItem::tuple(c7)).unwrap();
+    let mut engine = Engine::with_capabilities(0).with_vm_capabilities(VmCapabilities::CapLongGlobals as u64)
+        .setup_with_libraries(SliceData::new(code), Some(ctrls), None, None, vec![]);
+    engine.execute().unwrap();
+    // ordinary globals are not changed, item which is not a tuple has no long globals
+    assert_eq!(engine.stack().storage, vec![int!(7), StackItem::None]);
+}
+
+#[test]
+fn test_engine_builder() {
+    let data = BuilderData::with_raw(vec![0xAB], 8).unwrap().into_cell().unwrap();
+    // PUSH c4 CTOS PLDU 8
+    let code = SliceData::new(vec![0xED, 0x44, 0xD0, 0xD7, 0x0B, 0x07, 0x80]);
+    let mut engine = crate::executor::EngineBuilder::new(0)
+        .code(code.clone())
+        .data(data.clone())
+        .stack(Stack::with_storage(vec![int!(1)]))
+        .gas(Gas::test_with_limit(1000))
+        .build()
+        .unwrap();
+    engine.execute().unwrap();
+    assert_eq!(engine.stack().storage, vec![int!(1), int!(0xAB)]);
+
+    // c4 is required, c7 must be a tuple
+    assert!(crate::executor::EngineBuilder::new(0).code(code.clone()).build().is_err());
+    let result = crate::executor::EngineBuilder::new(0).code(code).data(data).c7(int!(0)).build();
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_from_state_init() {
+    use crate::{executor::StateInitCall, smart_contract_info::SmartContractInfo};
+    use ever_block::{Serializable, StateInit, TickTock, UInt256};
+
+    let data = BuilderData::with_raw(vec![0x55], 8).unwrap().into_cell().unwrap();
+    // DEPTH
+    let code = BuilderData::with_raw(vec![0x68], 8).unwrap().into_cell().unwrap();
+    let state_init = StateInit {
+        code: Some(code),
+        data: Some(data.clone()),
+        special: Some(TickTock { tick: true, tock: false }),
+        ..Default::default()
+    }.serialize().unwrap();
+    let address = UInt256::from([1; 32]);
+    let smc_info = SmartContractInfo::with_myself(crate::addr::std_addr(-1, &address).unwrap());
+
+    let mut engine = Engine::from_state_init(
+        0, state_init.clone(), smc_info.clone(), StateInitCall::TickTock { tock: false }, None
+    ).unwrap();
+    engine.execute().unwrap();
+    assert_eq!(engine.stack().get(0), &int!(4));
+    assert_eq!(engine.stack().get(1), &int!(-2));
+    assert_eq!(engine.stack().get(2), &int!(0));
+    assert_eq!(engine.stack().get(3), &StackItem::integer(IntegerData::from_unsigned_bytes_be(address.as_slice())));
+    assert_eq!(engine.ctrl(4).unwrap().as_cell().unwrap(), &data);
+
+    // tock is not enabled
+    assert!(Engine::from_state_init(0, state_init.clone(), smc_info.clone(), StateInitCall::TickTock { tock: true }, None).is_err());
+    let mut engine = Engine::from_state_init(0, state_init, smc_info, StateInitCall::Stack(Stack::new()), None).unwrap();
+    engine.execute().unwrap();
+    assert_eq!(engine.stack().storage, vec![int!(0)]);
+}
+
+#[test]
+fn test_execute_with_outcome() {
+    let data = BuilderData::with_raw(vec![0xAB], 8).unwrap().into_cell().unwrap();
+    // NEWC ENDC POP c4 COMMIT THROW 5
+    let code = SliceData::new(vec![0xC8, 0xC9, 0xED, 0x54, 0xF8, 0x0F, 0xF2, 0x05, 0x80]);
+    let mut engine = crate::executor::EngineBuilder::new(0)
+        .code(code)
+        .data(data.clone())
+        .gas(Gas::test_with_limit(1000))
+        .build()
+        .unwrap();
+    let outcome = engine.execute_with_outcome().unwrap();
+    assert_eq!(outcome.exit_code, 5);
+    assert_eq!(outcome.stack.storage, vec![int!(0)]);
+    assert_eq!(outcome.c4_before, Some(data));
+    assert_eq!(outcome.c4_after, Some(BuilderData::default().into_cell().unwrap()));
+    assert!(outcome.is_committed());
+    assert!(outcome.steps >= 5);
+    assert_eq!(outcome.gas_used, engine.gas_used());
+
+    // out of gas is reported like in transactions
+    // NEWC ENDC POP c4 COMMIT
+    let code = SliceData::new(vec![0xC8, 0xC9, 0xED, 0x54, 0xF8, 0x0F, 0x80]);
+    let mut engine = crate::executor::EngineBuilder::new(0)
+        .code(code)
+        .data(BuilderData::default().into_cell().unwrap())
+        .gas(Gas::test_with_limit(10))
+        .build()
+        .unwrap();
+    let outcome = engine.execute_with_outcome().unwrap();
+    assert_eq!(outcome.exit_code, -14);
+    assert!(!outcome.is_committed());
+}
+
+#[test]
+fn test_wide_little_endian_integers() {
+    let run = |vm_capabilities: u64, code: Vec<u8>, x: IntegerData| {
+        let stack = vec![StackItem::integer(x), StackItem::builder(BuilderData::new())];
+        let mut engine = Engine::with_capabilities(0).with_vm_capabilities(vm_capabilities)
+            .setup_with_libraries(SliceData::new(code), None, Some(Stack::with_storage(stack)), None, vec![]);
+        engine.execute().map(|_| engine.stack().storage.clone())
+    };
+    let caps = crate::executor::VmCapabilities::CapWideLittleEndian as u64;
+    let value = IntegerData::from_str_radix("0102030405060708090A0B0C0D0E0F10", 16).unwrap();
+
+    // STULE16 ENDC CTOS LDULE16
+    let stack = run(caps, vec![0xCF, 0x45, 0xC9, 0xD0, 0xD7, 0x81, 0x80], value.clone()).unwrap();
+    assert_eq!(stack[0], StackItem::integer(value.clone()));
+    assert_eq!(stack[1].as_slice().unwrap().remaining_bits(), 0);
+    // STULE16 ENDC CTOS PLDU 8: the least significant byte goes first
+    let stack = run(caps, vec![0xCF, 0x45, 0xC9, 0xD0, 0xD7, 0x0B, 0x07, 0x80], value.clone()).unwrap();
+    assert_eq!(stack, vec![int!(0x10)]);
+    // STILE32 ENDC CTOS PLDILE32
+    let stack = run(caps, vec![0xCF, 0x46, 0xC9, 0xD0, 0xD7, 0x86, 0x80], IntegerData::from_i32(-2)).unwrap();
+    assert_eq!(stack, vec![int!(-2)]);
+    // STILE4 ENDC CTOS PLDILE4 are not changed
+    let stack = run(0, vec![0xCF, 0x28, 0xC9, 0xD0, 0xD7, 0x54, 0x80], IntegerData::from_i32(-2)).unwrap();
+    assert_eq!(stack, vec![int!(-2)]);
+
+    // opcodes of other VMs are not taken
+    for code in [vec![0xCF, 0x45, 0x80], vec![0xCF, 0x2D, 0x80], vec![0xD7, 0x71, 0x80]] {
+        let err = run(0, code, value.clone()).unwrap_err();
+        assert_eq!(crate::error::tvm_exception_code(&err), Some(ever_block::ExceptionCode::InvalidOpcode));
+    }
+}
+
+#[test]
+fn test_commit_limits() {
+    use crate::executor::engine::CommitLimits;
+    // NEWC ENDC NEWC STREF ENDC POPROOT COMMIT
+    let code = vec![0xC8, 0xC9, 0xC8, 0xCC, 0xC9, 0xED, 0x54, 0xF8, 0x0F, 0x80];
+    let run = |limits: CommitLimits| {
+        let mut engine = Engine::with_capabilities(0)
+            .setup_with_libraries(SliceData::new(code.clone()), None, None, None, vec![]);
+        engine.set_commit_limits(limits);
+        engine.execute().map(|_| engine.get_committed_state().get_root().clone())
+    };
+    assert!(run(CommitLimits::default()).unwrap().as_cell().is_ok());
+    assert!(run(CommitLimits { max_data_cells: Some(2), max_data_depth: Some(1), ..CommitLimits::default() }).is_ok());
+    let err = run(CommitLimits { max_data_cells: Some(1), ..CommitLimits::default() }).unwrap_err();
+    assert_eq!(crate::error::VmStatusCode::from_error(&err).code(), crate::error::VmStatusCode::COMMIT_REJECTED);
+    assert!(run(CommitLimits { max_data_depth: Some(0), ..CommitLimits::default() }).is_err());
+    // PUSHINT 1 NEWC STU 8 ENDC POP c5 COMMIT: action without link to previous one
+    let code = SliceData::new(vec![0x71, 0xC8, 0xCB, 0x07, 0xC9, 0xED, 0x55, 0xF8, 0x0F, 0x80]);
+    let mut engine = Engine::with_capabilities(0).setup_with_libraries(code, None, None, None, vec![]);
+    engine.set_commit_limits(CommitLimits { max_actions: Some(255), ..CommitLimits::default() });
+    let err = engine.execute().unwrap_err();
+    assert_eq!(crate::error::VmStatusCode::from_error(&err).code(), crate::error::VmStatusCode::COMMIT_REJECTED);
+}
+
+#[test]
+fn test_set_ctrl() {
+    use crate::stack::{continuation::ContinuationData, savelist::Register};
+    // PUSHINT 5 THROWANY, handler set to c2 runs DROP2 PUSHINT 7
+    let mut engine = Engine::with_capabilities(0)
+        .setup_with_libraries(SliceData::new(vec![0x75, 0xF2, 0xF0, 0x80]), None, None, None, vec![]);
+    assert!(engine.set_ctrl(2, StackItem::int(1)).is_err());
+    assert!(engine.set_ctrl(6, StackItem::tuple(vec![])).is_err());
+    let handler = ContinuationData::with_code(SliceData::new(vec![0x5B, 0x77, 0x80]));
+    engine.set_ctrl(2, StackItem::continuation(handler.clone())).unwrap();
+    assert_eq!(engine.ctrls().get_typed(2).unwrap(), Some(Register::Continuation(&handler)));
+    assert!(engine.ctrls().get_typed(6).is_err());
+    assert_eq!(engine.ctrls().iter().map(|(index, _)| index).filter(|index| *index == 2).count(), 1);
+    engine.execute().unwrap();
+    assert_eq!(engine.stack().get(0), &StackItem::int(7));
+}
+
+#[test]
+fn test_tvm_version() {
+    use crate::executor::engine::TvmVersion;
+    assert_eq!(TvmVersion::Latest.resolve(), TvmVersion::V3);
+    assert_eq!(TvmVersion::V1.capabilities(), 0);
+    let network_specific = GlobalCapabilities::CapSignatureWithId as u64 | GlobalCapabilities::CapBigCells as u64;
+    assert_eq!(TvmVersion::Latest.capabilities() & network_specific, 0);
+    for version in TvmVersion::ALL {
+        assert_eq!(Engine::with_version(version).tvm_version(), version);
+    }
+    let capabilities = TvmVersion::V2.capabilities() | GlobalCapabilities::CapTvmV20 as u64;
+    assert_eq!(TvmVersion::from_capabilities(capabilities), TvmVersion::V2);
+    // BLS_G1_ZERO appeared in V3
+    let run = |version: TvmVersion| Engine::with_version(version)
+        .setup_with_libraries(SliceData::new(vec![0xF9, 0x30, 0x15, 0x80]), None, None, None, vec![])
+        .execute();
+    assert!(run(TvmVersion::V2).is_err());
+    assert!(run(TvmVersion::Latest).is_ok());
+}
+
+#[test]
+fn test_returned_frames_are_discarded() {
+    // PUSHINT 1 PUSHINT 2 PUSHINT 3 PUSHCONT { PUSHCONT { DROP } CALLXARGS 1,0 } CALLXARGS 2,0
+    let code = vec![0x71, 0x72, 0x73, 0x94, 0x91, 0x30, 0xDA, 0x10, 0xDA, 0x20, 0x80];
+    let mut engine = Engine::with_capabilities(0)
+        .setup_with_libraries(SliceData::new(code), None, None, None, vec![]);
+    engine.execute().unwrap();
+    assert_eq!(engine.stack(), &Stack::with_storage(vec![StackItem::int(1)]));
+    // no frame is left in c0 after returns
+    assert!(engine.ctrl(0).is_err());
+}
+
+#[test]
+fn test_loop_body_restart() {
+    // PUSHINT 0 PUSHINT 10 PUSHCONT { INC } REPEAT
+    // PUSHCONT { DUP PUSHINT 15 LESS } PUSHCONT { INC } WHILE
+    // PUSHCONT { INC DUP PUSHINT 18 EQUAL } UNTIL
+    // PUSHCONT { INC DUP PUSHINT 20 EQUAL THROWIF 5 } AGAIN
+    let code = vec![
+        0x70, 0x7A, 0x91, 0xA4, 0xE4,
+        0x94, 0x20, 0x80, 0x0F, 0xB9, 0x91, 0xA4, 0xE8,
+        0x95, 0xA4, 0x20, 0x80, 0x12, 0xBA, 0xE6,
+        0x97, 0xA4, 0x20, 0x80, 0x14, 0xBA, 0xF2, 0x45, 0xEA, 0x80
+    ];
+    let run = |trace: bool| {
+        let mut engine = Engine::with_capabilities(0)
+            .setup_with_libraries(SliceData::new(code.clone()), None, None, None, vec![]);
+        if trace {
+            // traced execution takes the general path of switching to loop continuation
+            engine.set_trace_callback(|_, _| {});
+        }
+        let err = engine.execute().unwrap_err();
+        (crate::error::tvm_exception_code(&err), engine.stack().clone(), engine.gas_used(), engine.steps())
+    };
+    let fast = run(false);
+    assert_eq!(fast.0, Some(ever_block::ExceptionCode::RangeCheckError));
+    assert_eq!(fast, run(true));
+}
+
+#[test]
+fn test_checkpoint_rollback() {
+    // CTOS PLDREF CTOS SBITS
+    let code = vec![0xD0, 0xD7, 0x4C, 0xD0, 0xD7, 0x49, 0x80];
+    let child = BuilderData::with_raw(vec![0xAB], 8).unwrap().into_cell().unwrap();
+    let mut root = BuilderData::new();
+    root.checked_append_reference(child).unwrap();
+    let stack = Stack::with_storage(vec![StackItem::cell(root.into_cell().unwrap())]);
+    let mut engine = Engine::with_capabilities(0)
+        .setup_with_libraries(SliceData::new(code), None, Some(stack), None, vec![]);
+    let checkpoint = engine.checkpoint();
+    engine.execute().unwrap();
+    let (gas_used, result) = (engine.gas_used(), engine.stack().clone());
+    assert_eq!(result.get(0), &StackItem::int(8));
+
+    engine.rollback(checkpoint);
+    assert_eq!(engine.gas_used(), 0);
+    assert_eq!(engine.stack().depth(), 1);
+    // cells are loaded for the first time again, so gas is the same
+    engine.execute().unwrap();
+    assert_eq!(engine.gas_used(), gas_used);
+    assert_eq!(engine.stack(), &result);
+}
+
+#[test]
+fn test_exception_dump() {
+    // THROWARG 100 with slice "hi"
+    let run = |capabilities: u64, limit: Option<usize>| {
+        let stack = Stack::with_storage(vec![StackItem::slice(SliceData::new(vec![0x68, 0x69, 0x80]))]);
+        let mut engine = Engine::with_capabilities(capabilities)
+            .setup_with_libraries(SliceData::new(vec![0xF2, 0xC8, 0x64, 0x80]), None, Some(stack), None, vec![]);
+        engine.set_exception_dump(limit);
+        engine.execute().unwrap_err().to_string()
+    };
+    for capabilities in [0, GlobalCapabilities::CapsTvmBugfixes2022 as u64] {
+        assert!(!run(capabilities, None).contains("argument"));
+        let message = run(capabilities, Some(100));
+        assert!(message.contains("argument: CS<"), "{}", message);
+        assert!(message.ends_with(" \"hi\""), "{}", message);
+        assert!(run(capabilities, Some(3)).ends_with("argument: CS<..."));
+    }
+}
+
+#[test]
+fn test_capability_set() {
+    use crate::executor::engine::CapabilitySet;
+    let set = CapabilitySet::from_bits(u64::MAX);
+    assert!(set.contains(GlobalCapabilities::CapTvmV20));
+    assert_eq!(set.iter().count(), crate::executor::engine::KNOWN_CAPABILITIES.len());
+    assert!(CapabilitySet::supported().iter().all(|info| info.is_built()));
+
+    // BLS_G1_ZERO
+    let mut engine = Engine::with_capabilities(0)
+        .setup_with_libraries(SliceData::new(vec![0xF9, 0x30, 0x15, 0x80]), None, None, None, vec![]);
+    assert!(!engine.supports(GlobalCapabilities::CapTvmV20));
+    engine.set_capability(GlobalCapabilities::CapTvmV20, true);
+    assert!(engine.supports(GlobalCapabilities::CapTvmV20));
+    assert_eq!(engine.capability_set(), CapabilitySet::default().with(GlobalCapabilities::CapTvmV20));
+    engine.execute().unwrap();
+
+    engine.set_capability(GlobalCapabilities::CapBigCells, true);
+    engine.set_capability(GlobalCapabilities::CapTvmV20, false);
+    assert_eq!(engine.capabilities(), GlobalCapabilities::CapBigCells as u64);
+    engine.set_vm_capability(crate::executor::VmCapabilities::CapBigCellLimits, true);
+    // limits depending on capabilities are updated as well
+    let limits = crate::executor::engine::BuilderLimits::for_capabilities(engine.capabilities(), engine.vm_capabilities());
+    assert_eq!(engine.builder_limits(), &limits);
+    assert_ne!(limits, crate::executor::engine::BuilderLimits::default());
+}
+
+#[test]
+fn test_ldcont_any_format() {
+    use crate::{executor::VmCapabilities, stack::continuation::{ContinuationData, ContinuationFormat}};
+    let mut cont = ContinuationData::with_code(SliceData::new(vec![0x71, 0x80]));
+    cont.stack.push(int!(5));
+    let (framed, _) = cont.serialize_framed(ContinuationFormat::New, &mut 0, &Default::default()).unwrap();
+    let run = |vm_capabilities: u64| {
+        let stack = vec![StackItem::Slice(SliceData::load_builder(framed.clone()).unwrap())];
+        let mut engine = Engine::with_capabilities(GlobalCapabilities::CapStcontNewFormat as u64)
+            .with_vm_capabilities(vm_capabilities)
+            .setup_with_libraries(SliceData::new(vec![0xD7, 0x66, 0x80]), None, Some(Stack::with_storage(stack)), None, vec![]);
+        engine.execute().map(|_| engine.stack().get(1).as_continuation().unwrap().clone())
+    };
+    assert_eq!(run(VmCapabilities::CapAnyContinuationFormat as u64).unwrap(), cont);
+    assert!(!matches!(run(0), Ok(loaded) if loaded == cont));
+}
+
+#[test]
+fn test_runvm() {
+    use crate::executor::VmCapabilities;
+    let run = |code: Vec<u8>, stack: Vec<StackItem>| {
+        let mut engine = Engine::with_capabilities(0).with_vm_capabilities(VmCapabilities::CapRunVm as u64)
+            .setup_with_libraries(SliceData::new(code), None, Some(Stack::with_storage(stack)), None, vec![]);
+        let result = engine.execute();
+        (result, engine)
+    };
+    // PUSHINT 2 ADD
+    let child = SliceData::new(vec![0x72, 0xA0, 0x80]);
+    let mut standalone = Engine::with_capabilities(0)
+        .setup_with_libraries(child.clone(), None, Some(Stack::with_storage(vec![int!(5)])), None, vec![]);
+    standalone.execute().unwrap();
+    let child_gas = standalone.gas_used();
+
+    // RUNVM 8: gas limit is popped and consumed gas is returned, items below arguments are kept
+    let stack = vec![int!(100), int!(5), int!(1), StackItem::Slice(child.clone()), int!(1000)];
+    let (result, engine) = run(vec![0xDB, 0x40, 0x08, 0x80], stack);
+    assert_eq!(result.unwrap(), 0);
+    assert_eq!(engine.stack().storage, vec![int!(100), int!(7), int!(0), int!(child_gas)]);
+    // RUNVM itself, its price, the child and implicit RET
+    assert_eq!(engine.gas_used(), 34 + Gas::runvm_price() + child_gas + 5);
+
+    // RUNVM 8: the child runs out of its gas limit
+    let stack = vec![int!(5), int!(1), StackItem::Slice(child.clone()), int!(30)];
+    let (result, engine) = run(vec![0xDB, 0x40, 0x08, 0x80], stack);
+    assert_eq!(result.unwrap(), 0);
+    let depth = engine.stack().depth();
+    assert_eq!(engine.stack().get(1), &int!(!(ever_block::ExceptionCode::OutOfGas as i32)));
+    assert_eq!(depth, 3);
+
+    // RUNVM 0x24: c4 is passed, committed c4 and c5 are returned; NEWC ENDC POP c4
+    let c4 = BuilderData::with_raw(vec![0xAA], 8).unwrap().into_cell().unwrap();
+    let code = SliceData::new(vec![0xC8, 0xC9, 0xED, 0x54, 0x80]);
+    let stack = vec![int!(0), StackItem::Slice(code), StackItem::cell(c4)];
+    let (result, engine) = run(vec![0xDB, 0x40, 0x24, 0x80], stack);
+    assert_eq!(result.unwrap(), 0);
+    assert_eq!(engine.stack().depth(), 3);
+    assert_eq!(engine.stack().get(2), &int!(0));
+    assert_eq!(engine.stack().get(1), &StackItem::cell(ever_block::Cell::default()));
+    assert!(engine.stack().get(0).as_cell().is_ok());
+
+    // RUNVM 0: the child sees only its arguments; DEPTH
+    let code = SliceData::new(vec![0x68, 0x80]);
+    let stack = vec![int!(100), int!(7), int!(1), StackItem::Slice(code)];
+    let (result, engine) = run(vec![0xDB, 0x40, 0x00, 0x80], stack);
+    assert_eq!(result.unwrap(), 0);
+    assert_eq!(engine.stack().storage, vec![int!(100), int!(7), int!(1), int!(0)]);
+
+    // RUNVM 0: exception of the child is returned as exit code; THROW 42
+    let code = SliceData::new(vec![0xF2, 0x2A, 0x80]);
+    let stack = vec![int!(100), int!(0), StackItem::Slice(code)];
+    let (result, engine) = run(vec![0xDB, 0x40, 0x00, 0x80], stack);
+    assert_eq!(result.unwrap(), 0);
+    assert_eq!(engine.stack().storage, vec![int!(100), int!(0), int!(42)]);
+
+    // RUNVMX with unknown flag
+    let stack = vec![int!(0), StackItem::Slice(child.clone()), int!(0x200)];
+    let (result, _) = run(vec![0xDB, 0x50, 0x80], stack);
+    assert_eq!(crate::error::tvm_exception_code(&result.unwrap_err()), Some(ever_block::ExceptionCode::RangeCheckError));
+
+    // RUNVM is unknown without capability
+    let stack = vec![int!(0), StackItem::Slice(child)];
+    let mut engine = Engine::with_capabilities(u64::MAX)
+        .setup_with_libraries(SliceData::new(vec![0xDB, 0x40, 0x00, 0x80]), None, Some(Stack::with_storage(stack)), None, vec![]);
+    let err = engine.execute().unwrap_err();
+    assert_eq!(crate::error::tvm_exception_code(&err), Some(ever_block::ExceptionCode::InvalidOpcode));
+}
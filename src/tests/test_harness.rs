@@ -0,0 +1,34 @@
+/*
+* Copyright (C) 2019-2024 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+use super::*;
+use ever_block::BuilderData;
+
+#[test]
+fn test_run_get_method() {
+    // DROP PUSHINT 7
+    let code = BuilderData::with_raw(vec![0x30, 0x77], 16).unwrap().into_cell().unwrap();
+    let result = run_get_method(code, Cell::default(), 12345, vec![int!(1)]).unwrap();
+    assert_eq!(result.exit_code, 0);
+    assert_eq!(result.stack.storage, vec![int!(1), int!(7)]);
+    assert!(result.data.is_none());
+    assert!(result.gas_used > 0);
+}
+
+#[test]
+fn test_run_get_method_exception() {
+    // THROW 42
+    let code = BuilderData::with_raw(vec![0xF2, 0x2A], 16).unwrap().into_cell().unwrap();
+    let result = run_get_method(code, Cell::default(), 0, vec![]).unwrap();
+    assert_eq!(result.exit_code, 42);
+}
@@ -0,0 +1,96 @@
+/*
+* Copyright (C) 2019-2024 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+use super::*;
+use crate::{executor::engine::VmCapabilities, stack::Stack};
+use ever_block::{BuilderData, GlobalCapabilities, Result, SliceData};
+
+fn run(engine: Engine, code: Vec<u8>, stack: Vec<StackItem>) -> Result<StackItem> {
+    let mut engine = engine.setup_with_libraries(
+        SliceData::new(code), None, Some(Stack::with_storage(stack)), None, vec![]
+    );
+    engine.execute()?;
+    Ok(engine.stack().get(0).clone())
+}
+
+fn engine() -> Engine {
+    Engine::with_capabilities(0).with_vm_capabilities(VmCapabilities::CapHashExt as u64)
+}
+
+fn bytes(data: &[u8]) -> StackItem {
+    StackItem::Slice(SliceData::from_raw(data.to_vec(), data.len() * 8))
+}
+
+fn hash(hex: &str) -> StackItem {
+    StackItem::integer(IntegerData::from_unsigned_bytes_be(hex::decode(hex).unwrap()))
+}
+
+const SHA256_ABC: &str = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+
+#[test]
+fn test_hashext() {
+    // HASHEXT SHA256
+    let result = run(engine(), vec![0xF9, 0x04, 0x00, 0x80], vec![bytes(b"a"), bytes(b"bc"), int!(2)]);
+    assert_eq!(result.unwrap(), hash(SHA256_ABC));
+    // HASHEXTR SHA256
+    let result = run(engine(), vec![0xF9, 0x05, 0x00, 0x80], vec![bytes(b"bc"), bytes(b"a"), int!(2)]);
+    assert_eq!(result.unwrap(), hash(SHA256_ABC));
+    // HASHEXT 255 with KECCAK256 id from stack
+    let result = run(engine(), vec![0xF9, 0x04, 0xFF, 0x80], vec![int!(0), int!(3)]);
+    assert_eq!(result.unwrap(), hash("c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470"));
+    // HASHEXT SHA512 returns tuple of two integers
+    let result = run(engine(), vec![0xF9, 0x04, 0x01, 0x80], vec![bytes(b"abc"), int!(1)]).unwrap();
+    assert_eq!(result, StackItem::tuple(vec![
+        hash("ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a"),
+        hash("2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f"),
+    ]));
+    // entries are concatenated bitwise: 0110 + 0001 0110 0010 = "ab"
+    let stack = vec![
+        StackItem::Slice(SliceData::from_raw(vec![0x60], 4)),
+        StackItem::Slice(SliceData::from_raw(vec![0x16, 0x20], 12)),
+        int!(2),
+    ];
+    let result = run(engine(), vec![0xF9, 0x04, 0x00, 0x80], stack);
+    assert_eq!(result.unwrap(), hash("fb8e20fc2e4c3f248c60c39bd652f3c1347298bb977b8b4d5903b85055620603"));
+    // not byte aligned data
+    let stack = vec![StackItem::Slice(SliceData::from_raw(vec![0x60], 4)), int!(1)];
+    assert!(run(engine(), vec![0xF9, 0x04, 0x00, 0x80], stack).is_err());
+    // unknown hash function
+    assert!(run(engine(), vec![0xF9, 0x04, 0x0A, 0x80], vec![int!(0)]).is_err());
+}
+
+#[test]
+fn test_hashexta() {
+    // HASHEXTA SHA256
+    let stack = vec![StackItem::builder(BuilderData::new()), bytes(b"abc"), int!(1)];
+    let result = run(engine(), vec![0xF9, 0x06, 0x00, 0x80], stack).unwrap();
+    assert_eq!(result.as_builder().unwrap().data(), hex::decode(SHA256_ABC).unwrap().as_slice());
+}
+
+#[test]
+fn test_registered_hash_function() {
+    let identity = HashFunction { name: "IDENTITY", digest: |data| data.to_vec(), bytes_per_gas: 1 };
+    let mut engine = engine();
+    assert!(engine.register_hash_function(3, identity).is_err());
+    assert!(engine.register_hash_function(255, identity).is_err());
+    engine.register_hash_function(10, identity).unwrap();
+    let result = run(engine, vec![0xF9, 0x04, 0x0A, 0x80], vec![bytes(b"abc"), int!(1)]);
+    assert_eq!(result.unwrap(), int!(0x616263));
+}
+
+#[test]
+fn test_hashext_gated() {
+    let engine = Engine::with_capabilities(GlobalCapabilities::CapTvmV20 as u64);
+    let err = run(engine, vec![0xF9, 0x04, 0x00, 0x80], vec![bytes(b"abc"), int!(1)]).unwrap_err();
+    assert_eq!(crate::error::tvm_exception_code(&err), Some(ever_block::ExceptionCode::InvalidOpcode));
+}
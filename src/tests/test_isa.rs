@@ -0,0 +1,99 @@
+/*
+* Copyright (C) 2019-2024 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+use super::*;
+use crate::executor::VmCapabilities;
+use ever_block::BuilderData;
+
+#[test]
+fn test_instruction_table() {
+    let add = find_by_opcode(&[0xA0]).unwrap();
+    assert_eq!(add.mnemonic, "ADD");
+    assert_eq!(add.operands, Operands::None);
+    assert_eq!(add.capabilities, 0);
+    assert_eq!(add.base_gas, 18);
+
+    let push = find_by_opcode(&[0x25]).unwrap();
+    assert_eq!(push.last_byte, 0x20..=0x2F);
+    assert_eq!(push.operands, Operands::StackRegister);
+
+    let runvm = find_by_opcode(&[0xDB, 0x4F]).unwrap();
+    assert_eq!(runvm.capabilities, 0);
+    assert_eq!(runvm.vm_capabilities, VmCapabilities::CapRunVm as u64);
+    assert_eq!(runvm.last_byte, 0x40..=0x4F);
+    assert!(!runvm.is_supported(u64::MAX, 0));
+
+    assert!(find_by_opcode(&[0xFE, 0xD0]).is_none());
+}
+
+#[test]
+fn test_validate_code() {
+    // PUSHINT 1 RUNVM 0 inside of CALLREF body
+    let body = BuilderData::with_raw(vec![0x71, 0xDB, 0x40, 0x00], 32).unwrap();
+    // NOP CALLREF
+    let mut code = BuilderData::with_raw(vec![0x00, 0xDB, 0x3C], 24).unwrap();
+    code.checked_append_reference(body.into_cell().unwrap()).unwrap();
+    let code = SliceData::load_builder(code).unwrap();
+
+    let diagnostics = validate_code(&code, 0, 0).unwrap();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].offset, 8);
+    assert_eq!(diagnostics[0].issue, CodeIssue::MissingCapabilities {
+        mnemonic: "RUNVM".to_string(),
+        capabilities: 0,
+        vm_capabilities: VmCapabilities::CapRunVm as u64,
+    });
+    assert!(validate_code(&code, 0, VmCapabilities::CapRunVm as u64).unwrap().is_empty());
+
+    let code = SliceData::new(vec![0x00, 0xF9, 0xFF, 0x80]);
+    let diagnostics = validate_code(&code, u64::MAX, u64::MAX).unwrap();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].offset, 8);
+    assert_eq!(diagnostics[0].issue, CodeIssue::InvalidOpcode);
+}
+
+#[test]
+fn test_slice_section_checklist() {
+    // cell deserialization section of the spec: opcode and mnemonic
+    let checklist: &[(&[u8], &str)] = &[
+        (&[0xD0], "CTOS"), (&[0xD1], "ENDS"), (&[0xD2, 0x00], "LDI"), (&[0xD3, 0x00], "LDU"),
+        (&[0xD4], "LDREF"), (&[0xD5], "LDREFRTOS"), (&[0xD6, 0x00], "LDSLICE"),
+        (&[0xD7, 0x00], "LDIX"), (&[0xD7, 0x01], "LDUX"), (&[0xD7, 0x02], "PLDIX"), (&[0xD7, 0x03], "PLDUX"),
+        (&[0xD7, 0x04], "LDIXQ"), (&[0xD7, 0x05], "LDUXQ"), (&[0xD7, 0x06], "PLDIXQ"), (&[0xD7, 0x07], "PLDUXQ"),
+        (&[0xD7, 0x08], "LDI"), (&[0xD7, 0x09], "LDU"), (&[0xD7, 0x0A], "PLDI"), (&[0xD7, 0x0B], "PLDU"),
+        (&[0xD7, 0x0C], "LDIQ"), (&[0xD7, 0x0D], "LDUQ"), (&[0xD7, 0x0E], "PLDIQ"), (&[0xD7, 0x0F], "PLDUQ"),
+        (&[0xD7, 0x10], "PLDUZ"),
+        (&[0xD7, 0x18], "LDSLICEX"), (&[0xD7, 0x19], "PLDSLICEX"),
+        (&[0xD7, 0x1A], "LDSLICEXQ"), (&[0xD7, 0x1B], "PLDSLICEXQ"),
+        (&[0xD7, 0x1C], "LDSLICE"), (&[0xD7, 0x1D], "PLDSLICE"), (&[0xD7, 0x1E], "LDSLICEQ"), (&[0xD7, 0x1F], "PLDSLICEQ"),
+        (&[0xD7, 0x20], "SDCUTFIRST"), (&[0xD7, 0x21], "SDSKIPFIRST"),
+        (&[0xD7, 0x22], "SDCUTLAST"), (&[0xD7, 0x23], "SDSKIPLAST"), (&[0xD7, 0x24], "SDSUBSTR"),
+        (&[0xD7, 0x26], "SDBEGINSX"), (&[0xD7, 0x27], "SDBEGINSXQ"),
+        (&[0xD7, 0x30], "SCUTFIRST"), (&[0xD7, 0x31], "SSKIPFIRST"),
+        (&[0xD7, 0x32], "SCUTLAST"), (&[0xD7, 0x33], "SSKIPLAST"), (&[0xD7, 0x34], "SUBSLICE"),
+        (&[0xD7, 0x36], "SPLIT"), (&[0xD7, 0x37], "SPLITQ"),
+        (&[0xD7, 0x39], "XCTOS"), (&[0xD7, 0x3A], "XLOAD"), (&[0xD7, 0x3B], "XLOADQ"),
+        (&[0xD7, 0x41], "SCHKBITS"), (&[0xD7, 0x42], "SCHKREFS"), (&[0xD7, 0x43], "SCHKBITREFS"),
+        (&[0xD7, 0x45], "SCHKBITSQ"), (&[0xD7, 0x46], "SCHKREFSQ"), (&[0xD7, 0x47], "SCHKBITREFSQ"),
+        (&[0xD7, 0x48], "PLDREFVAR"), (&[0xD7, 0x49], "SBITS"), (&[0xD7, 0x4A], "SREFS"), (&[0xD7, 0x4B], "SBITREFS"),
+        (&[0xD7, 0x4C], "PLDREF"), (&[0xD7, 0x4D], "PLDREFIDX"),
+        (&[0xD7, 0x50], "LDILE4"), (&[0xD7, 0x51], "LDULE4"), (&[0xD7, 0x52], "LDILE8"), (&[0xD7, 0x53], "LDULE8"),
+        (&[0xD7, 0x60], "LDZEROES"), (&[0xD7, 0x61], "LDONES"), (&[0xD7, 0x62], "LDSAME"),
+        (&[0xD7, 0x64], "SDEPTH"), (&[0xD7, 0x65], "CDEPTH"), (&[0xD7, 0x66], "LDCONT"),
+        (&[0xD7, 0x67], "CLEVELMASK"), (&[0xD7, 0x68], "CHASHI"), (&[0xD7, 0x6C], "CDEPTHI"),
+    ];
+    let missing = checklist.iter()
+        .filter(|(opcode, mnemonic)| find_by_opcode(opcode).map(|info| info.mnemonic.as_str()) != Some(*mnemonic))
+        .collect::<Vec<_>>();
+    assert!(missing.is_empty(), "{:?}", missing);
+}
@@ -0,0 +1,32 @@
+/*
+* Copyright (C) 2019-2024 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+use super::*;
+
+#[test]
+fn test_stack_json_round_trip() {
+    let stack = parse_stack(r#"[1, "-2", "0x2A", null, [3, []]]"#).unwrap();
+    assert_eq!(stack.storage, vec![
+        int!(1),
+        int!(-2),
+        int!(42),
+        StackItem::None,
+        StackItem::tuple(vec![int!(3), StackItem::tuple(vec![])]),
+    ]);
+    let json = stack_to_json(&stack).unwrap();
+    assert_eq!(json.to_string(), r#"["1","-2","42",null,["3",[]]]"#);
+    assert_eq!(parse_stack(&json.to_string()).unwrap(), stack);
+
+    parse_stack("{}").expect_err("stack must be array");
+    parse_stack("[true]").expect_err("booleans are not supported");
+}
@@ -0,0 +1,158 @@
+/*
+* Copyright (C) 2019-2024 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+use super::*;
+use crate::{executor::VmCapabilities, stack::{Stack, integer::behavior::Signaling}};
+use ever_block::{GlobalCapabilities, SliceData};
+
+struct Case {
+    name: &'static str,
+    opcode: &'static [u8],
+    args: usize,
+    // position of shift or length argument from the top, it must be a valid number in both variants
+    count: Option<usize>,
+    // exception of signaling variant for NaN argument
+    signal: ExceptionCode,
+}
+
+const fn case(name: &'static str, opcode: &'static [u8], args: usize) -> Case {
+    Case { name, opcode, args, count: None, signal: ExceptionCode::IntegerOverflow }
+}
+
+const fn counted(name: &'static str, opcode: &'static [u8], args: usize, count: usize) -> Case {
+    Case { name, opcode, args, count: Some(count), signal: ExceptionCode::IntegerOverflow }
+}
+
+const CASES: &[Case] = &[
+    case("ADD", &[0xA0], 2),
+    case("SUB", &[0xA1], 2),
+    case("SUBR", &[0xA2], 2),
+    case("NEGATE", &[0xA3], 1),
+    case("INC", &[0xA4], 1),
+    case("DEC", &[0xA5], 1),
+    case("ADDCONST", &[0xA6, 0x05], 1),
+    case("MULCONST", &[0xA7, 0x05], 1),
+    case("MUL", &[0xA8], 2),
+    case("DIVMOD", &[0xA9, 0x0C], 2),
+    case("DIVR", &[0xA9, 0x05], 2),
+    case("MULDIVMOD", &[0xA9, 0x8C], 3),
+    counted("RSHIFTMOD", &[0xA9, 0x2C], 2, 0),
+    case("LSHIFT#", &[0xAA, 0x02], 1),
+    case("RSHIFT#", &[0xAB, 0x02], 1),
+    counted("LSHIFT", &[0xAC], 2, 0),
+    counted("RSHIFT", &[0xAD], 2, 0),
+    counted("POW2", &[0xAE], 1, 0),
+    case("AND", &[0xB0], 2),
+    case("OR", &[0xB1], 2),
+    case("XOR", &[0xB2], 2),
+    case("NOT", &[0xB3], 1),
+    case("FITS", &[0xB4, 0x07], 1),
+    case("UFITS", &[0xB5, 0x07], 1),
+    counted("FITSX", &[0xB6, 0x00], 2, 0),
+    counted("UFITSX", &[0xB6, 0x01], 2, 0),
+    case("BITSIZE", &[0xB6, 0x02], 1),
+    Case { name: "UBITSIZE", opcode: &[0xB6, 0x03], args: 1, count: None, signal: ExceptionCode::RangeCheckError },
+    case("MIN", &[0xB6, 0x08], 2),
+    case("MAX", &[0xB6, 0x09], 2),
+    case("MINMAX", &[0xB6, 0x0A], 2),
+    case("ABS", &[0xB6, 0x0B], 1),
+    case("FIXMUL96", &[0xB6, 0x10], 2),
+    case("FIXDIV96", &[0xB6, 0x11], 2),
+    case("FIXMUL128", &[0xB6, 0x12], 2),
+    case("FIXDIV128", &[0xB6, 0x13], 2),
+    case("ISQRT", &[0xB6, 0x14], 1),
+    case("ILOG2", &[0xB6, 0x15], 1),
+    case("SGN", &[0xB8], 1),
+    case("LESS", &[0xB9], 2),
+    case("EQUAL", &[0xBA], 2),
+    case("LEQ", &[0xBB], 2),
+    case("GREATER", &[0xBC], 2),
+    case("NEQ", &[0xBD], 2),
+    case("GEQ", &[0xBE], 2),
+    case("CMP", &[0xBF], 2),
+    case("EQINT", &[0xC0, 0x05], 1),
+    case("LESSINT", &[0xC1, 0x05], 1),
+    case("GTINT", &[0xC2, 0x05], 1),
+    case("NEQINT", &[0xC3, 0x05], 1),
+];
+
+fn run_with(vm_capabilities: u64, opcode: &[u8], stack: Vec<StackItem>) -> Result<Vec<StackItem>> {
+    let mut code = opcode.to_vec();
+    code.push(0x80);
+    let mut engine = Engine::with_capabilities(GlobalCapabilities::CapTvmV20 as u64)
+        .with_vm_capabilities(vm_capabilities)
+        .setup_with_libraries(SliceData::new(code), None, Some(Stack::with_storage(stack)), None, vec![]);
+    engine.execute()?;
+    Ok(engine.stack().storage.clone())
+}
+
+fn run(opcode: &[u8], stack: Vec<StackItem>) -> Result<Vec<StackItem>> {
+    run_with(VmCapabilities::CapFixedPoint as u64, opcode, stack)
+}
+
+#[test]
+fn test_fixed_point_gated() {
+    // FIXMUL96 and QISQRT
+    for opcode in [vec![0xB6, 0x10], vec![0xB7, 0xB6, 0x14]] {
+        let err = run_with(0, &opcode, vec![int!(3), int!(3)]).unwrap_err();
+        assert_eq!(tvm_exception_code(&err), Some(ExceptionCode::InvalidOpcode));
+        assert!(run(&opcode, vec![int!(3), int!(3)]).is_ok());
+    }
+}
+
+#[test]
+fn test_nan_conformance() {
+    for case in CASES {
+        let quiet = [&[0xB7], case.opcode].concat();
+        for position in 0..case.args {
+            let mut stack = vec![int!(3); case.args];
+            stack[case.args - 1 - position] = int!(nan);
+            let signal = match case.count == Some(position) {
+                true => ExceptionCode::RangeCheckError,
+                false => case.signal
+            };
+            let err = run(case.opcode, stack.clone()).expect_err(case.name);
+            assert_eq!(tvm_exception_code(&err), Some(signal), "{} with NaN at {}", case.name, position);
+            match run(&quiet, stack) {
+                Ok(result) => {
+                    assert_eq!(signal, case.signal, "Q{} with NaN count must fail", case.name);
+                    assert!(!result.is_empty(), "Q{}", case.name);
+                    assert!(result.iter().all(|x| x.as_integer().unwrap().is_nan()), "Q{} with NaN at {}: {:?}", case.name, position, result);
+                }
+                Err(err) => {
+                    assert_eq!(case.count, Some(position), "Q{} with NaN at {}: {}", case.name, position, err);
+                    assert_eq!(tvm_exception_code(&err), Some(ExceptionCode::RangeCheckError), "Q{}", case.name);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_quiet_overflow() {
+    let max = StackItem::integer(IntegerData::from_str_radix(&"F".repeat(64), 16).unwrap());
+    // ADD, MUL, INC, LSHIFT# 1, POW2, NEGATE of -2^256
+    for (opcode, stack) in [
+        (vec![0xA0], vec![max.clone(), max.clone()]),
+        (vec![0xA8], vec![max.clone(), int!(2)]),
+        (vec![0xA4], vec![max.clone()]),
+        (vec![0xAA, 0x00], vec![max.clone()]),
+        (vec![0xAE], vec![int!(256)]),
+        (vec![0xA3], vec![StackItem::integer(IntegerData::minus_one().shl::<Signaling>(256).unwrap())]),
+    ] {
+        let err = run(&opcode, stack.clone()).unwrap_err();
+        assert_eq!(tvm_exception_code(&err), Some(ExceptionCode::IntegerOverflow));
+        let quiet = [vec![0xB7], opcode].concat();
+        assert_eq!(run(&quiet, stack).unwrap(), vec![int!(nan)]);
+    }
+}
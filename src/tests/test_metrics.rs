@@ -0,0 +1,34 @@
+/*
+* Copyright (C) 2019-2024 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+use super::*;
+use crate::executor::engine::Engine;
+use ever_block::SliceData;
+
+#[test]
+fn test_execution_metrics() {
+    // PUSHINT 1 NEWC ENDC CTOS THROW 5
+    let mut engine = Engine::with_capabilities(0).setup_with_libraries(
+        SliceData::new(vec![0x71, 0xC8, 0xC9, 0xD0, 0xF2, 0x05, 0x80]), None, None, None, vec![]
+    );
+    assert!(engine.metrics().is_none());
+    engine.enable_metrics();
+    engine.execute().unwrap_err();
+    let metrics = engine.metrics().unwrap();
+    let expected = ["PUSHINT", "NEWC", "ENDC", "CTOS", "THROW"].into_iter().map(|opcode| (opcode, 1)).collect();
+    assert_eq!(metrics.instructions, expected);
+    assert_eq!(metrics.exceptions, [(5, 1)].into_iter().collect());
+    assert_eq!(metrics.cells_created, 1);
+    assert!(metrics.cells_loaded >= 1);
+    assert_eq!(metrics.gas_used, engine.gas_used() as u64);
+}
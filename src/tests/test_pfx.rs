@@ -0,0 +1,67 @@
+/*
+* Copyright (C) 2019-2024 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+use super::*;
+use crate::stack::Stack;
+
+fn dispatcher() -> PfxHashmapE {
+    pfx_dict_from_entries(8, vec![
+        // "1" -> PUSHINT 1
+        (SliceData::from_raw(vec![0x80], 1), SliceData::new(vec![0x71, 0x80])),
+        // "01" -> PUSHINT 2
+        (SliceData::from_raw(vec![0x40], 2), SliceData::new(vec![0x72, 0x80])),
+    ]).unwrap()
+}
+
+fn run(code: BuilderData, input: SliceData) -> Stack {
+    let code = SliceData::load_builder(code).unwrap();
+    let mut engine = Engine::with_capabilities(0).setup_with_libraries(
+        code, None, Some(Stack::with_storage(vec![StackItem::Slice(input)])), None, vec![]
+    );
+    engine.execute().unwrap();
+    engine.stack().clone()
+}
+
+#[test]
+fn test_pfx_dict_from_entries() {
+    let dict = dispatcher();
+    assert!(dict.get(SliceData::from_raw(vec![0x40], 2)).unwrap().is_some());
+    assert!(dict.get(SliceData::from_raw(vec![0x00], 2)).unwrap().is_none());
+
+    // "1" is a prefix of "10"
+    pfx_dict_from_entries(8, vec![
+        (SliceData::from_raw(vec![0x80], 2), SliceData::default()),
+        (SliceData::from_raw(vec![0x80], 1), SliceData::default()),
+    ]).unwrap_err();
+    pfxdictswitch_code(&PfxHashmapE::with_bit_len(8)).unwrap_err();
+}
+
+#[test]
+fn test_pfxdictswitch() {
+    let code = pfxdictswitch_code(&dispatcher()).unwrap();
+    assert_eq!(code.length_in_bits(), 24);
+
+    // "011" is dispatched by prefix "01"
+    let stack = run(code.clone(), SliceData::from_raw(vec![0x60], 3));
+    assert_eq!(stack.depth(), 3);
+    assert_eq!(stack.get(0), &StackItem::int(2));
+    // with suffix "1"
+    let suffix = stack.get(1).as_slice().unwrap();
+    assert_eq!(suffix.remaining_bits(), 1);
+    assert!(suffix.get_bit(0).unwrap());
+
+    // no key is a prefix of "00", slice is left as is
+    let stack = run(code, SliceData::from_raw(vec![0x00], 2));
+    assert_eq!(stack.depth(), 1);
+    assert_eq!(stack.get(0).as_slice().unwrap(), &SliceData::from_raw(vec![0x00], 2));
+}
@@ -0,0 +1,55 @@
+/*
+* Copyright (C) 2019-2024 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+use super::*;
+use crate::{executor::Engine, stack::{Stack, StackItem}};
+use ever_block::SliceData;
+use std::sync::Arc;
+
+fn run(profiler: &Arc<SessionProfiler>, code: Vec<u8>, method_id: i64) -> i64 {
+    let mut engine = Engine::with_capabilities(0x572e).setup_with_libraries(
+        SliceData::new(code), None, Some(Stack::with_storage(vec![StackItem::int(method_id)])), None, vec![]
+    );
+    engine.set_profiler(profiler.clone());
+    let _ = engine.execute();
+    engine.gas_used()
+}
+
+#[test]
+fn test_session_profiler() {
+    let profiler = Arc::new(SessionProfiler::new());
+    // DROP
+    let cheap = vec![0x30, 0x80];
+    // DROP PUSHINT 0 PUSHINT 0 DIV
+    let failing = vec![0x30, 0x70, 0x70, 0xA9, 0x04, 0x80];
+    let cheap_hash = SliceData::new(cheap.clone()).into_cell().repr_hash();
+    let failing_hash = SliceData::new(failing.clone()).into_cell().repr_hash();
+
+    let gas1 = run(&profiler, cheap.clone(), 1);
+    let gas2 = run(&profiler, cheap, 2);
+    let gas3 = run(&profiler, failing.clone(), 1);
+    let gas4 = run(&profiler, failing, 1);
+
+    let by_method = profiler.by_method();
+    assert_eq!(by_method.len(), 3);
+    assert_eq!(by_method[&(cheap_hash.clone(), Some(1))], CallStats { calls: 1, failures: 0, gas_used: gas1, max_gas_used: gas1 });
+    assert_eq!(by_method[&(failing_hash.clone(), Some(1))], CallStats { calls: 2, failures: 2, gas_used: gas3 + gas4, max_gas_used: gas3 });
+
+    let top = profiler.top_by_gas(1);
+    assert_eq!(top.len(), 1);
+    assert_eq!(top[0].0, failing_hash);
+    assert_eq!(profiler.by_code()[&cheap_hash].gas_used, gas1 + gas2);
+
+    profiler.reset();
+    assert!(profiler.by_code().is_empty());
+}
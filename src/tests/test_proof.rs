@@ -0,0 +1,168 @@
+/*
+* Copyright (C) 2019-2024 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+use super::*;
+use crate::{
+    error::tvm_exception_code,
+    executor::{engine::VmCapabilities, gas::gas_state::Gas},
+    stack::{Stack, integer::IntegerData}
+};
+use ever_block::{BuilderData, CellType, ExceptionCode, SliceData};
+
+fn cell(data: u8, references: Vec<Cell>) -> Cell {
+    let mut builder = BuilderData::with_raw(vec![data], 8).unwrap();
+    for reference in references {
+        builder.checked_append_reference(reference).unwrap();
+    }
+    builder.into_cell().unwrap()
+}
+
+fn run_with(capabilities: u64, code: Vec<u8>, stack: Vec<StackItem>, gas_limit: Option<i64>) -> Result<Stack> {
+    let gas = gas_limit.map(|limit| Gas::test_with_limit(limit));
+    let mut engine = Engine::with_capabilities(capabilities)
+        .with_vm_capabilities(VmCapabilities::CapMerkleProofs as u64)
+        .setup_with_libraries(SliceData::new(code), None, Some(Stack::with_storage(stack)), gas, vec![]);
+    engine.execute()?;
+    Ok(engine.stack().clone())
+}
+
+fn run(code: Vec<u8>, stack: Vec<StackItem>) -> Result<Stack> {
+    run_with(GlobalCapabilities::CapResolveMerkleCell as u64, code, stack, None)
+}
+
+fn hash_item(cell: &Cell) -> StackItem {
+    StackItem::integer(IntegerData::from_unsigned_bytes_be(cell.repr_hash().as_slice()))
+}
+
+#[test]
+fn test_collect_proof_cells() {
+    let leaf = cell(3, vec![]);
+    let pruned = cell(2, vec![cell(4, vec![])]);
+    let path = cell(1, vec![leaf.clone()]);
+    let root = cell(0, vec![pruned.clone(), path.clone()]);
+    let targets = [leaf.repr_hash()].into_iter().collect();
+    let mut visited = 0;
+    let proof = collect_proof_cells(&root, &targets, |_| { visited += 1; Ok(()) }).unwrap();
+    assert_eq!(visited, 5);
+    assert_eq!(proof.included, [root.repr_hash(), path.repr_hash(), leaf.repr_hash()].into_iter().collect());
+    // root, path, leaf, pruned branch and proof cell itself
+    assert_eq!(proof.proof_size, 5);
+
+    let proof = collect_proof_cells(&root, &HashSet::new(), |_| Ok(())).unwrap();
+    assert_eq!(proof.included, [root.repr_hash()].into_iter().collect());
+    assert_eq!(proof.proof_size, 4);
+}
+
+#[test]
+fn test_createproof() {
+    let leaf = cell(3, vec![]);
+    let root = cell(0, vec![cell(2, vec![cell(4, vec![])]), cell(1, vec![leaf.clone()])]);
+    let stack = vec![StackItem::Cell(root.clone()), StackItem::tuple(vec![hash_item(&leaf)])];
+    // CREATEPROOF
+    let proof = run(vec![0xCF, 0x24, 0x80], stack.clone()).unwrap().get(0).as_cell().unwrap().clone();
+    assert_eq!(proof.cell_type(), CellType::MerkleProof);
+    assert_eq!(proof, create_proof(&root, &[leaf.repr_hash()].into_iter().collect()).unwrap());
+
+    // CTOS resolves proof to its root, kept branches can be loaded
+    // CREATEPROOF CTOS PLDREFIDX 1 CTOS PLDREF CTOS
+    let result = run(vec![0xCF, 0x24, 0xD0, 0xD7, 0x4D, 0xD0, 0xD7, 0x4C, 0xD0, 0x80], stack.clone()).unwrap();
+    assert_eq!(result.get(0).as_slice().unwrap().get_bytestring(0), vec![3]);
+    // while pruned ones can not
+    // CREATEPROOF CTOS PLDREF CTOS
+    let err = run(vec![0xCF, 0x24, 0xD0, 0xD7, 0x4C, 0xD0, 0x80], stack.clone()).unwrap_err();
+    assert_eq!(tvm_exception_code(&err), Some(ExceptionCode::CellUnderflow));
+
+    // without CapResolveMerkleCell proof is an exotic cell which CTOS does not open
+    // CREATEPROOF CTOS
+    let err = run_with(0, vec![0xCF, 0x24, 0xD0, 0x80], stack.clone(), None).unwrap_err();
+    assert_eq!(tvm_exception_code(&err), Some(ExceptionCode::CellUnderflow));
+    // CREATEPROOF XCTOS loads proof cell itself
+    let result = run_with(0, vec![0xCF, 0x24, 0xD7, 0x39, 0x80], stack, None).unwrap();
+    assert_eq!(result.get(0), &boolean!(true));
+    // type of Merkle proof cell
+    assert_eq!(result.get(1).as_slice().unwrap().get_bytestring(0)[0], 3);
+}
+
+#[test]
+fn test_createupdate() {
+    let old = cell(0, vec![cell(1, vec![]), cell(2, vec![])]);
+    let new = cell(0, vec![cell(1, vec![]), cell(5, vec![])]);
+    // CREATEUPDATE
+    let stack = run(vec![0xCF, 0x25, 0x80], vec![StackItem::Cell(old.clone()), StackItem::Cell(new.clone())]).unwrap();
+    let update = stack.get(0).as_cell().unwrap();
+    assert_eq!(update.cell_type(), CellType::MerkleUpdate);
+    assert_eq!(update, &create_update(&old, &new).unwrap());
+
+    // update is not built if gas is not enough for its worst size
+    let stack = vec![StackItem::Cell(old), StackItem::Cell(new)];
+    let err = run_with(0, vec![0xCF, 0x25, 0x80], stack, Some(1000)).unwrap_err();
+    assert_eq!(tvm_exception_code(&err), Some(ExceptionCode::OutOfGas));
+}
+
+#[test]
+fn test_cell_loader() {
+    use crate::executor::engine::CellLoader;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Default)]
+    struct Storage {
+        cells: HashMap<UInt256, Cell>,
+        prefetched: Mutex<Vec<UInt256>>,
+    }
+    impl CellLoader for Storage {
+        fn load_cell(&self, hash: &UInt256) -> Result<Option<Cell>> {
+            Ok(self.cells.get(hash).cloned())
+        }
+        fn prefetch(&self, hashes: &[UInt256]) {
+            self.prefetched.lock().unwrap().extend_from_slice(hashes)
+        }
+    }
+
+    let pruned = cell(2, vec![cell(4, vec![])]);
+    let leaf = cell(3, vec![]);
+    let root = cell(0, vec![pruned.clone(), cell(1, vec![leaf.clone()])]);
+    let proof = create_proof(&root, &[leaf.repr_hash()].into_iter().collect()).unwrap();
+    let storage = Arc::new(Storage {
+        cells: [(pruned.repr_hash(), pruned.clone())].into_iter().collect(),
+        ..Default::default()
+    });
+    // tree kept by embedder with pruned branch of the cell stored outside
+    let state = proof.reference(0).unwrap();
+    let state_hash = state.repr_hash();
+    let run = |root: Cell, vm_capabilities: u64| {
+        // CTOS PLDREF CTOS
+        let mut engine = Engine::with_capabilities(GlobalCapabilities::CapResolveMerkleCell as u64)
+            .with_vm_capabilities(vm_capabilities)
+            .setup_with_libraries(
+                SliceData::new(vec![0xD0, 0xD7, 0x4C, 0xD0, 0x80]),
+                None,
+                Some(Stack::with_storage(vec![StackItem::Cell(root)])),
+                None,
+                vec![]
+            );
+        engine.set_cell_loader(storage.clone(), [state_hash.clone()]);
+        engine.execute().map(|_| (engine.stack().get(0).clone(), engine.gas_used()))
+    };
+    let capability = VmCapabilities::CapCellLoader as u64;
+    let (result, gas) = run(state.clone(), capability).unwrap();
+    assert_eq!(result.as_slice().unwrap().get_bytestring(0), vec![2]);
+    assert_eq!(*storage.prefetched.lock().unwrap(), vec![pruned.repr_hash()]);
+    // fetched cell is charged once like the cell of the whole tree
+    assert_eq!(run(root, capability).unwrap().1, gas);
+
+    let err = run(state, 0).unwrap_err();
+    assert_eq!(tvm_exception_code(&err), Some(ExceptionCode::CellUnderflow));
+    // pruned branches of Merkle proofs are not fetched
+    let err = run(proof, capability).unwrap_err();
+    assert_eq!(tvm_exception_code(&err), Some(ExceptionCode::CellUnderflow));
+}
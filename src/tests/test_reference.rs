@@ -0,0 +1,36 @@
+/*
+* Copyright (C) 2019-2024 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+use super::*;
+use crate::stack::integer::IntegerData;
+
+#[test]
+fn test_parse_fift_output() {
+    let outcome = parse_fift_output(" 1 [ 2 (null) ] 0 26 \n").unwrap();
+    assert_eq!(outcome, ReferenceOutcome {
+        exit_code: 0,
+        gas_used: 26,
+        stack: "1 [ 2 (null) ]".to_string(),
+    });
+    parse_fift_output("0").expect_err("exit code and gas are expected");
+}
+
+#[test]
+fn test_run_local() {
+    // PUSHINT 1 PUSHINT 2 ADD
+    let code = SliceData::new(vec![0x71, 0x72, 0xA0, 0x80]).into_cell();
+    let outcome = ReferenceCompare::new("fift").run_local(&code, &Stack::new()).unwrap();
+    assert_eq!(outcome.exit_code, 0);
+    assert_eq!(outcome.stack, "3");
+    assert_eq!(fift_literal(&StackItem::tuple(vec![int!(1), StackItem::None])).unwrap(), "1 null 2 tuple");
+}
@@ -0,0 +1,83 @@
+/*
+* Copyright (C) 2019-2024 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+use super::*;
+use crate::error::tvm_exception_code;
+use ever_block::ExceptionCode;
+
+#[test]
+fn test_replay_bundle() {
+    // ADD PUSHINT 0 DIV
+    let code = SliceData::new(vec![0xA0, 0x70, 0xA9, 0x04, 0x80]);
+    let stack = Stack::with_storage(vec![StackItem::int(1), StackItem::int(2)]);
+    let mut engine = Engine::with_capabilities(0x572e).setup_with_libraries(
+        code, None, Some(stack.clone()), Some(Gas::test_with_limit(1000)), vec![]
+    );
+    let path = std::env::temp_dir().join(format!("replay-{}.boc", std::process::id()));
+    let err = engine.execute_or_dump(&path).unwrap_err();
+    assert_eq!(tvm_exception_code(&err), Some(ExceptionCode::IntegerOverflow));
+
+    let bundle = ReplayBundle::read_from_file(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(bundle.capabilities, 0x572e);
+    assert_eq!(bundle.gas.get_gas_limit(), 1000);
+    assert_eq!(bundle.stack.storage, stack.storage);
+
+    let mut replayed = Engine::replay(bundle).unwrap();
+    let err = replayed.execute().unwrap_err();
+    assert_eq!(tvm_exception_code(&err), Some(ExceptionCode::IntegerOverflow));
+    assert_eq!(replayed.gas_used(), engine.gas_used());
+}
+
+#[test]
+fn test_execute_or_dump_keeps_result_if_bundle_is_not_written() {
+    // PUSHINT 0 PUSHINT 0 DIV
+    let code = SliceData::new(vec![0x70, 0x70, 0xA9, 0x04, 0x80]);
+    let mut engine = Engine::with_capabilities(0).setup_with_libraries(code, None, None, None, vec![]);
+    let path = std::env::temp_dir().join(format!("replay-{}", std::process::id())).join("missing").join("replay.boc");
+    let err = engine.execute_or_dump(&path).unwrap_err();
+    assert_eq!(tvm_exception_code(&err), Some(ExceptionCode::IntegerOverflow));
+    assert!(!path.exists());
+}
+
+#[test]
+fn test_replay_bundle_keeps_engine_settings() {
+    // PUSHINT 1 PUSHINT 2 TUPLE 3
+    let code = SliceData::new(vec![0x71, 0x72, 0x6F, 0x03, 0x80]);
+    let items = vec![
+        StackItem::int(3),
+        StackItem::tuple(vec![StackItem::int(4), StackItem::None]),
+        StackItem::continuation(crate::stack::continuation::ContinuationData::new_empty()),
+    ];
+    let prices = GasPrices { instruction_base: 20, tuple_entry: 7, ..GasPrices::DEFAULT };
+    let vm_capabilities = crate::executor::VmCapabilities::CapLongTuples as u64;
+    let mut engine = Engine::with_capabilities(0x572e)
+        .with_vm_capabilities(vm_capabilities)
+        .with_gas_prices(prices.clone())
+        .with_unlimited_gas()
+        .setup_with_libraries(
+            code, None, Some(Stack::with_storage(items.clone())), Some(Gas::test_with_limit(10)), vec![]
+        );
+    let bundle = ReplayBundle::deserialize(engine.replay_bundle().unwrap().serialize().unwrap()).unwrap();
+    assert_eq!(bundle.vm_capabilities, vm_capabilities);
+    assert_eq!(bundle.gas_prices, prices);
+    assert!(bundle.gas.is_unlimited());
+    assert_eq!(bundle.stack.storage, items);
+
+    engine.execute().unwrap();
+    let mut replayed = Engine::replay(bundle).unwrap();
+    replayed.execute().unwrap();
+    assert_eq!(replayed.vm_capabilities(), vm_capabilities);
+    assert_eq!(replayed.gas_used(), engine.gas_used());
+    assert_eq!(replayed.stack().storage, engine.stack().storage);
+}
@@ -44,3 +44,39 @@ fn test_smart_contract_info_with_different_caps() {
         | GlobalCapabilities::CapStorageFeeToTvm as u64;
     check_additional_fields(capabilities, 13);
 }
+
+#[test]
+fn test_smart_contract_info_prev_blocks_and_global_id() {
+    let mut sci = SmartContractInfo {
+        vm_capabilities: VmCapabilities::CapBlockchainInfo as u64,
+        ..Default::default()
+    };
+    sci.set_prev_blocks_info(vec![BlockIdExt::default(); 2], None);
+    sci.set_global_id(42);
+    let item = sci.into_temp_data_item();
+    let params = item.as_tuple().unwrap()[0].as_tuple().unwrap();
+    assert_eq!(params.len(), 16);
+    let prev_blocks = params[14].as_tuple().unwrap();
+    assert_eq!(prev_blocks[0].as_tuple().unwrap().len(), 2);
+    assert!(prev_blocks[1].is_null());
+    assert_eq!(params[15], int!(42));
+}
+
+#[test]
+fn test_smart_contract_info_incoming_value_and_due_payment() {
+    let mut sci = SmartContractInfo {
+        vm_capabilities: VmCapabilities::CapTransactionInfo as u64,
+        ..Default::default()
+    };
+    sci.set_incoming_value(CurrencyCollection::with_grams(1_000_000));
+    let item = sci.clone().into_temp_data_item();
+    let params = item.as_tuple().unwrap()[0].as_tuple().unwrap();
+    let incoming_value = params[16].as_tuple().unwrap();
+    assert_eq!(incoming_value[0], int!(1_000_000));
+    assert!(incoming_value[1].is_null());
+    assert!(params[17].is_null());
+
+    sci.set_due_payment(Some(77));
+    let item = sci.into_temp_data_item();
+    assert_eq!(item.as_tuple().unwrap()[0].as_tuple().unwrap()[17], int!(77));
+}
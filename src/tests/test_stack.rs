@@ -12,7 +12,7 @@
 */
 
 use ever_block::{BuilderData, SliceData};
-use super::{Stack, StackItem};
+use super::{Stack, StackItem, integer::IntegerData};
 
 #[test]
 fn test_push_increases_depth() {
@@ -48,6 +48,8 @@ fn test_fift_output() {
     assert_eq!(StackItem::slice(SliceData::load_cell(cell).unwrap()).dump_as_fift(), "CS{Cell{00035774} bits: 0..13; refs: 0..0}");
     assert_eq!(StackItem::tuple(vec![]).dump_as_fift(), "[]");
     assert_eq!(StackItem::tuple(vec![StackItem::nan(), StackItem::int(1234567890)]).dump_as_fift(), "[ NaN 1234567890 ]");
+    let cont = crate::stack::continuation::ContinuationData::with_code(SliceData::default());
+    assert_eq!(StackItem::continuation(cont).dump_as_fift(), "Cont{vmc_std}");
 }
 
 mod test_serialization {
@@ -86,6 +88,38 @@ mod test_serialization {
         println!("finish")
     }
 
+    #[test]
+    fn test_continuation_format_migration() {
+        use crate::stack::continuation::ContinuationFormat;
+        let mut cont = ContinuationData::with_code(SliceData::new(vec![12, 13, 0x80]));
+        cont.stack.push(StackItem::int(5));
+
+        let (builder, _) = cont.serialize_with_format(ContinuationFormat::Old, &mut 0, &Default::default()).unwrap();
+        let mut slice = SliceData::load_builder(builder).unwrap();
+        let (migrated, _) = ContinuationData::migrate(&mut slice, ContinuationFormat::Old, &mut 0, &Default::default()).unwrap();
+
+        let mut slice = SliceData::load_builder(migrated).unwrap();
+        let (new_cont, format, _) = ContinuationData::deserialize_any(&mut slice, ContinuationFormat::Old, &mut 0, &Default::default()).unwrap();
+        assert_eq!(format, ContinuationFormat::New);
+        pretty_assertions::assert_eq!(cont, new_cont);
+    }
+
+    #[test]
+    fn test_old_format_gas_prices() {
+        use crate::{executor::gas::gas_state::GasPrices, stack::continuation::ContinuationFormat};
+        let mut cont = ContinuationData::with_code(SliceData::new(vec![12, 13, 0x80]));
+        cont.stack.push(StackItem::int(5));
+        cont.stack.push(StackItem::int(6));
+        let prices = GasPrices { cell_create: 1000, ..GasPrices::DEFAULT };
+        let (_, gas) = cont.serialize_with_format(ContinuationFormat::Old, &mut 0, &GasPrices::DEFAULT).unwrap();
+        let (builder, custom_gas) = cont.serialize_with_format(ContinuationFormat::Old, &mut 0, &prices).unwrap();
+        assert_eq!(custom_gas - gas, 2 * (1000 - GasPrices::DEFAULT.cell_create));
+
+        let mut slice = SliceData::load_builder(builder).unwrap();
+        let (new_cont, _) = ContinuationData::deserialize_with_format(&mut slice, ContinuationFormat::Old, &mut 0, &prices).unwrap();
+        pretty_assertions::assert_eq!(cont, new_cont);
+    }
+
     #[test]
     fn test_simple_item() {
         let item = StackItem::int(100500);
@@ -139,3 +173,93 @@ mod test_serialization {
         assert_eq!(item, new_item);
     }
 }
+
+#[test]
+fn test_normalize() {
+    let builder = BuilderData::with_bitstring(vec![0x57, 0x74]).unwrap();
+    let cell = builder.clone().into_cell().unwrap();
+    let mut slice = SliceData::new(vec![0xFF, 0x57, 0x74]);
+    slice.move_by(8).unwrap();
+
+    let mut stack1 = Stack::new();
+    stack1.push(StackItem::builder(builder.clone()));
+    stack1.push(StackItem::tuple(vec![StackItem::slice(slice), StackItem::nan()]));
+    let mut stack2 = Stack::new();
+    stack2.push(StackItem::cell(cell.clone()));
+    stack2.push(StackItem::tuple(vec![StackItem::slice(SliceData::load_cell(cell).unwrap()), StackItem::nan()]));
+
+    assert_ne!(stack1, stack2);
+    assert!(stack1.eq_normalized(&stack2).unwrap());
+}
+
+#[test]
+fn test_typed_accessors() {
+    let mut tail = BuilderData::new();
+    tail.append_raw(b"world", 40).unwrap();
+    let mut head = BuilderData::new();
+    head.append_raw(b"hello ", 48).unwrap();
+    head.checked_append_reference(tail.into_cell().unwrap()).unwrap();
+    let string = StackItem::Slice(SliceData::load_builder(head).unwrap());
+    assert_eq!(string.as_string().unwrap(), "hello world");
+
+    assert_eq!(int!(-1).as_i128().unwrap(), -1);
+    int!(-1).as_u64().expect_err("negative value is not u64");
+    assert_eq!(int!(0x0102).as_bytes32().unwrap()[30..], [1, 2]);
+
+    let mut stack = Stack::new();
+    stack.push(string).push(int!(u64::MAX)).push(int!(-1));
+    assert!(stack.pop_typed::<bool>().unwrap());
+    stack.pop_typed::<String>().expect_err("integer is not a string");
+    assert_eq!(stack.pop_typed::<u64>().unwrap(), u64::MAX);
+    assert_eq!(stack.pop_typed::<String>().unwrap(), "hello world");
+    stack.pop_typed::<StackItem>().expect_err("stack is empty");
+}
+
+#[test]
+fn test_savelist_copy_on_write() {
+    use crate::stack::savelist::SaveList;
+    let mut savelist = SaveList::new();
+    savelist.put(7, &mut StackItem::tuple(vec![StackItem::int(1)])).unwrap();
+    let mut copy = savelist.clone();
+    copy.put(4, &mut StackItem::cell(Default::default())).unwrap();
+    assert!(savelist.get(4).is_none());
+    assert_eq!(copy.get(7), savelist.get(7));
+
+    let mut target = SaveList::new();
+    target.put(4, &mut StackItem::cell(Default::default())).unwrap();
+    target.apply(&mut savelist);
+    assert!(savelist.is_empty());
+    assert_eq!(target, copy);
+    assert!(target.remove(7).is_some());
+    assert!(target.remove(7).is_none());
+}
+
+#[test]
+fn test_long_tuple() {
+    let items = (0..600).map(StackItem::int).collect::<Vec<_>>();
+    let tuple = StackItem::long_tuple(items.clone());
+    // 254 + 254 + 92 items in chain of three tuples
+    assert_eq!(tuple.as_tuple().unwrap().len(), super::MAX_TUPLE_SIZE);
+    assert_eq!(tuple.long_tuple_len().unwrap(), 600);
+    assert_eq!(tuple.long_tuple_items().unwrap(), items);
+    for i in [0, 253, 254, 507, 508, 599] {
+        assert_eq!(tuple.tuple_item_deep(i as usize).unwrap(), &StackItem::int(i));
+    }
+    assert!(tuple.tuple_item_deep(600).is_err());
+
+    // short sequences are ordinary tuples
+    let short = StackItem::long_tuple(items[..254].to_vec());
+    assert_eq!(short, StackItem::tuple(items[..254].to_vec()));
+    let full = StackItem::long_tuple(items[..255].to_vec());
+    assert_eq!(full.tuple_item_deep(254).unwrap(), &StackItem::int(254));
+    assert_eq!(StackItem::long_tuple(vec![]).long_tuple_len().unwrap(), 0);
+
+    // last item of full tuple which is not a tuple ends the chain
+    let plain = StackItem::tuple(items[..255].to_vec());
+    assert_eq!(plain.long_tuple_len().unwrap(), 255);
+    assert_eq!(plain.tuple_item_deep(254).unwrap(), &StackItem::int(254));
+    assert!(plain.tuple_item_deep(255).is_err());
+    assert_eq!(plain.long_tuple_items().unwrap(), items[..255].to_vec());
+    assert_eq!(tuple.tuple_item_deep_with_hops(599).unwrap().1, 2);
+    assert_eq!(tuple.long_tuple_len_with_hops().unwrap(), (600, 2));
+}
@@ -0,0 +1,40 @@
+/*
+* Copyright (C) 2019-2024 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+use super::*;
+use crate::executor::Engine;
+
+#[test]
+fn test_generated_programs_execute() {
+    let mut generator = Generator::new(42);
+    for _ in 0..200 {
+        let program = generator.program(40, 0);
+        let code = program.code().unwrap();
+        let mut engine = Engine::with_capabilities(0)
+            .setup_with_libraries(code, None, None, None, vec![]);
+        // exceptions are valid outcome, but engine must not panic
+        let _ = engine.execute();
+    }
+    assert_eq!(Generator::new(7).program(20, 0), Generator::new(7).program(20, 0));
+}
+
+#[test]
+fn test_program_minimize() {
+    let program = Generator::new(1).program(30, 0);
+    let minimized = program.minimize(|program| program.instructions.iter().any(|i| i.text == "PUSHNULL"));
+    if program.instructions.iter().any(|i| i.text == "PUSHNULL") {
+        assert_eq!(minimized.instructions.len(), 1);
+    } else {
+        assert_eq!(minimized, program);
+    }
+}
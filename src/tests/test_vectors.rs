@@ -0,0 +1,38 @@
+/*
+* Copyright (C) 2019-2024 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+use super::*;
+
+#[test]
+fn test_basic_vectors() {
+    let json = include_str!("../../tests/vectors/basic.json");
+    assert_eq!(parse_vectors(json).unwrap().len(), 5);
+    assert_eq!(check_vectors(json).unwrap(), Vec::<String>::new());
+}
+
+#[test]
+fn test_mismatch() {
+    let json = r#"{
+        "name": "ADD",
+        "code": "b5ee9c72010101010003000002a0",
+        "stack": [1, 2],
+        "gas_limit": 1000,
+        "expected": { "exit_code": 0, "stack": ["4"], "gas_used": 1 }
+    }"#;
+    let vector = &parse_vectors(json).unwrap()[0];
+    assert_eq!(vector.gas_limit, 1000);
+    let mismatch = vector.check().unwrap().unwrap();
+    assert!(mismatch.starts_with("ADD: stack [\"3\"] != [\"4\"], gas used "), "{}", mismatch);
+
+    assert!(parse_vectors(r#"{ "code": "b5ee9c72010101010003000002a0", "stack": [] }"#).is_err());
+}
@@ -0,0 +1,57 @@
+/*
+* Copyright (C) 2019-2024 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+use super::*;
+use ever_block::SliceData;
+use std::sync::Arc;
+
+fn run_with_log(code: Vec<u8>) -> Vec<String> {
+    let lines = Arc::new(Mutex::new(Vec::new()));
+    let mut engine = Engine::with_capabilities(0x572e).setup_with_libraries(
+        SliceData::new(code), None, None, None, vec![]
+    );
+    let sink = lines.clone();
+    engine.set_vm_log(move |line| sink.lock().unwrap().push(line.to_string()));
+    let _ = engine.execute();
+    let lines = lines.lock().unwrap().clone();
+    lines
+}
+
+#[test]
+fn test_vm_log_format() {
+    // PUSHINT 1 PUSHINT 2 ADD
+    let lines = run_with_log(vec![0x71, 0x72, 0xA0, 0x80]);
+    assert_eq!(lines.len(), 15);
+    assert_eq!(lines[0], "stack: [ ] ");
+    assert!(lines[1].starts_with("code cell hash: ") && lines[1].ends_with(" offset: 0"));
+    assert_eq!(lines[2], "execute PUSHINT 1");
+    assert_eq!(lines[3], "gas remaining: 999999982");
+    assert_eq!(lines[8], "stack: [ 1 2 ] ");
+    assert!(lines[9].ends_with(" offset: 16"));
+    assert_eq!(lines[10], "execute ADD");
+    assert_eq!(lines[12], "stack: [ 3 ] ");
+    assert_eq!(lines[13], "execute implicit RET");
+    assert_eq!(lines[14], "gas remaining: 999999941");
+}
+
+#[test]
+fn test_vm_log_exception() {
+    // PUSHNULL PUSHNAN INC
+    let lines = run_with_log(vec![0x6D, 0x83, 0xFF, 0xA4, 0x80]);
+    assert_eq!(lines[4], "stack: [ () ] ");
+    assert_eq!(lines[7], "gas remaining: 999999956");
+    assert_eq!(lines[8], "stack: [ () NaN ] ");
+    assert_eq!(lines[10], "execute INC");
+    assert_eq!(lines[11], "handling exception code 4: integer overflow");
+    assert_eq!(lines.last().unwrap(), "default exception handler, terminating vm with exit code 4");
+}
@@ -124,6 +124,13 @@ impl Exception {
     pub fn exception_or_custom_code(&self) -> i32 {
         self.exception.exception_or_custom_code()
     }
+    /// Exit code of execution terminated by the exception, out of gas is reported as -14
+    pub fn exit_code(&self) -> i32 {
+        match self.exception_code() {
+            Some(ExceptionCode::OutOfGas) => !(ExceptionCode::OutOfGas as i32),
+            _ => self.exception_or_custom_code()
+        }
+    }
     pub fn is_normal_termination(&self) -> Option<i32> {
         self.exception.is_normal_termination()
     }
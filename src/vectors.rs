@@ -0,0 +1,196 @@
+/*
+* Copyright (C) 2019-2024 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+//! Conformance test vectors shared between VM implementations.
+//!
+//! A vector file is a JSON array of objects (or a single object) with fields:
+//! - `name`: description of the vector
+//! - `code`: hex encoded BOC with code cell
+//! - `stack`: initial stack in the format of [`crate::stack::json`], the last item is the top
+//! - `c7` (optional): value of c7 in the same format, empty tuple by default
+//! - `data` (optional): hex encoded BOC with c4, empty cell by default
+//! - `capabilities` (optional): global capabilities as number or 0x-prefixed hex string, 0 by default
+//! - `gas_limit` (optional): gas limit, 1000000 by default
+//! - `expected`: object with `exit_code` and optional `stack` and `gas_used`.
+//!   Exit code of out of gas is -14, stack after exception holds its parameter only.
+//!
+//! ```json
+//! [{ "name": "ADD", "code": "b5ee9c72010101010003000002a0", "stack": [1, 2],
+//!    "expected": { "exit_code": 0, "stack": ["3"] } }]
+//! ```
+
+use crate::{
+    executor::{engine::Engine, gas::gas_state::Gas},
+    stack::{json::{parse_stack_item, stack_to_json}, savelist::SaveList, Stack, StackItem},
+};
+use ever_block::{fail, read_single_root_boc, Cell, Result, SliceData};
+use serde_json::Value;
+
+const DEFAULT_GAS_LIMIT: i64 = 1_000_000;
+
+/// Results expected from execution of vector
+#[derive(Clone, Debug)]
+pub struct Expected {
+    pub exit_code: i32,
+    pub stack: Option<Stack>,
+    pub gas_used: Option<i64>,
+}
+
+/// One conformance test case
+#[derive(Clone, Debug)]
+pub struct TestVector {
+    pub name: String,
+    pub code: Cell,
+    pub stack: Stack,
+    pub c7: StackItem,
+    pub data: Cell,
+    pub capabilities: u64,
+    pub gas_limit: i64,
+    pub expected: Expected,
+}
+
+fn parse_boc(value: &Value, field: &str) -> Result<Cell> {
+    match value.get(field).and_then(Value::as_str) {
+        Some(hex) => read_single_root_boc(hex::decode(hex)?),
+        None => fail!("field {} must be hex string with boc", field)
+    }
+}
+
+fn parse_stack(value: Option<&Value>) -> Result<Stack> {
+    match value {
+        Some(Value::Array(items)) => Ok(Stack::with_storage(
+            items.iter().map(parse_stack_item).collect::<Result<Vec<_>>>()?
+        )),
+        _ => fail!("stack must be JSON array")
+    }
+}
+
+fn parse_u64(value: &Value) -> Result<u64> {
+    match value {
+        Value::Number(number) => match number.as_u64() {
+            Some(number) => Ok(number),
+            None => fail!("{} is not unsigned integer", number)
+        }
+        Value::String(hex) => match hex.strip_prefix("0x") {
+            Some(hex) => Ok(u64::from_str_radix(hex, 16)?),
+            None => Ok(hex.parse()?)
+        }
+        _ => fail!("{} is not unsigned integer", value)
+    }
+}
+
+impl TestVector {
+    pub fn from_json(value: &Value) -> Result<Self> {
+        let name = value.get("name").and_then(Value::as_str).unwrap_or_default().to_string();
+        let expected = match value.get("expected") {
+            Some(expected) => Expected {
+                exit_code: match expected.get("exit_code").and_then(Value::as_i64) {
+                    Some(exit_code) => exit_code as i32,
+                    None => fail!("vector {} has no expected exit code", name)
+                },
+                stack: match expected.get("stack") {
+                    Some(stack) => Some(parse_stack(Some(stack))?),
+                    None => None
+                },
+                gas_used: expected.get("gas_used").and_then(Value::as_i64),
+            },
+            None => fail!("vector {} has no expected results", name)
+        };
+        Ok(Self {
+            code: parse_boc(value, "code")?,
+            stack: parse_stack(value.get("stack"))?,
+            c7: match value.get("c7") {
+                Some(c7) => parse_stack_item(c7)?,
+                None => StackItem::tuple(vec![])
+            },
+            data: match value.get("data") {
+                Some(_) => parse_boc(value, "data")?,
+                None => Cell::default()
+            },
+            capabilities: match value.get("capabilities") {
+                Some(capabilities) => parse_u64(capabilities)?,
+                None => 0
+            },
+            gas_limit: value.get("gas_limit").and_then(Value::as_i64).unwrap_or(DEFAULT_GAS_LIMIT),
+            expected,
+            name,
+        })
+    }
+
+    /// Runs vector returning actual results in the form of expected ones
+    pub fn run(&self) -> Result<Expected> {
+        let mut ctrls = SaveList::new();
+        ctrls.put(4, &mut StackItem::cell(self.data.clone()))?;
+        ctrls.put(7, &mut self.c7.clone())?;
+        let mut engine = Engine::with_capabilities(self.capabilities).setup_with_libraries(
+            SliceData::load_cell_ref(&self.code)?,
+            Some(ctrls),
+            Some(self.stack.clone()),
+            Some(Gas::test_with_limit(self.gas_limit)),
+            vec![]
+        );
+        let outcome = engine.execute_with_outcome()?;
+        Ok(Expected {
+            exit_code: outcome.exit_code,
+            stack: Some(outcome.stack),
+            gas_used: Some(outcome.gas_used),
+        })
+    }
+
+    /// Runs vector and returns description of mismatch with expected results if any
+    pub fn check(&self) -> Result<Option<String>> {
+        let actual = self.run()?;
+        let mut mismatches = Vec::new();
+        if actual.exit_code != self.expected.exit_code {
+            mismatches.push(format!("exit code {} != {}", actual.exit_code, self.expected.exit_code));
+        }
+        if let (Some(expected), Some(actual)) = (&self.expected.stack, &actual.stack) {
+            let (expected, actual) = (stack_to_json(expected)?, stack_to_json(actual)?);
+            if expected != actual {
+                mismatches.push(format!("stack {} != {}", actual, expected));
+            }
+        }
+        if let (Some(expected), Some(actual)) = (self.expected.gas_used, actual.gas_used) {
+            if expected != actual {
+                mismatches.push(format!("gas used {} != {}", actual, expected));
+            }
+        }
+        match mismatches.is_empty() {
+            true => Ok(None),
+            false => Ok(Some(format!("{}: {}", self.name, mismatches.join(", "))))
+        }
+    }
+}
+
+/// Parses vectors from JSON array or a single object
+pub fn parse_vectors(json: &str) -> Result<Vec<TestVector>> {
+    match serde_json::from_str::<Value>(json)? {
+        Value::Array(items) => items.iter().map(TestVector::from_json).collect(),
+        value => Ok(vec![TestVector::from_json(&value)?])
+    }
+}
+
+/// Runs all vectors of JSON file and returns descriptions of failed ones
+pub fn check_vectors(json: &str) -> Result<Vec<String>> {
+    let mut failures = Vec::new();
+    for vector in parse_vectors(json)? {
+        if let Some(mismatch) = vector.check()? {
+            failures.push(mismatch)
+        }
+    }
+    Ok(failures)
+}
+
+#[cfg(test)]
+#[path = "tests/test_vectors.rs"]
+mod tests;
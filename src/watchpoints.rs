@@ -0,0 +1,92 @@
+/*
+* Copyright (C) 2019-2024 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+use crate::stack::{integer::IntegerData, savelist::SaveList, Stack, StackItem};
+
+/// Condition checked after every instruction executed by Engine::execute_resumable()
+#[derive(Clone, Debug, PartialEq)]
+pub enum Watchpoint {
+    /// control register has another value than before instruction
+    CtrlChanged(usize),
+    /// stack has less than given number of items
+    StackDepthBelow(usize),
+    /// instruction has put given integer on top of the stack
+    IntegerPushed(IntegerData),
+}
+
+/// Registered watchpoints with state captured before current instruction
+#[derive(Default)]
+pub struct Watchpoints {
+    list: Vec<Option<Watchpoint>>, // removed ones are kept as None, so ids are stable
+    ctrls: Vec<Option<StackItem>>,
+    stack_top: Option<(usize, StackItem)>,
+    triggered: Option<usize>,
+}
+
+impl Watchpoints {
+    pub fn add(&mut self, watchpoint: Watchpoint) -> usize {
+        self.list.push(Some(watchpoint));
+        self.list.len() - 1
+    }
+
+    pub fn remove(&mut self, id: usize) -> Option<Watchpoint> {
+        self.list.get_mut(id)?.take()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.list.iter().all(Option::is_none)
+    }
+
+    /// Id of watchpoint which has paused execution last time
+    pub fn triggered(&self) -> Option<usize> {
+        self.triggered
+    }
+
+    fn active(&self) -> impl Iterator<Item = (usize, &Watchpoint)> {
+        self.list.iter().enumerate().filter_map(|(id, watchpoint)| Some((id, watchpoint.as_ref()?)))
+    }
+
+    /// Captures state watched by registered conditions
+    pub fn before(&mut self, stack: &Stack, ctrls: &SaveList) {
+        self.triggered = None;
+        self.ctrls = self.list.iter().map(|watchpoint| match watchpoint {
+            Some(Watchpoint::CtrlChanged(index)) => ctrls.get(*index).cloned(),
+            _ => None
+        }).collect();
+        self.stack_top = match stack.depth() {
+            0 => None,
+            depth => Some((depth, stack.get(0).clone()))
+        };
+    }
+
+    /// Returns id of the first watchpoint hit by the instruction
+    pub fn after(&mut self, stack: &Stack, ctrls: &SaveList) -> Option<usize> {
+        let hit = self.active().find(|(id, watchpoint)| match watchpoint {
+            Watchpoint::CtrlChanged(index) => self.ctrls.get(*id).and_then(Option::as_ref) != ctrls.get(*index),
+            Watchpoint::StackDepthBelow(depth) => stack.depth() < *depth,
+            Watchpoint::IntegerPushed(value) => {
+                let top = match stack.depth() {
+                    0 => return false,
+                    depth => (depth, stack.get(0))
+                };
+                let pushed = match &self.stack_top {
+                    Some((depth, item)) => top.0 != *depth || top.1 != item,
+                    None => true
+                };
+                pushed && top.1.as_integer().map_or(false, |x| x == value)
+            }
+        }).map(|(id, _)| id);
+        self.triggered = hit;
+        hit
+    }
+}
@@ -0,0 +1,56 @@
+/*
+ * Copyright (C) 2019-2024 EverX. All Rights Reserved.
+ *
+ * Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+ * this file except in compliance with the License.
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific EVERX DEV software governing permissions and
+ * limitations under the License.
+ */
+
+// Differential tests against reference implementation, they are run by `cargo test -- --ignored`.
+// FIFT_PATH must be set to fift binary, FIFT_LIB_PATH should point to directory with Fift.fif
+
+use ever_block::SliceData;
+use ever_vm::{reference::ReferenceCompare, stack::{Stack, StackItem}};
+
+fn reference_compare() -> ReferenceCompare {
+    let fift_path = std::env::var("FIFT_PATH").expect("FIFT_PATH must be set to fift binary");
+    let compare = ReferenceCompare::new(fift_path);
+    match std::env::var("FIFT_LIB_PATH") {
+        Ok(lib_path) => compare.with_lib_path(lib_path),
+        Err(_) => compare
+    }
+}
+
+fn check(code: Vec<u8>, stack: Vec<StackItem>) {
+    let compare = reference_compare();
+    let code = SliceData::new(code).into_cell();
+    if let Some(mismatch) = compare.compare(&code, &Stack::with_storage(stack)).unwrap() {
+        panic!("local {:?} != reference {:?}", mismatch.local, mismatch.reference)
+    }
+}
+
+#[test]
+#[ignore = "requires fift, see FIFT_PATH"]
+fn test_arithmetic() {
+    check(vec![0xA0, 0x80], vec![StackItem::int(1), StackItem::int(2)]); // ADD
+    check(vec![0xA8, 0x80], vec![StackItem::int(-7), StackItem::int(3)]); // MUL
+    check(vec![0xA9, 0x04, 0x80], vec![StackItem::int(-7), StackItem::int(3)]); // DIV
+}
+
+#[test]
+#[ignore = "requires fift, see FIFT_PATH"]
+fn test_exceptions() {
+    check(vec![0xF2, 0x2A, 0x80], vec![]); // THROW 42
+    check(vec![0xA0, 0x80], vec![StackItem::int(1)]); // ADD with stack underflow
+}
+
+#[test]
+#[ignore = "requires fift, see FIFT_PATH"]
+fn test_tuples() {
+    check(vec![0x6F, 0x02, 0x80], vec![StackItem::int(1), StackItem::None]); // TUPLE 2
+}
@@ -0,0 +1,79 @@
+/*
+* Copyright (C) 2019-2024 EverX. All Rights Reserved.
+*
+* Licensed under the SOFTWARE EVALUATION License (the "License"); you may not use
+* this file except in compliance with the License.
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific EVERX DEV software governing permissions and
+* limitations under the License.
+*/
+
+//! Thin wasm-bindgen wrapper of the VM for in-browser execution
+
+use ever_vm::{
+    executor::harness::{run_code, HarnessParams},
+    stack::json::{parse_stack, stack_to_json},
+};
+use ever_block::{read_single_root_boc, Cell, Result};
+use serde_json::json;
+use wasm_bindgen::prelude::*;
+
+fn execute_with_params(
+    code_boc: &[u8],
+    data_boc: &[u8],
+    stack_json: &str,
+    params: &HarnessParams
+) -> Result<String> {
+    let code = read_single_root_boc(code_boc)?;
+    let data = match data_boc.is_empty() {
+        true => Cell::default(),
+        false => read_single_root_boc(data_boc)?
+    };
+    let stack = match stack_json.trim().is_empty() {
+        true => Default::default(),
+        false => parse_stack(stack_json)?
+    };
+    let result = run_code(code, data, stack, params)?;
+    let mut committed = serde_json::Value::Null;
+    if let (Some(data), Some(actions)) = (result.data, result.actions) {
+        committed = json!({
+            "data": hex::encode(ever_block::write_boc(&data)?),
+            "actions": hex::encode(ever_block::write_boc(&actions)?),
+        });
+    }
+    Ok(json!({
+        "exit_code": result.exit_code,
+        "gas_used": result.gas_used,
+        "stack": stack_to_json(&result.stack)?,
+        "committed": committed,
+    }).to_string())
+}
+
+/// Executes code with data in c4 and initial stack in JSON format described in stack::json,
+/// returns JSON object with exit code, gas used, result stack and committed state
+#[wasm_bindgen]
+pub fn execute(code_boc: &[u8], data_boc: &[u8], stack_json: &str) -> std::result::Result<String, JsValue> {
+    execute_with_params(code_boc, data_boc, stack_json, &HarnessParams::default())
+        .map_err(|err| JsValue::from_str(&err.to_string()))
+}
+
+/// The same as execute with given gas limit and global capabilities
+#[wasm_bindgen(js_name = executeWithParams)]
+pub fn execute_with_gas_and_capabilities(
+    code_boc: &[u8],
+    data_boc: &[u8],
+    stack_json: &str,
+    gas_limit: i64,
+    capabilities: u64
+) -> std::result::Result<String, JsValue> {
+    let params = HarnessParams {
+        capabilities,
+        gas_limit,
+        ..Default::default()
+    };
+    execute_with_params(code_boc, data_boc, stack_json, &params)
+        .map_err(|err| JsValue::from_str(&err.to_string()))
+}